@@ -0,0 +1,422 @@
+//! Conversion of catalogs from and to Android string resources.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+
+use crate::xml::{self, Element};
+use crate::{Catalog, CatalogBuilder, Error, Message, PluralCategory};
+
+/// A resource of a `strings.xml` file.
+enum Resource {
+    String(String),
+    Array(Vec<String>),
+    Plurals(Vec<(PluralCategory, String)>),
+}
+
+/// Returns the text of a resource, undoing the escapes of Android.
+///
+/// Outside of double quotes, runs of whitespace are collapsed to a single space
+/// and the whitespace at either end is removed, as `aapt` does.
+fn unescape(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let (mut quoted, mut space) = (false, false);
+    // the length of the text that is not trailing, unquoted whitespace
+    let mut end = 0;
+    let mut chars = src.chars();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() && !quoted {
+            space = !out.is_empty();
+            continue;
+        }
+        if space {
+            out.push(' ');
+            space = false;
+        }
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('u') => {
+                    let hex = chars.clone().take(4).collect::<String>();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(c) if hex.len() == 4 => {
+                            out.push(c);
+                            chars.nth(3);
+                        }
+                        _ => out.push('u'),
+                    }
+                }
+                Some(c) => out.push(c),
+                None => {}
+            },
+            c => out.push(c),
+        }
+        end = out.len();
+    }
+    out.truncate(end);
+    out
+}
+
+/// Returns the text escaped for a resource, the way `unescape` reads it back.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '@' | '?' if i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    // whitespace is kept as it is only inside double quotes
+    if text.starts_with(' ') || text.ends_with(' ') || text.contains("  ") {
+        out = format!("\"{}\"", out);
+    }
+    out
+}
+
+/// Reads the resources of a `strings.xml` file, leaving out the untranslatable ones.
+fn read(reader: impl Read) -> Result<HashMap<String, Resource>, Error> {
+    let root = xml::read(reader)?;
+    if root.name != "resources" {
+        return Err(root.error());
+    }
+    let mut resources = HashMap::new();
+    for element in root.elements() {
+        if element.get("translatable") == Some("false") {
+            continue;
+        }
+        let items = || element.elements().filter(|e| e.name == "item");
+        let resource = match &element.name[..] {
+            "string" => Resource::String(unescape(&element.content())),
+            "string-array" => Resource::Array(items().map(|e| unescape(&e.content())).collect()),
+            "plurals" => Resource::Plurals(
+                items()
+                    .map(|item| {
+                        let quantity = item.get("quantity").and_then(|q| q.parse().ok());
+                        let quantity = quantity.ok_or_else(|| item.error())?;
+                        Ok((quantity, unescape(&item.content())))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            ),
+            _ => continue,
+        };
+        let name = element.get("name").ok_or_else(|| element.error())?;
+        resources.insert(name.to_owned(), resource);
+    }
+    Ok(resources)
+}
+
+/// Returns the name of a string array and the index of an item of it in a msgctxt.
+fn split_item(context: &str) -> Option<(&str, usize)> {
+    let (name, index) = context.strip_suffix(']')?.split_once('[')?;
+    Some((name, index.parse().ok()?))
+}
+
+impl Catalog {
+    /// Reads the default string resources of an Android app, `res/values/strings.xml`,
+    /// as a template with a message for each resource.
+    ///
+    /// The name of each resource becomes the msgctxt of its message and its text the msgid.
+    /// The items of a string array are named by their index, e.g. `planets[2]`,
+    /// and the `one` and `other` quantities of plurals become the msgid and plural msgid.
+    /// Untranslatable resources are left out and inline markup is flattened to its text.
+    ///
+    /// Returns `XmlSyntax` if the file is malformed or not a resources file.
+    pub fn parse_android<R: Read>(reader: R) -> Result<Catalog, Error> {
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .build()?;
+        for (name, resource) in read(reader)? {
+            match resource {
+                Resource::String(text) => {
+                    catalog.insert(Message::new(text, Some(name), vec![String::new()]));
+                }
+                Resource::Array(items) => {
+                    for (i, item) in items.into_iter().enumerate() {
+                        let context = format!("{}[{}]", name, i);
+                        catalog.insert(Message::new(item, Some(context), vec![String::new()]));
+                    }
+                }
+                Resource::Plurals(mut items) => {
+                    let mut take = |category| {
+                        let i = items.iter().position(|(c, _)| *c == category)?;
+                        Some(items.remove(i).1)
+                    };
+                    let other = take(PluralCategory::Other).unwrap_or_default();
+                    let one = take(PluralCategory::One).unwrap_or_else(|| other.clone());
+                    let mut msg = Message::new(one, Some(name), vec![String::new(); 2]);
+                    msg.id_plural = Some(other);
+                    catalog.insert(msg);
+                }
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Translates this template, as read by `parse_android`,
+    /// with the string resources of an Android app for `language`,
+    /// e.g. those in `res/values-lt/strings.xml` for `lt`.
+    ///
+    /// Resources are matched to the messages by their names,
+    /// and messages without a resource of the same kind are left untranslated.
+    /// The quantities of plurals are mapped to the plural forms of the language
+    /// through the numbers that CLDR assigns to them.
+    ///
+    /// Returns `XmlSyntax` if the file is malformed or not a resources file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::Catalog;
+    ///
+    /// let source = r#"<resources>
+    ///     <string name="open">Open</string>
+    ///     <plurals name="files">
+    ///         <item quantity="one">%d file</item>
+    ///         <item quantity="other">%d files</item>
+    ///     </plurals>
+    /// </resources>"#;
+    /// let translation = r#"<resources>
+    ///     <string name="open">Atidaryti</string>
+    ///     <plurals name="files">
+    ///         <item quantity="one">%d failas</item>
+    ///         <item quantity="few">%d failai</item>
+    ///         <item quantity="other">%d failų</item>
+    ///     </plurals>
+    /// </resources>"#;
+    ///
+    /// let template = Catalog::parse_android(source.as_bytes()).unwrap();
+    /// let catalog = template
+    ///     .parse_android_translation(translation.as_bytes(), "lt")
+    ///     .unwrap();
+    /// assert_eq!(catalog.pgettext("open", "Open"), "Atidaryti");
+    /// assert_eq!(catalog.npgettext("files", "%d file", "%d files", 3), "%d failai");
+    /// ```
+    pub fn parse_android_translation<R: Read>(
+        &self,
+        reader: R,
+        language: &str,
+    ) -> Result<Catalog, Error> {
+        let resources = read(reader)?;
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .header("Language", language)
+            .build()?;
+        let nplurals = catalog.nplurals();
+        for msg in self.strings.values().filter(|msg| !msg.is_header()) {
+            let context = msg.context.as_deref().unwrap_or("");
+            let item = split_item(context);
+            let resource = resources.get(item.map_or(context, |(name, _)| name));
+            let translated = match (resource, item) {
+                (Some(Resource::Array(items)), Some((_, i))) => {
+                    vec![items.get(i).cloned().unwrap_or_default()]
+                }
+                (Some(Resource::String(text)), None) if msg.id_plural.is_none() => {
+                    vec![text.clone()]
+                }
+                (Some(Resource::Plurals(items)), None) if msg.id_plural.is_some() => {
+                    let mut translated = vec![String::new(); nplurals];
+                    for (category, text) in items {
+                        let form = category
+                            .sample(language)
+                            .map(|n| catalog.resolver.resolve(n));
+                        if let Some(form) = form.and_then(|form| translated.get_mut(form)) {
+                            *form = text.clone();
+                        }
+                    }
+                    translated
+                }
+                _ if msg.id_plural.is_some() => vec![String::new(); nplurals],
+                _ => vec![String::new()],
+            };
+            catalog.insert(Message {
+                translated,
+                ..msg.clone()
+            });
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the translations of the catalog out as Android string resources,
+    /// e.g. for `res/values-lt/strings.xml`.
+    ///
+    /// Messages are named by their msgctxt as `parse_android` names them,
+    /// so those without a context are left out.
+    /// The plural forms of the catalog are written for all the quantities
+    /// that CLDR defines for its `Language`.
+    /// Untranslated messages are left out too, so that Android falls back
+    /// to the default resources for them, but untranslated items of string arrays
+    /// that have other items translated are written with their msgid.
+    pub fn write_android<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let metadata = self.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let mut resources = BTreeMap::new();
+        let mut arrays = BTreeMap::new();
+        for msg in self.strings.values() {
+            let context = match msg.context {
+                Some(ref context) => context,
+                None => continue,
+            };
+            if let Some((name, i)) = split_item(context) {
+                let items = arrays.entry(name).or_insert_with(BTreeMap::new);
+                items.insert(i, msg);
+                continue;
+            }
+            if !msg.is_translated() {
+                continue;
+            }
+            let resource = match msg.id_plural {
+                Some(_) => {
+                    let mut plurals = Element::new("plurals").attr("name", context);
+                    for &category in PluralCategory::all() {
+                        let form = category.sample(language).map(|n| self.resolver.resolve(n));
+                        let text = form.and_then(|form| msg.get_translated(form));
+                        if let Some(text) = text {
+                            let item = Element::new("item").attr("quantity", category.as_str());
+                            plurals = plurals.child(item.text(&escape(text)));
+                        }
+                    }
+                    plurals
+                }
+                None => Element::new("string")
+                    .attr("name", context)
+                    .text(&escape(&msg.translated[0])),
+            };
+            resources.insert(&context[..], resource);
+        }
+        for (name, items) in arrays {
+            if !items.values().any(|msg| msg.is_translated()) {
+                continue;
+            }
+            let mut array = Element::new("string-array").attr("name", name);
+            for msg in items.values() {
+                let text = msg.get_translated(0).unwrap_or(&msg.id);
+                array = array.child(Element::new("item").text(&escape(text)));
+            }
+            resources.insert(name, array);
+        }
+
+        let root = resources
+            .into_iter()
+            .fold(Element::new("resources"), |root, (_, resource)| {
+                root.child(resource)
+            });
+        xml::write(&root, writer)
+    }
+}
+
+#[test]
+fn test_escape() {
+    for &(escaped, text) in &[
+        ("plain", "plain"),
+        ("It\\'s \\\"quoted\\\"", "It's \"quoted\""),
+        ("a\\nb\\tc \\\\ d", "a\nb\tc \\ d"),
+        ("\\@string", "@string"),
+        ("\\?attr a@b", "?attr a@b"),
+        ("\" two  spaces \"", " two  spaces "),
+    ] {
+        assert_eq!(escape(text), escaped);
+        assert_eq!(unescape(escaped), text);
+    }
+    assert_eq!(unescape("\n   a \n  b\t"), "a b");
+    assert_eq!(unescape("\"It's\"  here"), "It's here");
+    assert_eq!(unescape("\\u0105\\u12"), "ąu12");
+    assert_eq!(unescape("trailing\\"), "trailing");
+}
+
+#[test]
+fn test_android() {
+    let source = r#"<?xml version="1.0" encoding="utf-8"?>
+<resources xmlns:tools="http://schemas.android.com/tools">
+    <string name="app_name" translatable="false">Notes</string>
+    <string name="open">Open</string>
+    <string name="quit">Quit</string>
+    <string name="help">Don\'t <b>panic</b></string>
+    <string-array name="sizes">
+        <item>Small</item>
+        <item>Large</item>
+    </string-array>
+    <plurals name="notes">
+        <item quantity="one">%d note</item>
+        <item quantity="other">%d notes</item>
+    </plurals>
+</resources>
+"#;
+    let template = Catalog::parse_android(source.as_bytes()).unwrap();
+    assert_eq!(template.len(), 7);
+    let help = template.lookup(Some("help"), "Don't panic").unwrap();
+    assert!(!help.is_translated());
+    assert!(template.lookup(Some("sizes[1]"), "Large").is_some());
+    let notes = template.lookup(Some("notes"), "%d note").unwrap();
+    assert_eq!(notes.id_plural(), Some("%d notes"));
+    assert_eq!(notes.translated(), &["", ""]);
+
+    let translation = r#"<resources>
+    <string name="open">Atidaryti</string>
+    <string name="help">"Nepanikuok "</string>
+    <string name="unknown">Nežinomas</string>
+    <string-array name="sizes">
+        <item>Mažas</item>
+    </string-array>
+    <plurals name="notes">
+        <item quantity="one">%d pastaba</item>
+        <item quantity="few">%d pastabos</item>
+        <item quantity="many">%d pastabos</item>
+        <item quantity="other">%d pastabų</item>
+    </plurals>
+</resources>
+"#;
+    let catalog = template
+        .parse_android_translation(translation.as_bytes(), "lt")
+        .unwrap();
+    assert_eq!(catalog.metadata().language(), Some("lt"));
+    assert_eq!(catalog.len(), 7);
+    assert_eq!(catalog.pgettext("open", "Open"), "Atidaryti");
+    assert_eq!(catalog.pgettext("help", "Don't panic"), "Nepanikuok ");
+    assert_eq!(catalog.pgettext("quit", "Quit"), "Quit");
+    assert_eq!(catalog.pgettext("sizes[0]", "Small"), "Mažas");
+    assert_eq!(catalog.pgettext("sizes[1]", "Large"), "Large");
+    let notes = catalog.lookup(Some("notes"), "%d note").unwrap();
+    assert_eq!(
+        notes.translated(),
+        &["%d pastaba", "%d pastabos", "%d pastabų"]
+    );
+
+    let mut xml = vec![];
+    catalog.write_android(&mut xml).unwrap();
+    assert_eq!(
+        String::from_utf8(xml).unwrap(),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<resources>
+  <string name="help">"Nepanikuok "</string>
+  <plurals name="notes">
+    <item quantity="one">%d pastaba</item>
+    <item quantity="few">%d pastabos</item>
+    <item quantity="other">%d pastabų</item>
+  </plurals>
+  <string name="open">Atidaryti</string>
+  <string-array name="sizes">
+    <item>Mažas</item>
+    <item>Large</item>
+  </string-array>
+</resources>
+"#
+    );
+
+    for bad in &[
+        "<resource/>",
+        "<resources><string>a</string></resources>",
+        "<resources><plurals name=\"a\"><item quantity=\"some\">a</item></plurals></resources>",
+    ] {
+        let result = Catalog::parse_android(bad.as_bytes());
+        assert!(matches!(result, Err(Error::XmlSyntax(_))), "{}", bad);
+    }
+}
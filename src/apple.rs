@@ -0,0 +1,691 @@
+//! Conversion of catalogs from and to Apple `.strings` and `.stringsdict` files.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::Chars;
+
+use crate::key::{Key, KeyParts};
+use crate::xml::{self, Element};
+use crate::Error::{self, *};
+use crate::{Catalog, CatalogBuilder, Message, PluralCategory};
+
+/// The name of the variable that decides the plural form in written `.stringsdict` files.
+const VARIABLE: &str = "count";
+
+/// Decodes a `.strings` file, which is either UTF-8 or UTF-16 with a byte order mark.
+fn decode(bytes: Vec<u8>) -> Result<String, Error> {
+    let utf16 = |bytes: &[u8], unit: fn([u8; 2]) -> u16| {
+        let units = bytes
+            .chunks(2)
+            .map(|c| unit([c[0], *c.get(1).unwrap_or(&0)]));
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| DecodingError)
+    };
+    match bytes.get(..2) {
+        Some([0xff, 0xfe]) => utf16(&bytes[2..], u16::from_le_bytes),
+        Some([0xfe, 0xff]) => utf16(&bytes[2..], u16::from_be_bytes),
+        _ => {
+            let src = String::from_utf8(bytes).map_err(|_| DecodingError)?;
+            Ok(match src.strip_prefix('\u{feff}') {
+                Some(src) => src.to_owned(),
+                None => src,
+            })
+        }
+    }
+}
+
+/// Reads the entries of a `.strings` file.
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn error(&self) -> Error {
+        StringsSyntax(self.line)
+    }
+
+    /// Skips whitespace and comments, returning the last comment.
+    fn skip(&mut self) -> Result<Option<String>, Error> {
+        let mut comment = None;
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.next();
+                }
+                Some('/') => {
+                    self.next();
+                    let mut text = String::new();
+                    match self.next() {
+                        Some('/') => {
+                            while let Some(c) = self.next().filter(|&c| c != '\n') {
+                                text.push(c);
+                            }
+                        }
+                        Some('*') => loop {
+                            match self.next().ok_or_else(|| self.error())? {
+                                '*' if self.chars.peek() == Some(&'/') => {
+                                    self.next();
+                                    break;
+                                }
+                                c => text.push(c),
+                            }
+                        },
+                        _ => return Err(self.error()),
+                    }
+                    comment = Some(text.trim().to_owned());
+                }
+                _ => return Ok(comment),
+            }
+        }
+    }
+
+    /// Reads a quoted or unquoted string.
+    fn string(&mut self) -> Result<String, Error> {
+        let mut out = String::new();
+        if self.chars.peek() != Some(&'"') {
+            while let Some(&c) = self.chars.peek() {
+                if !c.is_alphanumeric() && !"_.$:/-".contains(c) {
+                    break;
+                }
+                out.push(c);
+                self.next();
+            }
+            return match out.is_empty() {
+                true => Err(self.error()),
+                false => Ok(out),
+            };
+        }
+        self.next();
+        loop {
+            match self.next().ok_or_else(|| self.error())? {
+                '"' => return Ok(out),
+                '\\' => match self.next().ok_or_else(|| self.error())? {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    '0' => out.push('\0'),
+                    'U' | 'u' => {
+                        let mut units = vec![self.hex()?];
+                        // a surrogate pair is written as two escapes
+                        if (0xd800..0xdc00).contains(&units[0]) {
+                            let escape = (self.next(), self.next());
+                            if !matches!(escape, (Some('\\'), Some('U' | 'u'))) {
+                                return Err(self.error());
+                            }
+                            units.push(self.hex()?);
+                        }
+                        let c = char::decode_utf16(units).next().unwrap();
+                        out.push(c.map_err(|_| self.error())?);
+                    }
+                    c => out.push(c),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    /// Reads the four hexadecimal digits of a `\U` escape.
+    fn hex(&mut self) -> Result<u16, Error> {
+        let digits = (0..4).filter_map(|_| self.next()).collect::<String>();
+        match u16::from_str_radix(&digits, 16) {
+            Ok(unit) if digits.len() == 4 => Ok(unit),
+            _ => Err(self.error()),
+        }
+    }
+
+    /// Expects the given character after optional whitespace and comments.
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        self.skip()?;
+        match self.next() {
+            Some(next) if next == c => Ok(()),
+            _ => Err(self.error()),
+        }
+    }
+}
+
+/// Returns the string escaped for a `.strings` file, with its quotes.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\U{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Returns the message for a key of a `.strings` or `.stringsdict` file.
+fn message(key: &str, translated: Vec<String>) -> Message {
+    let key = Key::from(key);
+    let (context, id) = key.parts();
+    let mut msg = Message::new(id, context, vec![]);
+    msg.translated = translated;
+    msg
+}
+
+/// Returns the messages of the catalog but the header, sorted by their keys.
+fn sorted(catalog: &Catalog) -> Vec<(&Key, &Message)> {
+    let mut messages = catalog
+        .strings
+        .iter()
+        .filter(|(key, _)| !key.is_header())
+        .collect::<Vec<_>>();
+    messages.sort_by(|a, b| a.0.cmp(b.0));
+    messages
+}
+
+/// Returns the keys and values of a plist `dict` element.
+fn entries(dict: &Element) -> Result<Vec<(String, &Element)>, Error> {
+    if dict.name != "dict" {
+        return Err(dict.error());
+    }
+    let mut elements = dict.elements();
+    let mut entries = vec![];
+    while let Some(key) = elements.next() {
+        let value = elements.next().filter(|_| key.name == "key");
+        entries.push((key.content(), value.ok_or_else(|| key.error())?));
+    }
+    Ok(entries)
+}
+
+/// Returns the `%#@name@` references to variables in a format string,
+/// with their byte ranges.
+fn variables(format: &str) -> Vec<(Range<usize>, &str)> {
+    let mut variables = vec![];
+    let mut start = 0;
+    while let Some(i) = format[start..].find('%').map(|i| start + i) {
+        start = i + 1;
+        let rest = &format[start..];
+        if rest.starts_with('%') {
+            start += 1;
+            continue;
+        }
+        // an optional position, e.g. `%1$#@files@`
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let rest = match rest[digits..].strip_prefix('$') {
+            Some(rest) if digits > 0 => rest,
+            _ => rest,
+        };
+        if let Some(name) = rest.strip_prefix("#@") {
+            if let Some(len) = name.find('@') {
+                let name_start = format.len() - name.len();
+                let end = name_start + len;
+                variables.push((i..end + 1, &format[name_start..end]));
+                start = end + 1;
+            }
+        }
+    }
+    variables
+}
+
+/// Returns the printf conversion of the first directive of a msgid,
+/// e.g. `ld` for `%ld files`, which `.stringsdict` files need for the variable.
+fn value_type(id: &str) -> String {
+    let mut rest = id;
+    while let Some(i) = rest.find('%') {
+        rest = &rest[i + 1..];
+        if let Some(after) = rest.strip_prefix('%') {
+            rest = after;
+            continue;
+        }
+        let spec = rest.trim_start_matches(|c: char| "0123456789$-+ #'.*".contains(c));
+        let length = spec
+            .find(|c: char| !"hlqLjzt".contains(c))
+            .unwrap_or(spec.len());
+        match spec[length..].chars().next() {
+            Some(c) if "diouxXeEfFgGaAcCsSp@".contains(c) => {
+                return format!("{}{}", &spec[..length], c);
+            }
+            _ => {}
+        }
+    }
+    "d".to_owned()
+}
+
+impl Catalog {
+    /// Reads a catalog from an Apple `.strings` file, e.g. `lt.lproj/Localizable.strings`.
+    ///
+    /// The keys of a `.strings` file are the msgids, as `NSLocalizedString` uses them,
+    /// joined to the msgctxt with `\u{4}` for messages with a context, like in MO files.
+    /// The comment before an entry becomes its extracted comment.
+    /// Plural forms are kept in `.stringsdict` files, see `parse_stringsdict`.
+    ///
+    /// Returns `StringsSyntax` if the file is malformed.
+    pub fn parse_strings<R: Read>(mut reader: R) -> Result<Catalog, Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let src = decode(bytes)?;
+        let mut parser = Parser {
+            chars: src.chars().peekable(),
+            line: 1,
+        };
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .build()?;
+        loop {
+            let comment = parser.skip()?;
+            if parser.chars.peek().is_none() {
+                return Ok(catalog);
+            }
+            let key = parser.string()?;
+            parser.skip()?;
+            let value = match parser.chars.peek() {
+                Some('=') => {
+                    parser.next();
+                    parser.skip()?;
+                    parser.string()?
+                }
+                _ => key.clone(),
+            };
+            parser.expect(';')?;
+            let mut msg = message(&key, vec![value]);
+            msg.extracted_comments = comment.into_iter().collect();
+            catalog.insert(msg);
+        }
+    }
+
+    /// Reads the plural messages of an Apple `.stringsdict` file for `language`,
+    /// e.g. `lt.lproj/Localizable.stringsdict` for `lt`.
+    ///
+    /// The keys are the msgids like in `.strings` files, and the rules
+    /// of the variable referenced by `NSStringLocalizedFormatKey` become the plural forms,
+    /// mapped through the numbers that CLDR assigns to each category in the language.
+    /// Each form is the whole format string with the rule substituted for the variable.
+    /// Since gettext messages have a single plural form, any other variable in the format
+    /// is substituted with its `other` rule.
+    /// The plural msgid is the same as the msgid, as `.stringsdict` files have no other.
+    ///
+    /// Returns `XmlSyntax` if the file is malformed or not a property list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{Catalog, MergeStrategy};
+    ///
+    /// let strings = "/* A menu item */\n\"Open\" = \"Atidaryti\";\n";
+    /// let stringsdict = r#"<plist version="1.0"><dict>
+    ///   <key>%d files</key>
+    ///   <dict>
+    ///     <key>NSStringLocalizedFormatKey</key>
+    ///     <string>Found %#@files@</string>
+    ///     <key>files</key>
+    ///     <dict>
+    ///       <key>NSStringFormatSpecTypeKey</key>
+    ///       <string>NSStringPluralRuleType</string>
+    ///       <key>NSStringFormatValueTypeKey</key>
+    ///       <string>d</string>
+    ///       <key>one</key>
+    ///       <string>%d failas</string>
+    ///       <key>few</key>
+    ///       <string>%d failai</string>
+    ///       <key>other</key>
+    ///       <string>%d failų</string>
+    ///     </dict>
+    ///   </dict>
+    /// </dict></plist>"#;
+    ///
+    /// let mut catalog = Catalog::parse_stringsdict(stringsdict.as_bytes(), "lt").unwrap();
+    /// let strings = Catalog::parse_strings(strings.as_bytes()).unwrap();
+    /// catalog.merge(strings, MergeStrategy::KeepExisting);
+    /// assert_eq!(catalog.gettext("Open"), "Atidaryti");
+    /// assert_eq!(catalog.ngettext("%d files", "%d files", 3), "Found %d failai");
+    /// ```
+    pub fn parse_stringsdict<R: Read>(reader: R, language: &str) -> Result<Catalog, Error> {
+        let root = xml::read(reader)?;
+        let dict = root.elements().next().filter(|_| root.name == "plist");
+        let dict = dict.ok_or_else(|| root.error())?;
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .header("Language", language)
+            .build()?;
+        let nplurals = catalog.nplurals();
+        for (key, value) in entries(dict)? {
+            let values = entries(value)?;
+            let get = |name: &str| values.iter().find(|(k, _)| k == name).map(|e| e.1);
+            let format = get("NSStringLocalizedFormatKey").ok_or_else(|| value.error())?;
+            let format = format.content();
+            let rules = |name: &str| -> Result<Vec<(String, String)>, Error> {
+                let rules = get(name).ok_or_else(|| value.error())?;
+                let rules = entries(rules)?.into_iter();
+                Ok(rules.map(|(k, v)| (k, v.content())).collect())
+            };
+            let other = |rules: &[(String, String)]| {
+                let other = rules.iter().find(|(name, _)| name == "other");
+                other.map(|(_, text)| text.clone())
+            };
+            let variables = variables(&format);
+            if variables.is_empty() {
+                return Err(value.error());
+            }
+            // the format string around the plural variable, with the others substituted
+            let (mut prefix, mut suffix) = (String::new(), String::new());
+            let mut end = 0;
+            for (i, (range, name)) in variables.iter().enumerate() {
+                if i == 0 {
+                    prefix.push_str(&format[..range.start]);
+                } else {
+                    suffix.push_str(&format[end..range.start]);
+                    suffix.push_str(&other(&rules(name)?).unwrap_or_default());
+                }
+                end = range.end;
+            }
+            suffix.push_str(&format[end..]);
+            let form = |text: &str| format!("{}{}{}", prefix, text, suffix);
+
+            let rules = rules(variables[0].1)?;
+            let mut translated = vec![String::new(); nplurals];
+            for (name, text) in &rules {
+                let category = name.parse::<PluralCategory>().ok();
+                let n = category.and_then(|c| c.sample(language));
+                let form_no = n.map(|n| catalog.resolver.resolve(n));
+                if let Some(slot) = form_no.and_then(|i| translated.get_mut(i)) {
+                    *slot = form(text);
+                }
+            }
+            // the forms without a category of their own get the `other` rule
+            if let Some(other) = other(&rules) {
+                for slot in translated.iter_mut().filter(|s| s.is_empty()) {
+                    *slot = form(&other);
+                }
+            }
+            let mut msg = message(&key, translated);
+            msg.id_plural = Some(msg.id.clone());
+            catalog.insert(msg);
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the translated messages of the catalog without a plural form
+    /// out as an Apple `.strings` file, keyed as `parse_strings` reads them.
+    ///
+    /// Extracted comments are written as comments before their entries.
+    /// Untranslated messages are left out, so that `NSLocalizedString` falls back
+    /// to the key, and so are plural messages, see `write_stringsdict`.
+    pub fn write_strings<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let mut out = String::new();
+        for (key, msg) in sorted(self) {
+            if msg.id_plural.is_some() || !msg.is_translated() {
+                continue;
+            }
+            if !msg.extracted_comments.is_empty() {
+                let comment = msg.extracted_comments.join("\n").replace("*/", "* /");
+                out.push_str(&format!("/* {} */\n", comment));
+            }
+            out.push_str(&format!(
+                "{} = {};\n\n",
+                quote(key.as_str()),
+                quote(&msg.translated[0])
+            ));
+        }
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes the translated plural messages of the catalog out
+    /// as an Apple `.stringsdict` file, keyed as `parse_stringsdict` reads them.
+    ///
+    /// Each message gets a single `count` variable with a rule for each category
+    /// that CLDR defines for the `Language` of the catalog,
+    /// taken from the plural form that the catalog uses for that category,
+    /// and an `other` rule with the last form if the language only uses it for fractions.
+    /// The type of the variable is the conversion of the first `printf` directive
+    /// of the msgid, or `d` if it has none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{Catalog, CatalogBuilder};
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .header("Language", "lt")
+    ///     .add_plural("%ld file", "%ld files", vec!["%ld failas", "%ld failai", "%ld failų"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut plist = vec![];
+    /// catalog.write_stringsdict(&mut plist).unwrap();
+    /// let plist = String::from_utf8(plist).unwrap();
+    /// assert!(plist.contains("<key>few</key>\n        <string>%ld failai</string>"));
+    /// ```
+    pub fn write_stringsdict<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let metadata = self.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let mut dict = Element::new("dict");
+        for (key, msg) in sorted(self) {
+            if msg.id_plural.is_none() || !msg.is_translated() {
+                continue;
+            }
+            let mut rules = BTreeMap::new();
+            for &category in PluralCategory::all() {
+                let form = category.sample(language).map(|n| self.resolver.resolve(n));
+                if let Some(text) = form.and_then(|form| msg.get_translated(form)) {
+                    rules.insert(category, text);
+                }
+            }
+            // Apple requires `other`, which some languages only use for fractions
+            let last = msg.translated.iter().rev().find(|s| !s.is_empty());
+            rules.entry(PluralCategory::Other).or_insert(last.unwrap());
+
+            let entry = |dict: Element, key: &str, value: Element| {
+                dict.child(Element::new("key").text(key)).child(value)
+            };
+            let string = |text: &str| Element::new("string").text(text);
+            let mut variable = Element::new("dict");
+            variable = entry(
+                variable,
+                "NSStringFormatSpecTypeKey",
+                string("NSStringPluralRuleType"),
+            );
+            variable = entry(
+                variable,
+                "NSStringFormatValueTypeKey",
+                string(&value_type(&msg.id)),
+            );
+            for (category, text) in rules {
+                variable = entry(variable, category.as_str(), string(text));
+            }
+            let mut value = Element::new("dict");
+            let format = format!("%#@{}@", VARIABLE);
+            value = entry(value, "NSStringLocalizedFormatKey", string(&format));
+            value = entry(value, VARIABLE, variable);
+            dict = entry(dict, key.as_str(), value);
+        }
+        let root = Element::new("plist").attr("version", "1.0").child(dict);
+        xml::write(&root, writer)
+    }
+}
+
+#[test]
+fn test_variables() {
+    let format = "%#@files@ in %2$#@folders@, 100%% %d";
+    let found = variables(format);
+    assert_eq!(found, [(0..9, "files"), (13..26, "folders")]);
+    assert_eq!(&format[13..26], "%2$#@folders@");
+    assert!(variables("%d %%#@x@ %#@unclosed").is_empty());
+
+    assert_eq!(value_type("%d file"), "d");
+    assert_eq!(value_type("100%% of %1$ld files"), "ld");
+    assert_eq!(value_type("%@ has %lu items"), "@");
+    assert_eq!(value_type("{} files"), "d");
+}
+
+#[test]
+fn test_strings() {
+    let src = "\u{feff}/* Xcode comment */\n\"Open\" = \"Atidaryti\";\n// a button\n\"menu\\U0004Quit\"=\"Baigti\";\n\n\"Say \\\"hi\\\"\\n\" = \"Sakyk \\\"labas\\\"\\n \\UD83D\\UDE00\";\nunquoted_key = value;\n\"Same\";\n";
+    let catalog = Catalog::parse_strings(src.as_bytes()).unwrap();
+    assert_eq!(catalog.len(), 6);
+    assert_eq!(catalog.gettext("Open"), "Atidaryti");
+    assert_eq!(
+        catalog.get("Open").unwrap().extracted_comments(),
+        &["Xcode comment"]
+    );
+    assert_eq!(catalog.pgettext("menu", "Quit"), "Baigti");
+    assert_eq!(
+        catalog
+            .lookup(Some("menu"), "Quit")
+            .unwrap()
+            .extracted_comments(),
+        &["a button"]
+    );
+    assert_eq!(catalog.gettext("Say \"hi\"\n"), "Sakyk \"labas\"\n 😀");
+    assert_eq!(catalog.gettext("unquoted_key"), "value");
+    assert_eq!(catalog.gettext("Same"), "Same");
+
+    let utf16 = "\"Open\" = \"Atidaryti\";"
+        .encode_utf16()
+        .flat_map(u16::to_be_bytes);
+    let utf16 = [0xfe, 0xff]
+        .iter()
+        .cloned()
+        .chain(utf16)
+        .collect::<Vec<_>>();
+    let catalog = Catalog::parse_strings(&utf16[..]).unwrap();
+    assert_eq!(catalog.gettext("Open"), "Atidaryti");
+
+    for (bad, line) in &[
+        ("\"a\" = \"b\"", 1),
+        ("\"a\" = \"b\";\n\"c\" = ;", 2),
+        ("\"a\" = \"b", 1),
+        ("\n/* unclosed", 2),
+        ("\"a\" = \"\\U12\";", 1),
+        ("\"a\" = \"\\UD83D\";", 1),
+    ] {
+        assert!(
+            matches!(Catalog::parse_strings(bad.as_bytes()), Err(StringsSyntax(l)) if l == *line),
+            "{}",
+            bad
+        );
+    }
+
+    let source = CatalogBuilder::new()
+        .add("Open", "Atidaryti")
+        .add("Untranslated", "")
+        .add_with_context("menu", "Quit", "Baigti")
+        .add("Say \"hi\"\n", "Sakyk \"labas\"\n")
+        .add_plural("File", "Files", vec!["Failas", "Failai"])
+        .build()
+        .unwrap();
+    let mut source = source;
+    source
+        .strings
+        .get_mut(&Key::from("Open"))
+        .unwrap()
+        .extracted_comments = vec!["a menu item */".to_owned()];
+    let mut strings = vec![];
+    source.write_strings(&mut strings).unwrap();
+    assert_eq!(
+        String::from_utf8(strings.clone()).unwrap(),
+        "/* a menu item * / */\n\"Open\" = \"Atidaryti\";\n\n\"Say \\\"hi\\\"\\n\" = \"Sakyk \\\"labas\\\"\\n\";\n\n\"menu\\U0004Quit\" = \"Baigti\";\n\n"
+    );
+    let catalog = Catalog::parse_strings(&strings[..]).unwrap();
+    assert_eq!(catalog.len(), 4);
+    assert_eq!(catalog.pgettext("menu", "Quit"), "Baigti");
+    assert_eq!(catalog.gettext("Say \"hi\"\n"), "Sakyk \"labas\"\n");
+}
+
+#[test]
+fn test_stringsdict() {
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>%d files in %d folders</key>
+    <dict>
+        <key>NSStringLocalizedFormatKey</key>
+        <string>%#@files@ in %#@folders@</string>
+        <key>files</key>
+        <dict>
+            <key>NSStringFormatSpecTypeKey</key>
+            <string>NSStringPluralRuleType</string>
+            <key>NSStringFormatValueTypeKey</key>
+            <string>d</string>
+            <key>one</key>
+            <string>%d plik</string>
+            <key>few</key>
+            <string>%d pliki</string>
+            <key>many</key>
+            <string>%d plików</string>
+            <key>other</key>
+            <string>%d pliku</string>
+        </dict>
+        <key>folders</key>
+        <dict>
+            <key>NSStringFormatSpecTypeKey</key>
+            <string>NSStringPluralRuleType</string>
+            <key>other</key>
+            <string>%d folderach</string>
+        </dict>
+    </dict>
+    <key>%ld days</key>
+    <dict>
+        <key>NSStringLocalizedFormatKey</key>
+        <string>%#@days@</string>
+        <key>days</key>
+        <dict>
+            <key>zero</key>
+            <string>No days</string>
+            <key>other</key>
+            <string>%ld dni</string>
+        </dict>
+    </dict>
+</dict>
+</plist>
+"#;
+    let catalog = Catalog::parse_stringsdict(src.as_bytes(), "pl").unwrap();
+    assert_eq!(catalog.metadata().language(), Some("pl"));
+    let files = catalog.get("%d files in %d folders").unwrap();
+    assert_eq!(files.id_plural(), Some("%d files in %d folders"));
+    assert_eq!(
+        files.translated(),
+        &[
+            "%d plik in %d folderach",
+            "%d pliki in %d folderach",
+            "%d plików in %d folderach"
+        ]
+    );
+    assert_eq!(
+        catalog.get("%ld days").unwrap().translated(),
+        &["%ld dni", "%ld dni", "%ld dni"]
+    );
+
+    let mut plist = vec![];
+    catalog.write_stringsdict(&mut plist).unwrap();
+    let plist = String::from_utf8(plist).unwrap();
+    assert!(plist.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\">\n  <dict>\n    <key>%d files in %d folders</key>\n    <dict>\n      <key>NSStringLocalizedFormatKey</key>\n      <string>%#@count@</string>\n      <key>count</key>\n      <dict>\n        <key>NSStringFormatSpecTypeKey</key>\n        <string>NSStringPluralRuleType</string>\n        <key>NSStringFormatValueTypeKey</key>\n        <string>d</string>\n        <key>one</key>\n        <string>%d plik in %d folderach</string>\n        <key>few</key>\n        <string>%d pliki in %d folderach</string>\n        <key>many</key>\n        <string>%d plików in %d folderach</string>\n        <key>other</key>\n        <string>%d plików in %d folderach</string>\n      </dict>\n    </dict>\n"));
+    assert!(plist.contains("<key>NSStringFormatValueTypeKey</key>\n        <string>ld</string>"));
+    let read = Catalog::parse_stringsdict(plist.as_bytes(), "pl").unwrap();
+    assert_eq!(read.strings, catalog.strings);
+
+    for bad in &[
+        "<dict/>",
+        "<plist><array/></plist>",
+        "<plist><dict><key>a</key></dict></plist>",
+        "<plist><dict><string>a</string><dict/></dict></plist>",
+        "<plist><dict><key>a</key><dict/></dict></plist>",
+        "<plist><dict><key>a</key><dict><key>NSStringLocalizedFormatKey</key><string>%d</string></dict></dict></plist>",
+        "<plist><dict><key>a</key><dict><key>NSStringLocalizedFormatKey</key><string>%#@x@</string></dict></dict></plist>",
+    ] {
+        let result = Catalog::parse_stringsdict(bad.as_bytes(), "en");
+        assert!(matches!(result, Err(XmlSyntax(_))), "{}", bad);
+    }
+}
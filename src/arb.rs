@@ -0,0 +1,462 @@
+//! Conversion of catalogs from and to the ARB files of Flutter.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+
+use crate::json::{self, Member, Value};
+use crate::Error::{self, *};
+use crate::{Catalog, CatalogBuilder, Message, PluralCategory};
+
+/// An ICU plural in the value of a message,
+/// e.g. `Found {count, plural, one{{count} file} other{{count} files}}`.
+#[derive(Debug, PartialEq)]
+struct Plural<'a> {
+    prefix: &'a str,
+    variable: &'a str,
+    branches: Vec<(&'a str, &'a str)>,
+    suffix: &'a str,
+}
+
+impl<'a> Plural<'a> {
+    /// Returns the first plural in a value, if it has a well-formed one.
+    fn parse(value: &'a str) -> Option<Self> {
+        let mut start = 0;
+        while let Some(open) = value[start..].find('{').map(|i| start + i) {
+            start = open + 1;
+            if let Some(plural) = Self::parse_at(value, open) {
+                return Some(plural);
+            }
+        }
+        None
+    }
+
+    fn parse_at(value: &'a str, open: usize) -> Option<Self> {
+        let rest = value[open + 1..].trim_start();
+        let len = rest.find(|c: char| !c.is_alphanumeric() && c != '_')?;
+        let variable = &rest[..len];
+        let mut rest = rest[len..].trim_start().strip_prefix(',')?.trim_start();
+        rest = rest
+            .strip_prefix("plural")?
+            .trim_start()
+            .strip_prefix(',')?;
+        let mut branches = vec![];
+        loop {
+            rest = rest.trim_start();
+            if let Some(suffix) = rest.strip_prefix('}') {
+                if variable.is_empty() || branches.is_empty() {
+                    return None;
+                }
+                let prefix = &value[..open];
+                return Some(Plural {
+                    prefix,
+                    variable,
+                    branches,
+                    suffix,
+                });
+            }
+            let len = rest.find(|c: char| c.is_whitespace() || c == '{' || c == '}')?;
+            let selector = &rest[..len];
+            rest = rest[len..].trim_start().strip_prefix('{')?;
+            let end = closing(rest)?;
+            branches.push((selector, &rest[..end]));
+            rest = &rest[end + 1..];
+        }
+    }
+
+    /// Returns the whole text of each category of the plural.
+    ///
+    /// The exact selectors `=0`, `=1` and `=2` stand for the `zero`, `one`
+    /// and `two` categories when the plural has no branch for those.
+    fn forms(&self) -> Vec<(PluralCategory, String)> {
+        let mut forms = vec![];
+        let exact = [
+            ("=0", PluralCategory::Zero),
+            ("=1", PluralCategory::One),
+            ("=2", PluralCategory::Two),
+        ];
+        for &(selector, text) in &self.branches {
+            let category = match selector.parse::<PluralCategory>() {
+                Ok(category) => category,
+                Err(_) => match exact.iter().find(|&&(s, _)| s == selector) {
+                    Some(&(_, category)) => category,
+                    None => continue,
+                },
+            };
+            let text = format!("{}{}{}", self.prefix, text, self.suffix);
+            match forms.iter().position(|&(c, _)| c == category) {
+                // a branch for the category itself wins over an exact one
+                Some(i) if !selector.starts_with('=') => forms[i] = (category, text),
+                Some(_) => {}
+                None => forms.push((category, text)),
+            }
+        }
+        forms
+    }
+
+    /// Returns the text of a category, if the plural has it.
+    fn get(&self, category: PluralCategory) -> Option<String> {
+        let mut forms = self.forms().into_iter();
+        forms.find(|&(c, _)| c == category).map(|(_, text)| text)
+    }
+}
+
+/// Returns the index of the brace closing the text, which may have nested braces.
+fn closing(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the first `{name}` placeholder of a text,
+/// which is what the plural of a written message is about.
+fn placeholder(text: &str) -> Option<&str> {
+    let mut rest = text;
+    while let Some(i) = rest.find('{') {
+        rest = &rest[i + 1..];
+        let len = rest.find('}')?;
+        let name = &rest[..len];
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Some(name);
+        }
+    }
+    None
+}
+
+/// Reads the messages of an ARB file, with their descriptions.
+fn read(reader: impl Read) -> Result<HashMap<String, (String, Option<String>)>, Error> {
+    let members = match json::read(reader)? {
+        Value::Object(members) => members,
+        _ => return Err(JsonSyntax(1)),
+    };
+    let mut descriptions = HashMap::new();
+    let mut messages = HashMap::new();
+    for member in members {
+        match (member.name.strip_prefix('@'), member.value) {
+            (Some(name), ref attributes) if !name.starts_with('@') => {
+                let description = attributes.get("description").and_then(Value::as_str);
+                if let Some(description) = description {
+                    descriptions.insert(name.to_owned(), description.to_owned());
+                }
+            }
+            // global attributes like `@@locale`
+            (Some(_), _) => {}
+            (None, Value::String(value)) => {
+                messages.insert(member.name, value);
+            }
+            (None, _) => return Err(JsonSyntax(member.line)),
+        }
+    }
+    Ok(messages
+        .into_iter()
+        .map(|(name, value)| {
+            let description = descriptions.remove(&name);
+            (name, (value, description))
+        })
+        .collect())
+}
+
+impl Catalog {
+    /// Reads the template ARB file of a Flutter app, e.g. `lib/l10n/app_en.arb`,
+    /// with a message for each of its messages.
+    ///
+    /// The name of each message becomes the msgctxt of its message and its value the msgid,
+    /// and its description becomes the extracted comment.
+    /// For a value with an ICU plural, the `one` and `other` branches, with the text
+    /// around the plural, become the msgid and plural msgid.
+    ///
+    /// Returns `JsonSyntax` if the file is malformed or not an ARB file.
+    pub fn parse_arb<R: Read>(reader: R) -> Result<Catalog, Error> {
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .build()?;
+        for (name, (value, description)) in read(reader)? {
+            let mut msg = match Plural::parse(&value) {
+                Some(plural) => {
+                    let other = plural.get(PluralCategory::Other).unwrap_or_default();
+                    let one = plural.get(PluralCategory::One);
+                    let mut msg = Message::new(
+                        one.unwrap_or_else(|| other.clone()),
+                        Some(name),
+                        vec![String::new(); 2],
+                    );
+                    msg.id_plural = Some(other);
+                    msg
+                }
+                None => Message::new(value, Some(name), vec![String::new()]),
+            };
+            msg.extracted_comments = description.into_iter().collect();
+            catalog.insert(msg);
+        }
+        Ok(catalog)
+    }
+
+    /// Translates this template, as read by `parse_arb`,
+    /// with the ARB file of a Flutter app for `language`, e.g. `lib/l10n/app_lt.arb` for `lt`.
+    ///
+    /// Messages are matched by their names, and those missing from the file
+    /// are left untranslated. The branches of ICU plurals are mapped to the plural forms
+    /// of the language through the numbers that CLDR assigns to their categories,
+    /// and the forms without a branch of their own get the `other` branch.
+    /// A plural message translated without a plural gets its value for all the forms.
+    ///
+    /// Returns `JsonSyntax` if the file is malformed or not an ARB file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::Catalog;
+    ///
+    /// let template = r#"{
+    ///   "@@locale": "en",
+    ///   "open": "Open",
+    ///   "@open": {"description": "A menu item"},
+    ///   "files": "{count, plural, =1{One file} other{{count} files}}"
+    /// }"#;
+    /// let translation = r#"{
+    ///   "@@locale": "lt",
+    ///   "open": "Atidaryti",
+    ///   "files": "{count, plural, one{{count} failas} few{{count} failai} other{{count} failų}}"
+    /// }"#;
+    ///
+    /// let template = Catalog::parse_arb(template.as_bytes()).unwrap();
+    /// let catalog = template.parse_arb_translation(translation.as_bytes(), "lt").unwrap();
+    /// assert_eq!(catalog.pgettext("open", "Open"), "Atidaryti");
+    /// assert_eq!(catalog.npgettext("files", "One file", "{count} files", 3), "{count} failai");
+    /// ```
+    pub fn parse_arb_translation<R: Read>(
+        &self,
+        reader: R,
+        language: &str,
+    ) -> Result<Catalog, Error> {
+        let messages = read(reader)?;
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .header("Language", language)
+            .build()?;
+        let nplurals = catalog.nplurals();
+        for msg in self.strings.values().filter(|msg| !msg.is_header()) {
+            let value = messages.get(msg.context.as_deref().unwrap_or(""));
+            let translated = match value {
+                Some((value, _)) if msg.id_plural.is_some() => match Plural::parse(value) {
+                    Some(plural) => {
+                        let mut translated = vec![String::new(); nplurals];
+                        let forms = plural.forms();
+                        for (category, text) in &forms {
+                            let form = category
+                                .sample(language)
+                                .map(|n| catalog.resolver.resolve(n));
+                            if let Some(form) = form.and_then(|form| translated.get_mut(form)) {
+                                *form = text.clone();
+                            }
+                        }
+                        let other = forms.iter().find(|&&(c, _)| c == PluralCategory::Other);
+                        if let Some((_, other)) = other {
+                            for form in translated.iter_mut().filter(|s| s.is_empty()) {
+                                *form = other.clone();
+                            }
+                        }
+                        translated
+                    }
+                    None => vec![value.clone(); nplurals],
+                },
+                Some((value, _)) => vec![value.clone()],
+                None if msg.id_plural.is_some() => vec![String::new(); nplurals],
+                None => vec![String::new()],
+            };
+            catalog.insert(Message {
+                translated,
+                ..msg.clone()
+            });
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the translations of the catalog out as an ARB file,
+    /// e.g. `lib/l10n/app_lt.arb`, with `@@locale` set to its `Language`.
+    ///
+    /// Messages are named by their msgctxt as `parse_arb` names them,
+    /// so those without a context are left out, and so are untranslated messages,
+    /// so that Flutter falls back to the template for them.
+    /// Extracted comments are written as descriptions.
+    /// Plural messages are written as an ICU plural with a branch for each category
+    /// that CLDR defines for the language, taken from the plural form that the catalog
+    /// uses for that category, and an `other` branch with the last form
+    /// if the language only uses it for fractions. The plural is about the first
+    /// placeholder of the plural msgid, or `count` if it has none.
+    pub fn write_arb<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let metadata = self.metadata();
+        let language = metadata.language();
+        let mut messages = BTreeMap::new();
+        for msg in self.strings.values() {
+            match msg.context {
+                Some(ref name) if msg.is_translated() => {
+                    messages.insert(&name[..], msg);
+                }
+                _ => {}
+            }
+        }
+
+        let mut members = vec![];
+        if let Some(language) = language {
+            members.push(Member::new("@@locale", Value::String(language.to_owned())));
+        }
+        for (name, msg) in messages {
+            let value = match msg.id_plural {
+                Some(ref id_plural) => {
+                    let mut branches = vec![];
+                    for &category in PluralCategory::all() {
+                        let n = category.sample(language.unwrap_or("en"));
+                        let form = n.map(|n| self.resolver.resolve(n));
+                        if let Some(text) = form.and_then(|form| msg.get_translated(form)) {
+                            branches.push((category, text));
+                        }
+                    }
+                    // ICU requires `other`, which some languages only use for fractions
+                    if !branches.iter().any(|&(c, _)| c == PluralCategory::Other) {
+                        let last = msg.translated.iter().rev().find(|s| !s.is_empty());
+                        branches.push((PluralCategory::Other, last.unwrap()));
+                    }
+                    let variable = placeholder(id_plural).unwrap_or("count");
+                    let mut value = format!("{{{}, plural,", variable);
+                    for (category, text) in branches {
+                        value.push_str(&format!(" {}{{{}}}", category.as_str(), text));
+                    }
+                    value.push('}');
+                    value
+                }
+                None => msg.translated[0].clone(),
+            };
+            members.push(Member::new(name, Value::String(value)));
+            if !msg.extracted_comments.is_empty() {
+                let description = Value::String(msg.extracted_comments.join("\n"));
+                let attributes = Value::Object(vec![Member::new("description", description)]);
+                members.push(Member::new(&format!("@{}", name), attributes));
+            }
+        }
+        json::write(&Value::Object(members), writer)
+    }
+}
+
+#[test]
+fn test_plural() {
+    let value =
+        "Found {count, plural, =0{no files} one{{count} file} other {{count} {kind} files}}!";
+    let plural = Plural::parse(value).unwrap();
+    assert_eq!(
+        plural,
+        Plural {
+            prefix: "Found ",
+            variable: "count",
+            branches: vec![
+                ("=0", "no files"),
+                ("one", "{count} file"),
+                ("other", "{count} {kind} files"),
+            ],
+            suffix: "!",
+        }
+    );
+    assert_eq!(
+        plural.forms(),
+        [
+            (PluralCategory::Zero, "Found no files!".to_owned()),
+            (PluralCategory::One, "Found {count} file!".to_owned()),
+            (
+                PluralCategory::Other,
+                "Found {count} {kind} files!".to_owned()
+            ),
+        ]
+    );
+    let plural = Plural::parse("{n,plural,=1{a}one{b}other{c}}").unwrap();
+    assert_eq!(plural.get(PluralCategory::One).as_deref(), Some("b"));
+
+    for plain in &[
+        "No plural {count}",
+        "{count, select, male{him} other{them}}",
+        "{count, plural, one{unclosed}",
+        "{count, plural, one{a} other}",
+        "{count, plural,}",
+        "{, plural, other{a}}",
+    ] {
+        assert_eq!(Plural::parse(plain), None, "{}", plain);
+    }
+
+    assert_eq!(placeholder("{count} files in {dir}"), Some("count"));
+    assert_eq!(placeholder("{ not one } {n}"), Some("n"));
+    assert_eq!(placeholder("files"), None);
+}
+
+#[test]
+fn test_arb() {
+    let template = r#"{
+  "@@locale": "en",
+  "@@last_modified": "2024-01-01",
+  "open": "Open",
+  "@open": {
+    "description": "A menu item",
+    "placeholders": {}
+  },
+  "quit": "Quit",
+  "notes": "{n, plural, one{{n} note} other{{n} notes}}"
+}"#;
+    let template = Catalog::parse_arb(template.as_bytes()).unwrap();
+    assert_eq!(template.len(), 4);
+    let open = template.lookup(Some("open"), "Open").unwrap();
+    assert_eq!(open.extracted_comments(), &["A menu item"]);
+    assert!(!open.is_translated());
+    let notes = template.lookup(Some("notes"), "{n} note").unwrap();
+    assert_eq!(notes.id_plural(), Some("{n} notes"));
+
+    let translation = r#"{
+  "@@locale": "pl",
+  "open": "Otwórz",
+  "unknown": "Nieznany",
+  "notes": "{n, plural, one{{n} notatka} few{{n} notatki} other{{n} notatek}}"
+}"#;
+    let catalog = template
+        .parse_arb_translation(translation.as_bytes(), "pl")
+        .unwrap();
+    assert_eq!(catalog.len(), 4);
+    assert_eq!(catalog.pgettext("open", "Open"), "Otwórz");
+    assert_eq!(catalog.pgettext("quit", "Quit"), "Quit");
+    let notes = catalog.lookup(Some("notes"), "{n} note").unwrap();
+    assert_eq!(
+        notes.translated(),
+        &["{n} notatka", "{n} notatki", "{n} notatek"]
+    );
+
+    let mut arb = vec![];
+    catalog.write_arb(&mut arb).unwrap();
+    assert_eq!(
+        String::from_utf8(arb).unwrap(),
+        r#"{
+  "@@locale": "pl",
+  "notes": "{n, plural, one{{n} notatka} few{{n} notatki} many{{n} notatek} other{{n} notatek}}",
+  "open": "Otwórz",
+  "@open": {
+    "description": "A menu item"
+  }
+}
+"#
+    );
+
+    let japanese = r#"{"notes": "{n}件のメモ"}"#;
+    let catalog = template
+        .parse_arb_translation(japanese.as_bytes(), "ja")
+        .unwrap();
+    assert_eq!(
+        catalog.npgettext("notes", "{n} note", "{n} notes", 5),
+        "{n}件のメモ"
+    );
+
+    for bad in &["[]", "{\"a\": 1}", "{\"a\": "] {
+        let result = Catalog::parse_arb(bad.as_bytes());
+        assert!(matches!(result, Err(JsonSyntax(_))), "{}", bad);
+    }
+}
@@ -0,0 +1,153 @@
+//! Compilation of PO files into MO files from build scripts,
+//! so that no `msgfmt` is needed to build the program.
+//!
+//! # Examples
+//!
+//! In `build.rs`:
+//!
+//! ```ignore
+//! fn main() {
+//!     gettext::build::Compiler::new("po").index("catalogs.rs").run().unwrap();
+//! }
+//! ```
+//!
+//! In the program, the generated index lists the embedded catalogs by locale:
+//!
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/catalogs.rs"));
+//!
+//! fn catalog(locale: &str) -> Option<gettext::Catalog> {
+//!     let (_, mo) = CATALOGS.iter().find(|(l, _)| *l == locale)?;
+//!     gettext::Catalog::parse(*mo).ok()
+//! }
+//! ```
+
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, ParseOptions};
+
+/// Compiler turns every `.po` file of a directory into an `.mo` file
+/// named after its locale, e.g. `po/fr.po` into `fr.mo`.
+#[derive(Clone, Debug)]
+pub struct Compiler {
+    src_dir: PathBuf,
+    out_dir: Option<PathBuf>,
+    index: Option<String>,
+}
+
+impl Compiler {
+    /// Creates a compiler for the PO files in `src_dir`.
+    pub fn new<P: Into<PathBuf>>(src_dir: P) -> Self {
+        Compiler {
+            src_dir: src_dir.into(),
+            out_dir: None,
+            index: None,
+        }
+    }
+
+    /// Sets the directory to write the MO files to,
+    /// `OUT_DIR` of the build script by default.
+    pub fn out_dir<P: Into<PathBuf>>(mut self, out_dir: P) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Also generates a Rust source file with the given name in the output directory,
+    /// defining `CATALOGS: &[(&str, &[u8])]`,
+    /// a list of the locales and their embedded MO files sorted by locale.
+    pub fn index<T: Into<String>>(mut self, file_name: T) -> Self {
+        self.index = Some(file_name.into());
+        self
+    }
+
+    /// Compiles all the PO files, returning the paths of the written MO files.
+    ///
+    /// Tells Cargo to run the build script again when the PO files change.
+    /// Returns the `Err` variant if `OUT_DIR` is needed but not set,
+    /// or if reading, parsing or writing any of the files fails.
+    pub fn run(&self) -> Result<Vec<PathBuf>, Error> {
+        let out_dir = match self.out_dir {
+            Some(ref dir) => dir.clone(),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?,
+        };
+        println!("cargo:rerun-if-changed={}", self.src_dir.display());
+
+        let mut sources = fs::read_dir(&self.src_dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        sources.retain(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "po"));
+        sources.sort();
+
+        fs::create_dir_all(&out_dir)?;
+        let mut written = vec![];
+        for source in sources {
+            println!("cargo:rerun-if-changed={}", source.display());
+            let catalog = ParseOptions::new()
+                .skip_fuzzy(true)
+                .parse_po(File::open(&source)?)?;
+            let target = out_dir.join(source.with_extension("mo").file_name().unwrap());
+            let mut mo = vec![];
+            catalog.write_mo(&mut mo)?;
+            fs::write(&target, mo)?;
+            written.push(target);
+        }
+
+        if let Some(ref name) = self.index {
+            fs::write(out_dir.join(name), index_source(&written))?;
+        }
+        Ok(written)
+    }
+}
+
+/// Returns the source of the index listing the given MO files.
+fn index_source(mo_files: &[PathBuf]) -> String {
+    let mut src = String::from("/// The embedded catalogs, by locale.\n");
+    src.push_str("pub const CATALOGS: &[(&str, &[u8])] = &[\n");
+    for path in mo_files {
+        let locale = path.file_stem().unwrap().to_string_lossy();
+        src.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            locale,
+            absolute(path)
+        ));
+    }
+    src.push_str("];\n");
+    src
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+#[test]
+fn test_compiler() {
+    use crate::Catalog;
+
+    let dir = env::temp_dir().join(format!("gettext-build-{}", std::process::id()));
+    let (src_dir, out_dir) = (dir.join("po"), dir.join("out"));
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::copy("test_cases/integration.po", src_dir.join("lt.po")).unwrap();
+    fs::copy("test_cases/2.po", src_dir.join("de.po")).unwrap();
+    fs::write(src_dir.join("README"), "not a catalog").unwrap();
+
+    let written = Compiler::new(&src_dir)
+        .out_dir(&out_dir)
+        .index("catalogs.rs")
+        .run()
+        .unwrap();
+    assert_eq!(written, vec![out_dir.join("de.mo"), out_dir.join("lt.mo")]);
+    let catalog = Catalog::parse(File::open(out_dir.join("lt.mo")).unwrap()).unwrap();
+    assert_eq!(catalog.gettext("existent"), "egzistuojantis");
+    let index = fs::read_to_string(out_dir.join("catalogs.rs")).unwrap();
+    assert!(index.contains("\n    (\"de\", include_bytes!("));
+    assert!(index.contains("\n    (\"lt\", include_bytes!("));
+
+    fs::copy("test_cases/invalid_utf8.po", src_dir.join("xx.po")).unwrap();
+    assert!(Compiler::new(&src_dir).out_dir(&out_dir).run().is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
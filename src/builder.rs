@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use crate::metadata::parse_metadata;
+use crate::plurals::{PluralFallback, Resolver};
+use crate::Error::{self, *};
+use crate::{Catalog, Message};
+
+/// CatalogBuilder allows constructing a catalog in code
+/// rather than parsing it out of a file.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::CatalogBuilder;
+///
+/// let catalog = CatalogBuilder::new()
+///     .header("Language", "lt")
+///     .plural_forms("nplurals=2; plural=n != 1;")
+///     .add("Text", "Tekstas")
+///     .add_plural("Image", "Images", vec!["Paveikslėlis", "Paveikslėliai"])
+///     .add_with_context("menu", "Open", "Atidaryti")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(catalog.gettext("Text"), "Tekstas");
+/// assert_eq!(catalog.ngettext("Image", "Images", 5), "Paveikslėliai");
+/// assert_eq!(catalog.pgettext("menu", "Open"), "Atidaryti");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CatalogBuilder {
+    headers: Vec<(String, String)>,
+    messages: Vec<Message>,
+    plural_resolver: Option<Resolver>,
+    plural_fallback: PluralFallback,
+}
+
+impl CatalogBuilder {
+    /// Returns a new builder with no messages and no headers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the metadata header `name` to `value`,
+    /// replacing an earlier value of the same header.
+    pub fn header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        let (name, value) = (name.into(), value.into());
+        match self.headers.iter_mut().find(|(k, _)| *k == name) {
+            Some(header) => header.1 = value,
+            None => self.headers.push((name, value)),
+        }
+        self
+    }
+
+    /// Sets the `Plural-Forms` header, e.g. `nplurals=2; plural=n != 1;`,
+    /// which decides the plural form to use for a message.
+    pub fn plural_forms<T: Into<String>>(self, plural_forms: T) -> Self {
+        self.header("Plural-Forms", plural_forms)
+    }
+
+    /// Uses the given function or closure for deciding the plural form for a message
+    /// instead of the formula in the `Plural-Forms` header.
+    pub fn plural_resolver<F>(mut self, plural: F) -> Self
+    where
+        F: Fn(u64) -> usize + Send + Sync + 'static,
+    {
+        self.plural_resolver = Some(Resolver::Closure(Arc::new(plural)));
+        self
+    }
+
+    /// Sets the string returned by plural lookups
+    /// when the plural form for a number of objects is not translated.
+    pub fn plural_fallback(mut self, fallback: PluralFallback) -> Self {
+        self.plural_fallback = fallback;
+        self
+    }
+
+    /// Adds a singular message with its translation.
+    pub fn add<K: Into<String>, V: Into<String>>(self, msg_id: K, translated: V) -> Self {
+        self.push(Message::new(msg_id.into(), None, vec![translated.into()]))
+    }
+
+    /// Adds a plural message with all of its translated forms.
+    pub fn add_plural<K, P, V>(self, msg_id: K, msg_id_plural: P, translated: Vec<V>) -> Self
+    where
+        K: Into<String>,
+        P: Into<String>,
+        V: Into<String>,
+    {
+        let translated = translated.into_iter().map(Into::into).collect();
+        let mut msg = Message::new(msg_id.into(), None, translated);
+        msg.id_plural = Some(msg_id_plural.into());
+        self.push(msg)
+    }
+
+    /// Adds a singular message in the context `msg_context` with its translation.
+    pub fn add_with_context<C, K, V>(self, msg_context: C, msg_id: K, translated: V) -> Self
+    where
+        C: Into<String>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let msg = Message::new(
+            msg_id.into(),
+            Some(msg_context.into()),
+            vec![translated.into()],
+        );
+        self.push(msg)
+    }
+
+    /// Adds a plural message in the context `msg_context`
+    /// with all of its translated forms.
+    pub fn add_plural_with_context<C, K, P, V>(
+        self,
+        msg_context: C,
+        msg_id: K,
+        msg_id_plural: P,
+        translated: Vec<V>,
+    ) -> Self
+    where
+        C: Into<String>,
+        K: Into<String>,
+        P: Into<String>,
+        V: Into<String>,
+    {
+        let translated = translated.into_iter().map(Into::into).collect();
+        let mut msg = Message::new(msg_id.into(), Some(msg_context.into()), translated);
+        msg.id_plural = Some(msg_id_plural.into());
+        self.push(msg)
+    }
+
+    fn push(mut self, msg: Message) -> Self {
+        self.messages.push(msg);
+        self
+    }
+
+    /// Builds the catalog.
+    ///
+    /// Messages added later replace earlier ones with the same id and context.
+    /// Returns the `Err` variant if a header contains a line break
+    /// or the `Plural-Forms` header cannot be parsed.
+    pub fn build(self) -> Result<Catalog, Error> {
+        let mut catalog = Catalog::new();
+        catalog.plural_fallback = self.plural_fallback;
+
+        if !self.headers.is_empty() {
+            let mut blob = String::new();
+            for (name, value) in &self.headers {
+                if name.contains(['\n', ':']) || value.contains('\n') {
+                    return Err(MalformedMetadata);
+                }
+                blob.push_str(name);
+                blob.push_str(": ");
+                blob.push_str(value);
+                blob.push('\n');
+            }
+            if self.plural_resolver.is_none() {
+                if let Some(resolver) = parse_metadata(&blob)?.plural_resolver()? {
+                    catalog.resolver = resolver;
+                }
+            }
+            catalog.insert(Message::new(String::new(), None, vec![blob]));
+        }
+        if let Some(resolver) = self.plural_resolver {
+            catalog.resolver = resolver;
+        }
+
+        for msg in self.messages {
+            catalog.insert(msg);
+        }
+        Ok(catalog)
+    }
+}
+
+#[test]
+fn test_catalog_builder() {
+    use crate::key::Key;
+
+    let catalog = CatalogBuilder::new()
+        .header("Language", "lt")
+        .header("Project-Id-Version", "1.0")
+        .header("Language", "de")
+        .plural_forms("nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;")
+        .add("Text", "Tekstas")
+        .add_plural("Test", "Tests", vec!["Singular", "Plural 1", "Plural 2"])
+        .add_plural_with_context("ctx", "Test", "Tests", vec!["S", "P1", "P2"])
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        catalog.strings[&Key::from("")].translated,
+        vec!["Language: de\nProject-Id-Version: 1.0\nPlural-Forms: nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;\n"]
+    );
+    assert_eq!(catalog.gettext("Text"), "Tekstas");
+    assert_eq!(catalog.ngettext("Test", "Tests", 1), "Singular");
+    assert_eq!(catalog.ngettext("Test", "Tests", 2), "Plural 1");
+    assert_eq!(catalog.ngettext("Test", "Tests", 5), "Plural 2");
+    assert_eq!(catalog.npgettext("ctx", "Test", "Tests", 2), "P1");
+    assert_eq!(
+        catalog.strings[&Key::from("Test")].id_plural.as_deref(),
+        Some("Tests")
+    );
+}
+
+#[test]
+fn test_catalog_builder_without_headers() {
+    use crate::key::Key;
+
+    fn resolver(_: u64) -> usize {
+        1
+    }
+
+    let catalog = CatalogBuilder::new()
+        .plural_resolver(resolver)
+        .add_with_context("ctx", "Text", "Tekstas")
+        .add_plural("File", "Files", vec!["Failas", "Failai"])
+        .build()
+        .unwrap();
+    assert!(!catalog.strings.contains_key(&Key::from("")));
+    assert_eq!(catalog.pgettext("ctx", "Text"), "Tekstas");
+    assert_eq!(catalog.ngettext("File", "Files", 1), "Failai");
+}
+
+#[test]
+fn test_catalog_builder_errors() {
+    macro_rules! assert_variant {
+        ($value:expr, $variant:path) => {
+            match $value {
+                Err($variant) => (),
+                other => panic!("Expected {:?}, got {:?}", $variant, other),
+            }
+        };
+    }
+
+    assert_variant!(
+        CatalogBuilder::new().header("Language", "lt\nde").build(),
+        MalformedMetadata
+    );
+    assert_variant!(
+        CatalogBuilder::new().header("Lang: uage", "lt").build(),
+        MalformedMetadata
+    );
+    assert_variant!(
+        CatalogBuilder::new()
+            .plural_forms("nplurals=2; plural=n ?")
+            .build(),
+        PluralParsing
+    );
+}
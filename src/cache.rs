@@ -0,0 +1,375 @@
+//! A compact binary form of parsed catalogs, which loads without parsing.
+
+use std::collections::HashMap;
+use std::str;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::key::Key;
+use crate::parser::default_resolver;
+use crate::plurals::{Ast, Compiled, Operator, PluralFallback, Resolver, MAX_DEPTH};
+use crate::Error::{self, *};
+use crate::{Catalog, Flag, Message};
+
+const MAGIC: &[u8; 4] = b"GTXC";
+
+/// The version of the cache format, bumped on every incompatible change.
+const VERSION: u32 = 6;
+
+const OPERATORS: [Operator; 9] = [
+    Operator::Equal,
+    Operator::NotEqual,
+    Operator::GreaterOrEqual,
+    Operator::SmallerOrEqual,
+    Operator::Greater,
+    Operator::Smaller,
+    Operator::And,
+    Operator::Or,
+    Operator::Modulo,
+];
+
+const FALLBACKS: [PluralFallback; 4] = [
+    PluralFallback::Original,
+    PluralFallback::Singular,
+    PluralFallback::Plural,
+    PluralFallback::LastForm,
+];
+
+impl Catalog {
+    /// Returns the catalog in a compact, versioned binary form
+    /// that `from_cache` loads back without parsing an MO file
+    /// or parsing and simplifying its plural formula again.
+    /// The simplified formula is stored as a tree, which is compiled when it is loaded,
+    /// as compiled code cannot be stored.
+    ///
+    /// Plural resolvers given as functions or closures cannot be stored,
+    /// so catalogs using one are loaded back with the default `n != 1` formula.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{Catalog, CatalogBuilder};
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .plural_forms("nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;")
+    ///     .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let cache = catalog.to_cache();
+    /// let loaded = Catalog::from_cache(&cache).unwrap();
+    /// assert_eq!(loaded.ngettext("File", "Files", 5), "Failų");
+    /// ```
+    pub fn to_cache(&self) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        write_u32(&mut buf, VERSION);
+        buf.push(self.show_header as u8);
+        buf.push(
+            FALLBACKS
+                .iter()
+                .position(|&f| f == self.plural_fallback)
+                .unwrap() as u8,
+        );
+        match self.resolver {
+            Resolver::Expr(ref expr) => {
+                buf.push(1);
+                write_ast(&mut buf, expr.ast());
+            }
+            _ => buf.push(0),
+        }
+        for messages in &[&self.strings, &self.obsolete] {
+            write_u32(&mut buf, messages.len() as u32);
+            for msg in messages.values() {
+                write_message(&mut buf, msg);
+            }
+        }
+        buf
+    }
+
+    /// Loads a catalog written by `to_cache`.
+    ///
+    /// Returns `BadMagic` if `bytes` is not a cache
+    /// and `BadCacheVersion` if it was written by another version of this crate.
+    pub fn from_cache(bytes: &[u8]) -> Result<Catalog, Error> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(BadMagic);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(BadCacheVersion(version));
+        }
+
+        let mut catalog = Catalog::new();
+        catalog.show_header = reader.flag()?;
+        catalog.plural_fallback = *FALLBACKS.get(reader.u8()? as usize).ok_or(DecodingError)?;
+        catalog.resolver = match reader.u8()? {
+            0 => Resolver::Function(default_resolver),
+            // the formula was simplified before it was stored
+            1 => Resolver::Expr(Compiled::new(reader.ast(0)?)),
+            _ => return Err(DecodingError),
+        };
+        catalog.strings = reader.messages()?;
+        catalog.obsolete = reader.messages()?;
+        if !reader.bytes.is_empty() {
+            return Err(DecodingError);
+        }
+        Ok(catalog)
+    }
+}
+
+fn write_u32(buf: &mut Vec<u8>, n: u32) {
+    let mut bytes = [0; 4];
+    LittleEndian::write_u32(&mut bytes, n);
+    buf.extend_from_slice(&bytes);
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_u32(buf, s.len() as u32);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn write_opt_str(buf: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        Some(s) => {
+            buf.push(1);
+            write_str(buf, s);
+        }
+        None => buf.push(0),
+    }
+}
+
+fn write_message(buf: &mut Vec<u8>, msg: &Message) {
+    write_str(buf, &msg.id);
+    write_opt_str(buf, msg.context.as_deref());
+    write_opt_str(buf, msg.id_plural.as_deref());
+    write_strs(buf, &msg.translated);
+    write_strs(buf, &msg.translator_comments);
+    write_strs(buf, &msg.extracted_comments);
+    write_strs(buf, &msg.references);
+    let flags = msg.flags.iter().map(|flag| flag.as_str().to_owned());
+    write_strs(buf, &flags.collect::<Vec<_>>());
+    write_opt_str(buf, msg.previous_context.as_deref());
+    write_opt_str(buf, msg.previous_id.as_deref());
+    write_opt_str(buf, msg.previous_id_plural.as_deref());
+}
+
+fn write_strs(buf: &mut Vec<u8>, strs: &[String]) {
+    write_u32(buf, strs.len() as u32);
+    for s in strs {
+        write_str(buf, s);
+    }
+}
+
+/// Writes the expression in prefix order, with a tag byte for each node.
+fn write_ast(buf: &mut Vec<u8>, ast: &Ast) {
+    match *ast {
+        Ast::Ternary(ref cond, ref ok, ref nok) => {
+            buf.push(0);
+            write_ast(buf, cond);
+            write_ast(buf, ok);
+            write_ast(buf, nok);
+        }
+        Ast::N => buf.push(1),
+        Ast::Integer(x) => {
+            buf.push(2);
+            let mut bytes = [0; 8];
+            LittleEndian::write_u64(&mut bytes, x);
+            buf.extend_from_slice(&bytes);
+        }
+        Ast::Op(ref op, ref lhs, ref rhs) => {
+            buf.push(3);
+            buf.push(OPERATORS.iter().position(|o| o == op).unwrap() as u8);
+            write_ast(buf, lhs);
+            write_ast(buf, rhs);
+        }
+        Ast::Not(ref val) => {
+            buf.push(4);
+            write_ast(buf, val);
+        }
+    }
+}
+
+/// Reader consumes the values of a cache from the front of a slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < n {
+            return Err(Eof);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn flag(&mut self) -> Result<bool, Error> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodingError),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(LittleEndian::read_u32(self.take(4)?))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        Ok(str::from_utf8(bytes).map_err(|_| DecodingError)?.to_owned())
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>, Error> {
+        let count = self.u32()? as usize;
+        (0..count).map(|_| self.string()).collect()
+    }
+
+    fn opt_string(&mut self) -> Result<Option<String>, Error> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.string()?)),
+            _ => Err(DecodingError),
+        }
+    }
+
+    fn messages(&mut self) -> Result<HashMap<Key, Message>, Error> {
+        let count = self.u32()? as usize;
+        // every message takes at least 29 bytes, so a crafted count cannot over-allocate
+        let mut messages = HashMap::with_capacity(count.min(self.bytes.len() / 29));
+        for _ in 0..count {
+            let id = self.string()?;
+            let context = self.opt_string()?;
+            let id_plural = self.opt_string()?;
+            let translated = self.strings()?;
+            let translator_comments = self.strings()?;
+            let extracted_comments = self.strings()?;
+            let references = self.strings()?;
+            let flags = self.strings()?.iter().map(|f| Flag::from(&f[..])).collect();
+            let previous_context = self.opt_string()?;
+            let previous_id = self.opt_string()?;
+            let previous_id_plural = self.opt_string()?;
+            let msg = Message {
+                id,
+                context,
+                id_plural,
+                translated,
+                translator_comments,
+                extracted_comments,
+                references,
+                flags,
+                previous_context,
+                previous_id,
+                previous_id_plural,
+            };
+            messages.insert(Key::new(msg.context.as_deref(), &msg.id), msg);
+        }
+        Ok(messages)
+    }
+
+    fn ast(&mut self, depth: usize) -> Result<Ast, Error> {
+        // every stored formula was parsed, so it is at most as deep as the parser allows
+        if depth >= MAX_DEPTH {
+            return Err(PluralParsing);
+        }
+        let tag = self.u8()?;
+        let mut next = || self.ast(depth + 1).map(Box::new);
+        Ok(match tag {
+            0 => Ast::Ternary(next()?, next()?, next()?),
+            1 => Ast::N,
+            2 => Ast::Integer(LittleEndian::read_u64(self.take(8)?)),
+            3 => {
+                let op = OPERATORS.get(self.u8()? as usize).ok_or(PluralParsing)?;
+                let mut next = || self.ast(depth + 1).map(Box::new);
+                Ast::Op(op.clone(), next()?, next()?)
+            }
+            4 => Ast::Not(next()?),
+            _ => return Err(PluralParsing),
+        })
+    }
+}
+
+#[test]
+fn test_cache() {
+    use crate::{CatalogBuilder, PluralFallback};
+
+    let catalog = CatalogBuilder::new()
+        .header("Language", "lt")
+        .plural_forms("nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2);")
+        .plural_fallback(PluralFallback::LastForm)
+        .add("Text", "Tekstas")
+        .add_plural("File", "Files", vec!["Failas", "Failai", ""])
+        .add_with_context("menu", "Open", "Atidaryti")
+        .build()
+        .unwrap();
+    let mut old = catalog.clone();
+    old.set(None, "Quit", vec!["Išeiti"]);
+    let mut catalog = old.update(&catalog);
+    for msg in catalog.strings.values_mut().filter(|msg| msg.id == "Text") {
+        msg.flags.insert(Flag::Fuzzy);
+        msg.flags.insert(Flag::Other("python-format".to_owned()));
+        msg.translator_comments = vec!["reviewed".to_owned(), String::new()];
+        msg.extracted_comments = vec!["a label".to_owned()];
+        msg.references = vec!["src/main.rs:1".to_owned()];
+        msg.previous_id = Some("Test".to_owned());
+    }
+
+    let cache = catalog.to_cache();
+    let loaded = Catalog::from_cache(&cache).unwrap();
+    assert_eq!(loaded.strings, catalog.strings);
+    assert!(loaded.get("Text").unwrap().is_fuzzy());
+    assert_eq!(loaded.obsolete, catalog.obsolete);
+    assert_eq!(loaded.plural_fallback, PluralFallback::LastForm);
+    for n in 0..200 {
+        assert_eq!(loaded.resolver.resolve(n), catalog.resolver.resolve(n));
+    }
+    assert_eq!(loaded.ngettext("File", "Files", 21), "Failas");
+    assert_eq!(loaded.ngettext("File", "Files", 10), "Failai");
+    assert_eq!(loaded.metadata().language(), Some("lt"));
+    assert_eq!(loaded.gettext(""), "");
+
+    let empty = Catalog::from_cache(&Catalog::new().to_cache()).unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.ngettext("File", "Files", 1), "File");
+    assert_eq!(empty.resolver.resolve(2), 1);
+
+    assert!(matches!(Catalog::from_cache(b"GTX"), Err(Eof)));
+    assert!(matches!(
+        Catalog::from_cache(b"MO\0\0\x01\0\0\0"),
+        Err(BadMagic)
+    ));
+    let mut newer = cache.clone();
+    newer[4] = 7;
+    assert!(matches!(
+        Catalog::from_cache(&newer),
+        Err(BadCacheVersion(7))
+    ));
+    for len in 0..cache.len() {
+        assert!(Catalog::from_cache(&cache[..len]).is_err());
+    }
+    let mut trailing = cache;
+    trailing.push(0);
+    assert!(matches!(Catalog::from_cache(&trailing), Err(DecodingError)));
+
+    let mut deep = MAGIC.to_vec();
+    write_u32(&mut deep, VERSION);
+    deep.extend_from_slice(&[0, 0, 1]);
+    deep.extend(std::iter::repeat_n(4, MAX_DEPTH));
+    assert!(matches!(Catalog::from_cache(&deep), Err(PluralParsing)));
+
+    let deepest = Ast::parse(&format!("{}n", "!".repeat(MAX_DEPTH - 1))).unwrap();
+    let catalog = Catalog {
+        resolver: Resolver::Expr(Compiled::new(deepest)),
+        ..Catalog::new()
+    };
+    let loaded = Catalog::from_cache(&catalog.to_cache()).unwrap();
+    assert_eq!(loaded.resolver.resolve(0), 1);
+    assert_eq!(loaded.resolver.resolve(5), 0);
+}
@@ -0,0 +1,345 @@
+use std::borrow::Cow;
+
+use crate::locale::fallbacks;
+use crate::{untranslated, Catalog, Error, ToPluralNumber};
+
+/// ChainedCatalog looks up translations in a list of catalogs in order,
+/// e.g. `de_AT`, then `de`, falling back to the original strings
+/// if none of the catalogs has a translation.
+///
+/// Each catalog decides the plural form using its own plural formula.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{CatalogBuilder, ChainedCatalog};
+///
+/// let de_at = CatalogBuilder::new().add("January", "Jänner").build().unwrap();
+/// let de = CatalogBuilder::new()
+///     .add("January", "Januar")
+///     .add("February", "Februar")
+///     .build()
+///     .unwrap();
+///
+/// let chain = ChainedCatalog::new(vec![de_at, de]);
+/// assert_eq!(chain.gettext("January"), "Jänner");
+/// assert_eq!(chain.gettext("February"), "Februar");
+/// assert_eq!(chain.gettext("March"), "March");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ChainedCatalog {
+    catalogs: Vec<Catalog>,
+}
+
+impl ChainedCatalog {
+    /// Creates a chain of the given catalogs, in order of preference.
+    pub fn new(catalogs: Vec<Catalog>) -> Self {
+        ChainedCatalog { catalogs }
+    }
+
+    /// Creates a chain from a colon-separated list of locales
+    /// in order of preference, like the `LANGUAGE` environment variable,
+    /// e.g. `de_AT:de:en`, loading the catalog of each locale with `load`.
+    ///
+    /// Each locale is followed by its less specific fallbacks,
+    /// e.g. `pt_BR` for `pt_BR.UTF-8` and then `pt`, as returned by `locale::fallbacks`.
+    /// Locales for which `load` returns `Ok(None)` are skipped;
+    /// the first error returned by `load` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use std::env;
+    /// use std::fs::File;
+    /// use gettext::{Catalog, ChainedCatalog};
+    ///
+    /// let list = env::var("LANGUAGE").unwrap_or_default();
+    /// let chain = ChainedCatalog::from_language_list(&list, |locale| {
+    ///     match File::open(format!("locale/{}/LC_MESSAGES/myapp.mo", locale)) {
+    ///         Ok(file) => Catalog::parse(file).map(Some),
+    ///         Err(_) => Ok(None),
+    ///     }
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn from_language_list<F>(list: &str, mut load: F) -> Result<Self, Error>
+    where
+        F: FnMut(&str) -> Result<Option<Catalog>, Error>,
+    {
+        let mut chain = ChainedCatalog::default();
+        let mut tried = vec![];
+        for locale in list.split(':').filter(|l| !l.is_empty()) {
+            for locale in fallbacks(locale) {
+                if tried.contains(&locale) {
+                    continue;
+                }
+                if let Some(catalog) = load(&locale)? {
+                    chain.push(catalog);
+                }
+                tried.push(locale);
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Appends a catalog as the least preferred one.
+    pub fn push(&mut self, catalog: Catalog) {
+        self.catalogs.push(catalog);
+    }
+
+    /// Returns the catalogs of the chain, in order of preference.
+    pub fn catalogs(&self) -> &[Catalog] {
+        &self.catalogs
+    }
+
+    /// Returns the singular translation of `msg_id` from the first catalog
+    /// that has one, or `msg_id` itself if none of them does.
+    pub fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str {
+        self.find_translation(None, msg_id, None)
+            .unwrap_or_else(|| self.missing(None, msg_id))
+    }
+
+    /// Returns the plural translation of `msg_id` from the first catalog
+    /// that has the correct plural form for the number `n` of objects.
+    /// Returns msg_id if no catalog has a translation and `n == 1`,
+    /// msg_id_plural otherwise, unless the first catalog has another `PluralFallback`.
+    pub fn ngettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.find_translation(None, msg_id, Some(n))
+            .unwrap_or_else(|| self.fallback(None, msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context` from the first catalog that has one,
+    /// or `msg_id` itself if none of them does.
+    pub fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str {
+        self.find_translation(Some(msg_context), msg_id, None)
+            .unwrap_or_else(|| self.missing(Some(msg_context), msg_id))
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context` from the first catalog
+    /// that has the correct plural form for the number `n` of objects.
+    /// Returns msg_id if no catalog has a translation and `n == 1`,
+    /// msg_id_plural otherwise, unless the first catalog has another `PluralFallback`.
+    pub fn npgettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.find_translation(Some(msg_context), msg_id, Some(n))
+            .unwrap_or_else(|| self.fallback(Some(msg_context), msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the singular translation of `msg_id` from the first catalog that has one,
+    /// or `None` if none of them does.
+    pub fn try_gettext(&self, msg_id: &str) -> Option<&str> {
+        self.find_translation(None, msg_id, None)
+    }
+
+    /// Returns the plural translation of `msg_id` from the first catalog
+    /// that has the correct plural form for the number `n` of objects,
+    /// or `None` if none of them does.
+    pub fn try_ngettext<N: ToPluralNumber>(&self, msg_id: &str, n: N) -> Option<&str> {
+        self.find_translation(None, msg_id, Some(n.to_plural_number()))
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context` from the first catalog that has one,
+    /// or `None` if none of them does.
+    pub fn try_pgettext(&self, msg_context: &str, msg_id: &str) -> Option<&str> {
+        self.find_translation(Some(msg_context), msg_id, None)
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context` from the first catalog
+    /// that has the correct plural form for the number `n` of objects,
+    /// or `None` if none of them does.
+    pub fn try_npgettext<N: ToPluralNumber>(
+        &self,
+        msg_context: &str,
+        msg_id: &str,
+        n: N,
+    ) -> Option<&str> {
+        self.find_translation(Some(msg_context), msg_id, Some(n.to_plural_number()))
+    }
+
+    /// Returns the same string as `gettext` as a `Cow`,
+    /// so that post-processing helpers can own a modified string
+    /// without allocating when it is left untouched.
+    pub fn gettext_cow<'a>(&'a self, msg_id: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(self.gettext(msg_id))
+    }
+
+    /// Returns the same string as `ngettext` as a `Cow`.
+    pub fn ngettext_cow<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> Cow<'a, str> {
+        Cow::Borrowed(self.ngettext(msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the same string as `pgettext` as a `Cow`.
+    pub fn pgettext_cow<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(self.pgettext(msg_context, msg_id))
+    }
+
+    /// Returns the same string as `npgettext` as a `Cow`.
+    pub fn npgettext_cow<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> Cow<'a, str> {
+        Cow::Borrowed(self.npgettext(msg_context, msg_id, msg_id_plural, n))
+    }
+
+    fn find_translation(
+        &self,
+        msg_context: Option<&str>,
+        msg_id: &str,
+        n: Option<u64>,
+    ) -> Option<&str> {
+        self.catalogs
+            .iter()
+            .find_map(|c| c.find_translation(msg_context, msg_id, n))
+    }
+
+    fn missing<'a>(&self, msg_context: Option<&str>, msg_id: &'a str) -> &'a str {
+        match self.catalogs.first() {
+            Some(catalog) => catalog.missing(msg_context, msg_id),
+            None => msg_id,
+        }
+    }
+
+    fn fallback<'a>(
+        &'a self,
+        msg_context: Option<&str>,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        match self.catalogs.first() {
+            Some(catalog) => catalog.fallback(msg_context, msg_id, msg_id_plural, n),
+            None => untranslated(msg_id, msg_id_plural, n),
+        }
+    }
+}
+
+impl From<Vec<Catalog>> for ChainedCatalog {
+    fn from(catalogs: Vec<Catalog>) -> Self {
+        ChainedCatalog::new(catalogs)
+    }
+}
+
+impl Catalog {
+    /// Consumes the catalog, returning a chain
+    /// that falls back to `fallback` for missing translations.
+    pub fn with_fallback(self, fallback: Catalog) -> ChainedCatalog {
+        ChainedCatalog::new(vec![self, fallback])
+    }
+}
+
+#[test]
+fn test_chained_catalog() {
+    use crate::CatalogBuilder;
+
+    let lt = CatalogBuilder::new()
+        .plural_forms("nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2);")
+        .add_plural("File", "Files", vec!["Failas", "Failai", ""])
+        .add_with_context("menu", "Open", "Atidaryti")
+        .build()
+        .unwrap();
+    let en = CatalogBuilder::new()
+        .add_plural("File", "Files", vec!["File (en)", "Files (en)"])
+        .add_plural_with_context("menu", "Tab", "Tabs", vec!["Tab (en)", "Tabs (en)"])
+        .add("Close", "Close (en)")
+        .build()
+        .unwrap();
+    let chain = lt.with_fallback(en);
+
+    assert_eq!(chain.catalogs().len(), 2);
+    assert_eq!(chain.gettext("Close"), "Close (en)");
+    assert_eq!(chain.gettext("Missing"), "Missing");
+    assert_eq!(chain.ngettext("File", "Files", 1), "Failas");
+    assert_eq!(chain.ngettext("File", "Files", 2), "Failai");
+    // the form is missing in the first catalog, the second one uses its own formula
+    assert_eq!(chain.ngettext("File", "Files", 10), "Files (en)");
+    assert_eq!(chain.ngettext("Dir", "Dirs", 1), "Dir");
+    assert_eq!(chain.ngettext("Dir", "Dirs", 10), "Dirs");
+    assert_eq!(chain.pgettext("menu", "Open"), "Atidaryti");
+    assert_eq!(chain.pgettext("menu", "Close"), "Close");
+    assert_eq!(chain.npgettext("menu", "Tab", "Tabs", 1), "Tab (en)");
+    assert_eq!(chain.npgettext("menu", "Tab", "Tabs", 3), "Tabs (en)");
+    assert_eq!(chain.npgettext("other", "Tab", "Tabs", 3), "Tabs");
+
+    assert_eq!(chain.try_gettext("Close"), Some("Close (en)"));
+    assert_eq!(chain.try_gettext("Missing"), None);
+    assert_eq!(chain.try_ngettext("File", 10), Some("Files (en)"));
+    assert_eq!(chain.try_ngettext("Dir", 1), None);
+    assert_eq!(chain.try_pgettext("menu", "Open"), Some("Atidaryti"));
+    assert_eq!(chain.try_pgettext("menu", "Close"), None);
+    assert_eq!(chain.try_npgettext("menu", "Tab", 3), Some("Tabs (en)"));
+    assert_eq!(chain.try_npgettext("other", "Tab", 3), None);
+
+    assert_eq!(chain.ngettext_cow("File", "Files", 10), "Files (en)");
+    assert_eq!(chain.npgettext_cow("other", "Tab", "Tabs", 3), "Tabs");
+
+    let mut lt = chain.catalogs()[0].clone();
+    lt.plural_fallback = crate::PluralFallback::Singular;
+    let chain = ChainedCatalog::new(vec![lt, chain.catalogs()[1].clone()]);
+    assert_eq!(chain.npgettext("other", "Tab", "Tabs", 3), "Tab");
+
+    let empty = ChainedCatalog::default();
+    assert_eq!(empty.ngettext("Tab", "Tabs", 1), "Tab");
+}
+
+#[test]
+fn test_from_language_list() {
+    use crate::CatalogBuilder;
+
+    let catalog = |locale: &str| {
+        let translation = match locale {
+            "de_AT" => "Jänner",
+            "de" => "Januar",
+            "en" => "January (en)",
+            _ => return Ok(None),
+        };
+        let builder = CatalogBuilder::new().add("January", translation);
+        Ok(Some(builder.add(locale, "loaded").build()?))
+    };
+    let mut requested = vec![];
+    let chain = ChainedCatalog::from_language_list("de_AT:lt::de:en:de", |locale| {
+        requested.push(locale.to_owned());
+        catalog(locale)
+    })
+    .unwrap();
+    assert_eq!(requested, &["de_AT", "de", "lt", "en"]);
+    assert_eq!(chain.catalogs().len(), 3);
+    assert_eq!(chain.gettext("January"), "Jänner");
+    assert_eq!(chain.gettext("en"), "loaded");
+
+    let chain = ChainedCatalog::from_language_list("lt:de", catalog).unwrap();
+    assert_eq!(chain.gettext("January"), "Januar");
+    // the region falls back to the language
+    let chain = ChainedCatalog::from_language_list("de_CH.UTF-8:en", catalog).unwrap();
+    assert_eq!(chain.gettext("January"), "Januar");
+    assert_eq!(chain.catalogs().len(), 2);
+    assert!(ChainedCatalog::from_language_list("", catalog)
+        .unwrap()
+        .catalogs()
+        .is_empty());
+    let failing = ChainedCatalog::from_language_list("de:en", |_| Err(Error::Eof));
+    assert!(matches!(failing, Err(Error::Eof)));
+}
@@ -0,0 +1,309 @@
+//! Conversion of catalogs from and to CSV files for spreadsheets.
+
+use std::io::{Read, Write};
+
+use crate::key::Key;
+use crate::Error::{self, *};
+use crate::{Catalog, CatalogBuilder, Flag, Message};
+
+/// A column of a CSV file made from a catalog, see `Catalog::to_csv`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CsvColumn {
+    /// The msgctxt of the message, empty if it has none
+    Context,
+    /// The msgid of the message
+    Id,
+    /// The plural msgid of the message, empty if it has none
+    IdPlural,
+    /// The given translated form of the message, the only one for messages without a plural
+    Form(usize),
+    /// The comments of translators, one per line
+    TranslatorComments,
+    /// The comments extracted from the sources, one per line
+    ExtractedComments,
+    /// The references to the sources, separated by spaces
+    References,
+    /// The flags, separated by commas like on the `#,` line of a PO entry
+    Flags,
+}
+
+impl CsvColumn {
+    /// Returns the heading of the column, named after the PO keyword or comment.
+    fn heading(self) -> String {
+        match self {
+            CsvColumn::Context => "msgctxt".to_owned(),
+            CsvColumn::Id => "msgid".to_owned(),
+            CsvColumn::IdPlural => "msgid_plural".to_owned(),
+            CsvColumn::Form(i) => format!("msgstr[{}]", i),
+            CsvColumn::TranslatorComments => "translator comments".to_owned(),
+            CsvColumn::ExtractedComments => "extracted comments".to_owned(),
+            CsvColumn::References => "references".to_owned(),
+            CsvColumn::Flags => "flags".to_owned(),
+        }
+    }
+
+    /// Returns the field of the column for a message.
+    fn field(self, msg: &Message) -> String {
+        match self {
+            CsvColumn::Context => msg.context.clone().unwrap_or_default(),
+            CsvColumn::Id => msg.id.clone(),
+            CsvColumn::IdPlural => msg.id_plural.clone().unwrap_or_default(),
+            CsvColumn::Form(i) => msg.translated.get(i).cloned().unwrap_or_default(),
+            CsvColumn::TranslatorComments => msg.translator_comments.join("\n"),
+            CsvColumn::ExtractedComments => msg.extracted_comments.join("\n"),
+            CsvColumn::References => msg.references.join(" "),
+            CsvColumn::Flags => {
+                let flags = msg.flags.iter().map(Flag::as_str);
+                flags.collect::<Vec<_>>().join(", ")
+            }
+        }
+    }
+}
+
+/// Appends a field to a row, quoting it if it has to be.
+fn push_field(out: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
+    }
+}
+
+/// Reads the rows of a CSV file, as described in RFC 4180.
+fn rows(src: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut line = 1;
+    let mut chars = src.chars().peekable();
+    // whether the current field was quoted, so that an empty quoted field is kept
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                quoted = true;
+                let start = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => return Err(CsvSyntax(start)),
+                    }
+                }
+                match chars.peek() {
+                    Some(',') | Some('\r') | Some('\n') | None => {}
+                    Some(_) => return Err(CsvSyntax(line)),
+                }
+            }
+            ',' => {
+                row.push(field.split_off(0));
+                quoted = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                if quoted || !field.is_empty() || !row.is_empty() {
+                    row.push(field.split_off(0));
+                    rows.push(row.split_off(0));
+                }
+                quoted = false;
+                line += 1;
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted || !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+impl Catalog {
+    /// Writes the messages of the catalog out as a CSV file with the given columns,
+    /// e.g. for translators working in a spreadsheet.
+    ///
+    /// The first row has the headings of the columns, and each message but the header
+    /// follows in a row of its own, sorted like the strings of an MO file.
+    /// Untranslated messages are written too, with empty forms.
+    /// Fields are quoted as described in RFC 4180, so that they can span lines,
+    /// and the file starts with a byte order mark, which spreadsheets take as a sign of UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{CatalogBuilder, CsvColumn};
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .add("Open", "Atidaryti")
+    ///     .add("Say \"hi\",\nplease", "")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut csv = vec![];
+    /// catalog.to_csv(&mut csv, &[CsvColumn::Id, CsvColumn::Form(0)]).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(csv).unwrap(),
+    ///     "\u{feff}msgid,msgstr[0]\r\nOpen,Atidaryti\r\n\"Say \"\"hi\"\",\nplease\",\r\n",
+    /// );
+    /// ```
+    pub fn to_csv<W: Write>(&self, mut writer: W, columns: &[CsvColumn]) -> Result<(), Error> {
+        let mut messages = self
+            .strings
+            .iter()
+            .filter(|(key, _)| !key.is_header())
+            .collect::<Vec<(&Key, &Message)>>();
+        messages.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut out = String::from("\u{feff}");
+        let mut push_row = |fields: Vec<String>| {
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_field(&mut out, field);
+            }
+            out.push_str("\r\n");
+        };
+        push_row(columns.iter().map(|c| c.heading()).collect());
+        for (_, msg) in messages {
+            push_row(columns.iter().map(|c| c.field(msg)).collect());
+        }
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a catalog from a CSV file with the given columns, as `to_csv` writes them.
+    ///
+    /// The first row is taken for headings and skipped, and so are rows without a msgid.
+    /// Messages with a plural msgid get as many forms as the last `Form` column has,
+    /// and missing fields are empty. The catalog has no other header
+    /// than the `Content-Type`, so that it can be merged into one with the headers
+    /// of the language.
+    ///
+    /// Returns `CsvSyntax` if a quoted field is malformed.
+    pub fn from_csv<R: Read>(mut reader: R, columns: &[CsvColumn]) -> Result<Catalog, Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let src = String::from_utf8(bytes).map_err(|_| DecodingError)?;
+        let src = src.strip_prefix('\u{feff}').unwrap_or(&src);
+        let nforms = columns
+            .iter()
+            .filter_map(|c| match *c {
+                CsvColumn::Form(i) => Some(i + 1),
+                _ => None,
+            })
+            .max();
+
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .build()?;
+        for row in rows(src)?.into_iter().skip(1) {
+            let mut msg = Message::new("", None, vec![]);
+            for (&column, field) in columns.iter().zip(row) {
+                let lines = |field: String| match field.is_empty() {
+                    true => vec![],
+                    false => field.lines().map(str::to_owned).collect(),
+                };
+                match column {
+                    CsvColumn::Context => msg.context = Some(field),
+                    CsvColumn::Id => msg.id = field,
+                    CsvColumn::IdPlural if !field.is_empty() => msg.id_plural = Some(field),
+                    CsvColumn::IdPlural => {}
+                    CsvColumn::Form(i) => {
+                        if msg.translated.len() <= i {
+                            msg.translated.resize(i + 1, String::new());
+                        }
+                        msg.translated[i] = field;
+                    }
+                    CsvColumn::TranslatorComments => msg.translator_comments = lines(field),
+                    CsvColumn::ExtractedComments => msg.extracted_comments = lines(field),
+                    CsvColumn::References => {
+                        msg.references = field.split_whitespace().map(str::to_owned).collect();
+                    }
+                    CsvColumn::Flags => {
+                        let flags = field.split(',').map(str::trim).filter(|f| !f.is_empty());
+                        msg.flags = flags.map(Flag::from).collect();
+                    }
+                }
+            }
+            if msg.id.is_empty() {
+                continue;
+            }
+            // an empty msgctxt field is no context, as `to_csv` writes it
+            msg.context = msg.context.filter(|c| !c.is_empty());
+            let len = match msg.id_plural {
+                Some(_) => nforms.unwrap_or(2),
+                None => 1,
+            };
+            msg.translated.resize(len, String::new());
+            catalog.insert(msg);
+        }
+        Ok(catalog)
+    }
+}
+
+#[test]
+fn test_rows() {
+    let src = "a,b,c\r\n\"x, \"\"y\"\"\",\"multi\r\nline\",\n\n,\"\"\nlast";
+    assert_eq!(
+        rows(src).unwrap(),
+        [
+            vec!["a", "b", "c"],
+            vec!["x, \"y\"", "multi\r\nline", ""],
+            vec!["", ""],
+            vec!["last"],
+        ]
+    );
+    assert!(matches!(rows("a\n\"open"), Err(CsvSyntax(2))));
+    assert!(matches!(rows("\"a\nb\"c"), Err(CsvSyntax(2))));
+}
+
+#[test]
+fn test_csv() {
+    let po = "msgid \"\"\nmsgstr \"Language: lt\\n\"\n\n# checked\n#. a verb\n#. in the menu\n#: src/main.rs:10 src/menu.rs:2\n#, fuzzy, c-format\nmsgctxt \"menu\"\nmsgid \"Open %s\"\nmsgstr \"Atidaryti %s\"\n\nmsgid \"File\"\nmsgid_plural \"Files\"\nmsgstr[0] \"Failas\"\nmsgstr[1] \"Failai\"\nmsgstr[2] \"Failų\"\n\nmsgid \"Quit\"\nmsgstr \"\"\n";
+    let catalog = Catalog::parse_po(po.as_bytes()).unwrap();
+    let columns = [
+        CsvColumn::Context,
+        CsvColumn::Id,
+        CsvColumn::IdPlural,
+        CsvColumn::Form(0),
+        CsvColumn::Form(1),
+        CsvColumn::Form(2),
+        CsvColumn::TranslatorComments,
+        CsvColumn::ExtractedComments,
+        CsvColumn::References,
+        CsvColumn::Flags,
+    ];
+    let mut csv = vec![];
+    catalog.to_csv(&mut csv, &columns).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(
+        csv,
+        "\u{feff}msgctxt,msgid,msgid_plural,msgstr[0],msgstr[1],msgstr[2],translator comments,extracted comments,references,flags\r\n\
+         ,File,Files,Failas,Failai,Failų,,,,\r\n\
+         ,Quit,,,,,,,,\r\n\
+         menu,Open %s,,Atidaryti %s,,,checked,\"a verb\nin the menu\",src/main.rs:10 src/menu.rs:2,\"fuzzy, c-format\"\r\n"
+    );
+
+    let read = Catalog::from_csv(csv.as_bytes(), &columns).unwrap();
+    assert_eq!(read.len(), catalog.len());
+    for msg in catalog.strings.values().filter(|msg| !msg.is_header()) {
+        assert_eq!(read.lookup(msg.context(), msg.id()), Some(msg));
+    }
+
+    // the columns after the given ones are ignored
+    let read = Catalog::from_csv(csv.as_bytes(), &columns[..4]).unwrap();
+    assert_eq!(read.get("File").unwrap().translated(), &["Failas"]);
+    assert!(read.get("Quit").unwrap().translator_comments().is_empty());
+}
@@ -0,0 +1,175 @@
+use crate::{ToPluralNumber, Translator};
+
+/// Marks a string for extraction without translating it, returning it as it is,
+/// like the `gettext_noop` of C.
+///
+/// It is meant for strings that are translated later with the string in a variable,
+/// e.g. the items of a static table; `Extractor` finds its msgids.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{gettext_noop, CatalogBuilder};
+///
+/// static COLORS: &[&str] = &[gettext_noop("Red"), gettext_noop("Green")];
+///
+/// let catalog = CatalogBuilder::new().add("Red", "Raudona").build().unwrap();
+/// let colors = COLORS.iter().map(|&color| catalog.gettext(color)).collect::<Vec<_>>();
+/// assert_eq!(colors, ["Raudona", "Green"]);
+/// ```
+pub const fn gettext_noop(msg_id: &str) -> &str {
+    msg_id
+}
+
+/// Deferred is a message that is declared before it is translated,
+/// with the catalog chosen when it is displayed, e.g. by the locale of a request.
+///
+/// It keeps the msgid, and the msgctxt and plural msgid if the message has them.
+/// Its constructors are `const` and named after the lookup functions,
+/// so that messages can be declared in statics, and `Extractor` finds them.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{CatalogBuilder, Deferred};
+///
+/// static MENU: &[Deferred] = &[
+///     Deferred::pgettext("menu", "Open"),
+///     Deferred::gettext("Quit"),
+/// ];
+/// static FILES: Deferred = Deferred::ngettext("{} file", "{} files");
+///
+/// let catalog = CatalogBuilder::new()
+///     .add_with_context("menu", "Open", "Atidaryti")
+///     .add_plural("{} file", "{} files", vec!["{} failas", "{} failai"])
+///     .build()
+///     .unwrap();
+/// let menu = MENU.iter().map(|item| item.translate(&catalog)).collect::<Vec<_>>();
+/// assert_eq!(menu, ["Atidaryti", "Quit"]);
+/// assert_eq!(FILES.ntranslate(&catalog, 3), "{} failai");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Deferred {
+    context: Option<&'static str>,
+    msg_id: &'static str,
+    msg_id_plural: Option<&'static str>,
+}
+
+impl Deferred {
+    /// Creates a message without a context or a plural form.
+    pub const fn gettext(msg_id: &'static str) -> Self {
+        Deferred {
+            context: None,
+            msg_id,
+            msg_id_plural: None,
+        }
+    }
+
+    /// Creates a message with a plural form.
+    pub const fn ngettext(msg_id: &'static str, msg_id_plural: &'static str) -> Self {
+        Deferred {
+            context: None,
+            msg_id,
+            msg_id_plural: Some(msg_id_plural),
+        }
+    }
+
+    /// Creates a message in the context `msg_context`.
+    pub const fn pgettext(msg_context: &'static str, msg_id: &'static str) -> Self {
+        Deferred {
+            context: Some(msg_context),
+            msg_id,
+            msg_id_plural: None,
+        }
+    }
+
+    /// Creates a message with a plural form in the context `msg_context`.
+    pub const fn npgettext(
+        msg_context: &'static str,
+        msg_id: &'static str,
+        msg_id_plural: &'static str,
+    ) -> Self {
+        Deferred {
+            context: Some(msg_context),
+            msg_id,
+            msg_id_plural: Some(msg_id_plural),
+        }
+    }
+
+    /// Returns the msgctxt of the message, if any.
+    pub fn context(&self) -> Option<&'static str> {
+        self.context
+    }
+
+    /// Returns the msgid of the message.
+    pub fn msg_id(&self) -> &'static str {
+        self.msg_id
+    }
+
+    /// Returns the plural msgid of the message, if any.
+    pub fn msg_id_plural(&self) -> Option<&'static str> {
+        self.msg_id_plural
+    }
+
+    /// Translates the message with the given translator.
+    ///
+    /// A message with a plural form is translated for a single object.
+    pub fn translate<'a, T: Translator + ?Sized>(&self, translator: &'a T) -> &'a str {
+        self.ntranslate(translator, 1u64)
+    }
+
+    /// Translates the message with the given translator, with the correct plural form
+    /// for the number `n` of objects.
+    ///
+    /// A message without a plural form is translated as it is, whatever `n` is.
+    pub fn ntranslate<'a, T: Translator + ?Sized, N: ToPluralNumber>(
+        &self,
+        translator: &'a T,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        match (self.context, self.msg_id_plural) {
+            (None, None) => translator.gettext(self.msg_id),
+            (None, Some(plural)) => translator.ngettext(self.msg_id, plural, n),
+            (Some(context), None) => translator.pgettext(context, self.msg_id),
+            (Some(context), Some(plural)) => translator.npgettext(context, self.msg_id, plural, n),
+        }
+    }
+}
+
+#[test]
+fn test_deferred() {
+    use crate::{CatalogBuilder, ChainedCatalog};
+
+    let catalog = CatalogBuilder::new()
+        .add("Open", "Atidaryti")
+        .add_with_context("menu", "Quit", "Baigti")
+        .add_plural("file", "files", vec!["failas", "failai"])
+        .add_plural_with_context("size", "byte", "bytes", vec!["baitas", "baitai"])
+        .build()
+        .unwrap();
+    let messages = [
+        (Deferred::gettext("Open"), "Atidaryti", "Atidaryti"),
+        (Deferred::pgettext("menu", "Quit"), "Baigti", "Baigti"),
+        (Deferred::pgettext("menu", "Open"), "Open", "Open"),
+        (Deferred::ngettext("file", "files"), "failas", "failai"),
+        (
+            Deferred::npgettext("size", "byte", "bytes"),
+            "baitas",
+            "baitai",
+        ),
+        (Deferred::ngettext("dir", "dirs"), "dir", "dirs"),
+    ];
+    let chained: &dyn Translator = &ChainedCatalog::new(vec![catalog.clone()]);
+    for &(msg, one, many) in &messages {
+        assert_eq!(msg.translate(&catalog), one);
+        assert_eq!(msg.ntranslate(&catalog, 1), one);
+        assert_eq!(msg.ntranslate(&catalog, 5), many);
+        assert_eq!(msg.ntranslate(chained, 5), many);
+        assert_eq!(msg.ntranslate(&catalog, -5i8), many);
+        assert_eq!(msg.ntranslate(&catalog, 2usize), many);
+    }
+    assert_eq!(messages[4].0.context(), Some("size"));
+    assert_eq!(messages[4].0.msg_id(), "byte");
+    assert_eq!(messages[4].0.msg_id_plural(), Some("bytes"));
+}
@@ -0,0 +1,206 @@
+use crate::key::Key;
+use crate::{Catalog, Message};
+
+/// CatalogDiff lists the messages that differ between two versions of a catalog,
+/// as returned by `Catalog::diff`.
+///
+/// Messages are matched by their context and msgid, and each list is sorted by them.
+/// The header entry is not compared.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::CatalogBuilder;
+///
+/// let old = CatalogBuilder::new()
+///     .add("Open", "Atidaryti")
+///     .add("Quit", "Baigti")
+///     .add_plural("File", "Files", vec!["Failas", "Failai", ""])
+///     .build()
+///     .unwrap();
+/// let new = CatalogBuilder::new()
+///     .add("Open", "Atidaryti")
+///     .add("Close", "Uždaryti")
+///     .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+///     .build()
+///     .unwrap();
+///
+/// let diff = old.diff(&new);
+/// assert_eq!(diff.added()[0].id(), "Close");
+/// assert_eq!(diff.removed()[0].id(), "Quit");
+/// assert_eq!(diff.changed()[0].after().id(), "File");
+/// assert_eq!(diff.changed()[0].changed_forms(), vec![2]);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CatalogDiff<'a> {
+    added: Vec<&'a Message>,
+    removed: Vec<&'a Message>,
+    changed: Vec<MessageChange<'a>>,
+}
+
+/// MessageChange is a message found in both versions of a catalog
+/// with a different plural msgid, translation or flags.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MessageChange<'a> {
+    before: &'a Message,
+    after: &'a Message,
+}
+
+impl<'a> CatalogDiff<'a> {
+    /// Returns the messages found only in the new version.
+    pub fn added(&self) -> &[&'a Message] {
+        &self.added
+    }
+
+    /// Returns the messages found only in the old version.
+    pub fn removed(&self) -> &[&'a Message] {
+        &self.removed
+    }
+
+    /// Returns the messages that changed between the versions.
+    pub fn changed(&self) -> &[MessageChange<'a>] {
+        &self.changed
+    }
+
+    /// Returns whether the versions have the same messages.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<'a> MessageChange<'a> {
+    /// Returns the message as it is in the old version.
+    pub fn before(&self) -> &'a Message {
+        self.before
+    }
+
+    /// Returns the message as it is in the new version.
+    pub fn after(&self) -> &'a Message {
+        self.after
+    }
+
+    /// Returns the indices of the translated forms that differ,
+    /// counting a form missing from one version as empty.
+    pub fn changed_forms(&self) -> Vec<usize> {
+        let form = |msg: &'a Message, i: usize| msg.translated.get(i).map_or("", |s| &s[..]);
+        let forms = self
+            .before
+            .translated
+            .len()
+            .max(self.after.translated.len());
+        (0..forms)
+            .filter(|&i| form(self.before, i) != form(self.after, i))
+            .collect()
+    }
+}
+
+/// Returns the messages of the catalog but the header, sorted by their keys.
+fn sorted(catalog: &Catalog) -> Vec<(&Key, &Message)> {
+    let mut messages = catalog
+        .strings
+        .iter()
+        .filter(|(key, _)| !key.is_header())
+        .collect::<Vec<_>>();
+    messages.sort_by(|a, b| a.0.cmp(b.0));
+    messages
+}
+
+impl Catalog {
+    /// Compares this catalog with a newer version of it,
+    /// listing the messages added, removed and changed in `other`.
+    ///
+    /// Messages changed when their plural msgid, any of their translated forms
+    /// or their flags differ; comments and references are not compared.
+    /// Obsolete messages are not compared either.
+    pub fn diff<'a>(&'a self, other: &'a Catalog) -> CatalogDiff<'a> {
+        let mut diff = CatalogDiff {
+            added: vec![],
+            removed: vec![],
+            changed: vec![],
+        };
+        for (key, old) in sorted(self) {
+            match other.strings.get(key) {
+                Some(new) => {
+                    let change = MessageChange {
+                        before: old,
+                        after: new,
+                    };
+                    if old.id_plural != new.id_plural
+                        || old.flags != new.flags
+                        || !change.changed_forms().is_empty()
+                    {
+                        diff.changed.push(change);
+                    }
+                }
+                None => diff.removed.push(old),
+            }
+        }
+        for (key, new) in sorted(other) {
+            if !self.strings.contains_key(key) {
+                diff.added.push(new);
+            }
+        }
+        diff
+    }
+}
+
+#[test]
+fn test_diff() {
+    use crate::{CatalogBuilder, Flag};
+
+    let old = CatalogBuilder::new()
+        .header("Language", "lt")
+        .add("Open", "Atidaryti")
+        .add("Quit", "Baigti")
+        .add_with_context("menu", "Quit", "Išeiti")
+        .add_plural("File", "Files", vec!["Failas", "Failai", ""])
+        .add("Tab", "Kortelė")
+        .add("Save", "Saugoti")
+        .build()
+        .unwrap();
+    let mut new = CatalogBuilder::new()
+        .header("Language", "lt-LT")
+        .add("Open", "Atidaryti")
+        .add("Close", "Uždaryti")
+        .add_with_context("menu", "Close", "Uždaryti")
+        .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+        .add_plural("Tab", "Tabs", vec!["Kortelė", "Kortelės"])
+        .add("Save", "Saugoti")
+        .build()
+        .unwrap();
+    assert!(old.diff(&old).is_empty());
+
+    new.strings
+        .get_mut(&Key::from("Save"))
+        .unwrap()
+        .flags
+        .insert(Flag::Fuzzy);
+    let diff = old.diff(&new);
+    assert!(!diff.is_empty());
+    fn ids<'a>(messages: &[&'a Message]) -> Vec<(Option<&'a str>, &'a str)> {
+        messages
+            .iter()
+            .map(|msg| (msg.context(), msg.id()))
+            .collect()
+    }
+    assert_eq!(
+        ids(diff.added()),
+        [(None, "Close"), (Some("menu"), "Close")]
+    );
+    assert_eq!(
+        ids(diff.removed()),
+        [(None, "Quit"), (Some("menu"), "Quit")]
+    );
+    let changed = diff.changed();
+    assert_eq!(changed.len(), 3);
+    assert_eq!(changed[0].before().id(), "File");
+    assert_eq!(changed[0].changed_forms(), vec![2]);
+    assert_eq!(changed[1].after().id(), "Save");
+    assert!(changed[1].changed_forms().is_empty());
+    assert_eq!(changed[2].after().id_plural(), Some("Tabs"));
+    assert_eq!(changed[2].changed_forms(), vec![1]);
+
+    let reverse = new.diff(&old);
+    assert_eq!(ids(reverse.added()), ids(diff.removed()));
+    assert_eq!(ids(reverse.removed()), ids(diff.added()));
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::locale::fallbacks;
+use crate::{untranslated, Catalog, ChainedCatalog, Error, ToPluralNumber};
+
+/// TextDomain manages the catalogs of several text domains,
+/// like `bindtextdomain` and `textdomain` do in GNU gettext,
+/// so that programs do not need to pass a `Catalog` around.
+/// Libraries and plugins sharing one manager can each use their own domain
+/// through `dgettext` and the other `d` lookups.
+///
+/// It keeps no global state and never looks at the C locale:
+/// the locales to use are given explicitly, in order of preference.
+/// The catalog of a domain for a locale is read from
+/// `{dir}/{locale}/LC_MESSAGES/{domain}.mo`, where `dir` is bound to the domain,
+/// trying less specific variants of the locale as well, e.g. `de` for `de_AT`.
+///
+/// # Examples
+///
+/// ```ignore
+/// use gettext::TextDomain;
+///
+/// let mut text = TextDomain::new("myapp");
+/// text.set_locales(vec!["de_AT", "de"]).unwrap();
+/// text.bind("myapp", "/usr/share/locale").unwrap();
+/// println!("{}", text.gettext("Hello"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextDomain {
+    domain: String,
+    dirs: HashMap<String, PathBuf>,
+    locales: Vec<String>,
+    catalogs: HashMap<String, ChainedCatalog>,
+}
+
+impl TextDomain {
+    /// Creates a manager using `domain` for lookups,
+    /// with no directories bound and no locales set yet.
+    pub fn new<T: Into<String>>(domain: T) -> Self {
+        TextDomain {
+            domain: domain.into(),
+            dirs: HashMap::new(),
+            locales: vec![],
+            catalogs: HashMap::new(),
+        }
+    }
+
+    /// Returns the domain used for lookups.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Sets the domain used for lookups, like `textdomain` does.
+    pub fn set_domain<T: Into<String>>(&mut self, domain: T) {
+        self.domain = domain.into();
+    }
+
+    /// Binds `domain` to the directory its catalogs are read from,
+    /// like `bindtextdomain` does, and loads them for the current locales.
+    ///
+    /// Locales without a catalog are skipped;
+    /// returns the `Err` variant if reading or parsing a catalog fails.
+    pub fn bind<D: Into<String>, P: Into<PathBuf>>(
+        &mut self,
+        domain: D,
+        dir: P,
+    ) -> Result<(), Error> {
+        let (domain, dir) = (domain.into(), dir.into());
+        let chain = load(&dir, &domain, &self.locales)?;
+        self.catalogs.insert(domain.clone(), chain);
+        self.dirs.insert(domain, dir);
+        Ok(())
+    }
+
+    /// Returns the locales to use, in order of preference.
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// Sets the locales to use, in order of preference,
+    /// and loads the catalogs of all the bound domains for them again.
+    ///
+    /// The `C` and `POSIX` locales, which are untranslated, are ignored.
+    pub fn set_locales<I, S>(&mut self, locales: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let locales = locales.into_iter().map(Into::into).collect::<Vec<_>>();
+        let mut catalogs = HashMap::new();
+        for (domain, dir) in &self.dirs {
+            catalogs.insert(domain.clone(), load(dir, domain, &locales)?);
+        }
+        self.locales = locales;
+        self.catalogs = catalogs;
+        Ok(())
+    }
+
+    /// Returns the chain of catalogs loaded for `domain`,
+    /// or `None` if the domain is not bound.
+    pub fn catalogs(&self, domain: &str) -> Option<&ChainedCatalog> {
+        self.catalogs.get(domain)
+    }
+
+    /// Returns the singular translation of `msg_id` in the current domain
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str {
+        self.dgettext(&self.domain, msg_id)
+    }
+
+    /// Returns the plural translation of `msg_id` in the current domain
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    pub fn ngettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.dngettext(&self.domain, msg_id, msg_id_plural, n)
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context` in the current domain
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str {
+        self.dpgettext(&self.domain, msg_context, msg_id)
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context` in the current domain
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    pub fn npgettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.dnpgettext(&self.domain, msg_context, msg_id, msg_id_plural, n)
+    }
+
+    /// Returns the singular translation of `msg_id` in `domain`
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn dgettext<'a>(&'a self, domain: &str, msg_id: &'a str) -> &'a str {
+        match self.catalogs(domain) {
+            Some(chain) => chain.gettext(msg_id),
+            None => msg_id,
+        }
+    }
+
+    /// Returns the plural translation of `msg_id` in `domain`
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    pub fn dngettext<'a, N: ToPluralNumber>(
+        &'a self,
+        domain: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        match self.catalogs(domain) {
+            Some(chain) => chain.ngettext(msg_id, msg_id_plural, n),
+            None => untranslated(msg_id, msg_id_plural, n),
+        }
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context` in `domain`
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn dpgettext<'a>(&'a self, domain: &str, msg_context: &str, msg_id: &'a str) -> &'a str {
+        match self.catalogs(domain) {
+            Some(chain) => chain.pgettext(msg_context, msg_id),
+            None => msg_id,
+        }
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context` in `domain`
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    pub fn dnpgettext<'a, N: ToPluralNumber>(
+        &'a self,
+        domain: &str,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        match self.catalogs(domain) {
+            Some(chain) => chain.npgettext(msg_context, msg_id, msg_id_plural, n),
+            None => untranslated(msg_id, msg_id_plural, n),
+        }
+    }
+}
+
+/// Loads the catalogs of `domain` found in `dir` for the given locales.
+fn load(dir: &Path, domain: &str, locales: &[String]) -> Result<ChainedCatalog, Error> {
+    let file_name = format!("{}.mo", domain);
+    let mut chain = ChainedCatalog::default();
+    let mut tried = vec![];
+    for locale in locales.iter().filter(|l| *l != "C" && *l != "POSIX") {
+        for variant in fallbacks(locale) {
+            if tried.contains(&variant) {
+                continue;
+            }
+            let path = dir.join(&variant).join("LC_MESSAGES").join(&file_name);
+            match File::open(path) {
+                Ok(file) => chain.push(Catalog::parse(file)?),
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+            tried.push(variant);
+        }
+    }
+    Ok(chain)
+}
+
+#[test]
+fn test_text_domain() {
+    use std::env;
+    use std::fs;
+
+    let dir = env::temp_dir().join(format!("gettext-domain-{}", std::process::id()));
+    let messages = |locale: &str| {
+        let messages = dir.join(locale).join("LC_MESSAGES");
+        fs::create_dir_all(&messages).unwrap();
+        messages
+    };
+    fs::copy("test_cases/integration.mo", messages("lt").join("app.mo")).unwrap();
+    fs::copy("test_cases/1.mo", messages("lt_LT").join("app.mo")).unwrap();
+    fs::copy("test_cases/2.mo", messages("de").join("lib.mo")).unwrap();
+
+    let mut text = TextDomain::new("app");
+    assert_eq!(text.gettext("existent"), "existent");
+    text.bind("app", &dir).unwrap();
+    assert_eq!(text.catalogs("app").unwrap().catalogs().len(), 0);
+    text.set_locales(vec!["C", "lt_LT.UTF-8"]).unwrap();
+    assert_eq!(text.locales(), &["C", "lt_LT.UTF-8"]);
+    assert_eq!(text.catalogs("app").unwrap().catalogs().len(), 2);
+    assert_eq!(text.gettext("existent"), "egzistuojantis");
+    assert_eq!(
+        text.ngettext("a good string", "good strings", 2),
+        "geros eilutes"
+    );
+    assert_eq!(text.pgettext("this is context", "Text"), "Tekstas");
+    assert_eq!(
+        text.npgettext("this is context", "Text", "Texts", 2),
+        "Tekstai"
+    );
+    assert_eq!(text.gettext("non-existent"), "non-existent");
+
+    text.bind("lib", &dir).unwrap();
+    text.set_domain("lib");
+    assert_eq!(text.domain(), "lib");
+    assert_eq!(text.gettext("existent"), "existent");
+    text.set_locales(vec!["de_DE"]).unwrap();
+    assert_eq!(text.ngettext("Image", "Images", 5), "Nuotraukos");
+    // plugins look up their own domain without switching the current one
+    text.set_domain("app");
+    assert_eq!(text.dngettext("lib", "Image", "Images", 5), "Nuotraukos");
+    assert_eq!(text.dgettext("lib", "existent"), "existent");
+    assert_eq!(text.dgettext("unbound", "Image"), "Image");
+    assert_eq!(text.dpgettext("unbound", "ctx", "Image"), "Image");
+    assert_eq!(
+        text.dnpgettext("unbound", "ctx", "Image", "Images", 2),
+        "Images"
+    );
+    text.set_domain("unbound");
+    assert_eq!(text.ngettext("Image", "Images", 5), "Images");
+    assert!(text.catalogs("unbound").is_none());
+
+    fs::write(messages("xx").join("app.mo"), "garbage").unwrap();
+    assert!(text.set_locales(vec!["xx"]).is_err());
+    assert_eq!(text.locales(), &["de_DE"]);
+    fs::remove_dir_all(dir).unwrap();
+}
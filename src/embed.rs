@@ -0,0 +1,92 @@
+//! Loading of catalogs embedded in the program by asset crates like `rust-embed`,
+//! laid out like installed ones, as `{locale}/LC_MESSAGES/{domain}.mo`.
+//!
+//! The functions take the file names and the contents of the assets
+//! rather than a type of the asset crate, so any of them can be used.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! #[derive(rust_embed::RustEmbed)]
+//! #[folder = "locale/"]
+//! struct Locales;
+//!
+//! let locales = gettext::embed::locales(Locales::iter(), "myapp");
+//! let catalog = gettext::embed::load(|path| Locales::get(path).map(|f| f.data), "fr", "myapp")
+//!     .unwrap()
+//!     .unwrap_or_else(gettext::Catalog::empty);
+//! ```
+
+use crate::locale::fallbacks;
+use crate::{Catalog, Error};
+
+/// Returns the path of the MO file of `domain` for `locale`.
+pub fn path(locale: &str, domain: &str) -> String {
+    format!("{}/LC_MESSAGES/{}.mo", locale, domain)
+}
+
+/// Returns the locales that have an MO file of `domain` among the given file names,
+/// sorted and without duplicates.
+pub fn locales<I, S>(files: I, domain: &str) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let file_name = format!("/LC_MESSAGES/{}.mo", domain);
+    let mut locales = files
+        .into_iter()
+        .filter_map(|file| {
+            let locale = file.as_ref().strip_suffix(&file_name)?;
+            Some(locale.to_owned()).filter(|l| !l.is_empty() && !l.contains('/'))
+        })
+        .collect::<Vec<_>>();
+    locales.sort();
+    locales.dedup();
+    locales
+}
+
+/// Loads the catalog of `domain` for `locale`
+/// with `get`, which returns the contents of the asset at the given path.
+///
+/// Less specific locales are tried if there is no catalog for `locale` itself,
+/// e.g. `pt_BR` and then `pt` for `pt_BR.UTF-8`, as returned by `locale::fallbacks`.
+/// Returns `Ok(None)` if there is no such asset
+/// and the `Err` variant if it cannot be parsed.
+pub fn load<F, B>(mut get: F, locale: &str, domain: &str) -> Result<Option<Catalog>, Error>
+where
+    F: FnMut(&str) -> Option<B>,
+    B: AsRef<[u8]>,
+{
+    for locale in fallbacks(locale) {
+        if let Some(bytes) = get(&path(&locale, domain)) {
+            return Catalog::parse(bytes.as_ref()).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+#[test]
+fn test_embed() {
+    use std::borrow::Cow;
+
+    let mo = &include_bytes!("../test_cases/1.mo")[..];
+    let files: &[(&str, &[u8])] = &[
+        ("lt/LC_MESSAGES/app.mo", mo),
+        ("de_AT/LC_MESSAGES/app.mo", b"garbage"),
+        ("de_AT/LC_MESSAGES/other.mo", mo),
+        ("fr/LC_MESSAGES/app.po", mo),
+        ("nested/fr/LC_MESSAGES/app.mo", mo),
+        ("/LC_MESSAGES/app.mo", mo),
+    ];
+    let names = files.iter().map(|(name, _)| Cow::Borrowed(*name));
+    assert_eq!(locales(names, "app"), &["de_AT", "lt"]);
+    assert_eq!(path("lt", "app"), "lt/LC_MESSAGES/app.mo");
+
+    let get = |path: &str| files.iter().find(|(name, _)| *name == path).map(|f| f.1);
+    let catalog = load(get, "lt", "app").unwrap().unwrap();
+    assert_eq!(catalog.pgettext("this is context", "Text"), "Tekstas");
+    assert!(load(get, "fr", "app").unwrap().is_none());
+    assert!(load(get, "lt_LT.UTF-8", "app").unwrap().is_some());
+    assert!(load(get, "de_AT", "other").unwrap().is_some());
+    assert!(load(get, "de_AT", "app").is_err());
+}
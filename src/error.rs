@@ -3,25 +3,69 @@ use std::error;
 use std::fmt;
 use std::io;
 
-/// Represents an error encountered while parsing an MO file.
+/// Represents an error encountered while parsing or writing a catalog.
+///
+/// Errors encountered in a specific entry of an MO file are wrapped
+/// in the `InEntry` variant, which tells where the entry is.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// A catalog cache was written by an incompatible version of this crate
+    BadCacheVersion(u32),
     /// An incorrect magic number has been encountered
     BadMagic,
+    /// Incorrect syntax encountered on the given line of a CSV file
+    CsvSyntax(usize),
     /// An invalid byte sequence for the given encoding has been encountered
     DecodingError,
     /// An unexpected EOF occured
     Eof,
+    /// The placeholders of a translation do not match the ones of the given msgid
+    FormatMismatch(String),
+    /// Incorrect syntax encountered on the given line of a Fluent resource
+    FtlSyntax(usize),
+    /// Incorrect syntax encountered at the given byte offset of a message in ICU MessageFormat
+    IcuSyntax(usize),
+    /// An error encountered while reading an entry of an MO file
+    InEntry {
+        /// The index of the entry in the tables of the file
+        index: usize,
+        /// The byte offset of the entry's string where the error was encountered,
+        /// or of its original string when the error concerns the whole entry
+        offset: usize,
+        /// The error itself
+        error: Box<Error>,
+    },
     /// An I/O error occured
     Io(io::Error),
+    /// Malformed or unexpected JSON encountered on the given line of a JSON document
+    JsonSyntax(usize),
+    /// The catalog exceeds one of the limits set with `ParseOptions`
+    LimitExceeded,
     /// Incorrect syntax encountered while parsing the meta information
     MalformedMetadata,
     /// Meta information string was not the first string in the catalog
     MisplacedMetadata,
     /// Invalid Plural-Forms metadata
     PluralParsing,
+    /// Incorrect syntax encountered on the given line of a PO file
+    PoSyntax(usize),
+    /// A malformed `printf` directive encountered at the given byte offset of a format string
+    PrintfSyntax(usize),
+    /// A malformed `\uXXXX` escape encountered on the given line of a Java `.properties` file
+    PropertiesSyntax(usize),
+    /// Incorrect syntax encountered on the given line of an Apple `.strings` file
+    StringsSyntax(usize),
     /// An unknown encoding was specified in the metadata
     UnknownEncoding,
+    /// The given placeholder of a translation has no matching argument, or is an unmatched brace
+    UnknownPlaceholder(String),
+    /// A string could not be represented in the encoding of the catalog
+    Unencodable,
+    /// Malformed or unexpected XML encountered on the given line of an XML document
+    XmlSyntax(usize),
+    /// Malformed or unsupported YAML encountered on the given line of a YAML locale file
+    YamlSyntax(usize),
 }
 use self::Error::*;
 
@@ -29,6 +73,7 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
             Io(ref err) => Some(err),
+            InEntry { ref error, .. } => Some(&**error),
             _ => None,
         }
     }
@@ -37,14 +82,91 @@ impl error::Error for Error {
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            BadCacheVersion(version) => write!(fmt, "unsupported cache version {}", version),
             BadMagic => write!(fmt, "bad magic number"),
+            CsvSyntax(line) => write!(fmt, "CSV syntax error on line {}", line),
             DecodingError => write!(fmt, "invalid byte sequence in a string"),
             Eof => write!(fmt, "unxpected end of file"),
+            FormatMismatch(ref id) => {
+                write!(
+                    fmt,
+                    "placeholders of the translation of {:?} do not match",
+                    id
+                )
+            }
+            FtlSyntax(line) => write!(fmt, "FTL syntax error on line {}", line),
+            IcuSyntax(offset) => write!(fmt, "ICU message syntax error at byte {}", offset),
+            InEntry {
+                index,
+                offset,
+                ref error,
+            } => write!(fmt, "in entry {} at byte {}: {}", index, offset, error),
             Io(ref err) => err.fmt(fmt),
+            JsonSyntax(line) => write!(fmt, "JSON syntax error on line {}", line),
+            LimitExceeded => write!(fmt, "parser resource limit exceeded"),
             MalformedMetadata => write!(fmt, "metadata syntax error"),
             MisplacedMetadata => write!(fmt, "misplaced metadata"),
             UnknownEncoding => write!(fmt, "unknown encoding specified"),
+            UnknownPlaceholder(ref placeholder) => {
+                write!(
+                    fmt,
+                    "unknown placeholder or unmatched brace {:?}",
+                    placeholder
+                )
+            }
+            Unencodable => write!(fmt, "string cannot be represented in the encoding"),
             PluralParsing => write!(fmt, "invalid plural expression"),
+            PoSyntax(line) => write!(fmt, "PO syntax error on line {}", line),
+            PrintfSyntax(offset) => write!(fmt, "malformed printf directive at byte {}", offset),
+            PropertiesSyntax(line) => write!(fmt, "properties file syntax error on line {}", line),
+            StringsSyntax(line) => write!(fmt, "strings file syntax error on line {}", line),
+            XmlSyntax(line) => write!(fmt, "XML syntax error on line {}", line),
+            YamlSyntax(line) => write!(fmt, "YAML syntax error on line {}", line),
+        }
+    }
+}
+
+/// ParseWarning describes a malformed entry
+/// which was skipped because of `ParseOptions::lenient`.
+#[derive(Debug)]
+pub struct ParseWarning {
+    line: Option<usize>,
+    error: Error,
+}
+
+impl ParseWarning {
+    pub(crate) fn new(line: Option<usize>, error: Error) -> Self {
+        ParseWarning { line, error }
+    }
+
+    /// Returns the line of the PO file where the error was encountered.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Returns the error encountered in the entry.
+    /// Errors in MO files are wrapped in `InEntry`, which tells where the entry is.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(fmt, "skipped entry on line {}: {}", line, self.error),
+            None => write!(fmt, "skipped entry: {}", self.error),
+        }
+    }
+}
+
+impl Error {
+    /// Wraps the error with the location of the MO entry it was encountered in.
+    pub(crate) fn in_entry(self, index: usize, offset: usize) -> Error {
+        InEntry {
+            index,
+            offset,
+            error: Box::new(self),
         }
     }
 }
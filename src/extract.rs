@@ -0,0 +1,739 @@
+//! Extraction of translatable strings from Rust sources, like `xgettext` does.
+//!
+//! The [`Extractor`] looks for calls of the lookup functions and macros,
+//! e.g. `catalog.gettext("Open")` or `pgettext!("menu", "Open")`,
+//! whose message arguments are string literals,
+//! and collects them into a template catalog that can be written out as a POT file.
+//!
+//! # Examples
+//!
+//! ```
+//! use gettext::extract::Extractor;
+//!
+//! let mut extractor = Extractor::new();
+//! extractor.extract(r#"
+//!     println!("{}", catalog.ngettext("One file", "{} files", n));
+//!     let title = catalog.pgettext("menu", "Open");
+//! "#);
+//!
+//! let template = extractor.catalog();
+//! let mut pot = vec![];
+//! template.write_po(&mut pot).unwrap();
+//! assert!(String::from_utf8(pot).unwrap().contains("msgctxt \"menu\"\nmsgid \"Open\"\n"));
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use crate::key::Key;
+use crate::{Catalog, Error, Message};
+
+/// The header of extracted template catalogs.
+pub(crate) const TEMPLATE_HEADER: &str = "MIME-Version: 1.0\n\
+                               Content-Type: text/plain; charset=UTF-8\n\
+                               Content-Transfer-Encoding: 8bit\n";
+
+/// Keyword describes a function or macro whose arguments are translatable strings,
+/// in the same way as the `--keyword` option of `xgettext`.
+///
+/// Argument positions are counted from 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Keyword {
+    name: String,
+    context: Option<usize>,
+    id: usize,
+    id_plural: Option<usize>,
+}
+
+impl Keyword {
+    /// Creates a keyword whose argument at position `id` is the msgid.
+    pub fn new<T: Into<String>>(name: T, id: usize) -> Self {
+        Keyword {
+            name: name.into(),
+            context: None,
+            id,
+            id_plural: None,
+        }
+    }
+
+    /// Sets the position of the argument holding the message context.
+    pub fn context(mut self, position: usize) -> Self {
+        self.context = Some(position);
+        self
+    }
+
+    /// Sets the position of the argument holding the plural msgid.
+    pub fn plural(mut self, position: usize) -> Self {
+        self.id_plural = Some(position);
+        self
+    }
+}
+
+/// Extractor collects translatable strings out of Rust sources.
+#[derive(Clone, Debug)]
+pub struct Extractor {
+    keywords: Vec<Keyword>,
+    messages: Vec<Message>,
+}
+
+impl Default for Extractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor {
+    /// Creates an extractor that knows the lookup functions of this crate,
+    /// `gettext`, `ngettext`, `pgettext` and `npgettext`, which are also
+    /// the constructors of `Deferred`, and their variants such as `ngettext_category`,
+    /// the `gettext_noop` marker, and the `tr!` and `ntr!` macros.
+    pub fn new() -> Self {
+        Extractor {
+            keywords: vec![
+                Keyword::new("gettext", 1),
+                Keyword::new("ngettext", 1).plural(2),
+                Keyword::new("pgettext", 2).context(1),
+                Keyword::new("npgettext", 2).context(1).plural(3),
+                Keyword::new("ngettext_category", 1).plural(2),
+                Keyword::new("try_gettext", 1),
+                Keyword::new("try_ngettext", 1),
+                Keyword::new("try_pgettext", 2).context(1),
+                Keyword::new("try_npgettext", 2).context(1),
+                Keyword::new("gettext_cow", 1),
+                Keyword::new("ngettext_cow", 1).plural(2),
+                Keyword::new("pgettext_cow", 2).context(1),
+                Keyword::new("npgettext_cow", 2).context(1).plural(3),
+                Keyword::new("dgettext", 2),
+                Keyword::new("dngettext", 2).plural(3),
+                Keyword::new("dpgettext", 3).context(2),
+                Keyword::new("dnpgettext", 3).context(2).plural(4),
+                Keyword::new("gettext_fmt", 1),
+                Keyword::new("gettext_fmt_strict", 1),
+                Keyword::new("gettext_icu", 1),
+                Keyword::new("pgettext_icu", 2).context(1),
+                Keyword::new("sgettext", 1),
+                Keyword::new("nsgettext", 1).plural(2),
+                Keyword::new("ngettext_range", 1).plural(2),
+                Keyword::new("ngettext_f64", 1).plural(2),
+                Keyword::new("ngettext_operands", 1).plural(2),
+                Keyword::new("gettext_noop", 1),
+                Keyword::new("tr", 2),
+                Keyword::new("ntr", 2).plural(3),
+            ],
+            messages: vec![],
+        }
+    }
+
+    /// Adds a keyword to look for, replacing the keyword with the same name.
+    pub fn keyword(&mut self, keyword: Keyword) {
+        self.keywords.retain(|k| k.name != keyword.name);
+        self.keywords.push(keyword);
+    }
+
+    /// Collects the translatable strings of the given Rust source.
+    pub fn extract(&mut self, source: &str) {
+        self.collect(None, source);
+    }
+
+    /// Collects the translatable strings of the given Rust source,
+    /// referencing them as `file:line` in the template.
+    pub fn extract_source(&mut self, file: &str, source: &str) {
+        self.collect(Some(file), source);
+    }
+
+    /// Reads the file at `path` and collects its translatable strings,
+    /// referencing them by the path.
+    pub fn extract_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        self.extract_source(&path.display().to_string(), &source);
+        Ok(())
+    }
+
+    /// Returns the template catalog of all the collected strings,
+    /// with empty translations.
+    ///
+    /// A string collected more than once keeps the references of all its uses,
+    /// and its plural msgid if any of the uses has one, e.g. `try_ngettext`
+    /// takes none.
+    pub fn catalog(&self) -> Catalog {
+        let mut catalog = Catalog::new();
+        catalog.insert(Message::new("", None, vec![TEMPLATE_HEADER]));
+        for msg in &self.messages {
+            let mut msg = msg.clone();
+            let key = Key::new(msg.context.as_deref(), &msg.id);
+            if let Some(known) = catalog.strings.remove(&key) {
+                let mut references = known.references;
+                for reference in msg.references {
+                    if !references.contains(&reference) {
+                        references.push(reference);
+                    }
+                }
+                msg.references = references;
+                if msg.id_plural.is_none() && known.id_plural.is_some() {
+                    msg.id_plural = known.id_plural;
+                    msg.translated = known.translated;
+                }
+            }
+            catalog.insert(msg);
+        }
+        catalog
+    }
+
+    fn collect(&mut self, file: Option<&str>, source: &str) {
+        let (tokens, offsets) = tokenize(source);
+        // the line of the last keyword found, counted up to its offset
+        let (mut line, mut counted) = (1, 0);
+        for (i, token) in tokens.iter().enumerate() {
+            let keyword = match *token {
+                Token::Ident(name) => self.keywords.iter().find(|k| k.name == name),
+                _ => None,
+            };
+            // skip definitions such as `fn gettext(...)`
+            let defined = i > 0 && tokens[i - 1] == Token::Ident("fn");
+            if let (Some(keyword), false) = (keyword, defined) {
+                let msg = call_arguments(&tokens[i + 1..]).and_then(|args| keyword.message(&args));
+                if let Some(mut msg) = msg {
+                    if let Some(file) = file {
+                        line += source[counted..offsets[i]].matches('\n').count();
+                        counted = offsets[i];
+                        msg.references.push(format!("{}:{}", file, line));
+                    }
+                    self.messages.push(msg);
+                }
+            }
+        }
+    }
+}
+
+impl Keyword {
+    /// Builds the message out of the call arguments,
+    /// if all the required ones are string literals.
+    fn message(&self, args: &[Option<String>]) -> Option<Message> {
+        let arg = |position: usize| args.get(position.checked_sub(1)?).cloned()?;
+        let id = arg(self.id)?;
+        let context = match self.context {
+            Some(position) => Some(arg(position)?),
+            None => None,
+        };
+        let mut msg = Message::new(id, context, vec![String::new()]);
+        if let Some(position) = self.id_plural {
+            msg.id_plural = Some(arg(position)?);
+            msg.translated.push(String::new());
+        }
+        Some(msg)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Str(String),
+    Open,
+    Close,
+    Comma,
+    Bang,
+    Other,
+}
+
+/// Returns the arguments of the call starting at `tokens`,
+/// i.e. an optional `!` followed by a delimited list,
+/// with `None` for the arguments that are not plain string literals.
+fn call_arguments(tokens: &[Token]) -> Option<Vec<Option<String>>> {
+    let tokens = match tokens.first() {
+        Some(Token::Bang) => &tokens[1..],
+        _ => tokens,
+    };
+    if tokens.first() != Some(&Token::Open) {
+        return None;
+    }
+    let mut args = vec![];
+    let mut current = vec![];
+    let mut depth = 0;
+    for token in &tokens[1..] {
+        match token {
+            Token::Open => depth += 1,
+            Token::Close if depth == 0 => {
+                args.push(literal(&current));
+                return Some(args);
+            }
+            Token::Close => depth -= 1,
+            Token::Comma if depth == 0 => {
+                args.push(literal(&current));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(token);
+    }
+    None
+}
+
+fn literal(tokens: &[&Token]) -> Option<String> {
+    match tokens {
+        [Token::Str(s)] => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Splits Rust source into the tokens relevant for extraction,
+/// skipping comments and decoding string literals.
+///
+/// Also returns the byte offset at which each token starts.
+fn tokenize(src: &str) -> (Vec<Token<'_>>, Vec<usize>) {
+    let bytes = src.as_bytes();
+    let mut tokens = vec![];
+    let mut offsets = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let start = i;
+        match b {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let mut depth = 0;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"/*") {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b"*/") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            b'"' => {
+                let (value, end) = cooked_string(src, i + 1);
+                tokens.push(Token::Str(value));
+                i = end;
+            }
+            b'r' if raw_string_start(&bytes[i + 1..]).is_some() => {
+                let hashes = raw_string_start(&bytes[i + 1..]).unwrap();
+                let value = i + 2 + hashes;
+                let terminator = format!("\"{}", "#".repeat(hashes));
+                let len = src[value..].find(&terminator).unwrap_or(src.len() - value);
+                tokens.push(Token::Str(src[value..value + len].to_owned()));
+                i = (value + len + terminator.len()).min(src.len());
+            }
+            b'b' if matches!(bytes.get(i + 1), Some(b'"') | Some(b'\'')) => {
+                // byte strings are not translatable
+                let (_, end) = match bytes[i + 1] {
+                    b'"' => cooked_string(src, i + 2),
+                    _ => (String::new(), char_literal_end(bytes, i + 1)),
+                };
+                tokens.push(Token::Other);
+                i = end;
+            }
+            b'\'' => {
+                tokens.push(Token::Other);
+                i = char_literal_end(bytes, i);
+            }
+            _ if b == b'_' || b.is_ascii_alphabetic() => {
+                while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(&src[start..i]));
+            }
+            _ if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                tokens.push(match b {
+                    b'(' | b'[' | b'{' => Token::Open,
+                    b')' | b']' | b'}' => Token::Close,
+                    b',' => Token::Comma,
+                    b'!' => Token::Bang,
+                    _ => Token::Other,
+                });
+                // skip whole characters so that slicing stays valid
+                i += src[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        offsets.resize(tokens.len(), start);
+    }
+    (tokens, offsets)
+}
+
+/// Returns the number of `#`s of a raw string literal starting after its `r`.
+fn raw_string_start(bytes: &[u8]) -> Option<usize> {
+    let hashes = bytes.iter().take_while(|&&b| b == b'#').count();
+    match bytes.get(hashes) {
+        Some(b'"') => Some(hashes),
+        _ => None,
+    }
+}
+
+/// Returns the end of a character literal or a lifetime starting at `start`.
+fn char_literal_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    if bytes.get(i) == Some(&b'\\') {
+        i += 2;
+        while i < bytes.len() && bytes[i] != b'\'' {
+            i += 1;
+        }
+        return (i + 1).min(bytes.len());
+    }
+    // skip one character, which is either closed by a quote or is a lifetime
+    i += 1;
+    while i < bytes.len() && bytes[i] & 0xc0 == 0x80 {
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'\'') {
+        i + 1
+    } else {
+        i
+    }
+}
+
+/// Decodes a string literal whose contents start at `start`,
+/// returning the value and the position after the closing quote.
+fn cooked_string(src: &str, start: usize) -> (String, usize) {
+    let mut value = String::new();
+    let mut chars = src[start..].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, start + i + 1),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('0') => value.push('\0'),
+                Some('x') => {
+                    let hex = chars.by_ref().take(2).map(|(_, c)| c).collect::<String>();
+                    if let Ok(code) = u8::from_str_radix(&hex, 16) {
+                        value.push(char::from(code));
+                    }
+                }
+                Some('u') => {
+                    let code = chars
+                        .by_ref()
+                        .map(|(_, c)| c)
+                        .take_while(|&c| c != '}')
+                        .filter(|&c| c != '{' && c != '_')
+                        .collect::<String>();
+                    if let Some(c) = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                        value.push(c);
+                    }
+                }
+                Some('\n') => {
+                    // a line continuation skips the leading whitespace
+                    while let Some((_, c)) = chars.peek() {
+                        if !c.is_whitespace() {
+                            break;
+                        }
+                        chars.next();
+                    }
+                }
+                Some(c) => value.push(c),
+                None => {}
+            },
+            c => value.push(c),
+        }
+    }
+    (value, src.len())
+}
+
+#[test]
+fn test_tokenize() {
+    use self::Token::*;
+
+    assert_eq!(
+        tokenize("gettext!(\"a\\\"b\\n\\u{105}\\x41\", 'x', '\\'', b\"c\") // gettext(\"d\")").0,
+        vec![
+            Ident("gettext"),
+            Bang,
+            Open,
+            Str("a\"b\nąA".to_owned()),
+            Comma,
+            Other,
+            Comma,
+            Other,
+            Comma,
+            Other,
+            Close
+        ]
+    );
+    assert_eq!(
+        tokenize("fn f<'a>(x: &'a str) /* /* ą */ */ r#\"raw \"quote\"\"#").0,
+        vec![
+            Ident("fn"),
+            Ident("f"),
+            Other,
+            Other,
+            Other,
+            Open,
+            Ident("x"),
+            Other,
+            Other,
+            Other,
+            Ident("str"),
+            Close,
+            Str("raw \"quote\"".to_owned())
+        ]
+    );
+    assert_eq!(
+        tokenize("\"one \\\n    two\" ą").0,
+        vec![Str("one two".to_owned()), Other]
+    );
+}
+
+#[test]
+fn test_extract() {
+    let mut extractor = Extractor::new();
+    extractor.keyword(Keyword::new("label", 1));
+    extractor.extract(
+        r#"
+        fn gettext(msg_id: &str) -> &str { msg_id }
+
+        fn main() {
+            let n = 5;
+            println!("{}", catalog.gettext("Hello"));
+            println!("{}", catalog.ngettext("One file", "{} files", n));
+            let title = catalog.pgettext("menu", "Open");
+            let tabs = catalog.npgettext("menu", "Tab", "Tabs", count(&[1, 2]));
+            let nested = gettext(&format!("{}", gettext("Inner")));
+            let label = label!("Label");
+            let greeting = tr!(catalog, "Hi, {}!", name);
+            let files = ntr!(catalog, "{} file", "{} files", n);
+            let dynamic = catalog.gettext(name);
+            let pair = catalog.pgettext(context, "Missing context");
+        }
+
+        static COLORS: &[&str] = &[gettext_noop("Red")];
+        static SAVE: Deferred = Deferred::pgettext("menu", "Save");
+        "#,
+    );
+    let catalog = extractor.catalog();
+
+    let mut keys = catalog.strings.keys().map(Key::as_str).collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(
+        keys,
+        &[
+            "",
+            "Hello",
+            "Hi, {}!",
+            "Inner",
+            "Label",
+            "One file",
+            "Red",
+            "menu\x04Open",
+            "menu\x04Save",
+            "menu\x04Tab",
+            "{} file"
+        ]
+    );
+    assert_eq!(
+        catalog.strings[&Key::from("One file")].id_plural(),
+        Some("{} files")
+    );
+    assert_eq!(
+        catalog.strings[&Key::from("One file")].translated(),
+        &["", ""]
+    );
+    assert_eq!(
+        catalog.strings[&Key::from("menu\x04Tab")].id_plural(),
+        Some("Tabs")
+    );
+    assert_eq!(catalog.strings[&Key::from("Hello")].translated(), &[""]);
+    assert_eq!(
+        catalog.strings[&Key::from("{} file")].id_plural(),
+        Some("{} files")
+    );
+    assert_eq!(catalog.metadata().charset(), Some("UTF-8"));
+}
+
+#[test]
+fn test_extract_references() {
+    let mut extractor = Extractor::new();
+    extractor.extract_source(
+        "src/main.rs",
+        "catalog.gettext(\"Open\");\n\n/* gettext(\"skipped\")\n */ catalog.gettext(\"Quit\");\ngettext(\"Open\");",
+    );
+    extractor.extract_source("src/menu.rs", "\n\ngettext(\"Open\");");
+    extractor.extract("gettext(\"Open\");");
+    let catalog = extractor.catalog();
+    assert_eq!(
+        catalog.get("Open").unwrap().references(),
+        &["src/main.rs:1", "src/main.rs:5", "src/menu.rs:3"]
+    );
+    assert_eq!(
+        catalog.get("Quit").unwrap().references(),
+        &["src/main.rs:4"]
+    );
+
+    let mut pot = vec![];
+    catalog.write_po(&mut pot).unwrap();
+    let pot = String::from_utf8(pot).unwrap();
+    assert!(pot.contains("#: src/main.rs:4\nmsgid \"Quit\"\n"));
+    let parsed = Catalog::parse_po(pot.as_bytes()).unwrap();
+    assert_eq!(
+        parsed.get("Open").unwrap().references(),
+        catalog.get("Open").unwrap().references()
+    );
+}
+
+#[cfg(test)]
+fn extracted(source: &str) -> Vec<(String, Option<String>)> {
+    let mut extractor = Extractor::new();
+    extractor.extract(source);
+    let catalog = extractor.catalog();
+    let mut messages = catalog
+        .strings
+        .values()
+        .filter(|msg| !msg.is_header())
+        .map(|msg| {
+            let key = Key::new(msg.context.as_deref(), &msg.id);
+            (key.as_str().to_owned(), msg.id_plural.clone())
+        })
+        .collect::<Vec<_>>();
+    messages.sort();
+    messages
+}
+
+#[test]
+fn test_extract_category() {
+    assert_eq!(
+        extracted(r#"catalog.ngettext_category("One day", "{} days", PluralCategory::Few)"#),
+        &[("One day".to_owned(), Some("{} days".to_owned()))]
+    );
+}
+
+#[test]
+fn test_extract_try() {
+    let source = r#"
+        catalog.try_gettext("Open");
+        catalog.try_pgettext("menu", "Quit");
+        catalog.ngettext("One file", "{} files", n);
+        catalog.try_ngettext("One file", n);
+        catalog.try_npgettext("menu", "Tab", n);
+    "#;
+    let plural = Some("{} files".to_owned());
+    assert_eq!(
+        extracted(source),
+        &[
+            ("One file".to_owned(), plural),
+            ("Open".to_owned(), None),
+            ("menu\x04Quit".to_owned(), None),
+            ("menu\x04Tab".to_owned(), None),
+        ]
+    );
+}
+
+#[test]
+fn test_extract_cow() {
+    let source = r#"
+        catalog.gettext_cow("Open");
+        catalog.ngettext_cow("One file", "{} files", n);
+        catalog.pgettext_cow("menu", "Quit");
+        catalog.npgettext_cow("menu", "Tab", "Tabs", n);
+    "#;
+    assert_eq!(
+        extracted(source),
+        &[
+            ("One file".to_owned(), Some("{} files".to_owned())),
+            ("Open".to_owned(), None),
+            ("menu\x04Quit".to_owned(), None),
+            ("menu\x04Tab".to_owned(), Some("Tabs".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn test_extract_domains() {
+    let source = r#"
+        domain.dgettext("plugin", "Open");
+        domain.dngettext("plugin", "One file", "{} files", n);
+        domain.dpgettext("plugin", "menu", "Quit");
+        domain.dnpgettext("plugin", "menu", "Tab", "Tabs", n);
+    "#;
+    assert_eq!(
+        extracted(source),
+        &[
+            ("One file".to_owned(), Some("{} files".to_owned())),
+            ("Open".to_owned(), None),
+            ("menu\x04Quit".to_owned(), None),
+            ("menu\x04Tab".to_owned(), Some("Tabs".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn test_extract_fmt() {
+    let source = r#"
+        catalog.gettext_fmt("{user} opened {file}", &[("user", &"Jonas"), ("file", &file)]);
+        catalog.gettext_fmt_strict("{n} of {total}", &[("n", &3), ("total", &"10")])?;
+    "#;
+    assert_eq!(
+        extracted(source),
+        &[
+            ("{n} of {total}".to_owned(), None),
+            ("{user} opened {file}".to_owned(), None),
+        ]
+    );
+}
+
+#[test]
+fn test_extract_icu() {
+    let source = r#"
+        catalog.gettext_icu("{count, plural, one {# file} other {# files}}", &[("count", n.into())]);
+        catalog.pgettext_icu("status", "{user} is {state}", &args);
+    "#;
+    assert_eq!(
+        extracted(source),
+        &[
+            ("status\x04{user} is {state}".to_owned(), None),
+            (
+                "{count, plural, one {# file} other {# files}}".to_owned(),
+                None
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_extract_selectors() {
+    // the variants for the selectors are added by translators
+    let source = r#"
+        catalog.sgettext("{} is online", "gender=female");
+        catalog.nsgettext("One friend", "{} friends", &selector, n);
+    "#;
+    assert_eq!(
+        extracted(source),
+        &[
+            ("One friend".to_owned(), Some("{} friends".to_owned())),
+            ("{} is online".to_owned(), None),
+        ]
+    );
+}
+
+#[test]
+fn test_extract_range() {
+    assert_eq!(
+        extracted(r#"catalog.ngettext_range("{} day", "{}–{} days", start, end)"#),
+        &[("{} day".to_owned(), Some("{}–{} days".to_owned()))]
+    );
+}
+
+#[test]
+fn test_extract_decimals() {
+    let source = r#"
+        catalog.ngettext_f64("{} hour", "{} hours", 1.5);
+        catalog.ngettext_operands("{} mile", "{} miles", &"1.50".parse().unwrap());
+    "#;
+    assert_eq!(
+        extracted(source),
+        &[
+            ("{} hour".to_owned(), Some("{} hours".to_owned())),
+            ("{} mile".to_owned(), Some("{} miles".to_owned())),
+        ]
+    );
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+
+/// Flag is a flag of a PO entry, one of those listed on its `#,` line.
+///
+/// The flags known to this crate have their own variants,
+/// and any other flag is kept as it is written.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{Catalog, Flag};
+///
+/// let po = "#, fuzzy, c-format\nmsgid \"%d file\"\nmsgstr \"%d failas\"\n";
+/// let catalog = Catalog::parse_po(po.as_bytes()).unwrap();
+/// let msg = catalog.get("%d file").unwrap();
+/// assert!(msg.has_flag(Flag::CFormat));
+/// assert!(!msg.has_flag(Flag::NoWrap));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Flag {
+    /// `fuzzy`: the translation needs to be reviewed
+    Fuzzy,
+    /// `c-format`: the message is a C `printf` format string
+    CFormat,
+    /// `no-c-format`: the message is not a C format string, even if it looks like one
+    NoCFormat,
+    /// `rust-format`: the message is a Rust `format!` string
+    RustFormat,
+    /// `no-rust-format`: the message is not a Rust format string, even if it looks like one
+    NoRustFormat,
+    /// `wrap`: long lines of the message are wrapped when written out
+    Wrap,
+    /// `no-wrap`: long lines of the message are never wrapped
+    NoWrap,
+    /// Any other flag, e.g. `python-format` or `range: 0..10`
+    Other(String),
+}
+
+impl Flag {
+    /// Returns the flag as it is written in PO files.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Flag::Fuzzy => "fuzzy",
+            Flag::CFormat => "c-format",
+            Flag::NoCFormat => "no-c-format",
+            Flag::RustFormat => "rust-format",
+            Flag::NoRustFormat => "no-rust-format",
+            Flag::Wrap => "wrap",
+            Flag::NoWrap => "no-wrap",
+            Flag::Other(ref flag) => flag,
+        }
+    }
+}
+
+impl From<&str> for Flag {
+    /// Makes a flag from the way it is written in PO files.
+    fn from(flag: &str) -> Self {
+        match flag {
+            "fuzzy" => Flag::Fuzzy,
+            "c-format" => Flag::CFormat,
+            "no-c-format" => Flag::NoCFormat,
+            "rust-format" => Flag::RustFormat,
+            "no-rust-format" => Flag::NoRustFormat,
+            "wrap" => Flag::Wrap,
+            "no-wrap" => Flag::NoWrap,
+            _ => Flag::Other(flag.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Flag {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+#[test]
+fn test_flag() {
+    for flag in &[
+        "fuzzy",
+        "c-format",
+        "no-c-format",
+        "rust-format",
+        "no-rust-format",
+        "wrap",
+        "no-wrap",
+        "python-format",
+    ] {
+        assert_eq!(Flag::from(*flag).as_str(), *flag);
+    }
+    assert_eq!(Flag::from("c-format"), Flag::CFormat);
+    assert_eq!(
+        Flag::from("range: 0..10"),
+        Flag::Other("range: 0..10".to_owned())
+    );
+    assert_eq!(Flag::NoWrap.to_string(), "no-wrap");
+    assert!(Flag::Fuzzy < Flag::CFormat);
+}
@@ -0,0 +1,926 @@
+//! Lossy conversion of catalogs from and to Fluent resources.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::Error::{self, *};
+use crate::{Catalog, CatalogBuilder, Message, PluralCategory};
+
+/// The variable selecting the plural form of written messages
+/// whose plural msgid has no placeholder.
+const VARIABLE: &str = "n";
+
+/// Returns whether a name is a Fluent identifier, e.g. `open-file`.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Returns whether a msgid can be written as it is, as the identifier of a message,
+/// of a term like `-brand`, or of an attribute like `login.placeholder`.
+fn is_key(id: &str) -> bool {
+    let id = id.strip_prefix('-').unwrap_or(id);
+    match id.split_once('.') {
+        Some((name, attribute)) => is_identifier(name) && is_identifier(attribute),
+        None => is_identifier(id),
+    }
+}
+
+/// Makes an identifier from the msgctxt and the msgid of a message,
+/// e.g. `open-file` for `Open file…`.
+fn slug(msg: &Message) -> String {
+    let text = match msg.context {
+        Some(ref context) => format!("{} {}", context, msg.id),
+        None => msg.id.clone(),
+    };
+    let mut slug = String::from("m-");
+    for c in text.chars() {
+        if slug.len() >= 40 {
+            break;
+        }
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    // the prefix is only kept for identifiers that would not start with a letter
+    match slug.get(2..).and_then(|rest| rest.chars().next()) {
+        Some(c) if c.is_ascii_alphabetic() => slug[2..].to_owned(),
+        _ => slug.to_owned(),
+    }
+}
+
+/// Returns the text as the text of a Fluent pattern, with `{name}` placeholders
+/// as variables and what Fluent would read as syntax in string literals.
+/// The lines after the first one are indented with `indent`.
+fn escape(text: &str, indent: &str) -> String {
+    let mut out = String::new();
+    let lines = text.split('\n').collect::<Vec<_>>();
+    for (i, &line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+            out.push_str(indent);
+        }
+        if line.is_empty() {
+            // blank lines and trailing newlines would be dropped
+            if lines.len() > 1 {
+                out.push_str("{\"\"}");
+            }
+            continue;
+        }
+        let body = line.trim_start_matches(' ');
+        let leading = &line[..line.len() - body.len()];
+        let body = match i + 1 == lines.len() {
+            true => body.trim_end_matches(' '),
+            false => body,
+        };
+        let trailing = &line[leading.len() + body.len()..];
+        if !leading.is_empty() {
+            out.push_str(&format!("{{\"{}\"}}", leading));
+        }
+        let mut rest = body;
+        if rest.starts_with(['[', '*', '.']) {
+            out.push_str(&format!("{{\"{}\"}}", &rest[..1]));
+            rest = &rest[1..];
+        }
+        while let Some(i) = rest.find(['{', '}']) {
+            out.push_str(&rest[..i]);
+            let name = rest[i + 1..]
+                .split('}')
+                .next()
+                .filter(|_| rest[i..].starts_with('{'));
+            match name.filter(|name| is_identifier(name) && rest[i..].contains('}')) {
+                Some(name) => {
+                    out.push_str(&format!("{{ ${} }}", name));
+                    rest = &rest[i + name.len() + 2..];
+                }
+                None => {
+                    out.push_str(&format!("{{\"{}\"}}", &rest[i..i + 1]));
+                    rest = &rest[i + 1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        if !trailing.is_empty() {
+            out.push_str(&format!("{{\"{}\"}}", trailing));
+        }
+    }
+    out
+}
+
+/// Returns the depth of placeables after a line of a pattern, from the depth before it.
+fn depth_after(line: &str, mut depth: usize) -> usize {
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            // string literals may have braces
+            '"' if depth > 0 => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// A part of a Fluent pattern.
+#[derive(Debug, PartialEq)]
+enum Element {
+    Text(String),
+    Select(Vec<Variant>),
+}
+
+/// A variant of a select expression.
+#[derive(Debug, PartialEq)]
+struct Variant {
+    key: String,
+    default: bool,
+    elements: Vec<Element>,
+}
+
+/// Returns the text of the elements, with the default variants of any selects.
+fn flatten(elements: &[Element]) -> String {
+    let mut out = String::new();
+    for element in elements {
+        match *element {
+            Element::Text(ref text) => out.push_str(text),
+            Element::Select(ref variants) => {
+                let default = variants.iter().find(|v| v.default).or(variants.last());
+                out.push_str(&flatten(&default.unwrap().elements));
+            }
+        }
+    }
+    out
+}
+
+/// Returns whether the keys of the variants of a select are plural categories or numbers.
+fn is_plural(variants: &[Variant]) -> bool {
+    variants
+        .iter()
+        .all(|v| v.key.parse::<PluralCategory>().is_ok() || v.key.parse::<u64>().is_ok())
+}
+
+/// Reads the elements of the pattern of an entry, after its indentation has been removed.
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    /// The line of the entry, for errors.
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self) -> Error {
+        FtlSyntax(self.line)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Reads elements up to the end of the pattern or, in a variant, up to the next variant.
+    fn elements(&mut self, variant: bool) -> Result<Vec<Element>, Error> {
+        let mut elements = vec![];
+        let mut text = String::new();
+        loop {
+            match self.chars.peek() {
+                None if variant => return Err(self.error()),
+                None => break,
+                Some('\n') if variant => {
+                    self.skip_whitespace();
+                    match self.chars.peek() {
+                        Some('[') | Some('*') | Some('}') => break,
+                        _ => text.push('\n'),
+                    }
+                }
+                Some('{') => {
+                    self.chars.next();
+                    match self.placeable()? {
+                        Element::Text(s) => text.push_str(&s),
+                        select => {
+                            if !text.is_empty() {
+                                elements.push(Element::Text(text.split_off(0)));
+                            }
+                            elements.push(select);
+                        }
+                    }
+                }
+                Some('}') => return Err(self.error()),
+                Some(&c) => {
+                    self.chars.next();
+                    text.push(c);
+                }
+            }
+        }
+        if variant {
+            text.truncate(text.trim_end().len());
+        }
+        if !text.is_empty() {
+            elements.push(Element::Text(text));
+        }
+        Ok(elements)
+    }
+
+    /// Reads a placeable after its opening brace.
+    ///
+    /// String literals become their text, and variables `{name}` placeholders.
+    /// Other expressions, like references to messages, are kept as they are written.
+    fn placeable(&mut self) -> Result<Element, Error> {
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'"').is_some() {
+            let text = self.literal()?;
+            self.skip_whitespace();
+            return match self.chars.next() {
+                Some('}') => Ok(Element::Text(text)),
+                _ => Err(self.error()),
+            };
+        }
+        let mut expression = String::new();
+        loop {
+            match self.chars.next().ok_or_else(|| self.error())? {
+                '}' => break,
+                '-' if self.chars.next_if_eq(&'>').is_some() => return self.select(),
+                '"' => {
+                    let literal = self.literal()?;
+                    expression.push_str(&format!("{:?}", literal));
+                }
+                '{' => return Err(self.error()),
+                c => expression.push(c),
+            }
+        }
+        let expression = expression.trim();
+        Ok(Element::Text(match expression.strip_prefix('$') {
+            Some(name) if is_identifier(name) => format!("{{{}}}", name),
+            _ => format!("{{ {} }}", expression),
+        }))
+    }
+
+    /// Reads a string literal after its opening quote.
+    fn literal(&mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next().ok_or_else(|| self.error())? {
+                '"' => return Ok(text),
+                '\n' => return Err(self.error()),
+                '\\' => match self.chars.next().ok_or_else(|| self.error())? {
+                    c @ '"' | c @ '\\' => text.push(c),
+                    c @ 'u' | c @ 'U' => {
+                        let len = if c == 'u' { 4 } else { 6 };
+                        let hex = (0..len)
+                            .filter_map(|_| self.chars.next())
+                            .collect::<String>();
+                        let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                        text.push(c.filter(|_| hex.len() == len).ok_or_else(|| self.error())?);
+                    }
+                    _ => return Err(self.error()),
+                },
+                c => text.push(c),
+            }
+        }
+    }
+
+    /// Reads the variants of a select expression after its arrow.
+    fn select(&mut self) -> Result<Element, Error> {
+        let mut variants = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&'}').is_some() {
+                break;
+            }
+            let default = self.chars.next_if_eq(&'*').is_some();
+            if self.chars.next() != Some('[') {
+                return Err(self.error());
+            }
+            let mut key = String::new();
+            loop {
+                match self.chars.next().ok_or_else(|| self.error())? {
+                    ']' => break,
+                    '\n' => return Err(self.error()),
+                    c => key.push(c),
+                }
+            }
+            while self.chars.next_if_eq(&' ').is_some() {}
+            variants.push(Variant {
+                key: key.trim().to_owned(),
+                default,
+                elements: self.elements(true)?,
+            });
+        }
+        match variants.is_empty() {
+            true => Err(self.error()),
+            false => Ok(Element::Select(variants)),
+        }
+    }
+}
+
+/// Returns the elements of a pattern, from the text after the `=` of its entry
+/// and the indented lines after it.
+fn pattern(inline: &str, lines: &[&str], line: usize) -> Result<Vec<Element>, Error> {
+    let leading = |l: &str| l.len() - l.trim_start_matches(' ').len();
+    // the common indentation of the lines of text, whereas whitespace in placeables is free
+    let mut indent = None;
+    let mut depth = depth_after(inline, 0);
+    for l in lines {
+        if depth == 0 && !l.trim().is_empty() {
+            indent = Some(indent.unwrap_or(usize::MAX).min(leading(l)));
+        }
+        depth = depth_after(l, depth);
+    }
+    let indent = indent.unwrap_or(0);
+    let mut src = inline.trim_start().to_owned();
+    for l in lines {
+        if !src.is_empty() {
+            src.push('\n');
+        }
+        src.push_str(&l[leading(l).min(indent)..]);
+    }
+    let src = src.trim_end();
+    let mut parser = Parser {
+        chars: src.chars().peekable(),
+        line,
+    };
+    parser.elements(false)
+}
+
+/// An entry of a Fluent resource, with the patterns of its value and attributes.
+struct Entry {
+    name: String,
+    comment: Vec<String>,
+    value: Vec<Element>,
+    attributes: Vec<(String, Vec<Element>)>,
+}
+
+/// Reads the messages and terms of a Fluent resource.
+fn entries(src: &str) -> Result<Vec<Entry>, Error> {
+    let lines = src.lines().collect::<Vec<_>>();
+    let mut entries = vec![];
+    let mut comment = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let line_no = i + 1;
+        i += 1;
+        if line.trim().is_empty() {
+            comment.clear();
+            continue;
+        }
+        if line.starts_with('#') {
+            match line.strip_prefix("# ") {
+                Some(text) => comment.push(text.to_owned()),
+                None if line == "#" => comment.push(String::new()),
+                // group and resource comments
+                None => comment.clear(),
+            }
+            continue;
+        }
+        let (name, inline) = line.split_once('=').ok_or(FtlSyntax(line_no))?;
+        let name = name.trim_end();
+        if !is_identifier(name.strip_prefix('-').unwrap_or(name)) {
+            return Err(FtlSyntax(line_no));
+        }
+        // the indented lines of the entry, the blank lines between them
+        // and the lines in placeables
+        let start = i;
+        let mut depth = depth_after(inline, 0);
+        while i < lines.len()
+            && (depth > 0 || lines[i].starts_with(' ') || lines[i].trim().is_empty())
+        {
+            depth = depth_after(lines[i], depth);
+            i += 1;
+        }
+        while i > start && lines[i - 1].trim().is_empty() {
+            i -= 1;
+        }
+        let block = &lines[start..i];
+
+        // attributes start on lines beginning with `.` outside of placeables
+        let mut parts = vec![(inline, start, start)];
+        let mut depth = depth_after(inline, 0);
+        for (j, l) in block.iter().enumerate() {
+            if depth == 0 && l.trim_start().starts_with('.') {
+                parts.push((l.trim_start(), start + j + 1, start + j + 1));
+            } else {
+                parts.last_mut().unwrap().2 = start + j + 1;
+            }
+            depth = depth_after(l, depth);
+        }
+        let mut entry = Entry {
+            name: name.to_owned(),
+            comment: comment.split_off(0),
+            value: pattern(parts[0].0, &lines[parts[0].1..parts[0].2], line_no)?,
+            attributes: vec![],
+        };
+        for &(first, from, to) in &parts[1..] {
+            let (attribute, inline) = first[1..].split_once('=').ok_or(FtlSyntax(from))?;
+            let attribute = attribute.trim_end();
+            if !is_identifier(attribute) {
+                return Err(FtlSyntax(from));
+            }
+            let value = pattern(inline, &lines[from..to], from)?;
+            entry.attributes.push((attribute.to_owned(), value));
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+impl Catalog {
+    /// Reads a catalog for `language` from a Fluent resource, e.g. `lt/main.ftl` for `lt`.
+    ///
+    /// Fluent messages are keyed by identifiers, which become the msgids:
+    /// `open-file` for a message, `-brand` for a term and `login.placeholder`
+    /// for an attribute. The comment before an entry becomes its extracted comment.
+    /// The conversion is lossy:
+    ///
+    /// * variables become `{name}` placeholders, string literals become their text,
+    ///   and other placeables, like references to messages, are kept as they are written,
+    /// * a select on plural categories becomes the plural forms, mapped through
+    ///   the numbers that CLDR assigns to each category in the language,
+    ///   with the default variant for the forms without a variant of their own;
+    ///   `[0]`, `[1]` and `[2]` stand for the `zero`, `one` and `two` categories
+    ///   when those have no variant, and the plural msgid is the same as the msgid,
+    /// * other selects, and selects in variants, are replaced by their default variants.
+    ///
+    /// Returns `FtlSyntax` if the resource is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::Catalog;
+    ///
+    /// let ftl = "\
+    /// ## In the menu
+    /// open-file = Atidaryti { $name }
+    /// files =
+    ///     { $n ->
+    ///         [one] { $n } failas
+    ///         [few] { $n } failai
+    ///        *[other] { $n } failų
+    ///     }
+    /// ";
+    /// let catalog = Catalog::parse_ftl(ftl.as_bytes(), "lt").unwrap();
+    /// assert_eq!(catalog.gettext("open-file"), "Atidaryti {name}");
+    /// assert_eq!(catalog.ngettext("files", "files", 3), "{n} failai");
+    /// ```
+    pub fn parse_ftl<R: Read>(mut reader: R, language: &str) -> Result<Catalog, Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let src = String::from_utf8(bytes).map_err(|_| DecodingError)?;
+        let src = src.strip_prefix('\u{feff}').unwrap_or(&src);
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .header("Language", language)
+            .build()?;
+        let nplurals = catalog.nplurals();
+
+        for entry in entries(src)? {
+            let Entry {
+                name,
+                comment,
+                value,
+                attributes,
+            } = entry;
+            let attributes = attributes
+                .into_iter()
+                .map(|(attribute, value)| (format!("{}.{}", name, attribute), value));
+            let patterns = std::iter::once((name.clone(), value)).chain(attributes);
+            for (i, (id, elements)) in patterns.enumerate() {
+                if elements.is_empty() {
+                    continue;
+                }
+                let plural = elements.iter().position(|e| match *e {
+                    Element::Select(ref variants) => is_plural(variants),
+                    _ => false,
+                });
+                let mut msg = match plural {
+                    Some(p) => {
+                        let prefix = flatten(&elements[..p]);
+                        let suffix = flatten(&elements[p + 1..]);
+                        let variants = match elements[p] {
+                            Element::Select(ref variants) => variants,
+                            _ => unreachable!(),
+                        };
+                        let form = |variant: &Variant| {
+                            format!("{}{}{}", prefix, flatten(&variant.elements), suffix)
+                        };
+                        let category = |key: &str| match key.parse::<PluralCategory>() {
+                            Ok(category) => Some(category),
+                            Err(_) => {
+                                let exact = match key {
+                                    "0" => PluralCategory::Zero,
+                                    "1" => PluralCategory::One,
+                                    "2" => PluralCategory::Two,
+                                    _ => return None,
+                                };
+                                let taken = variants.iter().any(|v| v.key == exact.as_str());
+                                Some(exact).filter(|_| !taken)
+                            }
+                        };
+                        let mut translated = vec![String::new(); nplurals];
+                        for variant in variants {
+                            let n = category(&variant.key).and_then(|c| c.sample(language));
+                            let form_no = n.map(|n| catalog.resolver.resolve(n));
+                            if let Some(slot) = form_no.and_then(|i| translated.get_mut(i)) {
+                                *slot = form(variant);
+                            }
+                        }
+                        let default = variants.iter().find(|v| v.default).or(variants.last());
+                        let default = form(default.unwrap());
+                        for slot in translated.iter_mut().filter(|s| s.is_empty()) {
+                            *slot = default.clone();
+                        }
+                        let mut msg = Message::new(id.clone(), None, vec![]);
+                        msg.translated = translated;
+                        msg.id_plural = Some(id);
+                        msg
+                    }
+                    None => Message::new(id, None, vec![flatten(&elements)]),
+                };
+                if i == 0 {
+                    msg.extracted_comments = comment.clone();
+                }
+                catalog.insert(msg);
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the translated messages of the catalog out as a Fluent resource,
+    /// keyed as `parse_ftl` reads them.
+    ///
+    /// Messages whose msgid is not an identifier, or that have a msgctxt,
+    /// get an identifier made from their msgctxt and msgid, e.g. `open-file` for `Open file`,
+    /// and a comment with their msgid. `{name}` placeholders are written as variables,
+    /// while other placeholders, like `%s`, are left as they are.
+    /// Plural messages are written as a select on the first placeholder of their plural msgid,
+    /// or on `$n` if it has none, with a variant for each category that CLDR defines
+    /// for the `Language` of the catalog, taken from the plural form that the catalog uses
+    /// for that category, and an `other` default variant with the last form
+    /// if the language only uses it for fractions.
+    /// Extracted comments are written as comments before their entries,
+    /// and untranslated messages are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .header("Language", "lt")
+    ///     .add("Open {name}", "Atidaryti {name}")
+    ///     .add_plural("{count} file", "{count} files", vec!["{count} failas", "{count} failai", "{count} failų"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut ftl = vec![];
+    /// catalog.write_ftl(&mut ftl).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ftl).unwrap(),
+    ///     "\
+    /// ## msgid: {count} file
+    /// count-file =
+    ///     { $count ->
+    ///         [one] { $count } failas
+    ///         [few] { $count } failai
+    ///        *[other] { $count } failų
+    ///     }
+    ///
+    /// ## msgid: Open {name}
+    /// open-name = Atidaryti { $name }
+    /// ",
+    /// );
+    /// ```
+    pub fn write_ftl<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let metadata = self.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let mut messages = self
+            .strings
+            .values()
+            .filter(|msg| !msg.is_header() && msg.is_translated())
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|&msg| (&msg.context, &msg.id));
+
+        // the messages by entry and attribute, and the identifiers made from msgids
+        let mut entries = BTreeMap::<String, BTreeMap<&str, (&Message, bool)>>::new();
+        let mut made = vec![];
+        for msg in messages {
+            match msg.id.split_once('.') {
+                _ if !is_key(&msg.id) || msg.context.is_some() => made.push(msg),
+                Some((name, attribute)) => {
+                    let attributes = entries.entry(name.to_owned()).or_default();
+                    attributes.insert(attribute, (msg, false));
+                }
+                None => {
+                    let attributes = entries.entry(msg.id.clone()).or_default();
+                    attributes.insert("", (msg, false));
+                }
+            }
+        }
+        let mut taken = entries.keys().cloned().collect::<HashSet<_>>();
+        for msg in made {
+            let slug = slug(msg);
+            let mut name = slug.clone();
+            for i in 2.. {
+                if taken.insert(name.clone()) {
+                    break;
+                }
+                name = format!("{}-{}", slug, i);
+            }
+            entries.entry(name).or_default().insert("", (msg, true));
+        }
+
+        let mut out = String::new();
+        for (name, attributes) in entries {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            if let Some(&(msg, made)) = attributes.get("") {
+                for comment in &msg.extracted_comments {
+                    out.push_str(format!("# {}", comment).trim_end());
+                    out.push('\n');
+                }
+                if made {
+                    if let Some(ref context) = msg.context {
+                        out.push_str(&format!("# msgctxt: {}\n", context.replace('\n', "\\n")));
+                    }
+                    out.push_str(&format!("# msgid: {}\n", msg.id.replace('\n', "\\n")));
+                }
+            }
+            match attributes.get("") {
+                Some(&(msg, _)) => self.push_ftl_pattern(&mut out, &name, msg, language, 0),
+                None => out.push_str(&format!("{} =\n", name)),
+            }
+            for (attribute, &(msg, _)) in attributes.iter().filter(|(a, _)| !a.is_empty()) {
+                let attribute = format!(".{}", attribute);
+                self.push_ftl_pattern(&mut out, &attribute, msg, language, 4);
+            }
+        }
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    /// Appends `name = pattern` for the translation of a message, indented by `indent` spaces.
+    fn push_ftl_pattern(
+        &self,
+        out: &mut String,
+        name: &str,
+        msg: &Message,
+        language: &str,
+        indent: usize,
+    ) {
+        let inner = " ".repeat(indent + 4);
+        out.push_str(&format!("{}{} =", " ".repeat(indent), name));
+        let id_plural = match msg.id_plural {
+            Some(ref id_plural) => id_plural,
+            None => {
+                let text = &msg.translated[0];
+                // text on several lines is written as a block
+                match text.contains('\n') {
+                    true => out.push_str(&format!("\n{}", inner)),
+                    false => out.push(' '),
+                }
+                out.push_str(&escape(text, &inner));
+                out.push('\n');
+                return;
+            }
+        };
+        let mut variants = vec![];
+        for &category in PluralCategory::all() {
+            let form = category.sample(language).map(|n| self.resolver.resolve(n));
+            if let Some(text) = form.and_then(|form| msg.get_translated(form)) {
+                variants.push((category, text));
+            }
+        }
+        // Fluent requires a default variant, and `other` is only used for fractions in some languages
+        if !variants.iter().any(|&(c, _)| c == PluralCategory::Other) {
+            let last = msg.translated.iter().rev().find(|s| !s.is_empty());
+            variants.push((PluralCategory::Other, last.unwrap()));
+        }
+        let variable = placeholder(id_plural).unwrap_or(VARIABLE);
+        out.push_str(&format!("\n{}{{ ${} ->\n", inner, variable));
+        let continuation = format!("{}        ", inner);
+        for (category, text) in variants {
+            let marker = match category {
+                PluralCategory::Other => "   *",
+                _ => "    ",
+            };
+            out.push_str(&format!(
+                "{}{}[{}] {}\n",
+                inner,
+                marker,
+                category.as_str(),
+                escape(text, &continuation)
+            ));
+        }
+        out.push_str(&format!("{}}}\n", inner));
+    }
+}
+
+/// Returns the first `{name}` placeholder of a text that can be a Fluent variable.
+fn placeholder(text: &str) -> Option<&str> {
+    let mut rest = text;
+    while let Some(i) = rest.find('{') {
+        rest = &rest[i + 1..];
+        let name = rest.split('}').next().unwrap_or("");
+        if rest.contains('}') && is_identifier(name) {
+            return Some(name);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_escape() {
+    for &(text, escaped) in &[
+        ("plain", "plain"),
+        (
+            "Hi {name}, {0} {{x}} }",
+            "Hi { $name }, {\"{\"}0{\"}\"} {\"{\"}{ $x }{\"}\"} {\"}\"}",
+        ),
+        ("  padded ", "{\"  \"}padded{\" \"}"),
+        (
+            "[a]\n.b\n\n*c\n",
+            "{\"[\"}a]\n  {\".\"}b\n  {\"\"}\n  {\"*\"}c\n  {\"\"}",
+        ),
+    ] {
+        assert_eq!(escape(text, "  "), escaped);
+        let ftl = format!("m =\n  {}\n", escaped);
+        let catalog = Catalog::parse_ftl(ftl.as_bytes(), "en").unwrap();
+        assert_eq!(catalog.gettext("m"), text);
+    }
+    assert!(is_key("open-file"));
+    assert!(is_key("-brand"));
+    assert!(is_key("login.placeholder"));
+    assert!(is_key("Open"));
+    assert!(!is_key("Open file"));
+    assert!(!is_key("a.b.c"));
+    assert!(!is_key("1st"));
+}
+
+#[test]
+fn test_parse_ftl() {
+    let ftl = "\
+### Resource comment
+
+# A term
+-brand = Firefox
+
+## Group comment
+# About the login
+# in two lines
+login = Prisijungti prie { -brand }
+    .placeholder = el. paštas
+    .title =
+        { $n ->
+            [one] { $n } bandymas
+           *[other] { $n } bandymų
+        }
+emails =
+    Jūs turite { $count ->
+        [0] jokių laiškų
+        [one] { $count } laišką
+        [few] { $count }
+            laiškus
+       *[many] { $count } laiškų
+    }.
+gender = { $g ->
+    [female] Ji
+   *[other] Jie
+} atėjo
+empty-value =
+    .attr = Tik atributas
+literal = {\"\\u0105\\\"\"}
+";
+    let catalog = Catalog::parse_ftl(ftl.as_bytes(), "lt").unwrap();
+    assert_eq!(catalog.gettext("-brand"), "Firefox");
+    assert_eq!(
+        catalog.get("-brand").unwrap().extracted_comments(),
+        &["A term"]
+    );
+    assert_eq!(catalog.gettext("login"), "Prisijungti prie { -brand }");
+    assert_eq!(
+        catalog.get("login").unwrap().extracted_comments(),
+        &["About the login", "in two lines"]
+    );
+    assert_eq!(catalog.gettext("login.placeholder"), "el. paštas");
+    assert!(catalog
+        .get("login.placeholder")
+        .unwrap()
+        .extracted_comments()
+        .is_empty());
+    let title = catalog.get("login.title").unwrap();
+    assert_eq!(
+        title.translated(),
+        &["{n} bandymas", "{n} bandymų", "{n} bandymų"]
+    );
+    let emails = catalog.get("emails").unwrap();
+    assert_eq!(emails.id_plural(), Some("emails"));
+    assert_eq!(
+        emails.translated(),
+        &[
+            "Jūs turite {count} laišką.",
+            "Jūs turite {count}\nlaiškus.",
+            "Jūs turite {count} laiškų."
+        ]
+    );
+    assert_eq!(catalog.gettext("gender"), "Jie atėjo");
+    assert!(catalog.get("empty-value").is_none());
+    assert_eq!(catalog.gettext("empty-value.attr"), "Tik atributas");
+    assert_eq!(catalog.gettext("literal"), "ą\"");
+
+    for (bad, line) in &[
+        ("no equals sign", 1),
+        ("ok = a\n1st = b", 2),
+        ("a = {", 1),
+        ("a = }", 1),
+        ("a = { $n ->\n  [one] x\n", 1),
+        ("a = { $n -> }", 1),
+        ("a = {\"unclosed }", 1),
+        ("a = b\n  .1 = c", 2),
+    ] {
+        let result = Catalog::parse_ftl(bad.as_bytes(), "en");
+        assert!(matches!(result, Err(FtlSyntax(l)) if l == *line), "{}", bad);
+    }
+}
+
+#[test]
+fn test_write_ftl() {
+    let catalog = CatalogBuilder::new()
+        .header("Language", "pl")
+        .add("login", "Zaloguj")
+        .add("login.placeholder", "e-mail")
+        .add("about.title", "O programie")
+        .add("-brand", "Firefox")
+        .add("Open", "Otwórz")
+        .add("Open!", "Otwórz!")
+        .add_with_context("menu", "Open", "Otwórz menu")
+        .add("open", "otwórz")
+        .add("Two\nlines", "Dwie\nlinie")
+        .add_plural(
+            "%d file",
+            "%d files",
+            vec!["%d plik", "%d pliki", "%d plików"],
+        )
+        .add("untranslated", "")
+        .build()
+        .unwrap();
+    let mut ftl = vec![];
+    catalog.write_ftl(&mut ftl).unwrap();
+    let ftl = String::from_utf8(ftl).unwrap();
+    assert_eq!(
+        ftl,
+        "\
+-brand = Firefox
+
+Open = Otwórz
+
+about =
+    .title = O programie
+
+# msgid: %d file
+d-file =
+    { $n ->
+        [one] %d plik
+        [few] %d pliki
+        [many] %d plików
+       *[other] %d plików
+    }
+
+login = Zaloguj
+    .placeholder = e-mail
+
+# msgctxt: menu
+# msgid: Open
+menu-open = Otwórz menu
+
+open = otwórz
+
+# msgid: Open!
+open-2 = Otwórz!
+
+# msgid: Two\\nlines
+two-lines =
+    Dwie
+    linie
+"
+    );
+
+    let read = Catalog::parse_ftl(ftl.as_bytes(), "pl").unwrap();
+    assert_eq!(read.gettext("login.placeholder"), "e-mail");
+    assert_eq!(read.gettext("two-lines"), "Dwie\nlinie");
+    assert_eq!(read.ngettext("d-file", "d-file", 5), "%d plików");
+    assert!(read.get("about").is_none());
+}
@@ -0,0 +1,552 @@
+//! Runtime substitution of placeholders in translated strings.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+use crate::parser::Warnings;
+use crate::{printf, Catalog, Error, Flag, Message};
+
+/// FormatStyle is the syntax of the placeholders used in the messages of a catalog.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FormatStyle {
+    /// C `printf` directives, e.g. `%d`, `%.2f` or `%1$s`
+    Printf,
+    /// Rust `format!` placeholders, e.g. `{}`, `{0}` or `{name}`
+    Rust,
+}
+
+/// The placeholders of a string, by argument, with their printf conversions.
+type Placeholders = BTreeMap<String, char>;
+
+/// Returns the placeholders of a `format!`-style string,
+/// or `None` if the string is malformed.
+fn rust_placeholders(s: &str) -> Option<Placeholders> {
+    let mut placeholders = Placeholders::new();
+    let mut next = 0;
+    let mut rest = s;
+    while let Some(i) = rest.find(['{', '}']) {
+        let brace = &rest[i..];
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            rest = &brace[2..];
+            continue;
+        }
+        if brace.starts_with('}') {
+            return None;
+        }
+        let end = brace.find('}')?;
+        let name = brace[1..end].split(':').next().unwrap_or("");
+        let name = if name.is_empty() {
+            next += 1;
+            (next - 1).to_string()
+        } else {
+            name.to_owned()
+        };
+        placeholders.insert(name, ' ');
+        rest = &brace[end + 1..];
+    }
+    Some(placeholders)
+}
+
+/// Returns the directives of a `printf`-style string,
+/// or `None` if the string is malformed.
+fn printf_placeholders(s: &str) -> Option<Placeholders> {
+    let directives = printf::parse(s).ok()?;
+    let placeholders = directives
+        .iter()
+        .map(|d| (d.position().to_string(), d.conversion()));
+    Some(placeholders.collect())
+}
+
+/// Checks that the translations of the catalog use the placeholders of their msgids,
+/// leaving out the messages whose translations do not when parsing leniently.
+///
+/// If the catalog carries flags, as PO files do, only the messages flagged
+/// as `c-format` or `rust-format` are checked, like `msgfmt -c` does.
+/// Otherwise all messages whose msgids are valid format strings are.
+pub(crate) fn check_formats(
+    catalog: &mut Catalog,
+    style: FormatStyle,
+    warnings: &mut Warnings,
+) -> Result<(), Error> {
+    let (placeholders, marker): (fn(&str) -> _, _) = match style {
+        FormatStyle::Printf => (printf_placeholders, Flag::CFormat),
+        FormatStyle::Rust => (rust_placeholders, Flag::RustFormat),
+    };
+    let flagged = catalog.strings.values().any(|msg| !msg.flags.is_empty());
+    let mut mismatched = catalog
+        .strings
+        .iter()
+        .filter(|(_, msg)| !msg.id.is_empty() && (!flagged || msg.flags.contains(&marker)))
+        .filter(|(_, msg)| !uses_placeholders(msg, placeholders))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    mismatched.sort();
+    for key in mismatched {
+        if let Some(msg) = catalog.strings.remove(&key) {
+            warnings.skip(None, Error::FormatMismatch(msg.id))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the translations of the message use the placeholders of its msgid,
+/// which is always the case if the msgid is not a valid format string.
+fn uses_placeholders(msg: &Message, placeholders: fn(&str) -> Option<Placeholders>) -> bool {
+    let mut original = match placeholders(&msg.id) {
+        Some(original) => original,
+        None => return true,
+    };
+    if let Some(ref plural) = msg.id_plural {
+        match placeholders(plural) {
+            Some(plural) => original.extend(plural),
+            None => return true,
+        }
+    }
+    msg.translated
+        .iter()
+        .filter(|s| !s.is_empty())
+        .all(|form| match placeholders(form) {
+            Some(translated) if msg.id_plural.is_some() => translated
+                .iter()
+                .all(|(arg, conv)| original.get(arg) == Some(conv)),
+            Some(translated) => translated == original,
+            None => false,
+        })
+}
+
+/// Wraps a macro argument so that it can be substituted into a translation.
+pub fn arg<T: fmt::Display>(value: &T) -> &dyn fmt::Display {
+    value
+}
+
+/// Substitutes the placeholders of `template` with the given arguments,
+/// following the syntax of `format!`:
+/// `{}` takes the next positional argument, `{0}` the given one,
+/// `{name}` the named one, and `{{` and `}}` are literal braces.
+///
+/// Arguments without a name are positional.
+/// Format specs after a `:` are ignored, and placeholders without a matching
+/// argument are kept as they are, so that a bad translation never panics.
+pub fn interpolate(template: &str, args: &[(Option<&str>, &dyn fmt::Display)]) -> String {
+    substitute(template, args, false).unwrap()
+}
+
+/// Substitutes the placeholders of `template` like `interpolate`,
+/// but returns `UnknownPlaceholder` for placeholders without a matching argument
+/// and for unmatched braces if `strict` is set.
+fn substitute(
+    template: &str,
+    args: &[(Option<&str>, &dyn fmt::Display)],
+    strict: bool,
+) -> Result<String, Error> {
+    let positional = args
+        .iter()
+        .filter(|(name, _)| name.is_none())
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>();
+    let named = |name: &str| {
+        args.iter()
+            .find(|(n, _)| *n == Some(name))
+            .map(|(_, value)| *value)
+    };
+    let unknown = |placeholder: &str| match strict {
+        true => Err(Error::UnknownPlaceholder(placeholder.to_owned())),
+        false => Ok(()),
+    };
+
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let brace = &rest[i..];
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            out.push_str(&brace[..1]);
+            rest = &brace[2..];
+            continue;
+        }
+        let end = match brace.find('}') {
+            Some(end) if brace.starts_with('{') => end,
+            _ => {
+                unknown(&brace[..1])?;
+                out.push_str(&brace[..1]);
+                rest = &brace[1..];
+                continue;
+            }
+        };
+        let placeholder = &brace[1..end];
+        let name = placeholder.split(':').next().unwrap_or("");
+        let value = if name.is_empty() {
+            next += 1;
+            positional.get(next - 1).copied()
+        } else if let Ok(index) = name.parse::<usize>() {
+            positional.get(index).copied()
+        } else {
+            named(name)
+        };
+        match value {
+            Some(value) => write!(out, "{}", value).unwrap(),
+            None => {
+                unknown(&brace[..=end])?;
+                out.push_str(&brace[..=end]);
+            }
+        }
+        rest = &brace[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+impl Catalog {
+    /// Translates `msg_id` and substitutes the named arguments into the translation,
+    /// for `{name}` placeholders that translators can reorder.
+    ///
+    /// `{{` and `}}` are literal braces, and format specs after a `:` are ignored.
+    /// Placeholders without a matching argument are kept as they are,
+    /// so that a bad translation never fails; see `gettext_fmt_strict`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .add("{user} opened {file}", "{file} atidarė {user}")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let text = catalog.gettext_fmt("{user} opened {file}", &[("user", &"Jonas"), ("file", &"a.txt")]);
+    /// assert_eq!(text, "a.txt atidarė Jonas");
+    /// assert_eq!(catalog.gettext_fmt("{{{n}}} of {total}", &[("n", &3)]), "{3} of {total}");
+    /// ```
+    pub fn gettext_fmt(&self, msg_id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let args = named_args(args);
+        interpolate(self.gettext(msg_id), &args)
+    }
+
+    /// Translates `msg_id` and substitutes the named arguments into the translation
+    /// like `gettext_fmt`, but fails on placeholders that it can not substitute.
+    ///
+    /// Returns `UnknownPlaceholder` if the translation has a placeholder
+    /// without a matching argument, including positional ones, or an unmatched brace.
+    pub fn gettext_fmt_strict(
+        &self,
+        msg_id: &str,
+        args: &[(&str, &dyn fmt::Display)],
+    ) -> Result<String, Error> {
+        let args = named_args(args);
+        substitute(self.gettext(msg_id), &args, true)
+    }
+}
+
+/// Returns named arguments in the form that `interpolate` takes.
+fn named_args<'a>(
+    args: &[(&'a str, &'a dyn fmt::Display)],
+) -> Vec<(Option<&'a str>, &'a dyn fmt::Display)> {
+    args.iter()
+        .map(|&(name, value)| (Some(name), value))
+        .collect()
+}
+
+/// Checks at compile time that the placeholders of the msgids match the arguments:
+/// every placeholder refers to an argument, and every argument is used by a msgid.
+///
+/// With `implicit_n`, the first positional argument and the one named `n`
+/// hold the number of objects, which the msgids do not have to use.
+pub const fn check_placeholders(
+    msg_ids: &[&str],
+    positional: usize,
+    names: &[&str],
+    implicit_n: bool,
+) {
+    const fn eq(a: &[u8], b: &[u8]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    assert!(positional <= 64 && names.len() <= 64, "too many arguments");
+    let (mut used_positional, mut used_names) = (0u64, 0u64);
+    let mut m = 0;
+    while m < msg_ids.len() {
+        let bytes = msg_ids[m].as_bytes();
+        let (mut i, mut next) = (0, 0);
+        while i < bytes.len() {
+            if bytes[i] == b'}' {
+                assert!(
+                    i + 1 < bytes.len() && bytes[i + 1] == b'}',
+                    "invalid format string: unmatched closing brace in the msgid"
+                );
+                i += 2;
+                continue;
+            }
+            if bytes[i] != b'{' {
+                i += 1;
+                continue;
+            }
+            if i + 1 < bytes.len() && bytes[i + 1] == b'{' {
+                i += 2;
+                continue;
+            }
+            // the placeholder name lasts until the closing brace or the format spec
+            let start = i + 1;
+            let mut end = start;
+            while end < bytes.len() && bytes[end] != b'}' && bytes[end] != b':' {
+                end += 1;
+            }
+            let mut close = end;
+            while close < bytes.len() && bytes[close] != b'}' {
+                close += 1;
+            }
+            assert!(
+                close < bytes.len(),
+                "invalid format string: unmatched opening brace in the msgid"
+            );
+
+            let (name, _) = bytes.split_at(end);
+            let (_, name) = name.split_at(start);
+            if name.is_empty() {
+                assert!(
+                    next < positional,
+                    "a placeholder of the msgid has no matching argument"
+                );
+                used_positional |= 1 << next;
+                next += 1;
+            } else if name[0].is_ascii_digit() {
+                let (mut index, mut d) = (0, 0);
+                while d < name.len() {
+                    assert!(
+                        name[d].is_ascii_digit(),
+                        "invalid format string: bad placeholder in the msgid"
+                    );
+                    index = index * 10 + (name[d] - b'0') as usize;
+                    d += 1;
+                }
+                assert!(
+                    index < positional,
+                    "a placeholder of the msgid has no matching argument"
+                );
+                used_positional |= 1 << index;
+            } else {
+                let mut n = 0;
+                while n < names.len() && !eq(names[n].as_bytes(), name) {
+                    n += 1;
+                }
+                assert!(
+                    n < names.len(),
+                    "a named placeholder of the msgid has no matching argument"
+                );
+                used_names |= 1 << n;
+            }
+            i = close + 1;
+        }
+        m += 1;
+    }
+
+    let mut p = 0;
+    while p < positional {
+        assert!(
+            used_positional & (1 << p) != 0 || implicit_n && p == 0,
+            "an argument is not used by the msgid"
+        );
+        p += 1;
+    }
+    let mut n = 0;
+    while n < names.len() {
+        assert!(
+            used_names & (1 << n) != 0 || implicit_n && eq(names[n].as_bytes(), b"n"),
+            "a named argument is not used by the msgid"
+        );
+        n += 1;
+    }
+}
+
+#[test]
+fn test_interpolate() {
+    let (name, n) = ("Jonas", 3);
+    let args: &[(Option<&str>, &dyn fmt::Display)] =
+        &[(None, &name), (None, &n), (Some("user"), &"admin")];
+
+    assert_eq!(interpolate("Hello", args), "Hello");
+    assert_eq!(interpolate("{}: {} files", args), "Jonas: 3 files");
+    assert_eq!(interpolate("{1} files of {0}", args), "3 files of Jonas");
+    assert_eq!(interpolate("{user} is {0}", args), "admin is Jonas");
+    assert_eq!(interpolate("{{}} {:>5}", args), "{} Jonas");
+    assert_eq!(
+        interpolate("{} {} {} {5} {missing}", args),
+        "Jonas 3 {} {5} {missing}"
+    );
+    assert_eq!(interpolate("a } b { c", args), "a } b { c");
+    assert_eq!(interpolate("ąčę {}", args), "ąčę Jonas");
+}
+
+#[test]
+fn test_gettext_fmt() {
+    use crate::CatalogBuilder;
+
+    let catalog = CatalogBuilder::new()
+        .add("{n} files in {dir}", "{dir}: {n:>3} failai")
+        .add("Broken {n}", "Sugadinta {m}")
+        .add("Brace {n}", "Skliaustas {n} }")
+        .build()
+        .unwrap();
+    let args: &[(&str, &dyn fmt::Display)] = &[("n", &5), ("dir", &"/tmp")];
+
+    assert_eq!(
+        catalog.gettext_fmt("{n} files in {dir}", args),
+        "/tmp: 5 failai"
+    );
+    assert_eq!(
+        catalog.gettext_fmt("Untranslated {{{n}}}", args),
+        "Untranslated {5}"
+    );
+    assert_eq!(catalog.gettext_fmt("Broken {n}", args), "Sugadinta {m}");
+    assert_eq!(catalog.gettext_fmt("Positional {}", args), "Positional {}");
+    assert_eq!(
+        catalog
+            .gettext_fmt_strict("{n} files in {dir}", args)
+            .unwrap(),
+        "/tmp: 5 failai"
+    );
+    for &(msg_id, placeholder) in &[
+        ("Broken {n}", "{m}"),
+        ("Brace {n}", "}"),
+        ("Positional {}", "{}"),
+        ("Indexed {0}", "{0}"),
+        ("Open {", "{"),
+    ] {
+        match catalog.gettext_fmt_strict(msg_id, args) {
+            Err(Error::UnknownPlaceholder(p)) => assert_eq!(p, placeholder),
+            other => panic!("{}: {:?}", msg_id, other),
+        }
+    }
+}
+
+#[test]
+fn test_check_placeholders() {
+    use std::panic::catch_unwind;
+
+    check_placeholders(&["Hello"], 0, &[], false);
+    check_placeholders(&["{}: {{{}}}"], 2, &[], false);
+    check_placeholders(&["{1} of {0:>5}"], 2, &[], false);
+    check_placeholders(&["{user} and {}"], 1, &["user"], false);
+    check_placeholders(&["One file", "{} files in {dir}"], 1, &["n", "dir"], true);
+    check_placeholders(&["One file", "Files"], 1, &["n"], true);
+
+    let fails = |msg_id: &'static str, positional, names: &'static [&'static str]| {
+        catch_unwind(|| check_placeholders(&[msg_id], positional, names, false)).is_err()
+    };
+    assert!(fails("Hello, {}!", 0, &[]));
+    assert!(fails("Hello!", 1, &[]));
+    assert!(fails("{1}", 1, &[]));
+    assert!(fails("{user}", 0, &["name"]));
+    assert!(fails("Hello!", 0, &["name"]));
+    assert!(fails("{", 0, &[]));
+    assert!(fails("}", 0, &[]));
+    assert!(fails("{1x}", 2, &[]));
+}
+
+#[test]
+fn test_placeholders() {
+    let keys = |p: Option<Placeholders>| {
+        p.map(|p| {
+            p.into_iter()
+                .map(|(k, c)| format!("{}{}", k, c))
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(
+        keys(rust_placeholders("{} {{}} {name:>5} {}")),
+        Some(vec!["0 ".into(), "1 ".into(), "name ".into()])
+    );
+    assert_eq!(
+        keys(rust_placeholders("{1} {0}")),
+        Some(vec!["0 ".into(), "1 ".into()])
+    );
+    assert_eq!(keys(rust_placeholders("{")), None);
+    assert_eq!(keys(rust_placeholders("}")), None);
+
+    assert_eq!(
+        keys(printf_placeholders("%d%% of %-10.2f %s")),
+        Some(vec!["0d".into(), "1f".into(), "2s".into()])
+    );
+    assert_eq!(
+        keys(printf_placeholders("%2$s %1$i")),
+        Some(vec!["0d".into(), "1s".into()])
+    );
+    assert_eq!(keys(printf_placeholders("%lu")), Some(vec!["0u".into()]));
+    assert_eq!(keys(printf_placeholders("%1$s %1$d")), None);
+    assert_eq!(keys(printf_placeholders("%0$s")), None);
+    assert_eq!(keys(printf_placeholders("%y")), None);
+    assert_eq!(keys(printf_placeholders("50%")), None);
+}
+
+#[test]
+fn test_check_formats() {
+    use crate::{CatalogBuilder, ParseOptions};
+
+    let check = |style, id: &str, translated: &str| {
+        let mut catalog = CatalogBuilder::new().add(id, translated).build().unwrap();
+        check_formats(&mut catalog, style, &mut Warnings::default()).is_ok()
+    };
+    assert!(check(FormatStyle::Printf, "%d files", "%d failai"));
+    assert!(check(FormatStyle::Printf, "%s of %d", "%2$d iš %1$s"));
+    assert!(check(FormatStyle::Printf, "%d files", ""));
+    assert!(!check(FormatStyle::Printf, "%d files", "%s failai"));
+    assert!(!check(FormatStyle::Printf, "%d files", "failai"));
+    assert!(check(FormatStyle::Printf, "100%", "100 %"));
+    assert!(check(FormatStyle::Rust, "{} of {name}", "{name}: {}"));
+    assert!(!check(FormatStyle::Rust, "{} files", "{} {} failai"));
+    assert!(!check(FormatStyle::Rust, "{name}", "{vardas}"));
+    assert!(check(FormatStyle::Rust, "Use }", "Naudokite }"));
+
+    let mut catalog = CatalogBuilder::new()
+        .add("%d files", "failai")
+        .build()
+        .unwrap();
+    for msg in catalog.strings.values_mut() {
+        msg.flags.insert(Flag::NoCFormat);
+    }
+    assert!(check_formats(&mut catalog, FormatStyle::Printf, &mut Warnings::default()).is_ok());
+    assert!(check_formats(&mut catalog, FormatStyle::Rust, &mut Warnings::default()).is_ok());
+
+    let plural = |forms: Vec<&str>| {
+        let mut catalog = CatalogBuilder::new()
+            .add_plural("One file", "%d files", forms)
+            .build()
+            .unwrap();
+        check_formats(&mut catalog, FormatStyle::Printf, &mut Warnings::default())
+    };
+    assert!(plural(vec!["Vienas failas", "%d failai"]).is_ok());
+    match plural(vec!["Vienas failas", "%s failai"]) {
+        Err(Error::FormatMismatch(id)) => assert_eq!(id, "One file"),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // only the messages flagged as format strings are checked in PO files
+    let po = "#, c-format\nmsgid \"%d files\"\nmsgstr \"%s failai\"\n\n\
+              #, c-format\nmsgid \"%s of %d\"\nmsgstr \"%s iš %d\"\n\n\
+              msgid \"%d%% done\"\nmsgstr \"atlikta\"\n";
+    let options = || ParseOptions::new().validate_format_strings(FormatStyle::Printf);
+    match options().parse_po(po.as_bytes()) {
+        Err(Error::FormatMismatch(id)) => assert_eq!(id, "%d files"),
+        other => panic!("unexpected result {:?}", other),
+    }
+    let (catalog, warnings) = options()
+        .lenient(true)
+        .parse_po_with_warnings(po.as_bytes())
+        .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(warnings[0].error(), Error::FormatMismatch(id) if id == "%d files"));
+    assert_eq!(catalog.gettext("%d files"), "%d files");
+    assert_eq!(catalog.gettext("%s of %d"), "%s iš %d");
+    assert_eq!(catalog.gettext("%d%% done"), "atlikta");
+}
@@ -0,0 +1,211 @@
+//! A process-wide default catalog, for the classic API of gettext
+//! with free lookup functions.
+//!
+//! The catalog is set with `set_catalog`, typically once the locale of the user
+//! is known, and can be replaced at any time. Until then, the lookups return
+//! the original strings. The lookups return owned strings, as the catalog
+//! they are looked up in may be replaced while they are in use.
+//!
+//! The `gettext!` and `ngettext!` macros are `tr!` and `ntr!` with this catalog.
+//!
+//! Tests can override the catalog with `override_catalog` for the current thread only,
+//! so that tests running in parallel do not see each other's catalogs.
+//!
+//! # Examples
+//!
+//! ```
+//! use gettext::{global, CatalogBuilder};
+//!
+//! assert_eq!(global::gettext("Open"), "Open");
+//!
+//! let catalog = CatalogBuilder::new()
+//!     .add("Open", "Atidaryti")
+//!     .add("Hello, {}!", "Labas, {}!")
+//!     .add_plural("{} file", "{} files", vec!["{} failas", "{} failai"])
+//!     .build()
+//!     .unwrap();
+//! global::set_catalog(catalog);
+//!
+//! assert_eq!(global::gettext("Open"), "Atidaryti");
+//! assert_eq!(gettext::gettext!("Hello, {}!", "Jonas"), "Labas, Jonas!");
+//! assert_eq!(gettext::ngettext!("{} file", "{} files", 3), "3 failai");
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+use crate::{Catalog, ToPluralNumber};
+
+static CATALOG: RwLock<Option<Arc<Catalog>>> = RwLock::new(None);
+
+thread_local! {
+    /// The catalogs of the live overrides of the thread with their ids, innermost last.
+    static OVERRIDES: RefCell<Vec<(u64, Arc<Catalog>)>> = const { RefCell::new(Vec::new()) };
+    /// The id of the next override of the thread.
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Sets the catalog that the free lookup functions and macros use,
+/// replacing the previous one.
+pub fn set_catalog<C: Into<Arc<Catalog>>>(catalog: C) {
+    *CATALOG.write().unwrap_or_else(PoisonError::into_inner) = Some(catalog.into());
+}
+
+/// CatalogOverride is a guard that overrides the default catalog in the current thread,
+/// returned by `override_catalog`. Dropping it ends the override.
+#[derive(Debug)]
+pub struct CatalogOverride {
+    id: u64,
+    // the override belongs to the thread, so the guard has to stay in it
+    thread: PhantomData<Rc<()>>,
+}
+
+impl Drop for CatalogOverride {
+    fn drop(&mut self) {
+        OVERRIDES.with(|overrides| {
+            let mut overrides = overrides.borrow_mut();
+            if let Some(index) = overrides.iter().rposition(|&(id, _)| id == self.id) {
+                overrides.remove(index);
+            }
+        });
+    }
+}
+
+/// Overrides the default catalog in the current thread until the returned guard
+/// is dropped, e.g. for a test to look messages up in a known catalog.
+///
+/// Overrides take precedence over `set_catalog`, and can be nested,
+/// with the innermost live one used. Dropping a guard only ends its own override,
+/// even if overrides made after it are still live.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{global, CatalogBuilder};
+///
+/// let catalog = CatalogBuilder::new().add("Open", "Öffnen").build().unwrap();
+/// {
+///     let _guard = global::override_catalog(catalog);
+///     assert_eq!(global::gettext("Open"), "Öffnen");
+/// }
+/// assert_eq!(global::gettext("Open"), "Open");
+/// ```
+pub fn override_catalog<C: Into<Arc<Catalog>>>(catalog: C) -> CatalogOverride {
+    let id = NEXT_ID.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    OVERRIDES.with(|overrides| overrides.borrow_mut().push((id, catalog.into())));
+    CatalogOverride {
+        id,
+        thread: PhantomData,
+    }
+}
+
+/// Returns the catalog that the free lookup functions and macros use:
+/// the innermost override of the current thread, the catalog that was set,
+/// or an empty one.
+pub fn catalog() -> Arc<Catalog> {
+    static EMPTY: OnceLock<Arc<Catalog>> = OnceLock::new();
+    if let Some(catalog) = OVERRIDES.with(|overrides| {
+        overrides
+            .borrow()
+            .last()
+            .map(|(_, catalog)| catalog.clone())
+    }) {
+        return catalog;
+    }
+    let catalog = CATALOG.read().unwrap_or_else(PoisonError::into_inner);
+    match *catalog {
+        Some(ref catalog) => catalog.clone(),
+        None => EMPTY.get_or_init(|| Arc::new(Catalog::empty())).clone(),
+    }
+}
+
+/// Returns the singular translation of `msg_id` from the default catalog,
+/// like `Catalog::gettext`.
+pub fn gettext(msg_id: &str) -> String {
+    catalog().gettext(msg_id).to_owned()
+}
+
+/// Returns the plural translation of `msg_id` from the default catalog
+/// with the correct plural form for the number `n` of objects, like `Catalog::ngettext`.
+pub fn ngettext<N: ToPluralNumber>(msg_id: &str, msg_id_plural: &str, n: N) -> String {
+    catalog().ngettext(msg_id, msg_id_plural, n).to_owned()
+}
+
+/// Returns the singular translation of `msg_id` in the context `msg_context`
+/// from the default catalog, like `Catalog::pgettext`.
+pub fn pgettext(msg_context: &str, msg_id: &str) -> String {
+    catalog().pgettext(msg_context, msg_id).to_owned()
+}
+
+/// Returns the plural translation of `msg_id` in the context `msg_context`
+/// from the default catalog with the correct plural form for the number `n` of objects,
+/// like `Catalog::npgettext`.
+pub fn npgettext<N: ToPluralNumber>(
+    msg_context: &str,
+    msg_id: &str,
+    msg_id_plural: &str,
+    n: N,
+) -> String {
+    catalog()
+        .npgettext(msg_context, msg_id, msg_id_plural, n)
+        .to_owned()
+}
+
+#[test]
+fn test_global() {
+    use crate::CatalogBuilder;
+
+    let catalog = CatalogBuilder::new()
+        .add("Open", "Atidaryti")
+        .add_with_context("menu", "Quit", "Baigti")
+        .add_plural("file", "files", vec!["failas", "failai"])
+        .add_plural_with_context("size", "byte", "bytes", vec!["baitas", "baitai"])
+        .build()
+        .unwrap();
+    set_catalog(catalog);
+    assert_eq!(gettext("Open"), "Atidaryti");
+    assert_eq!(ngettext("file", "files", 2usize), "failai");
+    assert_eq!(pgettext("menu", "Quit"), "Baigti");
+    assert_eq!(npgettext("size", "byte", "bytes", -1), "baitas");
+    assert_eq!(crate::gettext!("Open"), "Atidaryti");
+    assert_eq!(crate::ngettext!("{} file", "{} files", 1), "1 file");
+
+    set_catalog(Arc::new(Catalog::empty()));
+    assert_eq!(gettext("Open"), "Open");
+}
+
+#[test]
+fn test_override_catalog() {
+    use std::thread;
+
+    use crate::CatalogBuilder;
+
+    let build = |translation| {
+        CatalogBuilder::new()
+            .add("Save", translation)
+            .build()
+            .unwrap()
+    };
+    let outer = override_catalog(build("Išsaugoti"));
+    assert_eq!(gettext("Save"), "Išsaugoti");
+    {
+        let _inner = override_catalog(build("Speichern"));
+        assert_eq!(gettext("Save"), "Speichern");
+        let other = thread::spawn(|| gettext("Save") == "Speichern");
+        assert!(!other.join().unwrap());
+    }
+    assert_eq!(gettext("Save"), "Išsaugoti");
+
+    let inner = override_catalog(build("Speichern"));
+    drop(outer);
+    assert_eq!(gettext("Save"), "Speichern");
+    drop(inner);
+    // the catalog set by other tests has no translation of it
+    assert_eq!(gettext("Save"), "Save");
+}
@@ -0,0 +1,437 @@
+//! Conversion of catalogs from and to i18next-style JSON files.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::json::{self, Member, Value};
+use crate::Error::{self, *};
+use crate::{Catalog, CatalogBuilder, Message, PluralCategory};
+
+/// The convention for the suffixes of the keys of plural forms in JSON files.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PluralSuffix {
+    /// The CLDR category of each form, e.g. `files_one` and `files_other`, as in i18next v4
+    Category,
+    /// The index of each gettext plural form, e.g. `files_0` and `files_1`, as in i18next v3
+    Index,
+}
+
+/// JsonFormat reads and writes catalogs as the JSON files of i18next
+/// and similar web libraries, which map keys to translated strings.
+///
+/// The keys are the msgids, and the msgctxt of a message, if any, is put before it
+/// with a separator, `\u{4}` by default like in MO files. i18next itself
+/// puts the context after the key, which this format can not tell apart
+/// from a msgid with the separator in it, so it is an option.
+/// Each plural form is a key of its own, the key of the message with a suffix,
+/// either its CLDR category or its index.
+/// Objects nested in the file are joined to their keys with a separator,
+/// `.` by default; writing nested files is an option.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{CatalogBuilder, JsonFormat};
+///
+/// let catalog = CatalogBuilder::new()
+///     .header("Language", "lt")
+///     .add_plural("menu.files", "menu.files", vec!["{{count}} failas", "{{count}} failai", "{{count}} failų"])
+///     .add("menu.quit", "Baigti")
+///     .build()
+///     .unwrap();
+///
+/// let format = JsonFormat::new().nested(true);
+/// let mut out = vec![];
+/// format.write(&catalog, &mut out).unwrap();
+/// let json = String::from_utf8(out).unwrap();
+/// assert!(json.contains(r#""menu": {
+///     "files_few": "{{count}} failai","#));
+///
+/// let catalog = format.parse(json.as_bytes(), "lt").unwrap();
+/// assert_eq!(catalog.ngettext("menu.files", "menu.files", 21), "{{count}} failas");
+/// assert_eq!(catalog.gettext("menu.quit"), "Baigti");
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct JsonFormat {
+    nested: bool,
+    key_separator: String,
+    context_separator: String,
+    plural_separator: String,
+    plural_suffix: PluralSuffix,
+}
+
+impl Default for JsonFormat {
+    fn default() -> Self {
+        JsonFormat {
+            nested: false,
+            key_separator: ".".to_owned(),
+            context_separator: "\u{4}".to_owned(),
+            plural_separator: "_".to_owned(),
+            plural_suffix: PluralSuffix::Category,
+        }
+    }
+}
+
+impl JsonFormat {
+    /// Creates a format writing flat files, with `_` and the CLDR category
+    /// after the keys of plural forms.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets whether keys with the key separator are written as nested objects,
+    /// e.g. `{"menu": {"quit": "Baigti"}}` for `menu.quit`.
+    ///
+    /// Keys that would clash with another key, or have empty parts, stay flat.
+    /// Nested objects are always read.
+    pub fn nested(mut self, nested: bool) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    /// Sets the separator of the keys of nested objects, `.` by default.
+    pub fn key_separator(mut self, separator: &str) -> Self {
+        self.key_separator = separator.to_owned();
+        self
+    }
+
+    /// Sets the separator between the msgctxt and the msgid, `\u{4}` by default.
+    ///
+    /// When reading, a key is split on its first separator,
+    /// so it should not appear in the msgids. An empty separator
+    /// leaves the keys unsplit.
+    pub fn context_separator(mut self, separator: &str) -> Self {
+        self.context_separator = separator.to_owned();
+        self
+    }
+
+    /// Sets the separator before the suffix of the keys of plural forms, `_` by default.
+    pub fn plural_separator(mut self, separator: &str) -> Self {
+        self.plural_separator = separator.to_owned();
+        self
+    }
+
+    /// Sets the convention for the suffixes of the keys of plural forms.
+    pub fn plural_suffix(mut self, suffix: PluralSuffix) -> Self {
+        self.plural_suffix = suffix;
+        self
+    }
+
+    /// Reads a catalog for `language` from a JSON file.
+    ///
+    /// Keys are plural forms when the keys of their message include an `other` form,
+    /// or a `0` form for indices. With categories, the forms are mapped through
+    /// the numbers that CLDR assigns to each category in the language,
+    /// and the forms without a category of their own get the `other` text.
+    /// Forms beyond the ones of the language are ignored.
+    /// The plural msgid is the same as the msgid, as the files have no other.
+    ///
+    /// Returns `JsonSyntax` if the file is malformed
+    /// or has values other than strings and objects.
+    pub fn parse<R: Read>(&self, reader: R, language: &str) -> Result<Catalog, Error> {
+        let members = match json::read(reader)? {
+            Value::Object(members) => members,
+            _ => return Err(JsonSyntax(1)),
+        };
+        let mut entries = vec![];
+        self.flatten(&members, "", &mut entries)?;
+
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .header("Language", language)
+            .build()?;
+        let nplurals = catalog.nplurals();
+
+        // the forms of each plural message, by its key
+        let mut plurals = BTreeMap::<&str, Vec<(usize, &str)>>::new();
+        for (i, (key, _)) in entries.iter().enumerate() {
+            let split = key.rsplit_once(self.plural_separator.as_str());
+            let split = split.filter(|_| !self.plural_separator.is_empty());
+            if let Some((base, suffix)) = split.filter(|&(_, suffix)| self.is_suffix(suffix)) {
+                plurals.entry(base).or_default().push((i, suffix));
+            }
+        }
+        let required = match self.plural_suffix {
+            PluralSuffix::Category => PluralCategory::Other.as_str(),
+            PluralSuffix::Index => "0",
+        };
+        plurals.retain(|_, forms| forms.iter().any(|&(_, suffix)| suffix == required));
+
+        let mut in_plural = vec![false; entries.len()];
+        for (base, forms) in &plurals {
+            let mut translated = vec![String::new(); nplurals];
+            let mut other = None;
+            for &(i, suffix) in forms {
+                in_plural[i] = true;
+                let text = &entries[i].1;
+                let form_no = match self.plural_suffix {
+                    PluralSuffix::Category => {
+                        let category = suffix.parse::<PluralCategory>().unwrap();
+                        if category == PluralCategory::Other {
+                            other = Some(text);
+                        }
+                        let n = category.sample(language);
+                        match n.map(|n| catalog.resolver.resolve(n)) {
+                            Some(form_no) => form_no,
+                            None => continue,
+                        }
+                    }
+                    PluralSuffix::Index => suffix.parse().unwrap_or(usize::MAX),
+                };
+                if let Some(slot) = translated.get_mut(form_no) {
+                    *slot = text.clone();
+                }
+            }
+            if let Some(other) = other {
+                for slot in translated.iter_mut().filter(|s| s.is_empty()) {
+                    *slot = other.clone();
+                }
+            }
+            let mut msg = self.message(base, translated);
+            msg.id_plural = Some(msg.id.clone());
+            catalog.insert(msg);
+        }
+        for (i, (key, text)) in entries.iter().enumerate() {
+            if !in_plural[i] {
+                catalog.insert(self.message(key, vec![text.clone()]));
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the translated messages of the catalog out as a JSON file,
+    /// keyed as `parse` reads them and sorted by their keys.
+    ///
+    /// With categories, a plural message gets a key for each category
+    /// that CLDR defines for the `Language` of the catalog,
+    /// taken from the plural form that the catalog uses for that category,
+    /// and an `other` key with the last form if the language only uses it for fractions.
+    /// Untranslated messages and forms are left out, so that the library falls back
+    /// to its default.
+    pub fn write<W: Write>(&self, catalog: &Catalog, writer: W) -> Result<(), Error> {
+        let metadata = catalog.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let mut entries = BTreeMap::new();
+        for msg in catalog.strings.values() {
+            if msg.is_header() || !msg.is_translated() {
+                continue;
+            }
+            let key = match msg.context {
+                Some(ref context) => format!("{}{}{}", context, self.context_separator, msg.id),
+                None => msg.id.clone(),
+            };
+            if msg.id_plural.is_none() {
+                entries.insert(key, msg.translated[0].clone());
+                continue;
+            }
+            let plural_key = |suffix: &str| format!("{}{}{}", key, self.plural_separator, suffix);
+            match self.plural_suffix {
+                PluralSuffix::Category => {
+                    for &category in PluralCategory::all() {
+                        let n = category.sample(language);
+                        let form = n.map(|n| catalog.resolver.resolve(n));
+                        if let Some(text) = form.and_then(|form| msg.get_translated(form)) {
+                            entries.insert(plural_key(category.as_str()), text.to_owned());
+                        }
+                    }
+                    // i18next requires `other`, which some languages only use for fractions
+                    let last = msg.translated.iter().rev().find(|s| !s.is_empty());
+                    entries
+                        .entry(plural_key(PluralCategory::Other.as_str()))
+                        .or_insert_with(|| last.unwrap().clone());
+                }
+                PluralSuffix::Index => {
+                    for (i, text) in msg.translated.iter().enumerate() {
+                        if !text.is_empty() {
+                            entries.insert(plural_key(&i.to_string()), text.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut members = vec![];
+        for (key, text) in entries {
+            let value = Value::String(text);
+            if !self.nested || self.key_separator.is_empty() {
+                members.push(Member::new(&key, value));
+                continue;
+            }
+            let path = key.split(self.key_separator.as_str()).collect::<Vec<_>>();
+            if path.iter().any(|part| part.is_empty()) || !insert(&mut members, &path, &value) {
+                members.push(Member::new(&key, value));
+            }
+        }
+        json::write(&Value::Object(members), writer)
+    }
+
+    /// Collects the strings of the objects of a file with their joined keys.
+    fn flatten(
+        &self,
+        members: &[Member],
+        prefix: &str,
+        entries: &mut Vec<(String, String)>,
+    ) -> Result<(), Error> {
+        for member in members {
+            let key = match prefix {
+                "" => member.name.clone(),
+                _ => format!("{}{}{}", prefix, self.key_separator, member.name),
+            };
+            match member.value {
+                Value::String(ref text) => entries.push((key, text.clone())),
+                Value::Object(ref members) => self.flatten(members, &key, entries)?,
+                _ => return Err(member.error()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether `suffix` is the suffix of a plural form in this format.
+    fn is_suffix(&self, suffix: &str) -> bool {
+        match self.plural_suffix {
+            PluralSuffix::Category => suffix.parse::<PluralCategory>().is_ok(),
+            PluralSuffix::Index => !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit()),
+        }
+    }
+
+    /// Returns the message for a key of a file.
+    fn message(&self, key: &str, translated: Vec<String>) -> Message {
+        let split = key.split_once(self.context_separator.as_str());
+        let mut msg = match split.filter(|_| !self.context_separator.is_empty()) {
+            Some((context, id)) => Message::new(id, Some(context), vec![]),
+            None => Message::new(key, None, vec![]),
+        };
+        msg.translated = translated;
+        msg
+    }
+}
+
+/// Inserts `value` at `path` in nested objects, returning false if it clashes with another key.
+fn insert(members: &mut Vec<Member>, path: &[&str], value: &Value) -> bool {
+    let existing = members.iter_mut().position(|m| m.name == path[0]);
+    if path.len() == 1 {
+        if existing.is_none() {
+            members.push(Member::new(path[0], value.clone()));
+        }
+        return existing.is_none();
+    }
+    let i = match existing {
+        Some(i) => i,
+        None => {
+            members.push(Member::new(path[0], Value::Object(vec![])));
+            members.len() - 1
+        }
+    };
+    match members[i].value {
+        Value::Object(ref mut members) => insert(members, &path[1..], value),
+        _ => false,
+    }
+}
+
+#[test]
+fn test_parse_json() {
+    let src = r#"{
+  "Open": "Atidaryti",
+  "menu": {
+    "quit": "Baigti",
+    "file_one": "{{count}} failas",
+    "file_other": "{{count}} failų"
+  },
+  "menu\u0004Quit": "Išeiti",
+  "size_many": "not a plural without other",
+  "Line": "Eilutė"
+}"#;
+    let catalog = JsonFormat::new().parse(src.as_bytes(), "lt").unwrap();
+    assert_eq!(catalog.len(), 7);
+    assert_eq!(catalog.gettext("Open"), "Atidaryti");
+    assert_eq!(catalog.gettext("menu.quit"), "Baigti");
+    assert_eq!(catalog.pgettext("menu", "Quit"), "Išeiti");
+    assert_eq!(catalog.gettext("size_many"), "not a plural without other");
+    let file = catalog.get("menu.file").unwrap();
+    assert_eq!(file.id_plural(), Some("menu.file"));
+    // `few` is missing, so it gets the `other` text
+    assert_eq!(
+        file.translated,
+        ["{{count}} failas", "{{count}} failų", "{{count}} failų"]
+    );
+
+    let src = r#"{"file_0": "failas", "file_2": "failų", "item_1": "daiktas", "note:ctx": "x"}"#;
+    let format = JsonFormat::new()
+        .plural_suffix(PluralSuffix::Index)
+        .context_separator(":");
+    let catalog = format.parse(src.as_bytes(), "lt").unwrap();
+    let file = catalog.get("file").unwrap();
+    assert_eq!(file.translated, ["failas", "", "failų"]);
+    assert_eq!(catalog.gettext("item_1"), "daiktas");
+    assert_eq!(catalog.pgettext("note", "ctx"), "x");
+
+    assert!(matches!(
+        JsonFormat::new().parse(&b"{\"a\": \"b\",\n\"c\": 1}"[..], "lt"),
+        Err(JsonSyntax(2))
+    ));
+    assert!(matches!(
+        JsonFormat::new().parse(&b"[]"[..], "lt"),
+        Err(JsonSyntax(1))
+    ));
+}
+
+#[test]
+fn test_write_json() {
+    let catalog = CatalogBuilder::new()
+        .header("Language", "pl")
+        .add("app.name", "Aplikacja")
+        .add("menu.open", "Otwórz")
+        .add("menu", "Menu")
+        .add("menu.quit.", "Zakończ.")
+        .add_with_context("verb", "Save", "Zapisz")
+        .add_plural("file", "files", vec!["plik", "pliki", "plików"])
+        .add_plural("dir", "dirs", vec!["katalog", "", ""])
+        .add("untranslated", "")
+        .build()
+        .unwrap();
+
+    let mut out = vec![];
+    JsonFormat::new()
+        .nested(true)
+        .context_separator("|")
+        .write(&catalog, &mut out)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"{
+  "app": {
+    "name": "Aplikacja"
+  },
+  "dir_one": "katalog",
+  "dir_other": "katalog",
+  "file_few": "pliki",
+  "file_many": "plików",
+  "file_one": "plik",
+  "file_other": "plików",
+  "menu": "Menu",
+  "menu.open": "Otwórz",
+  "menu.quit.": "Zakończ.",
+  "verb|Save": "Zapisz"
+}
+"#
+    );
+
+    let mut out = vec![];
+    let format = JsonFormat::new()
+        .plural_suffix(PluralSuffix::Index)
+        .plural_separator("#");
+    format.write(&catalog, &mut out).unwrap();
+    let json = String::from_utf8(out).unwrap();
+    assert!(json.contains("\"file#2\": \"plików\""));
+    assert!(json.contains("\"verb\\u0004Save\": \"Zapisz\""));
+    let read = format.parse(json.as_bytes(), "pl").unwrap();
+    for msg in catalog.strings.values().filter(|m| !m.is_header()) {
+        let read = read.lookup(msg.context(), msg.id());
+        if !msg.is_translated() {
+            assert!(read.is_none());
+            continue;
+        }
+        let read = read.unwrap();
+        assert_eq!(read.translated, msg.translated);
+    }
+}
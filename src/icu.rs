@@ -0,0 +1,515 @@
+//! Evaluation of the ICU MessageFormat syntax in translations.
+
+use std::fmt;
+
+use crate::Error::{self, *};
+use crate::{Catalog, PluralCategory};
+
+/// The deepest nesting of arguments parsed, so that a crafted message cannot exhaust the stack.
+const MAX_DEPTH: usize = 64;
+
+/// IcuArg is the value of an argument of a message in ICU MessageFormat,
+/// see `Catalog::gettext_icu`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IcuArg<'a> {
+    /// A number, which selects the branches of `plural` arguments
+    Number(f64),
+    /// A string, which selects the branches of `select` arguments
+    Str(&'a str),
+}
+
+macro_rules! impl_from_number {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for IcuArg<'_> {
+            fn from(n: $ty) -> Self {
+                IcuArg::Number(n as f64)
+            }
+        }
+    )*};
+}
+
+impl_from_number!(i32, i64, u32, u64, usize);
+
+impl From<f64> for IcuArg<'_> {
+    fn from(n: f64) -> Self {
+        IcuArg::Number(n)
+    }
+}
+
+impl<'a> From<&'a str> for IcuArg<'a> {
+    fn from(s: &'a str) -> Self {
+        IcuArg::Str(s)
+    }
+}
+
+impl<'a> From<&'a String> for IcuArg<'a> {
+    fn from(s: &'a String) -> Self {
+        IcuArg::Str(s)
+    }
+}
+
+impl fmt::Display for IcuArg<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IcuArg::Number(n) => Number(n).fmt(fmt),
+            IcuArg::Str(s) => fmt.write_str(s),
+        }
+    }
+}
+
+/// Formats a number without a fractional part like an integer.
+struct Number(f64);
+
+impl fmt::Display for Number {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.fract() == 0.0 && self.0.abs() < 1e15 {
+            write!(fmt, "{}", self.0 as i64)
+        } else {
+            write!(fmt, "{}", self.0)
+        }
+    }
+}
+
+/// A part of a message.
+#[derive(Debug, PartialEq)]
+enum Part<'a> {
+    Text(String),
+    /// The `#` of a plural branch, which stands for its number
+    Hash,
+    Argument(&'a str, Kind<'a>),
+}
+
+/// The type of an argument of a message.
+#[derive(Debug, PartialEq)]
+enum Kind<'a> {
+    /// A `{name}` argument
+    Simple,
+    /// A `{name, number}` argument with its style, if any
+    Number(Option<&'a str>),
+    /// A `{name, plural, ...}` argument, or a `selectordinal` one if `ordinal`
+    Plural {
+        offset: f64,
+        ordinal: bool,
+        branches: Vec<(&'a str, Vec<Part<'a>>)>,
+    },
+    /// A `{name, select, ...}` argument
+    Select(Vec<(&'a str, Vec<Part<'a>>)>),
+}
+
+/// Parses a message in ICU MessageFormat.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self) -> Error {
+        IcuSyntax(self.pos)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.src.len() - self.src[self.pos..].trim_start().len();
+    }
+
+    /// Reads the next character if it is `c`.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let eaten = self.peek() == Some(c);
+        if eaten {
+            self.pos += c.len_utf8();
+        }
+        eaten
+    }
+
+    /// Reads a word, e.g. the name of an argument or the key of a branch.
+    fn word(&mut self) -> Result<&'a str, Error> {
+        self.skip_whitespace();
+        let rest = &self.src[self.pos..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "{},".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error());
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Reads the parts of a message up to the `}` closing it, if it is nested,
+    /// or to the end of the source.
+    fn message(&mut self, depth: usize, in_plural: bool) -> Result<Vec<Part<'a>>, Error> {
+        if depth >= MAX_DEPTH {
+            return Err(self.error());
+        }
+        let mut parts = vec![];
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(text.split_off(0)));
+                    }
+                    self.pos += 1;
+                    parts.push(self.argument(depth, in_plural)?);
+                    continue;
+                }
+                '}' if depth > 0 => break,
+                '}' => return Err(self.error()),
+                '#' if in_plural => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(text.split_off(0)));
+                    }
+                    parts.push(Part::Hash);
+                }
+                '\'' => {
+                    let rest = &self.src[self.pos + 1..];
+                    if rest.starts_with('\'') {
+                        text.push('\'');
+                        self.pos += 2;
+                        continue;
+                    }
+                    // an apostrophe before syntax characters quotes them up to the next one
+                    let quoting =
+                        rest.starts_with(['{', '}']) || in_plural && rest.starts_with('#');
+                    if !quoting {
+                        text.push('\'');
+                        self.pos += 1;
+                        continue;
+                    }
+                    self.pos += 1;
+                    loop {
+                        let rest = &self.src[self.pos..];
+                        match rest.find('\'') {
+                            Some(i) if rest[i + 1..].starts_with('\'') => {
+                                text.push_str(&rest[..=i]);
+                                self.pos += i + 2;
+                            }
+                            Some(i) => {
+                                text.push_str(&rest[..i]);
+                                self.pos += i + 1;
+                                break;
+                            }
+                            None => {
+                                text.push_str(rest);
+                                self.pos = self.src.len();
+                                break;
+                            }
+                        }
+                    }
+                    continue;
+                }
+                c => text.push(c),
+            }
+            self.pos += c.len_utf8();
+        }
+        if depth > 0 && self.peek().is_none() {
+            return Err(self.error());
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(parts)
+    }
+
+    /// Reads an argument after its opening brace, up to its closing one.
+    fn argument(&mut self, depth: usize, in_plural: bool) -> Result<Part<'a>, Error> {
+        let name = self.word()?;
+        if self.eat('}') {
+            return Ok(Part::Argument(name, Kind::Simple));
+        }
+        if !self.eat(',') {
+            return Err(self.error());
+        }
+        self.skip_whitespace();
+        let start = self.pos;
+        let kind = match self.word()? {
+            "number" => {
+                let style = match self.eat(',') {
+                    true => {
+                        let rest = &self.src[self.pos..];
+                        let len = rest.find('}').ok_or_else(|| self.error())?;
+                        self.pos += len;
+                        Some(rest[..len].trim())
+                    }
+                    false => None,
+                };
+                Kind::Number(style)
+            }
+            word @ "plural" | word @ "selectordinal" => {
+                if !self.eat(',') {
+                    return Err(self.error());
+                }
+                self.skip_whitespace();
+                let mut offset = 0.0;
+                if self.src[self.pos..].starts_with("offset:") {
+                    self.pos += "offset:".len();
+                    let start = self.pos;
+                    offset = self.word()?.parse().map_err(|_| IcuSyntax(start))?;
+                }
+                Kind::Plural {
+                    offset,
+                    ordinal: word == "selectordinal",
+                    branches: self.branches(depth, true)?,
+                }
+            }
+            "select" => {
+                if !self.eat(',') {
+                    return Err(self.error());
+                }
+                Kind::Select(self.branches(depth, in_plural)?)
+            }
+            _ => return Err(IcuSyntax(start)),
+        };
+        if !self.eat('}') {
+            return Err(self.error());
+        }
+        Ok(Part::Argument(name, kind))
+    }
+
+    /// Reads the branches of a `plural` or `select` argument, which must include `other`.
+    fn branches(
+        &mut self,
+        depth: usize,
+        in_plural: bool,
+    ) -> Result<Vec<(&'a str, Vec<Part<'a>>)>, Error> {
+        let mut branches = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                break;
+            }
+            let key = self.word()?;
+            if !self.eat('{') {
+                return Err(self.error());
+            }
+            let message = self.message(depth + 1, in_plural)?;
+            self.pos += 1;
+            branches.push((key, message));
+        }
+        match branches.iter().any(|&(key, _)| key == "other") {
+            true => Ok(branches),
+            false => Err(self.error()),
+        }
+    }
+}
+
+/// Evaluates the parts of a message with the given arguments,
+/// and the number of the innermost plural for `#`.
+fn evaluate(
+    parts: &[Part],
+    args: &[(&str, IcuArg)],
+    language: &str,
+    number: Option<f64>,
+    out: &mut String,
+) -> Result<(), Error> {
+    use std::fmt::Write;
+
+    for part in parts {
+        let (name, kind) = match *part {
+            Part::Text(ref text) => {
+                out.push_str(text);
+                continue;
+            }
+            Part::Hash => {
+                match number {
+                    Some(n) => write!(out, "{}", Number(n)).unwrap(),
+                    None => out.push('#'),
+                }
+                continue;
+            }
+            Part::Argument(name, ref kind) => (name, kind),
+        };
+        let value = args
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, value)| value);
+        let value = value.ok_or_else(|| UnknownPlaceholder(format!("{{{}}}", name)))?;
+        let as_number = match value {
+            IcuArg::Number(n) => Some(n),
+            IcuArg::Str(s) => s.trim().parse::<f64>().ok(),
+        };
+        match *kind {
+            Kind::Simple | Kind::Number(None) => write!(out, "{}", value).unwrap(),
+            Kind::Number(Some(style)) => match (as_number, style) {
+                (Some(n), "integer") => write!(out, "{}", Number(n.round())).unwrap(),
+                (Some(n), "percent") => write!(out, "{}%", Number((n * 100.0).round())).unwrap(),
+                _ => write!(out, "{}", value).unwrap(),
+            },
+            Kind::Plural {
+                offset,
+                ordinal,
+                ref branches,
+            } => {
+                let n = as_number.unwrap_or(f64::NAN);
+                let exact = branches.iter().find(|&&(key, _)| {
+                    key.strip_prefix('=')
+                        .and_then(|k| k.parse::<f64>().ok())
+                        .is_some_and(|k| k == n)
+                });
+                // CLDR categories are only known for whole cardinal numbers
+                let shown = n - offset;
+                let category = match shown.fract() == 0.0 && shown >= 0.0 && !ordinal {
+                    true => PluralCategory::for_number(language, shown as u64),
+                    false => PluralCategory::Other,
+                };
+                let branch = exact
+                    .or_else(|| branches.iter().find(|&&(key, _)| key == category.as_str()))
+                    .or_else(|| branches.iter().find(|&&(key, _)| key == "other"));
+                let number = Some(shown).filter(|n| !n.is_nan());
+                evaluate(&branch.unwrap().1, args, language, number, out)?;
+            }
+            Kind::Select(ref branches) => {
+                let key = match value {
+                    IcuArg::Str(s) => s.to_owned(),
+                    IcuArg::Number(n) => Number(n).to_string(),
+                };
+                let branch = branches
+                    .iter()
+                    .find(|&&(k, _)| k == key)
+                    .or_else(|| branches.iter().find(|&&(k, _)| k == "other"));
+                evaluate(&branch.unwrap().1, args, language, number, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Formats a message in ICU MessageFormat with the plural rules of `language`.
+fn format(message: &str, args: &[(&str, IcuArg)], language: &str) -> Result<String, Error> {
+    let mut parser = Parser {
+        src: message,
+        pos: 0,
+    };
+    let parts = parser.message(0, false)?;
+    let mut out = String::with_capacity(message.len());
+    evaluate(&parts, args, language, None, &mut out)?;
+    Ok(out)
+}
+
+impl Catalog {
+    /// Translates `msg_id` and formats the translation as a message in ICU MessageFormat,
+    /// for catalogs whose translations are written in it.
+    ///
+    /// A subset of the syntax is supported: `{name}` arguments,
+    /// `{name, number}` ones with the `integer` and `percent` styles,
+    /// `{name, plural, ...}` ones with an `offset:`, `=n` branches and `#`,
+    /// `{name, select, ...}` ones, and apostrophes quoting syntax characters.
+    /// The branches of plurals are chosen with the CLDR rules of the `Language`
+    /// of the catalog, for whole numbers; fractions and `selectordinal` arguments
+    /// take their `=n` or `other` branches. Numbers are written without
+    /// the separators of the language.
+    ///
+    /// Returns `IcuSyntax` with the byte offset where the message is malformed,
+    /// and `UnknownPlaceholder` if it has an argument missing from `args`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .header("Language", "lt")
+    ///     .add(
+    ///         "{count, plural, one {# file} other {# files}} in {dir}",
+    ///         "{dir}: {count, plural, one {# failas} few {# failai} other {# failų}}",
+    ///     )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let msg_id = "{count, plural, one {# file} other {# files}} in {dir}";
+    /// let text = catalog.gettext_icu(msg_id, &[("count", 3.into()), ("dir", "/tmp".into())]);
+    /// assert_eq!(text.unwrap(), "/tmp: 3 failai");
+    /// let text = catalog.gettext_icu(msg_id, &[("count", 21.into()), ("dir", "/tmp".into())]);
+    /// assert_eq!(text.unwrap(), "/tmp: 21 failas");
+    /// ```
+    pub fn gettext_icu(&self, msg_id: &str, args: &[(&str, IcuArg)]) -> Result<String, Error> {
+        let metadata = self.metadata();
+        format(
+            self.gettext(msg_id),
+            args,
+            metadata.language().unwrap_or("en"),
+        )
+    }
+
+    /// Translates `msg_id` in the context `msg_context` and formats the translation
+    /// as a message in ICU MessageFormat, like `gettext_icu`.
+    pub fn pgettext_icu(
+        &self,
+        msg_context: &str,
+        msg_id: &str,
+        args: &[(&str, IcuArg)],
+    ) -> Result<String, Error> {
+        let metadata = self.metadata();
+        let translation = self.pgettext(msg_context, msg_id);
+        format(translation, args, metadata.language().unwrap_or("en"))
+    }
+}
+
+#[test]
+fn test_format() {
+    let args = [
+        ("n", IcuArg::from(3)),
+        ("one", IcuArg::from(1)),
+        ("ratio", IcuArg::from(0.256)),
+        ("half", IcuArg::from(1.5)),
+        ("gender", IcuArg::from("female")),
+        ("name", IcuArg::from("Ona")),
+    ];
+    for &(message, formatted) in &[
+        ("plain text", "plain text"),
+        ("{name} has {n} files", "Ona has 3 files"),
+        ("{ratio, number, percent} {half, number, integer} {half, number}", "26% 2 1.5"),
+        ("{n, plural, =3 {exactly three} one {# file} other {# files}}", "exactly three"),
+        ("{one, plural, one {# file} other {# files}}", "1 file"),
+        ("{half, plural, one {# file} other {# files}}", "1.5 files"),
+        ("{n, plural, offset:1 =0 {nobody} one {{name} and one other} other {{name} and # others}}", "Ona and 2 others"),
+        ("{one, selectordinal, one {#st} other {#th}}", "1th"),
+        ("{gender, select, male {He} female {She} other {They}} left", "She left"),
+        ("{name, select, male {He} other {{name}}} left", "Ona left"),
+        ("{n, plural, other {{gender, select, female {# of hers} other {#}}}}", "3 of hers"),
+        ("It''s '{quoted}' and # is '#", "It's {quoted} and # is '#"),
+        ("{n, plural, other {'#' is #}}", "# is 3"),
+        ("I'm {name}", "I'm Ona"),
+    ] {
+        assert_eq!(format(message, &args, "en").unwrap(), formatted, "{}", message);
+    }
+    let message = "{n, plural, one {# failas} few {# failai} other {# failų}}";
+    let lt = |n: u64| format(message, &[("n", n.into())], "lt").unwrap();
+    assert_eq!(
+        (lt(1), lt(3), lt(10), lt(21)),
+        (
+            "1 failas".into(),
+            "3 failai".into(),
+            "10 failų".into(),
+            "21 failas".into()
+        )
+    );
+
+    for &(message, offset) in &[
+        ("unclosed {name", 14),
+        ("stray }", 6),
+        ("{n, plural, one {x}}", 19),
+        ("{n, plural, other {x}", 21),
+        ("{n, choice, other {x}}", 4),
+        ("{n plural}", 3),
+        ("{n, plural, offset:x other {}}", 19),
+        ("{}", 1),
+    ] {
+        let result = format(message, &args, "en");
+        assert!(
+            matches!(result, Err(IcuSyntax(o)) if o == offset),
+            "{}: {:?}",
+            message,
+            result
+        );
+    }
+    let nested = "{n, select, other {".repeat(MAX_DEPTH) + &"}}".repeat(MAX_DEPTH);
+    assert!(matches!(format(&nested, &args, "en"), Err(IcuSyntax(_))));
+    assert!(
+        matches!(format("{missing}", &args, "en"), Err(UnknownPlaceholder(ref p)) if p == "{missing}")
+    );
+}
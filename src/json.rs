@@ -0,0 +1,381 @@
+//! A minimal JSON tree, enough for the JSON-based translation formats.
+//!
+//! Only UTF-8 documents are read, and numbers are kept as they are written.
+
+// each format only uses some of the helpers, and the formats are optional
+#![allow(dead_code)]
+
+use std::io;
+
+use crate::Error::{self, *};
+
+/// The deepest nesting of values read, so that a crafted document cannot exhaust the stack.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<Member>),
+}
+
+/// A member of an object, in the order of the document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub value: Value,
+    /// The line the name is on, for errors.
+    pub line: usize,
+}
+
+impl Member {
+    pub fn new(name: &str, value: Value) -> Self {
+        Member {
+            name: name.to_owned(),
+            value,
+            line: 0,
+        }
+    }
+
+    /// Returns the error for an unexpected value of this member.
+    pub fn error(&self) -> Error {
+        JsonSyntax(self.line)
+    }
+}
+
+impl Value {
+    /// Returns the value of the member with the given name, if this is an object.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match *self {
+            Value::Object(ref members) => members.iter().find(|m| m.name == name).map(|m| &m.value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the whole document and returns its value.
+pub fn read<R: io::Read>(mut reader: R) -> Result<Value, Error> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let src = String::from_utf8(bytes).map_err(|_| DecodingError)?;
+    parse(src.strip_prefix('\u{feff}').unwrap_or(&src))
+}
+
+/// Parses a document and returns its value.
+pub fn parse(src: &str) -> Result<Value, Error> {
+    let mut parser = Parser {
+        src,
+        pos: 0,
+        line: 1,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    match parser.pos == src.len() {
+        true => Ok(value),
+        false => Err(parser.error()),
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self) -> Error {
+        JsonSyntax(self.line)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        let trimmed = rest.trim_start_matches([' ', '\t', '\n', '\r']);
+        self.line += rest[..rest.len() - trimmed.len()].matches('\n').count();
+        self.pos += rest.len() - trimmed.len();
+    }
+
+    /// Skips whitespace and the given character, returning whether it was there.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth >= MAX_DEPTH {
+            return Err(self.error());
+        }
+        self.skip_whitespace();
+        let rest = self.rest();
+        for &(literal, ref value) in &[
+            ("null", Value::Null),
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+        ] {
+            if rest.starts_with(literal) {
+                self.pos += literal.len();
+                return Ok(value.clone());
+            }
+        }
+        match rest.chars().next() {
+            Some('"') => self.string().map(Value::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut values = vec![];
+                if !self.eat(']') {
+                    loop {
+                        values.push(self.value(depth + 1)?);
+                        if self.eat(']') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut members = vec![];
+                if !self.eat('}') {
+                    loop {
+                        self.skip_whitespace();
+                        let line = self.line;
+                        let name = self.string()?;
+                        self.expect(':')?;
+                        let value = self.value(depth + 1)?;
+                        members.push(Member { name, value, line });
+                        if self.eat('}') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                Ok(Value::Object(members))
+            }
+            Some('-' | '0'..='9') => {
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit() && !"+-.eE".contains(c))
+                    .unwrap_or(rest.len());
+                let number = &rest[..len];
+                if number.parse::<f64>().is_err() || number.starts_with('+') {
+                    return Err(self.error());
+                }
+                self.pos += len;
+                Ok(Value::Number(number.to_owned()))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        if !self.rest().starts_with('"') {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let rest = self.rest();
+            let end = rest
+                .find(|c: char| c == '"' || c == '\\' || c < ' ')
+                .ok_or_else(|| self.error())?;
+            out.push_str(&rest[..end]);
+            self.pos += end + 1;
+            match rest[end..].chars().next() {
+                Some('"') => return Ok(out),
+                Some('\\') => {}
+                _ => return Err(self.error()),
+            }
+            let escape = self.rest().chars().next().ok_or_else(|| self.error())?;
+            self.pos += 1;
+            match escape {
+                '"' | '\\' | '/' => out.push(escape),
+                'b' => out.push('\u{8}'),
+                'f' => out.push('\u{c}'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'u' => {
+                    let mut units = vec![self.hex()?];
+                    // characters outside the BMP are written as surrogate pairs
+                    if (0xd800..0xdc00).contains(&units[0]) && self.rest().starts_with("\\u") {
+                        self.pos += 2;
+                        units.push(self.hex()?);
+                    }
+                    let c = char::decode_utf16(units).next().unwrap();
+                    out.push(c.map_err(|_| self.error())?);
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// Reads the four hexadecimal digits of a `\u` escape.
+    fn hex(&mut self) -> Result<u16, Error> {
+        let digits = self.rest().get(..4).ok_or_else(|| self.error())?;
+        let unit = u16::from_str_radix(digits, 16).map_err(|_| self.error())?;
+        self.pos += 4;
+        Ok(unit)
+    }
+}
+
+/// Appends `s` to `out` as a JSON string.
+pub fn push_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Writes the document, indenting it by two spaces like `JSON.stringify(value, null, 2)`.
+pub fn write<W: io::Write>(value: &Value, mut writer: W) -> Result<(), Error> {
+    let mut out = String::new();
+    push_value(&mut out, value, 0);
+    out.push('\n');
+    writer.write_all(out.as_bytes())?;
+    Ok(())
+}
+
+fn push_value(out: &mut String, value: &Value, level: usize) {
+    let indent = |out: &mut String, level: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(level));
+    };
+    match *value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if b { "true" } else { "false" }),
+        Value::Number(ref n) => out.push_str(n),
+        Value::String(ref s) => push_string(out, s),
+        Value::Array(ref values) if values.is_empty() => out.push_str("[]"),
+        Value::Array(ref values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                indent(out, level + 1);
+                push_value(out, value, level + 1);
+            }
+            indent(out, level);
+            out.push(']');
+        }
+        Value::Object(ref members) if members.is_empty() => out.push_str("{}"),
+        Value::Object(ref members) => {
+            out.push('{');
+            for (i, member) in members.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                indent(out, level + 1);
+                push_string(out, &member.name);
+                out.push_str(": ");
+                push_value(out, &member.value, level + 1);
+            }
+            indent(out, level);
+            out.push('}');
+        }
+    }
+}
+
+#[test]
+fn test_parse() {
+    let src = "\u{feff}{\n  \"a\": \"x\\\"\\n\\u0105\\ud83d\\ude00\",\n  \"b\": [1, -2.5e3, true, false, null],\n  \"c\": {}\n}\n";
+    let value = read(src.as_bytes()).unwrap();
+    assert_eq!(value.get("a").and_then(Value::as_str), Some("x\"\ną😀"));
+    assert_eq!(
+        value.get("b"),
+        Some(&Value::Array(vec![
+            Value::Number("1".to_owned()),
+            Value::Number("-2.5e3".to_owned()),
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Null,
+        ]))
+    );
+    assert_eq!(value.get("c"), Some(&Value::Object(vec![])));
+    match value {
+        Value::Object(ref members) => assert_eq!(members[2].line, 4),
+        _ => unreachable!(),
+    }
+
+    for bad in &[
+        "",
+        "{",
+        "{\"a\"}",
+        "{\"a\": 1,}",
+        "[1 2]",
+        "\"unclosed",
+        "\"raw\nnewline\"",
+        "\"\\x\"",
+        "\"\\u12\"",
+        "+1",
+        "1.2.3",
+        "{} {}",
+        "nul",
+    ] {
+        assert!(matches!(parse(bad), Err(JsonSyntax(_))), "{}", bad);
+    }
+    assert!(matches!(parse("{\n\n\"a\": x}"), Err(JsonSyntax(3))));
+    let deep = "[".repeat(MAX_DEPTH + 1);
+    assert!(matches!(parse(&deep), Err(JsonSyntax(1))));
+    assert!(matches!(read(&b"\"\xff\""[..]), Err(DecodingError)));
+}
+
+#[test]
+fn test_write() {
+    let value = Value::Object(vec![
+        Member::new("a", Value::String("\"q\"\n\u{1}ą".to_owned())),
+        Member::new("empty", Value::Object(vec![])),
+        Member::new(
+            "list",
+            Value::Array(vec![Value::Number("1".to_owned()), Value::Null]),
+        ),
+        Member::new(
+            "nested",
+            Value::Object(vec![Member::new("b", Value::Bool(true))]),
+        ),
+    ]);
+    let mut out = vec![];
+    write(&value, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        "{\n  \"a\": \"\\\"q\\\"\\n\\u0001ą\",\n  \"empty\": {},\n  \"list\": [\n    1,\n    null\n  ],\n  \"nested\": {\n    \"b\": true\n  }\n}\n"
+    );
+    let mut again = vec![];
+    write(&parse(&out).unwrap(), &mut again).unwrap();
+    assert_eq!(String::from_utf8(again).unwrap(), out);
+}
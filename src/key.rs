@@ -0,0 +1,112 @@
+//! Keys of the messages of a catalog, which can be looked up without allocating.
+
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+
+/// The key of a message: its context and msgid joined with `\x04`,
+/// like in the original strings of MO files, which also gives their order.
+///
+/// Keys are hashed by their parts, so that a map of keys can be searched
+/// with the borrowed parts of a lookup through `KeyParts`.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Key(String);
+
+impl Key {
+    pub fn new(context: Option<&str>, id: &str) -> Self {
+        match context {
+            Some(ctxt) => {
+                let mut key = String::with_capacity(ctxt.len() + 1 + id.len());
+                key.push_str(ctxt);
+                key.push('\x04');
+                key.push_str(id);
+                Key(key)
+            }
+            None => Key(id.to_owned()),
+        }
+    }
+
+    /// Returns whether this is the key of the header entry.
+    pub fn is_header(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[cfg(any(test, feature = "apple", feature = "properties", feature = "yaml"))]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Key {
+    /// Makes a key from its joined form.
+    fn from(key: &str) -> Self {
+        Key(key.to_owned())
+    }
+}
+
+/// The context and msgid of a message,
+/// either owned by a `Key` or borrowed from the arguments of a lookup.
+pub trait KeyParts {
+    fn parts(&self) -> (Option<&str>, &str);
+}
+
+impl KeyParts for Key {
+    fn parts(&self) -> (Option<&str>, &str) {
+        match self.0.find('\x04') {
+            Some(i) => (Some(&self.0[..i]), &self.0[i + 1..]),
+            None => (None, &self.0),
+        }
+    }
+}
+
+impl KeyParts for (Option<&str>, &str) {
+    fn parts(&self) -> (Option<&str>, &str) {
+        *self
+    }
+}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state)
+    }
+}
+
+impl Hash for dyn KeyParts + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state)
+    }
+}
+
+impl PartialEq for dyn KeyParts + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for dyn KeyParts + '_ {}
+
+impl<'a> Borrow<dyn KeyParts + 'a> for Key {
+    fn borrow(&self) -> &(dyn KeyParts + 'a) {
+        self
+    }
+}
+
+#[test]
+fn test_key() {
+    use std::collections::HashMap;
+
+    let key = Key::new(Some("menu"), "Open");
+    assert_eq!(key, Key::from("menu\x04Open"));
+    assert_eq!(key.parts(), (Some("menu"), "Open"));
+    assert_eq!(Key::new(None, "Open").parts(), (None, "Open"));
+    assert!(Key::new(None, "").is_header());
+    assert!(!Key::new(Some(""), "").is_header());
+
+    let mut map = HashMap::new();
+    map.insert(key, 1);
+    map.insert(Key::new(None, "Open"), 2);
+    let get = |parts: &dyn KeyParts| map.get(parts).copied();
+    assert_eq!(get(&(Some("menu"), "Open")), Some(1));
+    assert_eq!(get(&(None, "Open")), Some(2));
+    assert_eq!(get(&(Some("menu"), "Close")), None);
+    assert_eq!(map[&Key::from("menu\x04Open")], 1);
+}
@@ -45,32 +45,194 @@
     unused_import_braces
 )]
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "android")]
+mod android;
+#[cfg(feature = "apple")]
+mod apple;
+#[cfg(feature = "arb")]
+mod arb;
+pub mod build;
+mod builder;
+mod cache;
+mod chain;
+#[cfg(feature = "csv")]
+mod csv;
+mod deferred;
+mod diff;
+mod domain;
+pub mod embed;
 mod error;
+pub mod extract;
+mod flag;
+#[cfg(feature = "fluent")]
+mod fluent;
+mod format;
+#[cfg(feature = "global")]
+pub mod global;
+#[cfg(feature = "json")]
+mod i18next;
+#[cfg(feature = "icu-message")]
+mod icu;
+#[cfg(any(feature = "arb", feature = "json"))]
+mod json;
+mod key;
+pub mod locale;
+mod memory;
+mod merge;
 mod metadata;
+mod missing;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+mod mo;
 mod parser;
-mod plurals;
+pub mod plurals;
+pub mod printf;
+#[cfg(feature = "properties")]
+mod properties;
+mod pseudo;
+#[cfg(feature = "qt")]
+mod qt;
+mod recording;
+mod registry;
+mod stats;
+mod translator;
+// there are no threads nor files to watch on WebAssembly without an OS
+#[cfg(all(
+    feature = "watch",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+mod watch;
+mod writer;
+#[cfg(feature = "xliff")]
+mod xliff;
+#[cfg(any(
+    feature = "android",
+    feature = "apple",
+    feature = "qt",
+    feature = "xliff"
+))]
+mod xml;
+#[cfg(feature = "yaml")]
+mod yaml;
 
-use std::collections::HashMap;
-use std::io::Read;
+use std::borrow::Cow;
+use std::collections::{hash_map, BTreeSet, HashMap};
+use std::io::{Read, Write};
 use std::ops::Deref;
+use std::sync::Arc;
 
+use crate::key::{Key, KeyParts};
+use crate::metadata::parse_metadata;
+use crate::missing::MissingHook;
 use crate::parser::default_resolver;
 use crate::plurals::*;
-pub use crate::{error::Error, parser::ParseOptions};
+pub use crate::{
+    builder::CatalogBuilder,
+    chain::ChainedCatalog,
+    deferred::{gettext_noop, Deferred},
+    diff::{CatalogDiff, MessageChange},
+    domain::TextDomain,
+    error::{Error, ParseWarning},
+    flag::Flag,
+    format::FormatStyle,
+    memory::{Suggestion, TranslationMemory},
+    merge::MergeStrategy,
+    metadata::MetadataMap,
+    missing::MissingLookup,
+    mo::{CatalogRef, MoCatalog, MoReader},
+    parser::ParseOptions,
+    plurals::{PluralCategory, PluralFallback, PluralForms, PluralOperands, ToPluralNumber},
+    pseudo::PseudoLocalizer,
+    recording::RecordingCatalog,
+    registry::CatalogRegistry,
+    stats::CatalogStats,
+    translator::Translator,
+};
+
+#[doc(hidden)]
+pub use crate::{
+    format::{
+        arg as __arg, check_placeholders as __check_placeholders, interpolate as __interpolate,
+    },
+    parser::is_valid_mo as __is_valid_mo,
+};
+
+#[cfg(all(feature = "mmap", unix))]
+pub use crate::mmap::Mmap;
+
+#[cfg(feature = "icu-message")]
+pub use crate::icu::IcuArg;
+
+#[cfg(feature = "properties")]
+pub use crate::properties::PropertiesEncoding;
+
+#[cfg(all(
+    feature = "watch",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub use crate::watch::WatchedCatalog;
+
+#[cfg(feature = "csv")]
+pub use crate::csv::CsvColumn;
 
-fn key_with_context(context: &str, key: &str) -> String {
-    let mut result = context.to_owned();
-    result.push('\x04');
-    result.push_str(key);
-    result
+#[cfg(feature = "json")]
+pub use crate::i18next::{JsonFormat, PluralSuffix};
+
+#[cfg(feature = "xliff")]
+pub use crate::xliff::XliffVersion;
+
+// Catalogs are shared between threads, e.g. in the state of servers,
+// so these types must stay `Send` and `Sync`, with whatever resolvers and hooks they hold.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Catalog>();
+    assert_send_sync::<ChainedCatalog>();
+    assert_send_sync::<MoCatalog<Vec<u8>>>();
+    assert_send_sync::<MoCatalog<&'static [u8]>>();
+    assert_send_sync::<MoCatalog<Arc<[u8]>>>();
+    assert_send_sync::<TextDomain>();
+    assert_send_sync::<CatalogRegistry>();
+    assert_send_sync::<RecordingCatalog<Catalog>>();
+    assert_send_sync::<PluralForms>();
+    assert_send_sync::<Box<dyn Translator + Send + Sync>>();
+    #[cfg(all(
+        feature = "watch",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    assert_send_sync::<WatchedCatalog>();
+};
+
+/// Returns `msg_id` if `n == 1`, `msg_id_plural` otherwise.
+fn untranslated<'a>(msg_id: &'a str, msg_id_plural: &'a str, n: u64) -> &'a str {
+    if n == 1 {
+        msg_id
+    } else {
+        msg_id_plural
+    }
 }
 
 /// Catalog represents a set of translation strings
 /// parsed out of one MO file.
+///
+/// The lookup methods fall back to the original strings
+/// when a message is not translated, so the strings they return
+/// borrow from both the catalog and the original strings given to them.
+/// The `try_` lookups return `None` for untranslated messages instead,
+/// borrowing from the catalog only, so that callers can apply their own fallback.
+///
+/// Catalogs are `Send` and `Sync`, including the plural resolvers and missing hooks
+/// they are given, so that they can be shared between threads, e.g. in an `Arc`.
 #[derive(Clone, Debug)]
 pub struct Catalog {
-    strings: HashMap<String, Message>,
+    strings: HashMap<Key, Message>,
+    obsolete: HashMap<Key, Message>,
     resolver: Resolver,
+    plural_fallback: PluralFallback,
+    show_header: bool,
+    on_missing: Option<MissingHook>,
 }
 
 impl Catalog {
@@ -85,7 +247,11 @@ impl Catalog {
     fn new() -> Self {
         Catalog {
             strings: HashMap::new(),
+            obsolete: HashMap::new(),
             resolver: Resolver::Function(default_resolver),
+            plural_fallback: PluralFallback::default(),
+            show_header: false,
+            on_missing: None,
         }
     }
 
@@ -93,6 +259,8 @@ impl Catalog {
     /// Returns the `Err` variant upon encountering an invalid file format
     /// or invalid byte sequence in strings.
     ///
+    /// Files written on machines of either byte order are supported.
+    ///
     /// Calling this method is equivalent to calling
     /// `ParseOptions::new().parse(reader)`.
     ///
@@ -109,80 +277,667 @@ impl Catalog {
         ParseOptions::new().parse(reader)
     }
 
+    /// Parses a gettext catalog from the given textual PO file.
+    /// Returns the `Err` variant upon encountering invalid syntax
+    /// or invalid byte sequence in strings.
+    ///
+    /// Untranslated entries are kept in the catalog,
+    /// but lookups treat them the same as missing ones.
+    ///
+    /// Calling this method is equivalent to calling
+    /// `ParseOptions::new().parse_po(reader)`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use gettext::Catalog;
+    /// use std::fs::File;
+    ///
+    /// let file = File::open("french.po").unwrap();
+    /// let catalog = Catalog::parse_po(file).unwrap();
+    /// ```
+    pub fn parse_po<R: Read>(reader: R) -> Result<Self, Error> {
+        ParseOptions::new().parse_po(reader)
+    }
+
+    /// Writes the catalog out as a binary MO file.
+    ///
+    /// Strings are encoded using the charset declared in the catalog's header,
+    /// and messages without any translation or flagged as `fuzzy` are left out,
+    /// as `msgfmt` does.
+    /// Returns the `Err` variant if the header is malformed,
+    /// declares an unknown charset or a string cannot be encoded in it.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use gettext::Catalog;
+    /// use std::fs::File;
+    ///
+    /// let catalog = Catalog::parse_po(File::open("french.po").unwrap()).unwrap();
+    /// catalog.write_mo(File::create("french.mo").unwrap()).unwrap();
+    /// ```
+    pub fn write_mo<W: Write>(&self, writer: W) -> Result<(), Error> {
+        writer::write_mo(self, writer)
+    }
+
+    /// Writes the catalog out as a textual PO file.
+    ///
+    /// The header comes first, followed by the messages sorted by context and msgid
+    /// and then by the obsolete messages, commented out with `#~`.
+    /// Strings are encoded using the charset declared in the catalog's header.
+    /// Returns the `Err` variant if the header is malformed,
+    /// declares an unknown charset or a string cannot be encoded in it.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use gettext::Catalog;
+    /// use std::fs::File;
+    ///
+    /// let catalog = Catalog::parse(File::open("french.mo").unwrap()).unwrap();
+    /// catalog.write_po(File::create("french.po").unwrap()).unwrap();
+    /// ```
+    pub fn write_po<W: Write>(&self, writer: W) -> Result<(), Error> {
+        writer::write_po(self, writer)
+    }
+
+    /// Returns the template of the catalog, like the POT file it was made from:
+    /// the same messages with their contexts, plural forms, extracted comments,
+    /// references and flags, but empty translations.
+    ///
+    /// The comments of translators, the `fuzzy` flags and the previous strings
+    /// belong to the translations, so they are left out, as are the obsolete messages.
+    /// The header is replaced with a template one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .plural_forms("nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;")
+    ///     .add_with_context("menu", "Open", "Atidaryti")
+    ///     .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut pot = vec![];
+    /// catalog.to_pot().write_po(&mut pot).unwrap();
+    /// let pot = String::from_utf8(pot).unwrap();
+    /// assert!(pot.contains("msgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"\"\n"));
+    /// assert!(pot.contains("msgid_plural \"Files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n"));
+    /// ```
+    pub fn to_pot(&self) -> Catalog {
+        let mut template = Catalog::new();
+        template.insert(Message::new("", None, vec![extract::TEMPLATE_HEADER]));
+        for msg in self.strings.values().filter(|msg| !msg.is_header()) {
+            let forms = if msg.id_plural.is_some() { 2 } else { 1 };
+            let mut flags = msg.flags.clone();
+            flags.remove(&Flag::Fuzzy);
+            template.insert(Message {
+                translated: vec![String::new(); forms],
+                translator_comments: vec![],
+                flags,
+                previous_context: None,
+                previous_id: None,
+                previous_id_plural: None,
+                ..msg.clone()
+            });
+        }
+        template
+    }
+
+    /// Returns the metadata stored in the header entry of the catalog.
+    ///
+    /// The returned map is empty if the catalog has no header.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use gettext::Catalog;
+    /// use std::fs::File;
+    ///
+    /// let catalog = Catalog::parse(File::open("french.mo").unwrap()).unwrap();
+    /// println!("{:?}", catalog.metadata().language());
+    /// ```
+    pub fn metadata(&self) -> MetadataMap<'_> {
+        self.message(None, "")
+            .and_then(|m| m.translated.first())
+            .and_then(|blob| parse_metadata(blob).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns an iterator over all the messages of the catalog, in arbitrary order,
+    /// including the header entry with an empty msgid and untranslated messages.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.strings.values())
+    }
+
+    /// Returns the messages that are no longer used by the program,
+    /// but whose translations are kept around in case they come back.
+    ///
+    /// They are read from the entries of PO files commented out with `#~`
+    /// and are never used for lookups.
+    pub fn obsolete(&self) -> impl Iterator<Item = &Message> {
+        self.obsolete.values()
+    }
+
+    /// Sets the callback called whenever a lookup falls back
+    /// because a message or its plural form is not translated,
+    /// e.g. to log untranslated strings or report them to telemetry.
+    ///
+    /// The `try_` lookups, which do not fall back, do not call it.
+    /// Chains call the callback of their first catalog.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use gettext::Catalog;
+    ///
+    /// let missing = Arc::new(Mutex::new(vec![]));
+    /// let reported = missing.clone();
+    /// let mut catalog = Catalog::empty();
+    /// catalog.on_missing(move |lookup| reported.lock().unwrap().push(lookup.id().to_owned()));
+    ///
+    /// assert_eq!(catalog.gettext("Open"), "Open");
+    /// assert_eq!(*missing.lock().unwrap(), ["Open"]);
+    /// ```
+    pub fn on_missing<F>(&mut self, callback: F)
+    where
+        F: Fn(&MissingLookup) + Send + Sync + 'static,
+    {
+        self.on_missing = Some(MissingHook(Arc::new(callback)));
+    }
+
+    /// Sets the translated forms of the message with the given context and msgid,
+    /// adding the message if the catalog does not have it yet,
+    /// e.g. for live translation editing or overrides pushed by a server.
+    ///
+    /// Setting the header entry does not change the plural formula of the catalog.
+    pub fn set<V: Into<String>>(&mut self, msg_context: Option<&str>, msg_id: &str, forms: Vec<V>) {
+        let translated = forms.into_iter().map(Into::into).collect();
+        let parts: &dyn KeyParts = &(msg_context, msg_id);
+        match self.strings.get_mut(parts) {
+            Some(msg) => msg.translated = translated,
+            None => self.insert(Message::new(
+                msg_id.to_owned(),
+                msg_context.map(str::to_owned),
+                translated,
+            )),
+        }
+    }
+
+    /// Removes the message with the given context and msgid from the catalog,
+    /// returning it if it was there.
+    pub fn remove(&mut self, msg_context: Option<&str>, msg_id: &str) -> Option<Message> {
+        let parts: &dyn KeyParts = &(msg_context, msg_id);
+        self.strings.remove(parts)
+    }
+
+    /// Keeps only the messages for which `keep` returns `true`,
+    /// e.g. the ones in a given context, removing all the others.
+    ///
+    /// The header entry is passed to `keep` as well;
+    /// removing it leaves the plural formula of the catalog as it is.
+    pub fn retain<F: FnMut(&Message) -> bool>(&mut self, mut keep: F) {
+        self.strings.retain(|_, msg| keep(msg));
+    }
+
+    /// Returns a catalog with every translated string of this one
+    /// replaced with what `map` returns for it, like `msgfilter` does,
+    /// e.g. to enforce terminology or fix the casing of translations.
+    ///
+    /// `map` is given the msgid of the message, the index of the translated form
+    /// and the translation. Untranslated forms and the header entry are left as they are,
+    /// while the translations of obsolete messages are mapped too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .add("Open", "atidaryti")
+    ///     .add_plural("File", "Files", vec!["failas", "failai", ""])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let capitalized = catalog.map_translations(|_, _, text| {
+    ///     let mut chars = text.chars();
+    ///     chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+    /// });
+    /// assert_eq!(capitalized.gettext("Open"), "Atidaryti");
+    /// assert_eq!(capitalized.ngettext("File", "Files", 2), "Failai");
+    /// ```
+    pub fn map_translations<F>(&self, mut map: F) -> Catalog
+    where
+        F: FnMut(&str, usize, &str) -> String,
+    {
+        let mut catalog = self.clone();
+        let messages = catalog
+            .strings
+            .values_mut()
+            .chain(catalog.obsolete.values_mut());
+        for msg in messages.filter(|msg| !msg.is_header()) {
+            for (form, text) in msg.translated.iter_mut().enumerate() {
+                if !text.is_empty() {
+                    *text = map(&msg.id, form, text);
+                }
+            }
+        }
+        catalog
+    }
+
     fn insert(&mut self, msg: Message) {
-        let key = match msg.context {
-            Some(ref ctxt) => key_with_context(ctxt, &msg.id),
-            None => msg.id.clone(),
-        };
+        let key = Key::new(msg.context.as_deref(), &msg.id);
         self.strings.insert(key, msg);
     }
 
+    /// Returns the number of plural forms of the catalog,
+    /// from its `Plural-Forms` header or else its `Language`, or 2 if neither tells.
+    ///
+    /// Each translated message with a plural form should have that many translations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let catalog = CatalogBuilder::new().header("Language", "lt").build().unwrap();
+    /// assert_eq!(catalog.nplurals(), 3);
+    /// ```
+    pub fn nplurals(&self) -> usize {
+        let metadata = self.metadata();
+        metadata
+            .plural_forms()
+            .0
+            .or_else(|| PluralForms::for_language(metadata.language()?)?.nplurals())
+            .unwrap_or(2)
+    }
+
+    /// Returns the message with the given context and msgid.
+    fn message(&self, msg_context: Option<&str>, msg_id: &str) -> Option<&Message> {
+        let parts: &dyn KeyParts = &(msg_context, msg_id);
+        self.strings.get(parts)
+    }
+
+    /// Returns the entry of `msg_id` with all of its translated forms,
+    /// or `None` if the catalog does not have it.
+    pub fn get(&self, msg_id: &str) -> Option<&Message> {
+        self.lookup(None, msg_id)
+    }
+
+    /// Returns the entry of `msg_id` in the context `msg_context`
+    /// with all of its translated forms,
+    /// or `None` if the catalog does not have it.
+    pub fn get_with_context(&self, msg_context: &str, msg_id: &str) -> Option<&Message> {
+        self.lookup(Some(msg_context), msg_id)
+    }
+
+    /// Returns whether the catalog has a translation of `msg_id`
+    /// in any of its plural forms, even if it is the same as `msg_id`.
+    pub fn has_message(&self, msg_id: &str) -> bool {
+        self.lookup(None, msg_id)
+            .is_some_and(Message::is_translated)
+    }
+
+    /// Returns whether the catalog has a translation of `msg_id`
+    /// in the context `msg_context`, in any of its plural forms.
+    pub fn has_message_ctx(&self, msg_context: &str, msg_id: &str) -> bool {
+        self.lookup(Some(msg_context), msg_id)
+            .is_some_and(Message::is_translated)
+    }
+
     /// Returns the singular translation of `msg_id` from the given catalog
     /// or `msg_id` itself if a translation does not exist.
     pub fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str {
-        self.strings
-            .get(msg_id)
-            .and_then(|msg| msg.get_translated(0))
-            .unwrap_or(msg_id)
+        self.find_translation(None, msg_id, None)
+            .unwrap_or_else(|| self.missing(None, msg_id))
     }
 
     /// Returns the plural translation of `msg_id` from the given catalog
     /// with the correct plural form for the number `n` of objects.
     /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise, unless another `PluralFallback` is configured.
+    pub fn ngettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.find_translation(None, msg_id, Some(n))
+            .unwrap_or_else(|| self.fallback(None, msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the plural form matching the CLDR plural `category`
+    /// rather than a number of objects.
+    ///
+    /// The category is mapped to a plural form through a number
+    /// that falls into it in the catalog's `Language`,
+    /// or into the `Other` category if the language does not use it.
+    /// Returns msg_id if a translation does not exist and that number is 1,
     /// msg_id_plural otherwise.
-    pub fn ngettext<'a>(&'a self, msg_id: &'a str, msg_id_plural: &'a str, n: u64) -> &'a str {
-        let form_no = self.resolver.resolve(n);
-        let message = self.strings.get(msg_id);
-        match message.and_then(|m| m.get_translated(form_no)) {
-            Some(msg) => msg,
-            None if n == 1 => msg_id,
-            None if n != 1 => msg_id_plural,
-            _ => unreachable!(),
+    pub fn ngettext_category<'a>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        category: PluralCategory,
+    ) -> &'a str {
+        let metadata = self.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let n = category
+            .sample(language)
+            .or_else(|| PluralCategory::Other.sample(language))
+            .unwrap_or(2);
+        self.ngettext(msg_id, msg_id_plural, n)
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the plural form for the range of numbers from `start` to `end`,
+    /// e.g. for "3–5 items selected".
+    ///
+    /// The form is that of the CLDR category of the range in the catalog's `Language`,
+    /// as `PluralCategory::for_range` gives it, and of the number `end`
+    /// if the language does not use that category.
+    /// Returns msg_id if a translation does not exist and that number is 1,
+    /// msg_id_plural otherwise.
+    pub fn ngettext_range<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        start: N,
+        end: N,
+    ) -> &'a str {
+        let (start, end) = (start.to_plural_number(), end.to_plural_number());
+        let metadata = self.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let category = PluralCategory::for_range(language, start, end);
+        let n = if PluralCategory::for_number(language, end) == category {
+            end
+        } else {
+            category.sample(language).unwrap_or(end)
+        };
+        self.ngettext(msg_id, msg_id_plural, n)
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the plural form for a decimal number `n` of objects, e.g. "1.5 hours".
+    ///
+    /// This is `ngettext_operands` with the shortest decimal of `n`,
+    /// so that whole numbers are treated as integers.
+    pub fn ngettext_f64<'a>(&'a self, msg_id: &'a str, msg_id_plural: &'a str, n: f64) -> &'a str {
+        self.ngettext_operands(msg_id, msg_id_plural, &PluralOperands::from(n))
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the plural form for the decimal number `operands` as it is shown,
+    /// e.g. `1.50`.
+    ///
+    /// Numbers without fraction digits use the plural form given
+    /// by the `Plural-Forms` expression, like with `ngettext`.
+    /// The expressions only see integers, so other numbers use
+    /// the form of their CLDR category in the catalog's `Language`,
+    /// like with `ngettext_category`.
+    pub fn ngettext_operands<'a>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        operands: &PluralOperands,
+    ) -> &'a str {
+        if operands.v == 0 {
+            return self.ngettext(msg_id, msg_id_plural, operands.i);
         }
+        let metadata = self.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let category = PluralCategory::for_operands(language, operands);
+        self.ngettext_category(msg_id, msg_id_plural, category)
     }
 
     /// Returns the singular translation of `msg_id`
     /// in the context `msg_context`
     /// or `msg_id` itself if a translation does not exist.
-    // TODO: DRY gettext/pgettext
     pub fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str {
-        let key = key_with_context(msg_context, &msg_id);
-        self.strings
-            .get(&key)
-            .and_then(|msg| msg.get_translated(0))
-            .unwrap_or(msg_id)
+        self.find_translation(Some(msg_context), msg_id, None)
+            .unwrap_or_else(|| self.missing(Some(msg_context), msg_id))
     }
 
     /// Returns the plural translation of `msg_id`
     /// in the context `msg_context`
     /// with the correct plural form for the number `n` of objects.
     /// Returns msg_id if a translation does not exist and `n == 1`,
-    /// msg_id_plural otherwise.
-    // TODO: DRY ngettext/npgettext
-    pub fn npgettext<'a>(
+    /// msg_id_plural otherwise, unless another `PluralFallback` is configured.
+    pub fn npgettext<'a, N: ToPluralNumber>(
         &'a self,
         msg_context: &str,
         msg_id: &'a str,
         msg_id_plural: &'a str,
-        n: u64,
+        n: N,
     ) -> &'a str {
-        let key = key_with_context(msg_context, &msg_id);
-        let form_no = self.resolver.resolve(n);
-        let message = self.strings.get(&key);
-        match message.and_then(|m| m.get_translated(form_no)) {
-            Some(msg) => msg,
-            None if n == 1 => msg_id,
-            None if n != 1 => msg_id_plural,
-            _ => unreachable!(),
+        let n = n.to_plural_number();
+        self.find_translation(Some(msg_context), msg_id, Some(n))
+            .unwrap_or_else(|| self.fallback(Some(msg_context), msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the singular translation of `msg_id` for the given `selector`,
+    /// e.g. `gender=female`, to let translations agree with the grammatical gender
+    /// or another property of an argument.
+    ///
+    /// By convention, the variants of a message are messages with the selector as their
+    /// msgctxt, `key=value` with the key naming the property. A message without
+    /// a translated variant for the selector falls back to the translation of `msg_id`
+    /// without a context, and to `msg_id` itself if that does not exist either.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .add("{} is online", "{} prisijungęs")
+    ///     .add_with_context("gender=female", "{} is online", "{} prisijungusi")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(catalog.sgettext("{} is online", "gender=female"), "{} prisijungusi");
+    /// assert_eq!(catalog.sgettext("{} is online", "gender=male"), "{} prisijungęs");
+    /// ```
+    pub fn sgettext<'a>(&'a self, msg_id: &'a str, selector: &str) -> &'a str {
+        self.try_pgettext(selector, msg_id)
+            .unwrap_or_else(|| self.gettext(msg_id))
+    }
+
+    /// Returns the plural translation of `msg_id` for the given `selector`
+    /// with the correct plural form for the number `n` of objects,
+    /// falling back to the plural translation without a context like `sgettext`.
+    pub fn nsgettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        selector: &str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.try_npgettext(selector, msg_id, n)
+            .unwrap_or_else(|| self.ngettext(msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the singular translation of `msg_id` from the given catalog,
+    /// or `None` if a translation does not exist.
+    pub fn try_gettext(&self, msg_id: &str) -> Option<&str> {
+        self.find_translation(None, msg_id, None)
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the correct plural form for the number `n` of objects,
+    /// or `None` if a translation does not exist.
+    pub fn try_ngettext<N: ToPluralNumber>(&self, msg_id: &str, n: N) -> Option<&str> {
+        self.find_translation(None, msg_id, Some(n.to_plural_number()))
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context` from the given catalog,
+    /// or `None` if a translation does not exist.
+    pub fn try_pgettext(&self, msg_context: &str, msg_id: &str) -> Option<&str> {
+        self.find_translation(Some(msg_context), msg_id, None)
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context` from the given catalog
+    /// with the correct plural form for the number `n` of objects,
+    /// or `None` if a translation does not exist.
+    pub fn try_npgettext<N: ToPluralNumber>(
+        &self,
+        msg_context: &str,
+        msg_id: &str,
+        n: N,
+    ) -> Option<&str> {
+        self.find_translation(Some(msg_context), msg_id, Some(n.to_plural_number()))
+    }
+
+    /// Returns the same string as `gettext` as a `Cow`,
+    /// so that post-processing helpers can own a modified string
+    /// without allocating when it is left untouched.
+    pub fn gettext_cow<'a>(&'a self, msg_id: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(self.gettext(msg_id))
+    }
+
+    /// Returns the same string as `ngettext` as a `Cow`.
+    pub fn ngettext_cow<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> Cow<'a, str> {
+        Cow::Borrowed(self.ngettext(msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the same string as `pgettext` as a `Cow`.
+    pub fn pgettext_cow<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(self.pgettext(msg_context, msg_id))
+    }
+
+    /// Returns the same string as `npgettext` as a `Cow`.
+    pub fn npgettext_cow<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> Cow<'a, str> {
+        Cow::Borrowed(self.npgettext(msg_context, msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the translation of `msg_id` in the optional context,
+    /// in the plural form for `n` objects if it is given
+    /// or in the singular form otherwise.
+    fn find_translation(
+        &self,
+        msg_context: Option<&str>,
+        msg_id: &str,
+        n: Option<u64>,
+    ) -> Option<&str> {
+        let form_no = n.map_or(0, |n| self.resolver.resolve(n));
+        self.lookup(msg_context, msg_id)?.get_translated(form_no)
+    }
+
+    /// Returns the message looked up with the given context and msgid,
+    /// keeping the header out of lookups unless it is shown.
+    fn lookup(&self, msg_context: Option<&str>, msg_id: &str) -> Option<&Message> {
+        if msg_context.is_none() && msg_id.is_empty() && !self.show_header {
+            return None;
+        }
+        self.message(msg_context, msg_id)
+    }
+
+    /// Reports a singular lookup without a translation and returns `msg_id`.
+    fn missing<'a>(&self, msg_context: Option<&str>, msg_id: &'a str) -> &'a str {
+        self.report_missing(MissingLookup {
+            msg_context,
+            msg_id,
+            msg_id_plural: None,
+            n: None,
+        });
+        msg_id
+    }
+
+    /// Calls the callback for missing translations, if one is set.
+    fn report_missing(&self, lookup: MissingLookup) {
+        if let Some(MissingHook(ref callback)) = self.on_missing {
+            callback(&lookup);
         }
     }
+
+    /// Returns the configured fallback for a plural message
+    /// without a translation in the plural form for `n` objects.
+    fn fallback<'a>(
+        &'a self,
+        msg_context: Option<&str>,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        self.report_missing(MissingLookup {
+            msg_context,
+            msg_id,
+            msg_id_plural: Some(msg_id_plural),
+            n: Some(n),
+        });
+        self.plural_fallback.select(msg_id, msg_id_plural, n, || {
+            let message = self.lookup(msg_context, msg_id)?;
+            let last = message.translated.iter().rev().find(|s| !s.is_empty());
+            last.map(String::as_str)
+        })
+    }
+}
+
+impl<'a> IntoIterator for &'a Catalog {
+    type Item = &'a Message;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iter is an iterator over the messages of a catalog,
+/// created by `Catalog::iter`.
+#[derive(Clone, Debug)]
+pub struct Iter<'a>(hash_map::Values<'a, Key, Message>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Message;
+
+    fn next(&mut self) -> Option<&'a Message> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
 }
 
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Message represents a single entry of a catalog:
+/// the original string, its optional context and plural form,
+/// and all of its translated forms.
 #[derive(Clone, Debug, Eq, PartialEq)]
-struct Message {
+pub struct Message {
     id: String,
     context: Option<String>,
+    id_plural: Option<String>,
     translated: Vec<String>,
+    translator_comments: Vec<String>,
+    extracted_comments: Vec<String>,
+    references: Vec<String>,
+    flags: BTreeSet<Flag>,
+    previous_context: Option<String>,
+    previous_id: Option<String>,
+    previous_id_plural: Option<String>,
 }
 
 impl Message {
@@ -190,19 +945,172 @@ impl Message {
         Message {
             id: id.into(),
             context: context.map(Into::into),
+            id_plural: None,
             translated: translated.into_iter().map(Into::into).collect(),
+            translator_comments: vec![],
+            extracted_comments: vec![],
+            references: vec![],
+            flags: BTreeSet::new(),
+            previous_context: None,
+            previous_id: None,
+            previous_id_plural: None,
         }
     }
 
+    #[cfg(test)]
+    fn with_plural<T: Into<String>>(mut self, id_plural: T) -> Self {
+        self.id_plural = Some(id_plural.into());
+        self
+    }
+
+    /// Returns the original singular string of the message.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the comments of translators on the message,
+    /// the `# ` lines of its PO entry.
+    pub fn translator_comments(&self) -> &[String] {
+        &self.translator_comments
+    }
+
+    /// Returns the comments for translators extracted from the source code,
+    /// the `#. ` lines of its PO entry.
+    pub fn extracted_comments(&self) -> &[String] {
+        &self.extracted_comments
+    }
+
+    /// Returns the places in the source code where the message is used,
+    /// e.g. `src/main.rs:10`, from the `#: ` lines of its PO entry.
+    pub fn references(&self) -> &[String] {
+        &self.references
+    }
+
+    /// Returns whether the translation is flagged as `fuzzy` in the PO file,
+    /// i.e. it needs to be reviewed by a translator.
+    ///
+    /// Messages read from MO files are never fuzzy.
+    pub fn is_fuzzy(&self) -> bool {
+        self.flags.contains(&Flag::Fuzzy)
+    }
+
+    /// Returns the flags of the message, from the `#, ` line of its PO entry.
+    ///
+    /// Messages read from MO files have no flags.
+    pub fn flags(&self) -> &BTreeSet<Flag> {
+        &self.flags
+    }
+
+    /// Returns whether the message has the given flag.
+    pub fn has_flag(&self, flag: Flag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    /// Returns the context that the translation was made for,
+    /// from the `#| msgctxt` line of a fuzzy PO entry.
+    pub fn previous_context(&self) -> Option<&str> {
+        self.previous_context.as_deref()
+    }
+
+    /// Returns the msgid that the translation was made for,
+    /// from the `#| msgid` line of a fuzzy PO entry.
+    ///
+    /// Comparing it with `id` shows what changed in the source string
+    /// since the translation was last reviewed.
+    pub fn previous_id(&self) -> Option<&str> {
+        self.previous_id.as_deref()
+    }
+
+    /// Returns the plural msgid that the translation was made for,
+    /// from the `#| msgid_plural` line of a fuzzy PO entry.
+    pub fn previous_id_plural(&self) -> Option<&str> {
+        self.previous_id_plural.as_deref()
+    }
+
+    /// Returns the context of the message, if it has one.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// Returns the original plural string of the message, if it has one.
+    pub fn id_plural(&self) -> Option<&str> {
+        self.id_plural.as_deref()
+    }
+
+    /// Returns all the translated forms of the message, in plural form order.
+    ///
+    /// Untranslated forms are represented by empty strings.
+    pub fn translated(&self) -> &[String] {
+        &self.translated
+    }
+
+    /// Returns whether this is the header entry, with an empty msgid and no context.
+    fn is_header(&self) -> bool {
+        self.id.is_empty() && self.context.is_none()
+    }
+
+    /// Returns whether any of the forms of the message is translated.
+    fn is_translated(&self) -> bool {
+        self.translated.iter().any(|s| !s.is_empty())
+    }
+
+    /// Returns the given translated form, treating empty strings as untranslated.
     fn get_translated(&self, form_no: usize) -> Option<&str> {
-        self.translated.get(form_no).map(|s| s.deref())
+        self.translated
+            .get(form_no)
+            .map(|s| s.deref())
+            .filter(|s| !s.is_empty())
     }
 }
 
 #[test]
 fn catalog_impls_send_sync() {
-    fn check<T: Send + Sync>(_: T) {};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    fn check<T: Send + Sync>(_: T) {}
     check(Catalog::new());
+
+    let missing = Arc::new(AtomicUsize::new(0));
+    let counter = missing.clone();
+    let mut catalog = CatalogBuilder::new()
+        .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+        .plural_resolver(|n| (n as usize).min(2))
+        .build()
+        .unwrap();
+    catalog.on_missing(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    let catalog = Arc::new(catalog);
+    let threads = (0..4)
+        .map(|n| {
+            let catalog = catalog.clone();
+            thread::spawn(move || {
+                assert_eq!(catalog.gettext("Missing"), "Missing");
+                catalog.ngettext("File", "Files", n).to_owned()
+            })
+        })
+        .collect::<Vec<_>>();
+    let forms = threads
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(forms, ["Failas", "Failai", "Failų", "Failų"]);
+    assert_eq!(missing.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn message_accessors() {
+    let msg = Message::new("Text", Some("ctx"), vec!["Tekstas", ""]).with_plural("Texts");
+    assert_eq!(msg.id(), "Text");
+    assert_eq!(msg.context(), Some("ctx"));
+    assert_eq!(msg.id_plural(), Some("Texts"));
+    assert_eq!(msg.translated(), &["Tekstas", ""]);
+
+    let msg = Message::new("Text", None, vec![]);
+    assert_eq!(msg.context(), None);
+    assert_eq!(msg.id_plural(), None);
+    assert!(msg.translated().is_empty());
 }
 
 #[test]
@@ -210,11 +1118,141 @@ fn catalog_insert() {
     let mut cat = Catalog::new();
     cat.insert(Message::new("thisisid", None, vec![]));
     cat.insert(Message::new("anotherid", Some("context"), vec![]));
-    let mut keys = cat.strings.keys().collect::<Vec<_>>();
+    let mut keys = cat.strings.keys().map(Key::as_str).collect::<Vec<_>>();
     keys.sort();
     assert_eq!(keys, &["context\x04anotherid", "thisisid"])
 }
 
+#[test]
+fn catalog_iter() {
+    let mut cat = Catalog::new();
+    cat.insert(Message::new("", None, vec!["Language: lt\n"]));
+    cat.insert(Message::new("Text", None, vec!["Tekstas"]));
+    cat.insert(Message::new("Open", Some("menu"), vec![""]));
+    assert_eq!(cat.iter().len(), 3);
+    let mut entries = cat
+        .iter()
+        .map(|msg| (msg.context(), msg.id()))
+        .collect::<Vec<_>>();
+    entries.sort();
+    assert_eq!(
+        entries,
+        &[(None, ""), (None, "Text"), (Some("menu"), "Open")]
+    );
+
+    let mut count = 0;
+    for msg in &cat {
+        assert!(cat.iter().any(|m| m == msg));
+        count += 1;
+    }
+    assert_eq!(count, 3);
+    assert_eq!(Catalog::new().iter().next(), None);
+}
+
+#[test]
+fn catalog_set() {
+    let mut cat = Catalog::new();
+    cat.set(None, "Text", vec!["Tekstas"]);
+    cat.set(Some("menu"), "Open", vec![String::from("Atidaryti")]);
+    assert_eq!(cat.gettext("Text"), "Tekstas");
+    assert_eq!(cat.pgettext("menu", "Open"), "Atidaryti");
+
+    cat.insert(Message::new("File", None, vec!["", ""]).with_plural("Files"));
+    cat.set(None, "File", vec!["Failas", "Failai"]);
+    assert_eq!(cat.ngettext("File", "Files", 2), "Failai");
+    assert_eq!(cat.get("File").unwrap().id_plural(), Some("Files"));
+    cat.set(None, "Text", vec![""]);
+    assert_eq!(cat.gettext("Text"), "Text");
+    assert_eq!(cat.len(), 3);
+}
+
+#[test]
+fn catalog_remove_retain() {
+    let mut cat = CatalogBuilder::new()
+        .plural_forms("nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;")
+        .add("Text", "Tekstas")
+        .add_with_context("debug", "Dump", "Išrašas")
+        .add_with_context("debug", "Trace", "Sekimas")
+        .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+        .build()
+        .unwrap();
+    let removed = cat.remove(None, "Text").unwrap();
+    assert_eq!(removed.translated(), &["Tekstas"]);
+    assert_eq!(cat.remove(None, "Text"), None);
+    assert_eq!(cat.remove(None, "Dump"), None);
+    assert_eq!(cat.gettext("Text"), "Text");
+    assert!(cat.remove(Some("debug"), "Dump").is_some());
+    assert_eq!(cat.pgettext("debug", "Dump"), "Dump");
+
+    cat.retain(|msg| msg.context() != Some("debug"));
+    assert_eq!(cat.len(), 2);
+    assert_eq!(cat.pgettext("debug", "Trace"), "Trace");
+    assert_eq!(cat.ngettext("File", "Files", 2), "Failai");
+    cat.retain(|msg| !msg.id().is_empty());
+    assert!(cat.metadata().is_empty());
+    assert_eq!(cat.ngettext("File", "Files", 2), "Failai");
+}
+
+#[test]
+fn catalog_on_missing() {
+    use std::sync::Mutex;
+
+    let mut cat = CatalogBuilder::new()
+        .plural_forms("nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;")
+        .add("Text", "Tekstas")
+        .add_plural("File", "Files", vec!["Failas", "", "Failų"])
+        .build()
+        .unwrap();
+    let missing = Arc::new(Mutex::new(vec![]));
+    let reported = missing.clone();
+    cat.on_missing(move |lookup| {
+        let lookup = (
+            lookup.context().map(str::to_owned),
+            lookup.id().to_owned(),
+            lookup.id_plural().map(str::to_owned),
+            lookup.n(),
+        );
+        reported.lock().unwrap().push(lookup);
+    });
+    assert_eq!(cat.gettext("Text"), "Tekstas");
+    assert_eq!(cat.ngettext("File", "Files", 1), "Failas");
+    assert_eq!(cat.try_gettext("Missing"), None);
+    assert!(missing.lock().unwrap().is_empty());
+
+    assert_eq!(cat.gettext("Missing"), "Missing");
+    assert_eq!(cat.pgettext("menu", "Text"), "Text");
+    assert_eq!(cat.ngettext("File", "Files", 2), "Files");
+    assert_eq!(cat.npgettext("menu", "Tab", "Tabs", 1), "Tab");
+    let chain = ChainedCatalog::new(vec![cat.clone()]);
+    assert_eq!(chain.gettext("Chained"), "Chained");
+    chain.gettext("Text");
+    let s = |s: &str| Some(s.to_owned());
+    assert_eq!(
+        *missing.lock().unwrap(),
+        [
+            (None, "Missing".to_owned(), None, None),
+            (s("menu"), "Text".to_owned(), None, None),
+            (None, "File".to_owned(), s("Files"), Some(2)),
+            (s("menu"), "Tab".to_owned(), s("Tabs"), Some(1)),
+            (None, "Chained".to_owned(), None, None),
+        ]
+    );
+}
+
+#[test]
+fn catalog_metadata() {
+    let mut cat = Catalog::new();
+    assert!(cat.metadata().is_empty());
+    cat.insert(Message::new(
+        "",
+        None,
+        vec!["Language: lt\nX-Generator: Poedit 2.2\n"],
+    ));
+    let metadata = cat.metadata();
+    assert_eq!(metadata.language(), Some("lt"));
+    assert_eq!(metadata["X-Generator"], "Poedit 2.2");
+}
+
 #[test]
 fn catalog_gettext() {
     let mut cat = Catalog::new();
@@ -224,6 +1262,51 @@ fn catalog_gettext() {
     assert_eq!(cat.gettext("Image"), "Image");
 }
 
+#[test]
+fn catalog_get() {
+    let mut cat = Catalog::new();
+    let text = Message::new("Text", None, vec!["Tekstas", ""]).with_plural("Texts");
+    cat.insert(text.clone());
+    cat.insert(Message::new("Open", Some("menu"), vec!["Atidaryti"]));
+    cat.insert(Message::new("", None, vec!["Language: lt\n"]));
+    assert_eq!(cat.get("Text"), Some(&text));
+    assert_eq!(cat.get("Text").unwrap().translated(), &["Tekstas", ""]);
+    assert_eq!(cat.get("Open"), None);
+    assert_eq!(cat.get(""), None);
+    let open = cat.get_with_context("menu", "Open").unwrap();
+    assert_eq!((open.context(), open.id()), (Some("menu"), "Open"));
+    assert_eq!(cat.get_with_context("menu", "Text"), None);
+}
+
+#[test]
+fn catalog_has_message() {
+    let mut cat = Catalog::new();
+    cat.insert(Message::new("Text", None, vec!["Text"]));
+    cat.insert(Message::new("Texts", None, vec!["", "Tekstai"]));
+    cat.insert(Message::new("Image", None, vec![""]));
+    cat.insert(Message::new("Open", Some("menu"), vec!["Atidaryti"]));
+    cat.insert(Message::new("", None, vec!["Language: lt\n"]));
+    assert!(cat.has_message("Text"));
+    assert!(cat.has_message("Texts"));
+    assert!(!cat.has_message("Image"));
+    assert!(!cat.has_message("Missing"));
+    assert!(!cat.has_message("Open"));
+    assert!(!cat.has_message(""));
+    assert!(cat.has_message_ctx("menu", "Open"));
+    assert!(!cat.has_message_ctx("toolbar", "Open"));
+    assert!(!cat.has_message_ctx("menu", "Text"));
+}
+
+#[test]
+fn catalog_gettext_empty_translation() {
+    let mut cat = Catalog::new();
+    cat.insert(Message::new("Text", None, vec![""]));
+    cat.insert(Message::new("Texts", None, vec!["", "Tekstai"]));
+    assert_eq!(cat.gettext("Text"), "Text");
+    assert_eq!(cat.ngettext("Texts", "Texts", 1), "Texts");
+    assert_eq!(cat.ngettext("Texts", "Texts", 2), "Tekstai");
+}
+
 #[test]
 fn catalog_ngettext() {
     let mut cat = Catalog::new();
@@ -276,6 +1359,158 @@ fn catalog_npgettext_not_enough_forms_in_message() {
     assert_eq!(cat.npgettext("ctx", "Text", "Texts", 2), "Texts");
 }
 
+#[test]
+fn catalog_plural_fallback() {
+    fn resolver(count: u64) -> usize {
+        count as usize
+    }
+
+    let mut cat = Catalog::new();
+    cat.insert(Message::new("Text", None, vec!["Tekstas", "Tekstai", ""]));
+    cat.insert(Message::new("Image", Some("ctx"), vec!["", ""]));
+    cat.resolver = Resolver::Function(resolver);
+    let cases = [
+        (PluralFallback::Original, ["Texts", "Texts", "Missing"]),
+        (PluralFallback::Singular, ["Text", "Text", "Missing"]),
+        (PluralFallback::Plural, ["Texts", "Texts", "Missings"]),
+        (PluralFallback::LastForm, ["Tekstai", "Tekstai", "Missing"]),
+    ];
+    for (fallback, [form_2, form_5, missing_1]) in cases.iter().copied() {
+        cat.plural_fallback = fallback;
+        assert_eq!(cat.ngettext("Text", "Texts", 1), "Tekstai");
+        // a form past the translated ones, and past all of them
+        assert_eq!(cat.ngettext("Text", "Texts", 2), form_2);
+        assert_eq!(cat.ngettext("Text", "Texts", 5), form_5);
+        assert_eq!(cat.ngettext("Missing", "Missings", 1), missing_1);
+    }
+    cat.plural_fallback = PluralFallback::LastForm;
+    assert_eq!(cat.npgettext("ctx", "Image", "Images", 1), "Image");
+    assert_eq!(cat.npgettext("ctx", "Image", "Images", 7), "Images");
+}
+
+#[test]
+fn catalog_nplurals() {
+    let cat = CatalogBuilder::new()
+        .header("Language", "ja")
+        .plural_forms("nplurals=4; plural=n;")
+        .build()
+        .unwrap();
+    assert_eq!(cat.nplurals(), 4);
+    let cat = CatalogBuilder::new()
+        .header("Language", "ja")
+        .build()
+        .unwrap();
+    assert_eq!(cat.nplurals(), 1);
+    assert_eq!(Catalog::new().nplurals(), 2);
+}
+
+#[test]
+fn catalog_ngettext_category() {
+    use crate::PluralCategory::*;
+
+    let cat = CatalogBuilder::new()
+        .header("Language", "ru")
+        .plural_forms("nplurals=3; plural=((n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20)) ? 1 : 2));")
+        .add_plural("File", "Files", vec!["Файл", "Файла", "Файлов"])
+        .build()
+        .unwrap();
+    assert_eq!(cat.ngettext_category("File", "Files", One), "Файл");
+    assert_eq!(cat.ngettext_category("File", "Files", Few), "Файла");
+    assert_eq!(cat.ngettext_category("File", "Files", Many), "Файлов");
+    assert_eq!(cat.ngettext_category("Dir", "Dirs", One), "Dir");
+    assert_eq!(cat.ngettext_category("Dir", "Dirs", Few), "Dirs");
+
+    let cat = Catalog::new();
+    assert_eq!(cat.ngettext_category("Dir", "Dirs", One), "Dir");
+    assert_eq!(cat.ngettext_category("Dir", "Dirs", Few), "Dirs");
+}
+
+#[test]
+fn catalog_ngettext_range() {
+    let cat = CatalogBuilder::new()
+        .header("Language", "ru")
+        .plural_forms("nplurals=3; plural=((n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20)) ? 1 : 2));")
+        .add_plural("day", "days", vec!["день", "дня", "дней"])
+        .build()
+        .unwrap();
+    assert_eq!(cat.ngettext_range("day", "days", 1, 3), "дня");
+    assert_eq!(cat.ngettext_range("day", "days", 3, 5), "дней");
+    assert_eq!(cat.ngettext_range("day", "days", 5, 21), "день");
+    assert_eq!(cat.ngettext_range("day", "days", 3usize, 5), "дней");
+    assert_eq!(cat.ngettext_range("day", "days", -1i32, 1), "день");
+
+    let cat = CatalogBuilder::new()
+        .header("Language", "en")
+        .add_plural("day", "days", vec!["Day", "Days"])
+        .build()
+        .unwrap();
+    assert_eq!(cat.ngettext_range("day", "days", 0, 1), "Days");
+    assert_eq!(cat.ngettext_range("day", "days", 1, 1), "Day");
+    assert_eq!(Catalog::new().ngettext_range("day", "days", 0, 1), "days");
+}
+
+#[test]
+fn catalog_ngettext_f64() {
+    let cat = CatalogBuilder::new()
+        .header("Language", "fr")
+        .plural_forms("nplurals=2; plural=(n > 1);")
+        .add_plural("hour", "hours", vec!["heure", "heures"])
+        .build()
+        .unwrap();
+    assert_eq!(cat.ngettext_f64("hour", "hours", 1.5), "heure");
+    assert_eq!(cat.ngettext_f64("hour", "hours", 2.5), "heures");
+    assert_eq!(cat.ngettext_f64("hour", "hours", 1.0), "heure");
+    assert_eq!(cat.ngettext_f64("hour", "hours", 3.0), "heures");
+
+    let cat = CatalogBuilder::new()
+        .header("Language", "en")
+        .add_plural("hour", "hours", vec!["Hour", "Hours"])
+        .build()
+        .unwrap();
+    assert_eq!(cat.ngettext_f64("hour", "hours", 1.0), "Hour");
+    assert_eq!(cat.ngettext_f64("hour", "hours", 1.5), "Hours");
+    let operands = "1.0".parse().unwrap();
+    assert_eq!(cat.ngettext_operands("hour", "hours", &operands), "Hours");
+    assert_eq!(Catalog::new().ngettext_f64("hour", "hours", 1.5), "hours");
+}
+
+#[test]
+fn catalog_sgettext() {
+    let cat = CatalogBuilder::new()
+        .add("Welcome", "Sveikas")
+        .add_with_context("gender=female", "Welcome", "Sveika")
+        .add_with_context("gender=other", "Welcome", "")
+        .add_plural("friend", "friends", vec!["draugas", "draugai"])
+        .add_plural_with_context(
+            "gender=female",
+            "friend",
+            "friends",
+            vec!["draugė", "draugės"],
+        )
+        .build()
+        .unwrap();
+    assert_eq!(cat.sgettext("Welcome", "gender=female"), "Sveika");
+    assert_eq!(cat.sgettext("Welcome", "gender=male"), "Sveikas");
+    assert_eq!(cat.sgettext("Welcome", "gender=other"), "Sveikas");
+    assert_eq!(cat.sgettext("Goodbye", "gender=female"), "Goodbye");
+    assert_eq!(
+        cat.nsgettext("friend", "friends", "gender=female", 1),
+        "draugė"
+    );
+    assert_eq!(
+        cat.nsgettext("friend", "friends", "gender=female", 2usize),
+        "draugės"
+    );
+    assert_eq!(
+        cat.nsgettext("friend", "friends", "gender=male", 2),
+        "draugai"
+    );
+    assert_eq!(
+        cat.nsgettext("enemy", "enemies", "gender=male", 2),
+        "enemies"
+    );
+}
+
 #[test]
 fn catalog_pgettext() {
     let mut cat = Catalog::new();
@@ -284,6 +1519,59 @@ fn catalog_pgettext() {
     assert_eq!(cat.pgettext("integration test", "Text"), "Text");
 }
 
+#[test]
+fn catalog_gettext_cow() {
+    let mut cat = Catalog::new();
+    cat.insert(Message::new(
+        "Text",
+        Some("ctx"),
+        vec!["Tekstas", "Tekstai"],
+    ));
+    assert_eq!(cat.pgettext_cow("ctx", "Text"), "Tekstas");
+    assert_eq!(cat.npgettext_cow("ctx", "Text", "Texts", 2), "Tekstai");
+    assert_eq!(cat.gettext_cow("Text"), "Text");
+    assert_eq!(cat.ngettext_cow("Text", "Texts", 2), "Texts");
+    assert_eq!(cat.ngettext_cow("Text", "Texts", -1i8), "Text");
+
+    let mut shouted = cat.pgettext_cow("ctx", "Text");
+    assert!(matches!(shouted, Cow::Borrowed(_)));
+    *shouted.to_mut() = shouted.to_uppercase();
+    assert_eq!(shouted, "TEKSTAS");
+    assert_eq!(cat.pgettext("ctx", "Text"), "Tekstas");
+}
+
+#[test]
+fn catalog_try_gettext() {
+    let mut cat = Catalog::new();
+    cat.insert(Message::new("Text", None, vec!["Tekstas", "Tekstai"]));
+    cat.insert(Message::new("Image", Some("ctx"), vec!["Paveikslelis", ""]));
+    cat.insert(Message::new("Same", None, vec!["Same"]));
+    assert_eq!(cat.try_gettext("Text"), Some("Tekstas"));
+    assert_eq!(cat.try_gettext("Image"), None);
+    // a translation equal to the msgid is still a hit
+    assert_eq!(cat.try_gettext("Same"), Some("Same"));
+    assert_eq!(cat.try_ngettext("Text", 2), Some("Tekstai"));
+    assert_eq!(cat.try_ngettext("Missing", 1), None);
+    assert_eq!(cat.try_ngettext("Text", -2i64), Some("Tekstai"));
+    assert_eq!(cat.try_pgettext("ctx", "Image"), Some("Paveikslelis"));
+    assert_eq!(cat.try_pgettext("other", "Image"), None);
+    assert_eq!(cat.try_npgettext("ctx", "Image", 1), Some("Paveikslelis"));
+    assert_eq!(
+        cat.try_npgettext("ctx", "Image", 1usize),
+        Some("Paveikslelis")
+    );
+    // untranslated forms are misses
+    assert_eq!(cat.try_npgettext("ctx", "Image", 2), None);
+    assert_eq!(cat.try_gettext(""), None);
+
+    // the result outlives the msgid it was looked up with
+    let translation = {
+        let msg_id = String::from("Text");
+        cat.try_gettext(&msg_id)
+    };
+    assert_eq!(translation, Some("Tekstas"));
+}
+
 #[test]
 fn catalog_npgettext() {
     let mut cat = Catalog::new();
@@ -310,3 +1598,71 @@ fn catalog_npgettext() {
         "Texts"
     );
 }
+
+#[test]
+fn catalog_to_pot() {
+    let po = "# Lithuanian translation.\nmsgid \"\"\nmsgstr \"Language: lt\\n\"\n\n# checked\n#. a verb\n#: src/main.rs:10\n#, fuzzy, c-format\n#| msgid \"Opem %s\"\nmsgctxt \"menu\"\nmsgid \"Open %s\"\nmsgstr \"Atidaryti %s\"\n\nmsgid \"File\"\nmsgid_plural \"Files\"\nmsgstr[0] \"Failas\"\nmsgstr[1] \"Failai\"\nmsgstr[2] \"Failų\"\n\n#~ msgid \"Quit\"\n#~ msgstr \"Baigti\"\n";
+    let catalog = Catalog::parse_po(po.as_bytes()).unwrap();
+    let template = catalog.to_pot();
+    assert_eq!(template.len(), 3);
+    assert!(template.obsolete.is_empty());
+    assert_eq!(template.metadata().language(), None);
+    assert_eq!(template.metadata().charset(), Some("UTF-8"));
+
+    let open = template.get_with_context("menu", "Open %s").unwrap();
+    assert_eq!(open.translated(), &[""]);
+    assert!(open.translator_comments().is_empty());
+    assert_eq!(open.extracted_comments(), &["a verb"]);
+    assert_eq!(open.references(), &["src/main.rs:10"]);
+    assert!(open.has_flag(Flag::CFormat));
+    assert!(!open.is_fuzzy());
+    assert_eq!(open.previous_id(), None);
+    let file = template.get("File").unwrap();
+    assert_eq!(file.id_plural(), Some("Files"));
+    assert_eq!(file.translated(), &["", ""]);
+
+    assert_eq!(template.to_pot().strings, template.strings);
+}
+
+#[test]
+fn catalog_map_translations() {
+    let mut cat = CatalogBuilder::new()
+        .header("Language", "lt")
+        .add("Text", "Tekstas")
+        .add_with_context("menu", "Text", "Tekstą")
+        .add_plural("File", "Files", vec!["Failas", "", "Failų"])
+        .build()
+        .unwrap();
+    cat.obsolete.insert(
+        Key::from("Quit"),
+        Message::new("Quit", None, vec!["Baigti"]),
+    );
+    let mut calls = vec![];
+    let mapped = cat.map_translations(|id, form, text| {
+        calls.push((id.to_owned(), form));
+        format!("{}:{}", form, text.to_uppercase())
+    });
+    calls.sort();
+    assert_eq!(
+        calls,
+        [
+            ("File".to_owned(), 0),
+            ("File".to_owned(), 2),
+            ("Quit".to_owned(), 0),
+            ("Text".to_owned(), 0),
+            ("Text".to_owned(), 0)
+        ]
+    );
+    assert_eq!(mapped.gettext("Text"), "0:TEKSTAS");
+    assert_eq!(mapped.pgettext("menu", "Text"), "0:TEKSTĄ");
+    assert_eq!(
+        mapped.get("File").unwrap().translated(),
+        &["0:FAILAS", "", "2:FAILŲ"]
+    );
+    assert_eq!(
+        mapped.obsolete[&Key::from("Quit")].translated(),
+        &["0:BAIGTI"]
+    );
+    assert_eq!(mapped.metadata().language(), Some("lt"));
+    assert_eq!(cat.gettext("Text"), "Tekstas");
+}
@@ -0,0 +1,431 @@
+//! Detection of the locales requested by the user,
+//! for choosing the catalogs to use without touching the C locale.
+//!
+//! `detect` reads the environment variables used on Unix.
+//! The languages chosen in the system settings are returned by
+//! `preferred_ui_languages` on Windows and macOS,
+//! with the `windows` and `macos` features respectively.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! let mut text = gettext::TextDomain::new("myapp");
+//! text.set_locales(gettext::locale::detect()).unwrap();
+//! text.bind("myapp", "/usr/share/locale").unwrap();
+//! ```
+
+use std::env;
+
+#[cfg(all(feature = "windows", windows))]
+mod windows;
+#[cfg(all(feature = "windows", windows))]
+pub use self::windows::preferred_ui_languages;
+
+#[cfg(all(feature = "macos", target_os = "macos"))]
+mod macos;
+#[cfg(all(feature = "macos", target_os = "macos"))]
+pub use self::macos::preferred_ui_languages;
+
+/// Returns the locales requested through the environment, in order of preference,
+/// following the precedence rules of GNU gettext.
+///
+/// The locale is taken from the first one of `LC_ALL`, `LC_MESSAGES` and `LANG`
+/// that is set and not empty. Unless it is `C` or `POSIX`,
+/// the colon-separated list in `LANGUAGE` takes priority over it.
+/// Returns an empty list if none of the variables is set.
+pub fn detect() -> Vec<String> {
+    detect_with(|name| env::var(name).ok())
+}
+
+/// Detects the locales with the environment variables returned by `var`.
+fn detect_with<F: Fn(&str) -> Option<String>>(var: F) -> Vec<String> {
+    let get = |name| var(name).filter(|value| !value.is_empty());
+    let locale = match get("LC_ALL")
+        .or_else(|| get("LC_MESSAGES"))
+        .or_else(|| get("LANG"))
+    {
+        Some(locale) => locale,
+        None => return vec![],
+    };
+    if locale != "C" && locale != "POSIX" {
+        if let Some(language) = get("LANGUAGE") {
+            let list = language
+                .split(':')
+                .filter(|l| !l.is_empty())
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+            if !list.is_empty() {
+                return list;
+            }
+        }
+    }
+    vec![locale]
+}
+
+/// Converts a BCP 47 language tag, e.g. `de-AT` or `zh-Hant-TW`,
+/// into a gettext-style locale name, e.g. `de_AT` or `zh_TW`.
+///
+/// Scripts are dropped, except for the ones gettext names with a modifier,
+/// e.g. `sr-Latn-RS` becomes `sr_RS@latin`;
+/// Chinese scripts without a region choose one, e.g. `zh-Hans` becomes `zh_CN`.
+pub fn from_bcp47(tag: &str) -> String {
+    let mut subtags = tag.split(['-', '_']);
+    let language = subtags.next().unwrap_or_default().to_ascii_lowercase();
+    let (mut script, mut region) = (None, None);
+    for subtag in subtags {
+        let is_alpha = subtag.bytes().all(|b| b.is_ascii_alphabetic());
+        let is_digit = subtag.bytes().all(|b| b.is_ascii_digit());
+        match subtag.len() {
+            4 if is_alpha && script.is_none() && region.is_none() => script = Some(subtag),
+            2 if is_alpha && region.is_none() => region = Some(subtag.to_ascii_uppercase()),
+            3 if is_digit && region.is_none() => region = Some(subtag.to_owned()),
+            _ => break,
+        }
+    }
+    let script = script.map(str::to_ascii_lowercase);
+    let modifier = match (language.as_str(), script.as_deref()) {
+        ("zh", Some("hans")) => {
+            region = region.or_else(|| Some("CN".to_owned()));
+            None
+        }
+        ("zh", Some("hant")) => {
+            region = region.or_else(|| Some("TW".to_owned()));
+            None
+        }
+        ("sr", Some("latn")) | ("be", Some("latn")) => Some("latin"),
+        ("uz", Some("cyrl")) => Some("cyrillic"),
+        _ => None,
+    };
+    let mut locale = language;
+    if let Some(region) = region {
+        locale.push('_');
+        locale.push_str(&region);
+    }
+    if let Some(modifier) = modifier {
+        locale.push('@');
+        locale.push_str(modifier);
+    }
+    locale
+}
+
+/// Returns the `available` locales matching the `requested` ones,
+/// best matches first, for choosing among the catalogs an application ships.
+///
+/// Both lists may hold BCP 47 tags or gettext-style locale names,
+/// compared case-insensitively. For each requested locale in turn,
+/// an exact match comes first, then the same language without a region
+/// or a script, then the same language in another region.
+/// Languages written in different scripts, e.g. `zh-Hans` and `zh-Hant`, never match.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::locale::negotiate;
+///
+/// let available = ["en", "de", "de-CH", "pt-BR", "zh-Hant"];
+/// assert_eq!(negotiate(&["de-AT", "en-US"], &available), &["de", "de-CH", "en"]);
+/// assert_eq!(negotiate(&["zh-Hant-TW", "pt"], &available), &["zh-Hant", "pt-BR"]);
+/// assert!(negotiate(&["zh-Hans"], &available).is_empty());
+/// ```
+pub fn negotiate<'a>(requested: &[&str], available: &[&'a str]) -> Vec<&'a str> {
+    let parsed = available
+        .iter()
+        .map(|a| Subtags::parse(a))
+        .collect::<Vec<_>>();
+    let mut matches = vec![];
+    for wanted in requested.iter().map(|r| Subtags::parse(r)) {
+        let mut candidates = parsed
+            .iter()
+            .enumerate()
+            .filter_map(|(i, have)| Some((wanted.distance(have)?, i)))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        for (_, i) in candidates {
+            if !matches.contains(&available[i]) {
+                matches.push(available[i]);
+            }
+        }
+    }
+    matches
+}
+
+/// Returns the locales to try for a `language_TERRITORY.codeset@modifier` locale,
+/// from the locale itself to the bare language, e.g. `pt_BR.UTF-8`, `pt_BR`, `pt`,
+/// dropping the codeset first, then the territory, then the modifier,
+/// like GNU gettext does when looking for catalogs.
+pub fn fallbacks(locale: &str) -> Vec<String> {
+    let (rest, modifier) = split_off(locale, '@');
+    let (rest, codeset) = split_off(rest, '.');
+    let (language, territory) = split_off(rest, '_');
+    let mut variants = vec![];
+    for mask in (0..8).rev() {
+        let part = |bit: u8, sep: &str, value: Option<&str>| match value {
+            Some(value) if mask & bit != 0 => Some(format!("{}{}", sep, value)),
+            Some(_) => Some(String::new()),
+            None if mask & bit != 0 => None,
+            None => Some(String::new()),
+        };
+        let parts = (
+            part(2, "_", territory),
+            part(1, ".", codeset),
+            part(4, "@", modifier),
+        );
+        if let (Some(territory), Some(codeset), Some(modifier)) = parts {
+            let variant = format!("{}{}{}{}", language, territory, codeset, modifier);
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+    }
+    variants
+}
+
+/// Splits `s` at the first `sep`, returning the part after it separately.
+fn split_off(s: &str, sep: char) -> (&str, Option<&str>) {
+    match s.find(sep) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    }
+}
+
+/// Returns the language ranges of an HTTP `Accept-Language` header,
+/// e.g. `fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5`, in order of preference.
+///
+/// Ranges with a zero or invalid quality value are left out;
+/// ranges of the same quality keep their order.
+pub fn parse_accept_language(header: &str) -> Vec<&str> {
+    let mut ranges = header
+        .split(',')
+        .filter_map(|item| {
+            let mut params = item.split(';').map(str::trim);
+            let range = params.next().filter(|r| !r.is_empty())?;
+            let mut quality = 1.0;
+            for param in params {
+                if let Some(q) = param
+                    .strip_prefix("q=")
+                    .or_else(|| param.strip_prefix("Q="))
+                {
+                    quality = q.parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?;
+                }
+            }
+            Some((range, quality)).filter(|&(_, q)| q > 0.0)
+        })
+        .collect::<Vec<_>>();
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().map(|(range, _)| range).collect()
+}
+
+/// Selects the catalog best matching an HTTP `Accept-Language` header
+/// among catalogs listed by locale, like the index generated by `build::Compiler`.
+///
+/// Returns the first catalog if nothing matches but the header accepts any language
+/// with `*`, and `None` if nothing matches otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::locale::select;
+///
+/// let catalogs = [("de", "German"), ("fr", "French"), ("fr-CA", "Canadian French")];
+/// assert_eq!(select("fr-CH, fr;q=0.9, en;q=0.8", &catalogs), Some(&"French"));
+/// assert_eq!(select("en-US, de;q=0.5", &catalogs), Some(&"German"));
+/// assert_eq!(select("en-US", &catalogs), None);
+/// ```
+pub fn select<'a, T>(accept_language: &str, catalogs: &'a [(&str, T)]) -> Option<&'a T> {
+    let ranges = parse_accept_language(accept_language);
+    let locales = catalogs
+        .iter()
+        .map(|(locale, _)| *locale)
+        .collect::<Vec<_>>();
+    let best = match negotiate(&ranges, &locales).first() {
+        Some(best) => locales.iter().position(|l| l == best)?,
+        None if ranges.contains(&"*") => 0,
+        None => return None,
+    };
+    catalogs.get(best).map(|(_, catalog)| catalog)
+}
+
+/// The language, script and region of a locale, in lower case.
+struct Subtags {
+    language: String,
+    script: Option<String>,
+    region: Option<String>,
+}
+
+impl Subtags {
+    fn parse(locale: &str) -> Self {
+        // gettext-style codesets and modifiers are not compared
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let mut subtags = locale.split(['-', '_']).map(str::to_ascii_lowercase);
+        let language = subtags.next().unwrap_or_default();
+        let (mut script, mut region) = (None, None);
+        for subtag in subtags {
+            match subtag.len() {
+                4 if script.is_none() && region.is_none() => script = Some(subtag),
+                2 | 3 if region.is_none() => region = Some(subtag),
+                _ => break,
+            }
+        }
+        Subtags {
+            language,
+            script,
+            region,
+        }
+    }
+
+    /// Returns how far `other` is from the wanted locale,
+    /// or `None` if it does not match at all.
+    fn distance(&self, other: &Subtags) -> Option<u8> {
+        fn compare(wanted: &Option<String>, have: &Option<String>) -> u8 {
+            match (wanted, have) {
+                (Some(a), Some(b)) if a == b => 0,
+                (None, None) => 0,
+                (_, None) => 1,
+                (None, Some(_)) => 2,
+                (Some(_), Some(_)) => 3,
+            }
+        }
+        if self.language != other.language {
+            return None;
+        }
+        let script = compare(&self.script, &other.script);
+        if script == 3 {
+            return None;
+        }
+        Some(script.max(compare(&self.region, &other.region)))
+    }
+}
+
+#[test]
+fn test_negotiate() {
+    let available = [
+        "en",
+        "en-GB",
+        "de_DE",
+        "de-CH",
+        "fr",
+        "sr-Latn",
+        "sr",
+        "zh-Hant-TW",
+    ];
+    assert_eq!(negotiate(&["de-DE"], &available), &["de_DE", "de-CH"]);
+    assert_eq!(negotiate(&["DE_ch.UTF-8"], &available), &["de-CH", "de_DE"]);
+    assert_eq!(negotiate(&["en-US"], &available), &["en", "en-GB"]);
+    assert_eq!(negotiate(&["en"], &available), &["en", "en-GB"]);
+    assert_eq!(negotiate(&["en-GB"], &available), &["en-GB", "en"]);
+    assert_eq!(negotiate(&["sr-Latn-RS"], &available), &["sr-Latn", "sr"]);
+    assert_eq!(negotiate(&["sr-Cyrl"], &available), &["sr"]);
+    assert_eq!(negotiate(&["zh-TW"], &available), &["zh-Hant-TW"]);
+    assert!(negotiate(&["zh-Hans-CN"], &available).is_empty());
+    assert!(negotiate(&["ja", "lt"], &available).is_empty());
+    assert_eq!(
+        negotiate(&["fr-CA", "en", "fr"], &available),
+        &["fr", "en", "en-GB"]
+    );
+    assert!(negotiate(&[], &available).is_empty());
+}
+
+#[test]
+fn test_fallbacks() {
+    assert_eq!(fallbacks("de"), &["de"]);
+    assert_eq!(fallbacks("de_AT"), &["de_AT", "de"]);
+    assert_eq!(
+        fallbacks("de_AT.UTF-8@euro"),
+        &[
+            "de_AT.UTF-8@euro",
+            "de_AT@euro",
+            "de.UTF-8@euro",
+            "de@euro",
+            "de_AT.UTF-8",
+            "de_AT",
+            "de.UTF-8",
+            "de",
+        ]
+    );
+    assert_eq!(fallbacks("sr@latin"), &["sr@latin", "sr"]);
+    assert_eq!(
+        fallbacks("pt_BR.UTF-8"),
+        &["pt_BR.UTF-8", "pt_BR", "pt.UTF-8", "pt"]
+    );
+}
+
+#[test]
+fn test_accept_language() {
+    assert_eq!(
+        parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+        &["fr-CH", "fr", "en", "de", "*"]
+    );
+    assert_eq!(
+        parse_accept_language("en;q=0.5,de , lt;q=0.5,pl;Q=0.8"),
+        &["de", "pl", "en", "lt"]
+    );
+    assert_eq!(
+        parse_accept_language("en;q=0, de;q=abc, fr;q=2, lt;q=0.1"),
+        &["lt"]
+    );
+    assert!(parse_accept_language("").is_empty());
+    assert!(parse_accept_language(" , ;q=1").is_empty());
+
+    let catalogs = [("lt", 1), ("de-AT", 2), ("de", 3)];
+    assert_eq!(select("de-AT;q=0.9, de", &catalogs), Some(&3));
+    assert_eq!(select("de-DE", &catalogs), Some(&3));
+    assert_eq!(select("de-CH, de-AT;q=0.9", &catalogs), Some(&3));
+    assert_eq!(select("ja, *;q=0.1", &catalogs), Some(&1));
+    assert_eq!(select("ja, de;q=0", &catalogs), None);
+    assert_eq!(select::<u8>("*", &[]), None);
+}
+
+#[test]
+fn test_from_bcp47() {
+    assert_eq!(from_bcp47("de"), "de");
+    assert_eq!(from_bcp47("de-AT"), "de_AT");
+    assert_eq!(from_bcp47("pt-br"), "pt_BR");
+    assert_eq!(from_bcp47("es-419"), "es_419");
+    assert_eq!(from_bcp47("zh-Hant-TW"), "zh_TW");
+    assert_eq!(from_bcp47("zh-Hans"), "zh_CN");
+    assert_eq!(from_bcp47("zh-Hant-HK"), "zh_HK");
+    assert_eq!(from_bcp47("sr-Latn-RS"), "sr_RS@latin");
+    assert_eq!(from_bcp47("uz-Cyrl"), "uz@cyrillic");
+    assert_eq!(from_bcp47("en-Latn-US"), "en_US");
+    assert_eq!(from_bcp47("sr-Cyrl"), "sr");
+    assert_eq!(from_bcp47("de-CH-1996"), "de_CH");
+    assert_eq!(from_bcp47("EN_gb"), "en_GB");
+}
+
+#[test]
+fn test_detect() {
+    fn detect(vars: &[(&str, &str)]) -> Vec<String> {
+        detect_with(|name| {
+            vars.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    assert!(detect(&[]).is_empty());
+    assert_eq!(detect(&[("LANG", "de_AT.UTF-8")]), &["de_AT.UTF-8"]);
+    assert_eq!(
+        detect(&[("LANG", "de_AT.UTF-8"), ("LC_MESSAGES", "fr_FR")]),
+        &["fr_FR"]
+    );
+    assert_eq!(
+        detect(&[("LANG", "de"), ("LC_MESSAGES", "fr"), ("LC_ALL", "lt")]),
+        &["lt"]
+    );
+    assert_eq!(detect(&[("LANG", "de"), ("LC_ALL", "")]), &["de"]);
+    assert_eq!(
+        detect(&[("LANG", "de_AT"), ("LANGUAGE", "de_AT:de::en")]),
+        &["de_AT", "de", "en"]
+    );
+    assert_eq!(detect(&[("LANG", "de_AT"), ("LANGUAGE", ":")]), &["de_AT"]);
+    // LANGUAGE is ignored without a locale and with the C locale
+    assert!(detect(&[("LANGUAGE", "de")]).is_empty());
+    assert_eq!(
+        detect(&[("LANG", "de"), ("LC_ALL", "C"), ("LANGUAGE", "fr")]),
+        &["C"]
+    );
+    assert_eq!(
+        detect(&[("LC_MESSAGES", "POSIX"), ("LANGUAGE", "fr")]),
+        &["POSIX"]
+    );
+}
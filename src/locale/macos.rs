@@ -0,0 +1,50 @@
+//! The preferred languages of the user on macOS.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_long, c_void};
+
+use super::from_bcp47;
+
+const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFLocaleCopyPreferredLanguages() -> *const c_void;
+    fn CFArrayGetCount(array: *const c_void) -> c_long;
+    fn CFArrayGetValueAtIndex(array: *const c_void, index: c_long) -> *const c_void;
+    fn CFStringGetCString(
+        string: *const c_void,
+        buffer: *mut c_char,
+        buffer_len: c_long,
+        encoding: u32,
+    ) -> u8;
+    fn CFRelease(object: *const c_void);
+}
+
+/// Returns the languages chosen by the user in the macOS settings,
+/// stored in the `AppleLanguages` preference, in order of preference,
+/// as gettext-style locale names.
+///
+/// Unlike `LANG`, the preference is also set for programs
+/// that are not started from a terminal.
+/// Returns an empty list if the languages cannot be read.
+pub fn preferred_ui_languages() -> Vec<String> {
+    let mut languages = vec![];
+    unsafe {
+        let array = CFLocaleCopyPreferredLanguages();
+        if array.is_null() {
+            return languages;
+        }
+        for i in 0..CFArrayGetCount(array) {
+            let mut buffer: [c_char; 64] = [0; 64];
+            let string = CFArrayGetValueAtIndex(array, i);
+            let len = buffer.len() as c_long;
+            if CFStringGetCString(string, buffer.as_mut_ptr(), len, CF_STRING_ENCODING_UTF8) != 0 {
+                let tag = CStr::from_ptr(buffer.as_ptr()).to_string_lossy();
+                languages.push(from_bcp47(&tag));
+            }
+        }
+        CFRelease(array);
+    }
+    languages
+}
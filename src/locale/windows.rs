@@ -0,0 +1,46 @@
+//! The preferred UI languages of the user on Windows.
+
+use std::ptr;
+
+use super::from_bcp47;
+
+const MUI_LANGUAGE_NAME: u32 = 0x8;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetUserPreferredUILanguages(
+        flags: u32,
+        num_languages: *mut u32,
+        buffer: *mut u16,
+        buffer_len: *mut u32,
+    ) -> i32;
+}
+
+/// Returns the UI languages chosen by the user in the Windows settings,
+/// in order of preference, as gettext-style locale names.
+///
+/// Returns an empty list if the languages cannot be read.
+pub fn preferred_ui_languages() -> Vec<String> {
+    let (mut count, mut len) = (0, 0);
+    // the first call only returns the size of the buffer
+    let ok = unsafe {
+        GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, ptr::null_mut(), &mut len)
+    };
+    if ok == 0 {
+        return vec![];
+    }
+    let mut buffer = vec![0u16; len as usize];
+    let ok = unsafe {
+        GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, buffer.as_mut_ptr(), &mut len)
+    };
+    if ok == 0 {
+        return vec![];
+    }
+    // the names are separated and ended by NUL characters
+    buffer.truncate(len as usize);
+    buffer
+        .split(|&c| c == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| from_bcp47(&String::from_utf16_lossy(name)))
+        .collect()
+}
@@ -0,0 +1,191 @@
+/// Embeds an MO file into the binary at compile time
+/// and evaluates to a `&'static Catalog` parsed from it on first use.
+///
+/// The path is resolved relative to the current file, like `include_bytes!`.
+/// A file that is not a well-formed MO file fails the build,
+/// as do strings that are not valid UTF-8 unless the header declares another charset.
+/// The header and strings in other charsets are decoded at runtime,
+/// where errors in them panic.
+///
+/// The strings are copied out of the embedded file when it is parsed;
+/// use a `CatalogRef` to look them up in place instead.
+///
+/// # Examples
+///
+/// ```ignore
+/// let catalog: &'static gettext::Catalog = gettext::include_mo!("../po/fr.mo");
+/// println!("{}", catalog.gettext("Name"));
+/// ```
+#[macro_export]
+macro_rules! include_mo {
+    ($path:expr) => {{
+        const BYTES: &[u8] = include_bytes!($path);
+        const _: () = assert!(
+            $crate::__is_valid_mo(BYTES),
+            concat!("not a valid MO file: ", $path)
+        );
+        static CATALOG: ::std::sync::OnceLock<$crate::Catalog> = ::std::sync::OnceLock::new();
+        CATALOG.get_or_init(|| {
+            $crate::ParseOptions::new()
+                .parse_slice(BYTES)
+                .expect(concat!("could not parse ", $path))
+        })
+    }};
+}
+
+/// Translates a message using the given catalog
+/// and substitutes the arguments into the translation.
+///
+/// Placeholders follow the syntax of `format!`, i.e. `{}`, `{0}` and `{name}`,
+/// so translators can reorder the arguments.
+/// Like with `format!`, the build fails if the placeholders of the msgid literal
+/// and the arguments do not match.
+/// Format specs are ignored, and placeholders of the translation
+/// without a matching argument are left as they are.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{tr, CatalogBuilder};
+///
+/// let catalog = CatalogBuilder::new()
+///     .add("Hello, {}!", "Labas, {}!")
+///     .add("{user} opened {file}", "{file} atidarė {user}")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(tr!(catalog, "Hello, {}!", "Jonas"), "Labas, Jonas!");
+/// assert_eq!(
+///     tr!(catalog, "{user} opened {file}", user = "Jonas", file = "a.txt"),
+///     "a.txt atidarė Jonas"
+/// );
+/// ```
+///
+/// ```compile_fail
+/// # let catalog = gettext::Catalog::empty();
+/// gettext::tr!(catalog, "Hello, {}!");
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($catalog:expr, $msg_id:literal $(, $($args:tt)*)?) => {
+        $crate::__tr_args!(
+            ($catalog).gettext($msg_id);
+            [$msg_id] false [] [] []
+            $($($args)*)?
+        )
+    };
+}
+
+/// Translates a message with a plural form using the given catalog
+/// and substitutes the arguments into the translation.
+///
+/// The number `n` of objects can be of any integer type.
+/// It is also available to the translation as the first positional argument
+/// and as `{n}`, followed by the other arguments.
+/// The msgids are checked against the arguments like with `tr!`,
+/// except that they do not have to use `n`.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{ntr, CatalogBuilder};
+///
+/// let catalog = CatalogBuilder::new()
+///     .add_plural("{} file", "{} files", vec!["{} failas", "{} failai"])
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(ntr!(catalog, "{} file", "{} files", 1), "1 failas");
+/// assert_eq!(ntr!(catalog, "{} file", "{} files", 3), "3 failai");
+/// assert_eq!(ntr!(catalog, "{} file in {}", "{} files in {}", 3, "/"), "3 files in /");
+/// assert_eq!(ntr!(catalog, "One file", "{n} files", 3), "3 files");
+/// ```
+///
+/// ```compile_fail
+/// # let catalog = gettext::Catalog::empty();
+/// gettext::ntr!(catalog, "{} file in {dir}", "{} files in {dir}", 3);
+/// ```
+#[macro_export]
+macro_rules! ntr {
+    ($catalog:expr, $msg_id:literal, $msg_id_plural:literal, $n:expr $(, $($args:tt)*)?) => {{
+        let n = $n;
+        $crate::__tr_args!(
+            ($catalog).ngettext($msg_id, $msg_id_plural, $crate::ToPluralNumber::to_plural_number(n));
+            [$msg_id, $msg_id_plural] true
+            [(None, $crate::__arg(&n)), (Some("n"), $crate::__arg(&n)),] ["n",] [(),]
+            $($($args)*)?
+        )
+    }};
+}
+
+/// Translates a message using the default catalog of the `global` module
+/// and substitutes the arguments into the translation, like `tr!`.
+///
+/// `Extractor` finds the messages of `gettext!`, like those of `gettext`.
+#[cfg(feature = "global")]
+#[macro_export]
+macro_rules! gettext {
+    ($msg_id:literal $(, $($args:tt)*)?) => {
+        $crate::tr!($crate::global::catalog(), $msg_id $(, $($args)*)?)
+    };
+}
+
+/// Translates a message with a plural form using the default catalog
+/// of the `global` module and substitutes the arguments into the translation, like `ntr!`.
+#[cfg(feature = "global")]
+#[macro_export]
+macro_rules! ngettext {
+    ($msg_id:literal, $msg_id_plural:literal, $n:expr $(, $($args:tt)*)?) => {
+        $crate::ntr!($crate::global::catalog(), $msg_id, $msg_id_plural, $n $(, $($args)*)?)
+    };
+}
+
+/// Collects the arguments of `tr!` and `ntr!` into the lists of arguments,
+/// argument names and positional arguments, checking them against the msgids.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tr_args {
+    (
+        $translation:expr;
+        [$($msg_id:expr),*] $implicit_n:tt
+        [$($out:tt)*] [$($names:tt)*] [$($positional:tt)*]
+    ) => {{
+        const _: () = $crate::__check_placeholders(
+            &[$($msg_id),*],
+            <[()]>::len(&[$($positional)*]),
+            &[$($names)*],
+            $implicit_n,
+        );
+        $crate::__interpolate($translation, &[$($out)*])
+    }};
+    (
+        $translation:expr;
+        $msg_ids:tt $implicit_n:tt
+        [$($out:tt)*] [$($names:tt)*] $positional:tt
+        $name:ident = $value:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::__tr_args!(
+            $translation;
+            $msg_ids $implicit_n
+            [$($out)* (Some(stringify!($name)), $crate::__arg(&$value)),]
+            [$($names)* stringify!($name),]
+            $positional
+            $($($rest)*)?
+        )
+    };
+    (
+        $translation:expr;
+        $msg_ids:tt $implicit_n:tt
+        [$($out:tt)*] $names:tt [$($positional:tt)*]
+        $value:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::__tr_args!(
+            $translation;
+            $msg_ids $implicit_n
+            [$($out)* (None, $crate::__arg(&$value)),]
+            $names
+            [$($positional)* (),]
+            $($($rest)*)?
+        )
+    };
+}
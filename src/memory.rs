@@ -0,0 +1,250 @@
+use crate::{Catalog, Flag, Message};
+
+/// TranslationMemory holds the translated messages of existing catalogs
+/// and finds the ones whose msgids are similar to a new msgid,
+/// like the compendium given to `msgmerge --compendium`.
+///
+/// Similarity is the edit distance between the msgids, counted in characters,
+/// normalized by the length of the longer one: identical msgids score 1
+/// and entirely different ones score 0. Contexts are not compared.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{CatalogBuilder, TranslationMemory};
+///
+/// let legacy = CatalogBuilder::new()
+///     .add("Open the file", "Atidaryti failą")
+///     .add("Close", "Uždaryti")
+///     .build()
+///     .unwrap();
+/// let mut memory = TranslationMemory::new();
+/// memory.add(&legacy);
+///
+/// let suggestions = memory.lookup("Open a file", 0.7);
+/// assert_eq!(suggestions.len(), 1);
+/// assert_eq!(suggestions[0].message().translated(), &["Atidaryti failą"]);
+/// assert!(suggestions[0].similarity() < 1.0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TranslationMemory {
+    messages: Vec<Message>,
+}
+
+/// Suggestion is a translated message of a `TranslationMemory`
+/// found for a msgid, with the similarity of their msgids.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Suggestion<'a> {
+    message: &'a Message,
+    similarity: f64,
+}
+
+impl<'a> Suggestion<'a> {
+    /// Returns the message of the memory.
+    pub fn message(&self) -> &'a Message {
+        self.message
+    }
+
+    /// Returns the similarity of the msgids, between 0 and 1.
+    pub fn similarity(&self) -> f64 {
+        self.similarity
+    }
+}
+
+impl TranslationMemory {
+    /// Creates an empty memory.
+    pub fn new() -> Self {
+        TranslationMemory::default()
+    }
+
+    /// Adds the translated messages of `catalog` to the memory.
+    ///
+    /// The header, fuzzy messages and messages with untranslated forms are left out,
+    /// and so are obsolete messages.
+    pub fn add(&mut self, catalog: &Catalog) {
+        let mut translated = catalog
+            .strings
+            .iter()
+            .filter(|(_, msg)| {
+                !msg.is_header() && !msg.is_fuzzy() && msg.translated.iter().all(|s| !s.is_empty())
+            })
+            .collect::<Vec<_>>();
+        // in a stable order, which breaks the ties between suggestions
+        translated.sort_by(|a, b| a.0.cmp(b.0));
+        self.messages
+            .extend(translated.into_iter().map(|(_, msg)| msg.clone()));
+    }
+
+    /// Returns the number of messages in the memory.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Returns whether the memory has no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Returns the messages whose msgids are at least `min_similarity` similar to `msg_id`,
+    /// the most similar first.
+    pub fn lookup(&self, msg_id: &str, min_similarity: f64) -> Vec<Suggestion<'_>> {
+        let id = msg_id.chars().collect::<Vec<_>>();
+        let mut suggestions = self
+            .messages
+            .iter()
+            .filter_map(|message| {
+                let similarity = similarity(&id, &message.id, min_similarity)?;
+                Some(Suggestion {
+                    message,
+                    similarity,
+                })
+            })
+            .collect::<Vec<_>>();
+        // the sort is stable, so equally similar messages stay in the order they were added
+        suggestions.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
+        suggestions
+    }
+
+    /// Translates the untranslated messages of `catalog`, e.g. a new template,
+    /// with the most similar message of the memory, if it is at least `min_similarity` similar.
+    ///
+    /// Only messages that both have or both lack a plural form are used for each other.
+    /// Translations taken from a different msgid are flagged as `fuzzy`
+    /// and keep that msgid as their previous one, so that translators review them.
+    /// Returns the number of messages translated.
+    pub fn pretranslate(&self, catalog: &mut Catalog, min_similarity: f64) -> usize {
+        let mut translated = 0;
+        for msg in catalog.strings.values_mut() {
+            if msg.is_header() || msg.is_translated() {
+                continue;
+            }
+            let plural = msg.id_plural.is_some();
+            let best = self
+                .lookup(&msg.id, min_similarity)
+                .into_iter()
+                .find(|s| s.message.id_plural.is_some() == plural);
+            if let Some(best) = best {
+                msg.translated = best.message.translated.clone();
+                if best.message.id != msg.id || best.message.id_plural != msg.id_plural {
+                    msg.flags.insert(Flag::Fuzzy);
+                    msg.previous_id = Some(best.message.id.clone());
+                    msg.previous_id_plural = best.message.id_plural.clone();
+                }
+                translated += 1;
+            }
+        }
+        translated
+    }
+}
+
+/// Returns the normalized similarity of the strings,
+/// or `None` if it is below `min`.
+fn similarity(a: &[char], b: &str, min: f64) -> Option<f64> {
+    let b = b.chars().collect::<Vec<_>>();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return Some(1.0);
+    }
+    let score = |distance: usize| 1.0 - distance as f64 / longest as f64;
+    // the difference in length is the least possible distance
+    if score(a.len().abs_diff(b.len())) < min {
+        return None;
+    }
+    // Levenshtein distance, keeping a single row of the table
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    Some(score(row[b.len()])).filter(|&s| s >= min)
+}
+
+#[test]
+fn test_similarity() {
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    assert_eq!(similarity(&chars(""), "", 1.0), Some(1.0));
+    assert_eq!(similarity(&chars("kitten"), "kitten", 1.0), Some(1.0));
+    assert_eq!(
+        similarity(&chars("kitten"), "sitting", 0.0),
+        Some(1.0 - 3.0 / 7.0)
+    );
+    assert_eq!(similarity(&chars("ąčę"), "ące", 0.0), Some(1.0 - 2.0 / 3.0));
+    assert_eq!(similarity(&chars("abc"), "", 0.0), Some(0.0));
+    assert_eq!(similarity(&chars("kitten"), "sitting", 0.6), None);
+    assert_eq!(similarity(&chars("a"), "abcdef", 0.5), None);
+}
+
+#[test]
+fn test_translation_memory() {
+    use crate::key::Key;
+    use crate::CatalogBuilder;
+
+    let mut legacy = CatalogBuilder::new()
+        .header("Language", "lt")
+        .add("Open file", "Atidaryti failą")
+        .add("Open files", "Atidaryti failus")
+        .add("Untranslated", "")
+        .add("Fuzzy", "Neaiškus")
+        .add_plural(
+            "{} file",
+            "{} files",
+            vec!["{} failas", "{} failai", "{} failų"],
+        )
+        .build()
+        .unwrap();
+    legacy
+        .strings
+        .get_mut(&Key::from("Fuzzy"))
+        .unwrap()
+        .flags
+        .insert(Flag::Fuzzy);
+    let mut memory = TranslationMemory::new();
+    assert!(memory.is_empty());
+    memory.add(&legacy);
+    assert_eq!(memory.len(), 3);
+
+    let ids = |suggestions: Vec<Suggestion>| {
+        suggestions
+            .iter()
+            .map(|s| s.message().id().to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        ids(memory.lookup("Open files", 0.8)),
+        ["Open files", "Open file"]
+    );
+    assert_eq!(memory.lookup("Open files", 0.8)[0].similarity(), 1.0);
+    assert_eq!(ids(memory.lookup("Open file", 0.95)), ["Open file"]);
+    assert!(memory.lookup("Fuzzy", 0.5).is_empty());
+    assert!(memory.lookup("Something else", 0.5).is_empty());
+
+    let mut template = CatalogBuilder::new()
+        .add("Open file", "")
+        .add("Open a file", "")
+        .add("Open", "Atverti")
+        .add("Quit", "")
+        .add_plural("{} file found", "{} files found", vec!["", ""])
+        .add_plural("Open files", "Open {} files", vec!["", ""])
+        .build()
+        .unwrap();
+    assert_eq!(memory.pretranslate(&mut template, 0.52), 3);
+    let exact = template.get("Open file").unwrap();
+    assert_eq!(exact.translated(), &["Atidaryti failą"]);
+    assert!(!exact.is_fuzzy());
+    let similar = template.get("Open a file").unwrap();
+    assert_eq!(similar.translated(), &["Atidaryti failą"]);
+    assert!(similar.is_fuzzy());
+    assert_eq!(similar.previous_id(), Some("Open file"));
+    assert_eq!(template.gettext("Open"), "Atverti");
+    assert_eq!(template.gettext("Quit"), "Quit");
+    let tabs = template.get("{} file found").unwrap();
+    assert_eq!(tabs.translated(), &["{} failas", "{} failai", "{} failų"]);
+    assert_eq!(tabs.previous_id_plural(), Some("{} files"));
+    // singular messages are not used for plural ones
+    assert!(!template.get("Open files").unwrap().is_translated());
+}
@@ -0,0 +1,357 @@
+use std::collections::hash_map::Entry;
+
+use crate::{Catalog, Flag, Message};
+
+/// MergeStrategy decides which message is kept
+/// when both merged catalogs contain the same message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MergeStrategy {
+    /// Keep the message of the catalog being merged into
+    KeepExisting,
+    /// Replace the message with the one of the other catalog
+    Replace,
+    /// Keep the message of the catalog being merged into,
+    /// unless it is untranslated and the other one is not
+    PreferTranslated,
+    /// Unite the messages like `msguniq` does: their comments, references and flags
+    /// are merged, and the translation is chosen like with `PreferTranslated`
+    Unite,
+}
+
+/// Unites `other` into `msg`, a message with the same context and msgid,
+/// returning whether the translation of `other` was taken.
+pub(crate) fn unite(msg: &mut Message, other: Message) -> bool {
+    let take = !msg.is_translated() && other.is_translated();
+    let fuzzy = if take {
+        other.is_fuzzy()
+    } else {
+        msg.is_fuzzy()
+    };
+    append_new(&mut msg.translator_comments, other.translator_comments);
+    append_new(&mut msg.extracted_comments, other.extracted_comments);
+    append_new(&mut msg.references, other.references);
+    msg.flags.extend(other.flags);
+    // the translation kept decides whether the message needs review
+    msg.flags.remove(&Flag::Fuzzy);
+    if fuzzy {
+        msg.flags.insert(Flag::Fuzzy);
+    }
+    if take {
+        msg.id_plural = other.id_plural;
+        msg.translated = other.translated;
+        msg.previous_context = other.previous_context;
+        msg.previous_id = other.previous_id;
+        msg.previous_id_plural = other.previous_id_plural;
+    }
+    take
+}
+
+/// Appends the strings of `other` which `strings` does not have yet.
+fn append_new(strings: &mut Vec<String>, other: Vec<String>) {
+    for s in other {
+        if !strings.contains(&s) {
+            strings.push(s);
+        }
+    }
+}
+
+impl Catalog {
+    /// Merges all the messages of `other` into this catalog,
+    /// like `msgcat` does for several PO files.
+    ///
+    /// Messages present in both catalogs, including the header,
+    /// are reconciled according to `strategy`.
+    /// If the header of `other` is taken, its plural formula is taken as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{CatalogBuilder, MergeStrategy};
+    ///
+    /// let mut app = CatalogBuilder::new().add("Open", "Ouvrir").build().unwrap();
+    /// let lib = CatalogBuilder::new()
+    ///     .add("Open", "Ouvrir le fichier")
+    ///     .add("Close", "Fermer")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// app.merge(lib, MergeStrategy::KeepExisting);
+    /// assert_eq!(app.gettext("Open"), "Ouvrir");
+    /// assert_eq!(app.gettext("Close"), "Fermer");
+    /// ```
+    pub fn merge(&mut self, other: Catalog, strategy: MergeStrategy) {
+        let mut took_header = false;
+        for (key, msg) in other.strings {
+            let is_header = key.is_header();
+            match self.strings.entry(key) {
+                Entry::Vacant(entry) => {
+                    took_header |= is_header;
+                    entry.insert(msg);
+                }
+                Entry::Occupied(mut entry) => {
+                    let replace = match strategy {
+                        MergeStrategy::KeepExisting => false,
+                        MergeStrategy::Replace => true,
+                        MergeStrategy::PreferTranslated => {
+                            !entry.get().is_translated() && msg.is_translated()
+                        }
+                        MergeStrategy::Unite => {
+                            took_header |= unite(entry.get_mut(), msg) && is_header;
+                            continue;
+                        }
+                    };
+                    if replace {
+                        took_header |= is_header;
+                        entry.insert(msg);
+                    }
+                }
+            }
+        }
+        for (key, msg) in other.obsolete {
+            if !self.strings.contains_key(&key) {
+                self.obsolete.entry(key).or_insert(msg);
+            }
+        }
+        if took_header {
+            self.resolver = other.resolver;
+        }
+    }
+
+    /// Updates the catalog against a newer template (POT) catalog,
+    /// like `msgmerge` does, returning the updated catalog.
+    ///
+    /// Messages of the template keep their existing translations,
+    /// including ones that were obsolete, and new messages are added untranslated.
+    /// Messages missing from the template are marked obsolete.
+    /// The header and the plural formula of this catalog are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::CatalogBuilder;
+    ///
+    /// let old = CatalogBuilder::new()
+    ///     .add("Open", "Ouvrir")
+    ///     .add("Quit", "Quitter")
+    ///     .build()
+    ///     .unwrap();
+    /// let template = CatalogBuilder::new()
+    ///     .add("Open", "")
+    ///     .add("Save", "")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let updated = old.update(&template);
+    /// assert_eq!(updated.gettext("Open"), "Ouvrir");
+    /// assert_eq!(updated.gettext("Save"), "Save");
+    /// assert_eq!(updated.gettext("Quit"), "Quit");
+    /// assert_eq!(updated.obsolete().next().unwrap().id(), "Quit");
+    /// ```
+    pub fn update(&self, template: &Catalog) -> Catalog {
+        let nplurals = self.nplurals();
+        let mut updated = Catalog::new();
+        updated.resolver = self.resolver.clone();
+        updated.plural_fallback = self.plural_fallback;
+        updated.show_header = self.show_header;
+        if let Some(header) = self.message(None, "") {
+            updated.insert(header.clone());
+        }
+
+        for (key, msg) in template.strings.iter().filter(|(k, _)| !k.is_header()) {
+            let existing = self.strings.get(key).or_else(|| self.obsolete.get(key));
+            let (translated, fuzzy) = match existing {
+                Some(old) if old.id_plural.is_some() == msg.id_plural.is_some() => {
+                    (old.translated.clone(), old.is_fuzzy())
+                }
+                _ if msg.id_plural.is_some() => (vec![String::new(); nplurals], false),
+                _ => (vec![String::new()], false),
+            };
+            // the comments of translators are kept,
+            // the extracted ones and the references come from the template
+            let translator_comments =
+                existing.map_or_else(Vec::new, |old| old.translator_comments.clone());
+            // the other flags describe the msgid, so they come from the template too
+            let mut flags = msg.flags.clone();
+            flags.remove(&Flag::Fuzzy);
+            if fuzzy {
+                flags.insert(Flag::Fuzzy);
+            }
+            let mut message = Message {
+                translated,
+                translator_comments,
+                flags,
+                ..msg.clone()
+            };
+            // the previous strings tell what changed since a fuzzy translation was made
+            if let Some(old) = existing.filter(|_| fuzzy) {
+                message.previous_context = old.previous_context.clone();
+                message.previous_id = old.previous_id.clone();
+                message.previous_id_plural = old.previous_id_plural.clone();
+            }
+            updated.strings.insert(key.clone(), message);
+        }
+
+        let removed = self
+            .strings
+            .iter()
+            .chain(&self.obsolete)
+            .filter(|(k, _)| !k.is_header() && !template.strings.contains_key(*k));
+        for (key, msg) in removed {
+            updated.obsolete.insert(key.clone(), msg.clone());
+        }
+        updated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+    use crate::CatalogBuilder;
+
+    fn catalogs() -> (Catalog, Catalog) {
+        let first = CatalogBuilder::new()
+            .header("Language", "lt")
+            .add("Both", "Abu (1)")
+            .add("Untranslated", "")
+            .add("First", "Pirmas")
+            .build()
+            .unwrap();
+        let second = CatalogBuilder::new()
+            .header("Language", "pl")
+            .add("Both", "Abu (2)")
+            .add("Untranslated", "Neisverstas")
+            .add_with_context("ctx", "Second", "Antras")
+            .build()
+            .unwrap();
+        (first, second)
+    }
+
+    #[test]
+    fn test_merge_keep_existing() {
+        let (mut first, second) = catalogs();
+        first.merge(second, MergeStrategy::KeepExisting);
+        assert_eq!(first.strings.len(), 5);
+        assert_eq!(first.metadata().language(), Some("lt"));
+        assert_eq!(first.gettext("Both"), "Abu (1)");
+        assert_eq!(first.gettext("Untranslated"), "Untranslated");
+        assert_eq!(first.gettext("First"), "Pirmas");
+        assert_eq!(first.pgettext("ctx", "Second"), "Antras");
+        assert_eq!(first.ngettext("x", "xs", 5), "xs");
+    }
+
+    #[test]
+    fn test_merge_replace() {
+        let (mut first, second) = catalogs();
+        first.merge(second, MergeStrategy::Replace);
+        assert_eq!(first.metadata().language(), Some("pl"));
+        assert_eq!(first.gettext("Both"), "Abu (2)");
+        assert_eq!(first.gettext("Untranslated"), "Neisverstas");
+        assert_eq!(first.gettext("First"), "Pirmas");
+        // the Polish plural formula came along with the header
+        assert_eq!(first.resolver.resolve(5), 2);
+    }
+
+    #[test]
+    fn test_merge_prefer_translated() {
+        let (mut first, second) = catalogs();
+        first.merge(second, MergeStrategy::PreferTranslated);
+        assert_eq!(first.metadata().language(), Some("lt"));
+        assert_eq!(first.gettext("Both"), "Abu (1)");
+        assert_eq!(first.gettext("Untranslated"), "Neisverstas");
+    }
+
+    #[test]
+    fn test_merge_unite() {
+        let (mut first, mut second) = catalogs();
+        let both = second.strings.get_mut(&Key::from("Both")).unwrap();
+        both.translator_comments = vec!["second".to_owned()];
+        both.references = vec!["b.rs:1".to_owned(), "a.rs:1".to_owned()];
+        both.flags.insert(Flag::Fuzzy);
+        let untranslated = second.strings.get_mut(&Key::from("Untranslated")).unwrap();
+        untranslated.flags.insert(Flag::CFormat);
+        let both = first.strings.get_mut(&Key::from("Both")).unwrap();
+        both.translator_comments = vec!["first".to_owned()];
+        both.references = vec!["a.rs:1".to_owned()];
+
+        first.merge(second, MergeStrategy::Unite);
+        assert_eq!(first.metadata().language(), Some("lt"));
+        assert_eq!(first.gettext("Both"), "Abu (1)");
+        let both = first.get("Both").unwrap();
+        assert_eq!(both.translator_comments(), &["first", "second"]);
+        assert_eq!(both.references(), &["a.rs:1", "b.rs:1"]);
+        assert!(!both.is_fuzzy());
+        assert_eq!(first.gettext("Untranslated"), "Neisverstas");
+        assert!(first.get("Untranslated").unwrap().has_flag(Flag::CFormat));
+        assert_eq!(first.gettext("First"), "Pirmas");
+        assert_eq!(first.pgettext("ctx", "Second"), "Antras");
+    }
+
+    #[test]
+    fn test_merge_into_empty() {
+        let (first, _) = catalogs();
+        let mut empty = Catalog::empty();
+        empty.merge(first, MergeStrategy::KeepExisting);
+        assert_eq!(empty.metadata().language(), Some("lt"));
+        assert_eq!(empty.resolver.resolve(2), 1);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut old = CatalogBuilder::new()
+            .header("Language", "lt")
+            .add("Kept", "Paliktas")
+            .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+            .add("Now plural", "Dabar daugiskaita")
+            .add("Removed", "Pašalintas")
+            .build()
+            .unwrap();
+        let mut template = CatalogBuilder::new()
+            .header("Project-Id-Version", "app 2.0")
+            .add("Kept", "")
+            .add_plural("File", "Files", vec!["", ""])
+            .add_plural("Now plural", "Now plurals", vec!["", ""])
+            .add_plural_with_context("menu", "New", "News", vec!["", ""])
+            .build()
+            .unwrap();
+        let kept = old.strings.get_mut(&Key::from("Kept")).unwrap();
+        kept.translator_comments = vec!["checked".to_owned()];
+        kept.extracted_comments = vec!["old comment".to_owned()];
+        kept.flags.insert(Flag::Fuzzy);
+        kept.previous_id = Some("Kep".to_owned());
+        let kept = template.strings.get_mut(&Key::from("Kept")).unwrap();
+        kept.extracted_comments = vec!["new comment".to_owned()];
+        kept.flags.insert(Flag::CFormat);
+
+        let updated = old.update(&template);
+        assert_eq!(updated.metadata().language(), Some("lt"));
+        assert_eq!(updated.metadata().project_id_version(), None);
+        assert_eq!(updated.gettext("Kept"), "Paliktas");
+        let kept = &updated.strings[&Key::from("Kept")];
+        assert_eq!(kept.translator_comments(), &["checked"]);
+        assert_eq!(kept.extracted_comments(), &["new comment"]);
+        assert!(kept.has_flag(Flag::CFormat));
+        assert!(kept.is_fuzzy());
+        assert_eq!(kept.previous_id(), Some("Kep"));
+        assert_eq!(updated.ngettext("File", "Files", 10), "Failų");
+        let now_plural = &updated.strings[&Key::from("Now plural")];
+        assert_eq!(now_plural.id_plural(), Some("Now plurals"));
+        assert_eq!(now_plural.translated(), &["", "", ""]);
+        assert_eq!(
+            updated.strings[&Key::from("menu\x04New")].translated(),
+            &["", "", ""]
+        );
+        assert_eq!(updated.gettext("Removed"), "Removed");
+
+        let obsolete = updated.obsolete().map(Message::id).collect::<Vec<_>>();
+        assert_eq!(obsolete, &["Removed"]);
+
+        // obsolete translations come back if the messages do
+        let back = updated.update(&old);
+        assert_eq!(back.gettext("Removed"), "Pašalintas");
+        assert_eq!(back.gettext("Now plural"), "Now plural");
+        assert_eq!(back.ngettext("File", "Files", 10), "Failų");
+        let obsolete = back.obsolete().map(Message::id).collect::<Vec<_>>();
+        assert_eq!(obsolete, &["New"]);
+    }
+}
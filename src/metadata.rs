@@ -2,12 +2,35 @@ use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
 use super::Error;
+use crate::plurals::{split_plural_forms, Ast, PluralForms, Resolver};
 use crate::Error::MalformedMetadata;
 
-#[derive(Debug)]
+/// MetadataMap is a view of the header entry of a catalog,
+/// mapping the names of the header fields to their values.
+///
+/// Besides the accessors for well-known fields,
+/// every field is available through the underlying `HashMap`.
+#[derive(Debug, Default)]
 pub struct MetadataMap<'a>(HashMap<&'a str, &'a str>);
 
 impl<'a> MetadataMap<'a> {
+    /// Returns the language of the catalog, as given in the `Language` field.
+    pub fn language(&self) -> Option<&'a str> {
+        self.get("Language").cloned().filter(|s| !s.is_empty())
+    }
+
+    /// Returns the name and version of the package,
+    /// as given in the `Project-Id-Version` field.
+    pub fn project_id_version(&self) -> Option<&'a str> {
+        self.get("Project-Id-Version").cloned()
+    }
+
+    /// Returns the date of the last revision of the translations,
+    /// as given in the `PO-Revision-Date` field.
+    pub fn revision_date(&self) -> Option<&'a str> {
+        self.get("PO-Revision-Date").cloned()
+    }
+
     /// Returns a string that indicates the character set.
     pub fn charset(&self) -> Option<&'a str> {
         self.get("Content-Type")
@@ -21,26 +44,26 @@ impl<'a> MetadataMap<'a> {
     /// Defaults to `n_plurals = 2` and `plural = n!=1` (as in English).
     pub fn plural_forms(&self) -> (Option<usize>, Option<&'a str>) {
         self.get("Plural-Forms")
-            .map(|f| {
-                f.split(';').fold((None, None), |(n_pl, pl), prop| {
-                    match prop.chars().position(|c| c == '=') {
-                        Some(index) => {
-                            let (name, value) = prop.split_at(index);
-                            let value = value[1..value.len()].trim();
-                            match name.trim() {
-                                "n_plurals" => (usize::from_str_radix(value, 10).ok(), pl),
-                                "plural" => (n_pl, Some(value)),
-                                _ => (n_pl, pl),
-                            }
-                        }
-                        None => (n_pl, pl),
-                    }
-                })
-            })
+            .map(|f| split_plural_forms(f))
             .unwrap_or((None, None))
     }
 }
 
+impl<'a> MetadataMap<'a> {
+    /// Returns the resolver for the plural formula of the catalog,
+    /// falling back to the built-in formula for its language
+    /// if the `Plural-Forms` header does not specify one.
+    pub(crate) fn plural_resolver(&self) -> Result<Option<Resolver>, Error> {
+        if let Some(p) = self.plural_forms().1 {
+            return Ast::parse(p).map(|ast| Some(Resolver::from_ast(&ast)));
+        }
+        Ok(self
+            .language()
+            .and_then(PluralForms::for_language)
+            .map(|forms| Resolver::from_ast(forms.expr())))
+    }
+}
+
 impl<'a> Deref for MetadataMap<'a> {
     type Target = HashMap<&'a str, &'a str>;
     fn deref(&self) -> &Self::Target {
@@ -54,7 +77,7 @@ impl<'a> DerefMut for MetadataMap<'a> {
     }
 }
 
-pub fn parse_metadata(blob: &str) -> Result<MetadataMap, Error> {
+pub fn parse_metadata(blob: &str) -> Result<MetadataMap<'_>, Error> {
     let mut map = MetadataMap(HashMap::new());
     for line in blob.split('\n').filter(|s| s != &"") {
         let pos = match line.bytes().position(|b| b == b':') {
@@ -80,6 +103,40 @@ fn test_metadatamap_charset() {
     }
 }
 
+#[test]
+fn test_metadatamap_fields() {
+    let map = parse_metadata(
+        "Project-Id-Version: gettext 1.0\nPO-Revision-Date: 2016-02-09 12:43+0200\nLanguage: lt\n",
+    )
+    .unwrap();
+    assert_eq!(map.language(), Some("lt"));
+    assert_eq!(map.project_id_version(), Some("gettext 1.0"));
+    assert_eq!(map.revision_date(), Some("2016-02-09 12:43+0200"));
+
+    let map = parse_metadata("Language: \n").unwrap();
+    assert_eq!(map.language(), None);
+    assert_eq!(map.project_id_version(), None);
+    assert_eq!(map.revision_date(), None);
+}
+
+#[test]
+fn test_metadatamap_plural_resolver() {
+    let map = parse_metadata("Language: pl\nPlural-Forms: nplurals=1; plural=0;\n").unwrap();
+    assert_eq!(map.plural_resolver().unwrap().unwrap().resolve(5), 0);
+
+    let map = parse_metadata("Language: pl\n").unwrap();
+    assert_eq!(map.plural_resolver().unwrap().unwrap().resolve(5), 2);
+
+    let map = parse_metadata("Language: tlh\n").unwrap();
+    assert!(map.plural_resolver().unwrap().is_none());
+
+    let map = parse_metadata("Plural-Forms: nplurals=2; plural=n ? : 1;\n").unwrap();
+    assert!(map.plural_resolver().is_err());
+
+    let map = parse_metadata("Plural-Forms: é=2; plural=n != 1;\n").unwrap();
+    assert_eq!(map.plural_resolver().unwrap().unwrap().resolve(5), 1);
+}
+
 #[test]
 fn test_metadatamap_plural() {
     {
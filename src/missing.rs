@@ -0,0 +1,44 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// MissingLookup describes a lookup that found no translation
+/// and fell back, as reported to the callback set with `Catalog::on_missing`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MissingLookup<'a> {
+    pub(crate) msg_context: Option<&'a str>,
+    pub(crate) msg_id: &'a str,
+    pub(crate) msg_id_plural: Option<&'a str>,
+    pub(crate) n: Option<u64>,
+}
+
+impl<'a> MissingLookup<'a> {
+    /// Returns the context of the message looked up, if any.
+    pub fn context(&self) -> Option<&'a str> {
+        self.msg_context
+    }
+
+    /// Returns the msgid looked up.
+    pub fn id(&self) -> &'a str {
+        self.msg_id
+    }
+
+    /// Returns the plural msgid of a plural lookup.
+    pub fn id_plural(&self) -> Option<&'a str> {
+        self.msg_id_plural
+    }
+
+    /// Returns the number of objects of a plural lookup.
+    pub fn n(&self) -> Option<u64> {
+        self.n
+    }
+}
+
+/// The callback for missing translations, shareable between threads.
+#[derive(Clone)]
+pub(crate) struct MissingHook(pub(crate) Arc<dyn Fn(&MissingLookup) + Send + Sync>);
+
+impl fmt::Debug for MissingHook {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_tuple("MissingHook").finish()
+    }
+}
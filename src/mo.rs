@@ -0,0 +1,757 @@
+//! Access to the tables of binary MO files.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str;
+use std::sync::OnceLock;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use encoding::label::encoding_from_whatwg_label;
+use encoding::types::{DecoderTrap, EncoderTrap, EncodingRef, StringWriter};
+
+use crate::metadata::{parse_metadata, MetadataMap};
+use crate::parser::{default_resolver, utf8_encoding, Limits, ParseOptions, Warnings};
+use crate::plurals::{PluralFallback, Resolver, ToPluralNumber};
+use crate::Error::{self, *};
+
+mod reader;
+pub use self::reader::MoReader;
+
+/// The GNU `hashpjw` function used for the hash table of MO files.
+pub fn hash_string(key: &[u8]) -> u32 {
+    hash_parts(&[key])
+}
+
+/// Hashes the concatenation of the given parts with `hashpjw`.
+fn hash_parts(parts: &[&[u8]]) -> u32 {
+    parts.iter().flat_map(|p| p.iter()).fold(0u32, |hval, &b| {
+        let hval = (hval << 4).wrapping_add(u32::from(b));
+        let g = hval & 0xf000_0000;
+        if g != 0 {
+            (hval ^ (g >> 24)) ^ g
+        } else {
+            hval
+        }
+    })
+}
+
+/// According to the given magic number of a MO file,
+/// returns the function which reads a `u32` in the relevant endianness.
+fn get_read_u32_fn(magic: &[u8]) -> Option<fn(&[u8]) -> u32> {
+    if magic == [0xde, 0x12, 0x04, 0x95] {
+        Some(LittleEndian::read_u32)
+    } else if magic == [0x95, 0x04, 0x12, 0xde] {
+        Some(BigEndian::read_u32)
+    } else {
+        None
+    }
+}
+
+/// MoTable gives access to the strings of an MO file kept in memory
+/// through its offset tables, without decoding them.
+///
+/// The bounds of all the strings are checked up front,
+/// so accessing them never fails.
+#[derive(Clone)]
+pub struct MoTable<B> {
+    bytes: B,
+    read_u32: fn(&[u8]) -> u32,
+    len: usize,
+    off_originals: usize,
+    off_translations: usize,
+    hash_size: usize,
+    off_hash: usize,
+}
+
+impl<B: AsRef<[u8]>> MoTable<B> {
+    /// Reads the header of the MO file and checks its tables,
+    /// and its strings against the given limits.
+    pub fn new(bytes: B, limits: &Limits) -> Result<Self, Error> {
+        let contents = bytes.as_ref();
+        limits.check_total_size(contents.len())?;
+        if contents.len() < 28 {
+            return Err(Eof);
+        }
+        let read_u32 = get_read_u32_fn(&contents[0..4]).ok_or(BadMagic)?;
+        let word = |at: usize| read_u32(&contents[at..at + 4]) as usize;
+        let table = MoTable {
+            read_u32,
+            len: word(8),
+            off_originals: word(12),
+            off_translations: word(16),
+            hash_size: word(20),
+            off_hash: word(24),
+            bytes,
+        };
+
+        limits.check_entries(table.len)?;
+        let contents = table.bytes.as_ref();
+        let fits = |offset: usize, size: usize, count: usize| {
+            size.checked_mul(count)
+                .and_then(|s| s.checked_add(offset))
+                .is_some_and(|end| end <= contents.len())
+        };
+        if !fits(table.off_originals, 8, table.len) || !fits(table.off_translations, 8, table.len) {
+            return Err(Eof);
+        }
+        for i in 0..table.len {
+            for &(len, off) in &[
+                table.entry(table.off_originals, i),
+                table.entry(table.off_translations, i),
+            ] {
+                limits
+                    .check_string_len(len)
+                    .map_err(|err| err.in_entry(i, off))?;
+                // +1 for the ending NUL byte which is not included in length
+                if !fits(off, 1, len.saturating_add(1)) {
+                    return Err(Eof.in_entry(i, off));
+                }
+            }
+        }
+        // a missing or truncated hash table is not used
+        let hash_size = table.hash_size;
+        let hash_size = if hash_size >= 3 && fits(table.off_hash, 4, hash_size) {
+            hash_size
+        } else {
+            0
+        };
+        Ok(MoTable { hash_size, ..table })
+    }
+
+    /// Returns the number of strings in the file.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn word(&self, at: usize) -> usize {
+        (self.read_u32)(&self.bytes.as_ref()[at..at + 4]) as usize
+    }
+
+    /// Returns the length and the offset of a string from the given table.
+    fn entry(&self, table: usize, i: usize) -> (usize, usize) {
+        (self.word(table + i * 8), self.word(table + i * 8 + 4))
+    }
+
+    fn string(&self, table: usize, i: usize) -> &[u8] {
+        let (len, off) = self.entry(table, i);
+        &self.bytes.as_ref()[off..off + len]
+    }
+
+    /// Returns the original string at the given index,
+    /// i.e. the context and the msgid separated by `\x04`,
+    /// followed by a NUL and the plural msgid if there is one.
+    pub fn original(&self, i: usize) -> &[u8] {
+        self.string(self.off_originals, i)
+    }
+
+    /// Returns the translated forms at the given index, separated by NULs.
+    pub fn translation(&self, i: usize) -> &[u8] {
+        self.string(self.off_translations, i)
+    }
+
+    /// Returns the byte offset of the original string at the given index.
+    pub fn original_offset(&self, i: usize) -> usize {
+        self.entry(self.off_originals, i).1
+    }
+
+    /// Returns the byte offset of the translated forms at the given index.
+    pub fn translation_offset(&self, i: usize) -> usize {
+        self.entry(self.off_translations, i).1
+    }
+
+    /// Returns whether the original string at the given index
+    /// has the lookup key made of the given parts.
+    fn has_key(&self, i: usize, parts: &[&[u8]]) -> bool {
+        let mut original = self.original(i);
+        for part in parts {
+            if !original.starts_with(part) {
+                return false;
+            }
+            original = &original[part.len()..];
+        }
+        original.is_empty() || original[0] == 0
+    }
+
+    /// Returns the index of the message with the given context and msgid,
+    /// using the hash table of the file if it has one.
+    pub fn find(&self, context: Option<&[u8]>, id: &[u8]) -> Option<usize> {
+        let parts: &[&[u8]] = match context {
+            Some(ctxt) => &[ctxt, b"\x04", id],
+            None => &[id],
+        };
+        if self.hash_size == 0 {
+            // msgfmt sorts the original strings, so a binary search works
+            let key = parts.concat();
+            let (mut low, mut high) = (0, self.len);
+            while low < high {
+                let mid = (low + high) / 2;
+                let original = self.original(mid);
+                let original = original.split(|&b| b == 0).next().unwrap_or(original);
+                match original.cmp(&key[..]) {
+                    std::cmp::Ordering::Less => low = mid + 1,
+                    std::cmp::Ordering::Greater => high = mid,
+                    std::cmp::Ordering::Equal => return Some(mid),
+                }
+            }
+            return None;
+        }
+
+        let size = self.hash_size;
+        let hash = hash_parts(parts) as usize;
+        let incr = 1 + hash % (size - 2);
+        let mut idx = hash % size;
+        for _ in 0..size {
+            let entry = self.word(self.off_hash + idx * 4);
+            if entry == 0 {
+                return None;
+            }
+            if entry <= self.len && self.has_key(entry - 1, parts) {
+                return Some(entry - 1);
+            }
+            idx = if idx >= size - incr {
+                idx - (size - incr)
+            } else {
+                idx + incr
+            };
+        }
+        None
+    }
+}
+
+/// MoCatalog looks up translations directly in an MO file kept in memory,
+/// through the hash table of the file, instead of building a map of all the strings.
+///
+/// This makes loading big catalogs much cheaper than `Catalog::parse`,
+/// as the translations are only decoded the first time they are looked up
+/// and then cached. All the strings are checked when the catalog is read,
+/// which needs no allocation, so that an invalid one fails like with `Catalog::parse`,
+/// or is skipped and treated as missing when parsing leniently.
+/// The translations of UTF-8 catalogs are not copied at all.
+///
+/// # Examples
+///
+/// ```ignore
+/// use gettext::ParseOptions;
+/// use std::fs::File;
+///
+/// let file = File::open("french.mo").unwrap();
+/// let catalog = ParseOptions::new().parse_hashed(file).unwrap();
+/// println!("{}", catalog.gettext("Name"));
+/// ```
+#[derive(Clone)]
+pub struct MoCatalog<B = Vec<u8>> {
+    table: MoTable<B>,
+    header: String,
+    resolver: Resolver,
+    encoding: EncodingRef,
+    decoded: Vec<OnceLock<Forms>>,
+    /// The address and length of the bytes whose strings were checked
+    checked: (usize, usize),
+    plural_fallback: PluralFallback,
+    show_header: bool,
+}
+
+/// The translated forms of a message, once decoded.
+#[derive(Clone, Debug)]
+enum Forms {
+    /// The forms are valid UTF-8 and can be used in place
+    Valid,
+    /// The forms decoded from another encoding, or none if the entry was skipped
+    Decoded(Vec<String>),
+}
+
+/// Discards decoded strings, for checking that they can be decoded without copying them.
+struct Discard;
+
+impl StringWriter for Discard {
+    fn write_char(&mut self, _: char) {}
+    fn write_str(&mut self, _: &str) {}
+}
+
+/// CatalogRef is a `MoCatalog` borrowing the MO file from a byte slice,
+/// e.g. one embedded with `include_bytes!`, without copying any of its strings.
+///
+/// # Examples
+///
+/// ```ignore
+/// use gettext::CatalogRef;
+///
+/// static FRENCH: &[u8] = include_bytes!("../po/fr.mo");
+///
+/// let catalog = CatalogRef::from_bytes(FRENCH).unwrap();
+/// println!("{}", catalog.gettext("Name"));
+/// ```
+pub type CatalogRef<'a> = MoCatalog<&'a [u8]>;
+
+impl<B: AsRef<[u8]>> MoCatalog<B> {
+    /// Uses the MO file in `bytes` for lookups,
+    /// reading only its tables and its header.
+    ///
+    /// Calling this method is equivalent to calling
+    /// `ParseOptions::new().parse_bytes(bytes)`.
+    pub fn from_bytes(bytes: B) -> Result<Self, Error> {
+        ParseOptions::new().parse_bytes(bytes)
+    }
+
+    /// Reads the tables and the header of the MO file in `bytes`.
+    pub(crate) fn with_options(
+        bytes: B,
+        opts: ParseOptions,
+        warnings: &mut Warnings,
+    ) -> Result<Self, Error> {
+        let table = MoTable::new(bytes, &opts.limits)?;
+        let raw_header = match table.len() {
+            0 => &[][..],
+            _ if table.original(0).is_empty() => table.translation(0),
+            _ => &[][..],
+        };
+        let in_header = |err: Error| err.in_entry(0, table.translation_offset(0));
+        let mut encoding = opts.force_encoding.unwrap_or(utf8_encoding);
+        let mut header = encoding
+            .decode(raw_header, DecoderTrap::Strict)
+            .map_err(|err| in_header(err.into()))?;
+
+        let map = parse_metadata(&header).map_err(in_header)?;
+        if let (Some(c), None) = (map.charset(), opts.force_encoding) {
+            encoding = encoding_from_whatwg_label(c).ok_or_else(|| in_header(UnknownEncoding))?;
+        }
+        let resolver = match (opts.force_plural, map.plural_resolver().map_err(in_header)?) {
+            (Some(resolver), _) | (None, Some(resolver)) => resolver,
+            (None, None) => Resolver::Function(default_resolver),
+        };
+        if encoding.name() != utf8_encoding.name() {
+            header = encoding
+                .decode(raw_header, DecoderTrap::Strict)
+                .map_err(|err| in_header(err.into()))?;
+        }
+        let decoded = (0..table.len())
+            .map(|_| OnceLock::new())
+            .collect::<Vec<_>>();
+        let utf8 = encoding.name() == utf8_encoding.name();
+        for (i, forms) in decoded.iter().enumerate() {
+            let is_valid = |s: &[u8]| {
+                s.split(|&b| b == 0 || b == 4).all(|s| match utf8 {
+                    true => str::from_utf8(s).is_ok(),
+                    false => encoding
+                        .decode_to(s, DecoderTrap::Strict, &mut Discard)
+                        .is_ok(),
+                })
+            };
+            if !is_valid(table.original(i)) || !is_valid(table.translation(i)) {
+                warnings.skip(None, DecodingError.in_entry(i, table.original_offset(i)))?;
+                let _ = forms.set(Forms::Decoded(vec![]));
+            }
+        }
+        let contents = table.bytes.as_ref();
+        Ok(MoCatalog {
+            checked: (contents.as_ptr() as usize, contents.len()),
+            table,
+            header,
+            resolver,
+            encoding,
+            decoded,
+            plural_fallback: opts.plural_fallback,
+            show_header: opts.show_header,
+        })
+    }
+
+    /// Returns the number of messages in the catalog, including the header.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns whether the catalog has no messages at all.
+    pub fn is_empty(&self) -> bool {
+        self.table.len() == 0
+    }
+
+    /// Returns the metadata stored in the header entry of the catalog.
+    ///
+    /// The returned map is empty if the catalog has no header.
+    pub fn metadata(&self) -> MetadataMap<'_> {
+        parse_metadata(&self.header).unwrap_or_default()
+    }
+
+    /// Returns the singular translation of `msg_id` from the given catalog
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str {
+        self.find_translation(None, msg_id, None).unwrap_or(msg_id)
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise, unless another `PluralFallback` is configured.
+    pub fn ngettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.find_translation(None, msg_id, Some(n))
+            .unwrap_or_else(|| self.fallback(None, msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context`
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str {
+        self.find_translation(Some(msg_context), msg_id, None)
+            .unwrap_or(msg_id)
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context`
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise, unless another `PluralFallback` is configured.
+    pub fn npgettext<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> &'a str {
+        let n = n.to_plural_number();
+        self.find_translation(Some(msg_context), msg_id, Some(n))
+            .unwrap_or_else(|| self.fallback(Some(msg_context), msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the singular translation of `msg_id` from the given catalog,
+    /// or `None` if a translation does not exist.
+    pub fn try_gettext(&self, msg_id: &str) -> Option<&str> {
+        self.find_translation(None, msg_id, None)
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the correct plural form for the number `n` of objects,
+    /// or `None` if a translation does not exist.
+    pub fn try_ngettext<N: ToPluralNumber>(&self, msg_id: &str, n: N) -> Option<&str> {
+        self.find_translation(None, msg_id, Some(n.to_plural_number()))
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context` from the given catalog,
+    /// or `None` if a translation does not exist.
+    pub fn try_pgettext(&self, msg_context: &str, msg_id: &str) -> Option<&str> {
+        self.find_translation(Some(msg_context), msg_id, None)
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context` from the given catalog
+    /// with the correct plural form for the number `n` of objects,
+    /// or `None` if a translation does not exist.
+    pub fn try_npgettext<N: ToPluralNumber>(
+        &self,
+        msg_context: &str,
+        msg_id: &str,
+        n: N,
+    ) -> Option<&str> {
+        self.find_translation(Some(msg_context), msg_id, Some(n.to_plural_number()))
+    }
+
+    /// Returns the same string as `gettext` as a `Cow`,
+    /// so that post-processing helpers can own a modified string
+    /// without allocating when it is left untouched.
+    pub fn gettext_cow<'a>(&'a self, msg_id: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(self.gettext(msg_id))
+    }
+
+    /// Returns the same string as `ngettext` as a `Cow`.
+    pub fn ngettext_cow<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> Cow<'a, str> {
+        Cow::Borrowed(self.ngettext(msg_id, msg_id_plural, n))
+    }
+
+    /// Returns the same string as `pgettext` as a `Cow`.
+    pub fn pgettext_cow<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(self.pgettext(msg_context, msg_id))
+    }
+
+    /// Returns the same string as `npgettext` as a `Cow`.
+    pub fn npgettext_cow<'a, N: ToPluralNumber>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: N,
+    ) -> Cow<'a, str> {
+        Cow::Borrowed(self.npgettext(msg_context, msg_id, msg_id_plural, n))
+    }
+
+    fn find_translation(
+        &self,
+        msg_context: Option<&str>,
+        msg_id: &str,
+        n: Option<u64>,
+    ) -> Option<&str> {
+        let i = self.lookup(msg_context, msg_id)?;
+        let form_no = n.map_or(0, |n| self.resolver.resolve(n));
+        let form = match self.forms(i) {
+            Forms::Valid => {
+                let form = self.table.translation(i).split(|&b| b == 0).nth(form_no)?;
+                self.checked_str(form)?
+            }
+            Forms::Decoded(forms) => forms.get(form_no)?,
+        };
+        Some(form).filter(|s| !s.is_empty())
+    }
+
+    /// Returns the index of the message with the given context and msgid,
+    /// keeping the header out of lookups unless it is shown.
+    fn lookup(&self, msg_context: Option<&str>, msg_id: &str) -> Option<usize> {
+        if msg_context.is_none() && msg_id.is_empty() && !self.show_header {
+            return None;
+        }
+        if self.encoding.name() == utf8_encoding.name() {
+            self.table
+                .find(msg_context.map(str::as_bytes), msg_id.as_bytes())
+        } else {
+            let encode = |s| self.encoding.encode(s, EncoderTrap::Strict).ok();
+            let context = match msg_context {
+                Some(ctxt) => Some(encode(ctxt)?),
+                None => None,
+            };
+            self.table.find(context.as_deref(), &encode(msg_id)?)
+        }
+    }
+
+    /// Returns the configured fallback for a plural message
+    /// without a translation in the plural form for `n` objects.
+    fn fallback<'a>(
+        &'a self,
+        msg_context: Option<&str>,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        self.plural_fallback.select(msg_id, msg_id_plural, n, || {
+            let i = self.lookup(msg_context, msg_id)?;
+            match self.forms(i) {
+                Forms::Valid => {
+                    let mut forms = self.table.translation(i).split(|&b| b == 0);
+                    self.checked_str(forms.rfind(|form| !form.is_empty())?)
+                }
+                Forms::Decoded(forms) => forms.iter().rfind(|s| !s.is_empty()).map(String::as_str),
+            }
+        })
+    }
+
+    /// Returns the translated forms at the given index, decoding them on first use.
+    fn forms(&self, i: usize) -> &Forms {
+        self.decoded[i].get_or_init(|| {
+            if self.encoding.name() == utf8_encoding.name() {
+                return Forms::Valid;
+            }
+            // the entries that cannot be decoded have been skipped already
+            let forms = self
+                .table
+                .translation(i)
+                .split(|&b| b == 0)
+                .map(|b| self.encoding.decode(b, DecoderTrap::Strict))
+                .collect::<Result<_, Cow<'static, str>>>();
+            Forms::Decoded(forms.unwrap_or_default())
+        })
+    }
+
+    /// Returns a form of a UTF-8 catalog as a string.
+    ///
+    /// The bytes given by the buffer can only change through `&mut B`, which
+    /// the catalog never hands out, so its strings are those checked when it was read,
+    /// unless a clone of the catalog has copied them elsewhere.
+    fn checked_str<'a>(&self, form: &'a [u8]) -> Option<&'a str> {
+        let contents = self.table.bytes.as_ref();
+        if (contents.as_ptr() as usize, contents.len()) == self.checked {
+            // SAFETY: the form is part of a string of these bytes that was valid UTF-8,
+            // split at a NUL byte, which is a character of its own
+            Some(unsafe { str::from_utf8_unchecked(form) })
+        } else {
+            str::from_utf8(form).ok()
+        }
+    }
+}
+
+impl<B> fmt::Debug for MoCatalog<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("MoCatalog")
+            .field("len", &self.table.len)
+            .field("header", &self.header)
+            .field("resolver", &self.resolver)
+            .field("encoding", &self.encoding.name())
+            .finish()
+    }
+}
+
+#[test]
+fn test_hash_string() {
+    assert_eq!(hash_string(b""), 0);
+    assert_eq!(hash_string(b"a"), 0x61);
+    assert_eq!(hash_string(b"ab"), 0x672);
+    // long keys exercise the folding of the top nibble
+    assert_eq!(hash_string(b"abcdefghij"), 0x0aba_a66a);
+    assert_eq!(hash_parts(&[b"abc", b"defghij"]), 0x0aba_a66a);
+}
+
+#[test]
+fn test_get_read_u32_fn() {
+    assert!(get_read_u32_fn(&[]).is_none());
+    assert!(get_read_u32_fn(&[0xde, 0x12, 0x04, 0x95, 0x00]).is_none());
+
+    {
+        let le_ptr = LittleEndian::read_u32 as *const ();
+        let ret_ptr = get_read_u32_fn(&[0xde, 0x12, 0x04, 0x95]).unwrap() as _;
+        assert_eq!(le_ptr, ret_ptr);
+    }
+
+    {
+        let be_ptr = BigEndian::read_u32 as *const ();
+        let ret_ptr = get_read_u32_fn(&[0x95, 0x04, 0x12, 0xde]).unwrap() as _;
+        assert_eq!(be_ptr, ret_ptr);
+    }
+}
+
+#[test]
+fn test_mo_table() {
+    let mo: &[u8] = include_bytes!("../test_cases/integration.mo");
+    let be: &[u8] = include_bytes!("../test_cases/integration_be.mo");
+    for bytes in &[mo, be] {
+        let table = MoTable::new(*bytes, &Limits::default()).unwrap();
+        assert_eq!(table.len(), 5);
+        assert_eq!(table.original(0), b"");
+        let i = table.find(None, b"existent").unwrap();
+        assert_eq!(table.original(i), b"existent");
+        assert_eq!(table.translation(i), "egzistuojantis".as_bytes());
+        let i = table.find(None, b"a good string").unwrap();
+        assert_eq!(table.original(i), b"a good string\0good strings");
+        let i = table.find(Some(b"ctxt"), b"a good string").unwrap();
+        assert_eq!(table.original(i), b"ctxt\x04a good string\0good strings");
+        assert_eq!(table.find(None, b"a good"), None);
+        assert_eq!(table.find(None, b"missing"), None);
+        assert_eq!(table.find(Some(b"ctx"), b"existent"), None);
+
+        // the same lookups work without the hash table
+        let mut bytes = bytes.to_vec();
+        bytes[20..24].copy_from_slice(&[0; 4]);
+        let table = MoTable::new(bytes, &Limits::default()).unwrap();
+        assert!(table.find(None, b"existent").is_some());
+        assert!(table.find(Some(b"ctxt"), b"existent").is_some());
+        assert_eq!(table.find(None, b"missing"), None);
+    }
+
+    let ctx: &[u8] = include_bytes!("../test_cases/1.mo");
+    let table = MoTable::new(ctx, &Limits::default()).unwrap();
+    let i = table.find(Some(b"this is context"), b"Text").unwrap();
+    assert_eq!(table.translation(i), "Tekstas\0Tekstai".as_bytes());
+    assert_eq!(table.find(None, b"Text"), None);
+
+    assert!(matches!(
+        MoTable::new(&ctx[..27], &Limits::default()),
+        Err(Eof)
+    ));
+    match MoTable::new(&ctx[..ctx.len() - 1], &Limits::default()) {
+        Err(InEntry { index, error, .. }) => {
+            assert_eq!(index, 0);
+            assert!(matches!(*error, Eof));
+        }
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    }
+    assert!(matches!(
+        MoTable::new(&ctx[1..], &Limits::default()),
+        Err(BadMagic)
+    ));
+}
+
+#[test]
+fn test_mo_catalog() {
+    let catalog = ParseOptions::new()
+        .parse_hashed(&include_bytes!("../test_cases/complex_plural.mo")[..])
+        .unwrap();
+    assert!(!catalog.is_empty());
+    assert_eq!(catalog.ngettext("Test", "Tests", 0), "Plural 2");
+    assert_eq!(catalog.ngettext("Test", "Tests", 1), "Singular");
+    assert_eq!(catalog.ngettext("Test", "Tests", 2), "Plural 1");
+    assert_eq!(catalog.gettext("Missing"), "Missing");
+    assert_eq!(catalog.ngettext("Missing", "Missings", 2), "Missings");
+
+    let catalog = ParseOptions::new()
+        .plural_resolver(|n| n as usize)
+        .plural_fallback(PluralFallback::LastForm)
+        .parse_hashed(&include_bytes!("../test_cases/complex_plural.mo")[..])
+        .unwrap();
+    assert_eq!(catalog.ngettext("Test", "Tests", 2), "Plural 2");
+    assert_eq!(catalog.ngettext("Test", "Tests", 10), "Plural 2");
+    assert_eq!(catalog.ngettext("Missing", "Missings", 2), "Missings");
+
+    let catalog = ParseOptions::new()
+        .parse_hashed(&include_bytes!("../test_cases/1.mo")[..])
+        .unwrap();
+    assert_eq!(catalog.len(), 1);
+    assert!(catalog.metadata().is_empty());
+    assert_eq!(catalog.pgettext("this is context", "Text"), "Tekstas");
+    assert_eq!(
+        catalog.npgettext("this is context", "Text", "Texts", 2),
+        "Tekstai"
+    );
+    assert_eq!(catalog.gettext("Text"), "Text");
+    assert_eq!(
+        catalog.try_pgettext("this is context", "Text"),
+        Some("Tekstas")
+    );
+    assert_eq!(
+        catalog.try_npgettext("this is context", "Text", 2usize),
+        Some("Tekstai")
+    );
+    assert_eq!(catalog.try_gettext("Text"), None);
+    assert_eq!(catalog.try_ngettext("Text", -2), None);
+    assert_eq!(catalog.pgettext_cow("this is context", "Text"), "Tekstas");
+    assert_eq!(catalog.ngettext_cow("Text", "Texts", 2), "Texts");
+
+    let bytes = include_bytes!("../test_cases/2.mo").to_vec();
+    let catalog: CatalogRef = MoCatalog::from_bytes(&bytes[..]).unwrap();
+    assert_eq!(catalog.ngettext("Image", "Images", 5), "Nuotraukos");
+    let translation = catalog.gettext("Image");
+    assert_eq!(translation, "Nuotrauka");
+    // the translation points into the buffer
+    assert!(bytes.as_ptr_range().contains(&translation.as_ptr()));
+
+    // the translations are decoded on first use
+    let cp1257 = &include_bytes!("../test_cases/cp1257_meta.mo")[..];
+    let catalog = ParseOptions::new().parse_hashed(cp1257).unwrap();
+    assert_eq!(catalog.metadata().charset(), Some("cp1257"));
+    assert_eq!(decoded(&catalog), 0);
+    assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+    assert_eq!(decoded(&catalog), 1);
+    assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+    assert_eq!(decoded(&catalog), 1);
+
+    // the strings are checked up front
+    let invalid = &include_bytes!("../test_cases/invalid_utf8.mo")[..];
+    match ParseOptions::new().parse_bytes(invalid) {
+        Err(InEntry { index, error, .. }) => {
+            assert_eq!(index, 0);
+            assert!(matches!(*error, DecodingError));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+    let (catalog, warnings) = ParseOptions::new()
+        .lenient(true)
+        .parse_bytes_with_warnings(invalid)
+        .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(catalog.gettext("Image"), "Image");
+    assert_eq!(catalog.pgettext("this is context", "Text"), "Tekstas");
+
+    // a clone checks the strings it copied again
+    let catalog = MoCatalog::from_bytes(bytes.clone()).unwrap();
+    assert_eq!(catalog.clone().gettext("Image"), "Nuotrauka");
+}
+
+#[cfg(test)]
+fn decoded<B>(catalog: &MoCatalog<B>) -> usize {
+    catalog.decoded.iter().filter(|d| d.get().is_some()).count()
+}
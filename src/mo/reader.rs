@@ -0,0 +1,226 @@
+//! Streaming access to the entries of MO files.
+
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use encoding::label::encoding_from_whatwg_label;
+use encoding::types::{DecoderTrap, EncodingRef};
+
+use super::get_read_u32_fn;
+use crate::metadata::{parse_metadata, MetadataMap};
+use crate::parser::{decode_message, utf8_encoding, Limits, ParseOptions};
+use crate::Error::{self, *};
+use crate::Message;
+
+/// MoReader reads the entries of an MO file one by one from a stream,
+/// without building a `Catalog` out of them.
+///
+/// Only the header and the current entry are kept in memory,
+/// so tools converting or analyzing big catalogs use constant memory.
+/// The entries are yielded in the order of the file,
+/// including the metadata entry with an empty msgid;
+/// the iteration stops after the first error.
+///
+/// # Examples
+///
+/// ```ignore
+/// use std::fs::File;
+/// use gettext::MoReader;
+///
+/// let reader = MoReader::new(File::open("french.mo").unwrap()).unwrap();
+/// for message in reader {
+///     let message = message.unwrap();
+///     println!("{:?} => {:?}", message.id(), message.translated());
+/// }
+/// ```
+pub struct MoReader<R> {
+    reader: R,
+    read_u32: fn(&[u8]) -> u32,
+    len: usize,
+    off_originals: u64,
+    off_translations: u64,
+    next: usize,
+    encoding: EncodingRef,
+    header: String,
+    limits: Limits,
+}
+
+impl<R: Read + Seek> MoReader<R> {
+    /// Reads the header of the MO file from the given reader.
+    pub fn new(reader: R) -> Result<Self, Error> {
+        Self::with_options(reader, ParseOptions::new())
+    }
+
+    pub(crate) fn with_options(mut reader: R, opts: ParseOptions) -> Result<Self, Error> {
+        let mut header = [0; 20];
+        reader.seek(SeekFrom::Start(0))?;
+        read_exact(&mut reader, &mut header)?;
+        let read_u32 = get_read_u32_fn(&header[0..4]).ok_or(BadMagic)?;
+        let word = |at: usize| read_u32(&header[at..at + 4]);
+        let mut mo = MoReader {
+            reader,
+            read_u32,
+            len: word(8) as usize,
+            off_originals: word(12).into(),
+            off_translations: word(16).into(),
+            next: 0,
+            encoding: opts.force_encoding.unwrap_or(utf8_encoding),
+            header: String::new(),
+            limits: opts.limits,
+        };
+        mo.limits.check_entries(mo.len)?;
+
+        if mo.len > 0 && mo.read_string(mo.off_originals, 0)?.1.is_empty() {
+            let (offset, raw_header) = mo.read_string(mo.off_translations, 0)?;
+            let in_header = |err: Error| err.in_entry(0, offset);
+            let decode = |encoding: EncodingRef| {
+                encoding
+                    .decode(&raw_header, DecoderTrap::Strict)
+                    .map_err(|err| in_header(err.into()))
+            };
+            mo.header = decode(mo.encoding)?;
+            let map = parse_metadata(&mo.header).map_err(in_header)?;
+            if let (Some(c), None) = (map.charset(), opts.force_encoding) {
+                mo.encoding =
+                    encoding_from_whatwg_label(c).ok_or_else(|| in_header(UnknownEncoding))?;
+                mo.header = decode(mo.encoding)?;
+            }
+        }
+        Ok(mo)
+    }
+
+    /// Returns the number of entries in the file.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the file has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the metadata stored in the header entry of the file.
+    ///
+    /// The returned map is empty if the file has no header.
+    pub fn metadata(&self) -> MetadataMap<'_> {
+        parse_metadata(&self.header).unwrap_or_default()
+    }
+
+    /// Reads the string at the given index of the given offset table,
+    /// returning it with its byte offset.
+    fn read_string(&mut self, table: u64, i: usize) -> Result<(usize, Vec<u8>), Error> {
+        let mut entry = [0; 8];
+        let at = table + i as u64 * 8;
+        self.reader.seek(SeekFrom::Start(at))?;
+        read_exact(&mut self.reader, &mut entry).map_err(|err| err.in_entry(i, at as usize))?;
+        let len = (self.read_u32)(&entry[..4]);
+        let off = (self.read_u32)(&entry[4..]);
+        let in_entry = |err: Error| err.in_entry(i, off as usize);
+        self.limits
+            .check_string_len(len as usize)
+            .map_err(in_entry)?;
+        // the string and its ending NUL byte have to fit in the maximum size
+        let end = u64::from(off) + u64::from(len) + 1;
+        self.limits
+            .check_total_size(end as usize)
+            .map_err(in_entry)?;
+
+        // a crafted length cannot make us allocate more than what the stream holds
+        let mut string = vec![];
+        self.reader.seek(SeekFrom::Start(off.into()))?;
+        (&mut self.reader)
+            .take(len.into())
+            .read_to_end(&mut string)?;
+        if string.len() != len as usize {
+            return Err(in_entry(Eof));
+        }
+        Ok((off as usize, string))
+    }
+
+    fn read_message(&mut self, i: usize) -> Result<Message, Error> {
+        let (offset, original) = self.read_string(self.off_originals, i)?;
+        let (_, translation) = self.read_string(self.off_translations, i)?;
+        let in_entry = |err: Error| err.in_entry(i, offset);
+        let message = decode_message(&original, &translation, self.encoding).map_err(in_entry)?;
+        if message.id().is_empty() && i != 0 {
+            return Err(in_entry(MisplacedMetadata));
+        }
+        Ok(message)
+    }
+}
+
+impl<R: Read + Seek> Iterator for MoReader<R> {
+    type Item = Result<Message, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.len {
+            return None;
+        }
+        let result = self.read_message(self.next);
+        self.next = match result {
+            Ok(_) => self.next + 1,
+            Err(_) => self.len,
+        };
+        Some(result)
+    }
+}
+
+impl<R> fmt::Debug for MoReader<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("MoReader")
+            .field("len", &self.len)
+            .field("next", &self.next)
+            .field("header", &self.header)
+            .field("encoding", &self.encoding.name())
+            .finish()
+    }
+}
+
+/// Reads exactly enough bytes to fill the buffer, failing with `Eof` otherwise.
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), Error> {
+    reader.read_exact(buf).map_err(|err| match err.kind() {
+        io::ErrorKind::UnexpectedEof => Eof,
+        _ => Io(err),
+    })
+}
+
+#[test]
+fn test_mo_reader() {
+    use std::io::Cursor;
+
+    let bytes = &include_bytes!("../../test_cases/integration.mo")[..];
+    let catalog = crate::Catalog::parse(bytes).unwrap();
+    let reader = MoReader::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(
+        reader.metadata().project_id_version(),
+        Some("PACKAGE VERSION")
+    );
+    let messages = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(messages.len(), catalog.strings.len());
+    assert_eq!(messages[0].id(), "");
+    for message in &messages[1..] {
+        let key = crate::key::Key::new(message.context(), message.id());
+        assert_eq!(Some(message), catalog.strings.get(&key));
+    }
+
+    let cp1257 = &include_bytes!("../../test_cases/cp1257_meta.mo")[..];
+    let mut reader = MoReader::new(Cursor::new(cp1257)).unwrap();
+    assert_eq!(reader.metadata().charset(), Some("cp1257"));
+    assert!(reader.any(|m| m.unwrap().translated() == ["Česnakas"]));
+
+    assert!(matches!(MoReader::new(Cursor::new(&bytes[..10])), Err(Eof)));
+    assert!(matches!(
+        MoReader::new(Cursor::new(&b"\x00\x00\x00\x00 garbage garbage"[..])),
+        Err(BadMagic)
+    ));
+    // the entries are read lazily, so a truncated file fails midway
+    let truncated = &bytes[..bytes.len() - 10];
+    let results = MoReader::new(Cursor::new(truncated))
+        .unwrap()
+        .collect::<Vec<_>>();
+    match results.last() {
+        Some(Err(InEntry { error, .. })) => assert!(matches!(**error, Eof)),
+        last => panic!("unexpected result {:?}", last),
+    }
+    assert!(results.len() <= messages.len());
+}
@@ -1,20 +1,26 @@
 use std::default::Default;
-use std::io;
+use std::io::{self, Read};
+use std::sync::Arc;
 
-use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use encoding::label::encoding_from_whatwg_label;
 use encoding::types::DecoderTrap::Strict;
 use encoding::types::EncodingRef;
 
+use crate::error::ParseWarning;
+use crate::format::{check_formats, FormatStyle};
 use crate::metadata::parse_metadata;
-use crate::plurals::{Ast, Resolver};
+use crate::mo::{MoCatalog, MoReader, MoTable};
+use crate::plurals::{PluralFallback, Resolver};
+use crate::pseudo::PseudoLocalizer;
 use crate::Error::{self, *};
 use crate::{Catalog, Message};
 
+mod po;
+
 #[allow(non_upper_case_globals)]
-static utf8_encoding: EncodingRef = &encoding::codec::utf_8::UTF8Encoding;
+pub(crate) static utf8_encoding: EncodingRef = &encoding::codec::utf_8::UTF8Encoding;
 
-/// ParseOptions allows setting options for parsing MO catalogs.
+/// ParseOptions allows setting options for parsing MO and PO catalogs.
 ///
 /// # Examples
 /// ```ignore
@@ -27,8 +33,81 @@ static utf8_encoding: EncodingRef = &encoding::codec::utf_8::UTF8Encoding;
 #[allow(missing_debug_implementations)]
 #[derive(Default)]
 pub struct ParseOptions {
-    force_encoding: Option<EncodingRef>,
-    force_plural: Option<fn(u64) -> usize>,
+    pub(crate) force_encoding: Option<EncodingRef>,
+    pub(crate) force_plural: Option<Resolver>,
+    format_style: Option<FormatStyle>,
+    pub(crate) limits: Limits,
+    lenient: bool,
+    pub(crate) plural_fallback: PluralFallback,
+    pseudo: Option<PseudoLocalizer>,
+    pub(crate) show_header: bool,
+    pub(crate) skip_fuzzy: bool,
+    pub(crate) unite_duplicates: bool,
+}
+
+/// Bounds on the resources used for parsing a catalog.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Limits {
+    max_entries: Option<usize>,
+    max_string_len: Option<usize>,
+    max_total_size: Option<usize>,
+}
+
+impl Limits {
+    fn check(limit: Option<usize>, value: usize) -> Result<(), Error> {
+        match limit {
+            Some(max) if value > max => Err(LimitExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_entries(&self, n: usize) -> Result<(), Error> {
+        Self::check(self.max_entries, n)
+    }
+
+    pub fn check_string_len(&self, len: usize) -> Result<(), Error> {
+        Self::check(self.max_string_len, len)
+    }
+
+    pub fn check_total_size(&self, size: usize) -> Result<(), Error> {
+        Self::check(self.max_total_size, size)
+    }
+
+    /// Reads the whole file, failing as soon as it exceeds the maximum size.
+    pub fn read_to_end<R: io::Read>(&self, reader: R) -> Result<Vec<u8>, Error> {
+        let max = self.max_total_size.map_or(u64::MAX, |max| max as u64 + 1);
+        let mut contents = vec![];
+        reader.take(max).read_to_end(&mut contents)?;
+        self.check_total_size(contents.len())?;
+        Ok(contents)
+    }
+}
+
+/// Collects the warnings about the malformed entries skipped in lenient mode.
+#[derive(Debug, Default)]
+pub(crate) struct Warnings {
+    lenient: bool,
+    warnings: Vec<ParseWarning>,
+}
+
+impl Warnings {
+    fn new(lenient: bool) -> Self {
+        Warnings {
+            lenient,
+            warnings: vec![],
+        }
+    }
+
+    /// Records the error of a malformed entry when parsing leniently,
+    /// or returns it otherwise.
+    /// Exceeding a limit is never skipped.
+    pub fn skip(&mut self, line: Option<usize>, error: Error) -> Result<(), Error> {
+        if !self.lenient || matches!(error, LimitExceeded) {
+            return Err(error);
+        }
+        self.warnings.push(ParseWarning::new(line, error));
+        Ok(())
+    }
 }
 
 impl ParseOptions {
@@ -39,7 +118,239 @@ impl ParseOptions {
 
     /// Tries to parse the catalog from the given reader using the specified options.
     pub fn parse<R: io::Read>(self, reader: R) -> Result<Catalog, Error> {
-        parse_catalog(reader, self)
+        let contents = self.limits.read_to_end(reader)?;
+        self.parse_slice(&contents)
+    }
+
+    /// Tries to parse the catalog from the MO file in the given buffer
+    /// using the specified options, without copying the buffer first.
+    ///
+    /// No I/O happens while parsing, so async code can read the file
+    /// with the `AsyncRead` of its runtime and parse it without blocking.
+    /// There is no `parse_async` taking an `AsyncRead` itself, as the crate
+    /// does not depend on any runtime; PO files read this way can be parsed
+    /// with `parse_po(&contents[..])`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use gettext::ParseOptions;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// let mut contents = vec![];
+    /// object.body.read_to_end(&mut contents).await?;
+    /// let catalog = ParseOptions::new().parse_slice(&contents)?;
+    /// ```
+    pub fn parse_slice(self, contents: &[u8]) -> Result<Catalog, Error> {
+        self.parse_slice_with_warnings(contents)
+            .map(|(catalog, _)| catalog)
+    }
+
+    /// Tries to parse the catalog from the given reader like `parse`,
+    /// also returning the malformed entries which were skipped in lenient mode.
+    pub fn parse_with_warnings<R: io::Read>(
+        self,
+        reader: R,
+    ) -> Result<(Catalog, Vec<ParseWarning>), Error> {
+        let contents = self.limits.read_to_end(reader)?;
+        self.parse_slice_with_warnings(&contents)
+    }
+
+    fn parse_slice_with_warnings(
+        self,
+        contents: &[u8],
+    ) -> Result<(Catalog, Vec<ParseWarning>), Error> {
+        let (style, pseudo) = (self.format_style, self.pseudo);
+        let mut warnings = Warnings::new(self.lenient);
+        let mut catalog = parse_catalog(contents, self, &mut warnings)?;
+        if let Some(style) = style {
+            check_formats(&mut catalog, style, &mut warnings)?;
+        }
+        Ok((pseudo_localize(catalog, pseudo), warnings.warnings))
+    }
+
+    /// Tries to parse the catalog from the given textual PO file
+    /// using the specified options.
+    pub fn parse_po<R: io::Read>(self, reader: R) -> Result<Catalog, Error> {
+        self.parse_po_with_warnings(reader)
+            .map(|(catalog, _)| catalog)
+    }
+
+    /// Tries to parse the catalog from the given textual PO file like `parse_po`,
+    /// also returning the malformed entries which were skipped in lenient mode.
+    pub fn parse_po_with_warnings<R: io::Read>(
+        self,
+        reader: R,
+    ) -> Result<(Catalog, Vec<ParseWarning>), Error> {
+        let (style, pseudo) = (self.format_style, self.pseudo);
+        let mut warnings = Warnings::new(self.lenient);
+        let mut catalog = po::parse_po_catalog(reader, self, &mut warnings)?;
+        if let Some(style) = style {
+            check_formats(&mut catalog, style, &mut warnings)?;
+        }
+        Ok((pseudo_localize(catalog, pseudo), warnings.warnings))
+    }
+
+    /// Reads the MO file from the given reader into memory
+    /// for looking up translations through its hash table.
+    /// See `MoCatalog` for the differences from `parse`.
+    ///
+    /// The format string option is not supported.
+    pub fn parse_hashed<R: io::Read>(self, reader: R) -> Result<MoCatalog, Error> {
+        let contents = self.limits.read_to_end(reader)?;
+        self.parse_bytes(contents)
+    }
+
+    /// Uses the MO file in the given buffer for looking up translations
+    /// through its hash table, without copying it.
+    /// The buffer can be borrowed, e.g. a `&[u8]` for a `CatalogRef`, or owned,
+    /// e.g. a `Vec<u8>` or a memory map such as the `Mmap` of the `memmap2` crate.
+    ///
+    /// The format string option is not supported.
+    pub fn parse_bytes<B: AsRef<[u8]>>(self, bytes: B) -> Result<MoCatalog<B>, Error> {
+        self.parse_bytes_with_warnings(bytes)
+            .map(|(catalog, _)| catalog)
+    }
+
+    /// Uses the MO file in the given buffer for looking up translations like `parse_bytes`,
+    /// also returning the entries with invalid strings which were skipped in lenient mode.
+    pub fn parse_bytes_with_warnings<B: AsRef<[u8]>>(
+        self,
+        bytes: B,
+    ) -> Result<(MoCatalog<B>, Vec<ParseWarning>), Error> {
+        let mut warnings = Warnings::new(self.lenient);
+        let catalog = MoCatalog::with_options(bytes, self, &mut warnings)?;
+        Ok((catalog, warnings.warnings))
+    }
+
+    /// Maps the MO file at the given path into memory
+    /// for looking up translations through its hash table,
+    /// so that only the pages holding the strings that are looked up are read.
+    ///
+    /// The format string option is not supported.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the catalog is in use.
+    #[cfg(all(feature = "mmap", unix))]
+    pub unsafe fn parse_mmap<P: AsRef<std::path::Path>>(
+        self,
+        path: P,
+    ) -> Result<MoCatalog<crate::Mmap>, Error> {
+        self.parse_bytes(crate::Mmap::open(path)?)
+    }
+
+    /// Starts reading the entries of the MO file from the given reader one by one.
+    /// See `MoReader` for the differences from `parse`.
+    ///
+    /// Only the encoding option is supported.
+    pub fn read_entries<R: io::Read + io::Seek>(self, reader: R) -> Result<MoReader<R>, Error> {
+        MoReader::with_options(reader, self)
+    }
+
+    /// Rejects catalogs whose translations use placeholders
+    /// that do not match the ones of their msgids, like `msgfmt -c` does,
+    /// with the `FormatMismatch` error.
+    ///
+    /// Translations of messages with a plural form may leave out placeholders,
+    /// e.g. the number in the singular form, but cannot introduce new ones.
+    /// Untranslated forms are not checked, nor are msgids that are not valid
+    /// format strings, e.g. `100%`. In catalogs that carry flags, as PO files do,
+    /// only the messages flagged as `c-format` or `rust-format` are checked.
+    ///
+    /// When parsing leniently, the messages that do not match are left out instead
+    /// and returned as warnings by `parse_with_warnings` and `parse_po_with_warnings`.
+    pub fn validate_format_strings(mut self, style: FormatStyle) -> Self {
+        self.format_style = Some(style);
+        self
+    }
+
+    /// Rejects catalogs with more than the given number of entries
+    /// with the `LimitExceeded` error, counting the metadata entry.
+    ///
+    /// Together with `max_string_len` and `max_total_size`, this bounds
+    /// the memory used for parsing files from untrusted sources.
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.limits.max_entries = Some(max);
+        self
+    }
+
+    /// Rejects catalogs with strings longer than the given number of bytes
+    /// with the `LimitExceeded` error.
+    /// Strings are measured like they are stored in MO files: the context,
+    /// msgid and plural msgid of a message together, and all its translated forms together.
+    pub fn max_string_len(mut self, max: usize) -> Self {
+        self.limits.max_string_len = Some(max);
+        self
+    }
+
+    /// Rejects files larger than the given number of bytes
+    /// with the `LimitExceeded` error, without reading them further.
+    pub fn max_total_size(mut self, max: usize) -> Self {
+        self.limits.max_total_size = Some(max);
+        self
+    }
+
+    /// Skips malformed entries, e.g. with invalid byte sequences or syntax errors,
+    /// instead of failing to parse the whole catalog.
+    /// The skipped entries are returned by `parse_with_warnings`,
+    /// `parse_po_with_warnings` and `parse_bytes_with_warnings`.
+    ///
+    /// A malformed header is skipped too, so the other entries are parsed
+    /// with the default encoding and plural formula.
+    /// Errors in the structure of MO files and exceeded limits are still fatal.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Keeps the header entry, which holds the metadata under the empty msgid,
+    /// out of lookups, so that `gettext("")` returns an empty string
+    /// instead of the whole header. This is enabled by default;
+    /// the metadata is still available through `metadata`.
+    pub fn hide_header(mut self, hide: bool) -> Self {
+        self.show_header = !hide;
+        self
+    }
+
+    /// Leaves the entries of PO files flagged as `fuzzy` out of the catalog,
+    /// like `msgfmt` does unless it is given `--use-fuzzy`.
+    /// The header entry is kept even if it is fuzzy.
+    ///
+    /// Fuzzy entries are included by default, and `Message::is_fuzzy` tells them apart.
+    /// MO files do not record the flag, so this only affects `parse_po`.
+    pub fn skip_fuzzy(mut self, skip: bool) -> Self {
+        self.skip_fuzzy = skip;
+        self
+    }
+
+    /// Unites the entries of PO files with the same context and msgid,
+    /// e.g. those of concatenated files, like `msguniq` does,
+    /// instead of keeping only the last one.
+    ///
+    /// Their comments, references and flags are merged,
+    /// and the first translated entry provides the translation.
+    /// The first header still decides how the file is decoded.
+    pub fn unite_duplicates(mut self, unite: bool) -> Self {
+        self.unite_duplicates = unite;
+        self
+    }
+
+    /// Sets the string returned by plural lookups
+    /// when the plural form for a number of objects is not translated.
+    /// This is `PluralFallback::Original` by default.
+    pub fn plural_fallback(mut self, fallback: PluralFallback) -> Self {
+        self.plural_fallback = fallback;
+        self
+    }
+
+    /// Replaces the translations of the parsed catalog
+    /// with pseudo-translations of the msgids, see `Catalog::pseudo_localized`.
+    ///
+    /// Only `parse` and `parse_po` and their variants support this option.
+    pub fn pseudo_localize(mut self, pseudo: PseudoLocalizer) -> Self {
+        self.pseudo = Some(pseudo);
+        self
     }
 
     /// Forces a use of a specific encoding
@@ -58,115 +369,247 @@ impl ParseOptions {
     /// the parser tries to use the plural formula specified in the metadata
     /// or `n != 1` if metadata is non-existent.
     pub fn force_plural(mut self, plural: fn(u64) -> usize) -> Self {
-        self.force_plural = Some(plural);
+        self.force_plural = Some(Resolver::Function(plural));
         self
     }
-}
 
-/// According to the given magic number of a MO file,
-/// returns the function which reads a `u32` in the relevant endianness.
-fn get_read_u32_fn(magic: &[u8]) -> Option<fn(&[u8]) -> u32> {
-    if magic == [0xde, 0x12, 0x04, 0x95] {
-        Some(LittleEndian::read_u32)
-    } else if magic == [0x95, 0x04, 0x12, 0xde] {
-        Some(BigEndian::read_u32)
-    } else {
-        None
+    /// Forces a use of the given closure
+    /// for deciding the proper plural form for a message,
+    /// e.g. when the catalog's `Plural-Forms` header is wrong or missing.
+    /// This works in the same way as `force_plural`,
+    /// but allows the resolver to capture its environment.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// use std::fs::File;
+    /// use gettext::ParseOptions;
+    ///
+    /// let nplurals = 3;
+    /// let file = File::open("polish.mo").unwrap();
+    /// let catalog = ParseOptions::new()
+    ///     .plural_resolver(move |n| (n as usize).min(nplurals - 1))
+    ///     .parse(file)
+    ///     .unwrap();
+    /// ```
+    pub fn plural_resolver<F>(mut self, plural: F) -> Self
+    where
+        F: Fn(u64) -> usize + Send + Sync + 'static,
+    {
+        self.force_plural = Some(Resolver::Closure(Arc::new(plural)));
+        self
     }
 }
 
-pub fn parse_catalog<R: io::Read>(mut file: R, opts: ParseOptions) -> Result<Catalog, Error> {
-    let mut contents = vec![];
-    let n = file.read_to_end(&mut contents)?;
-    if n < 28 {
-        return Err(Eof);
+/// Checks an MO file at compile time, like `MoTable::new` does:
+/// the magic number and that all the strings fit in the file,
+/// and that the strings are valid UTF-8 unless the header declares another charset.
+///
+/// Strings in other charsets and the header itself are only decoded
+/// when the catalog is parsed.
+pub const fn is_valid_mo(bytes: &[u8]) -> bool {
+    const fn read_u32(bytes: &[u8], at: usize, big_endian: bool) -> usize {
+        let b = [bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]];
+        let value = if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        };
+        value as usize
+    }
+    /// Checks that `n` strings described by the table at `offset` are in bounds.
+    const fn check_table(bytes: &[u8], offset: usize, n: usize, big_endian: bool) -> bool {
+        if offset > bytes.len() || n > (bytes.len() - offset) / 8 {
+            return false;
+        }
+        let mut i = 0;
+        while i < n {
+            let len = read_u32(bytes, offset + i * 8, big_endian);
+            let off = read_u32(bytes, offset + i * 8 + 4, big_endian);
+            // the ending NUL byte is not included in the length
+            if off >= bytes.len() || len >= bytes.len() - off {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+    /// Returns the `i`th string of the table at `offset`, which has been checked.
+    const fn string(bytes: &[u8], offset: usize, i: usize, big_endian: bool) -> &[u8] {
+        let len = read_u32(bytes, offset + i * 8, big_endian);
+        let (_, rest) = bytes.split_at(read_u32(bytes, offset + i * 8 + 4, big_endian));
+        rest.split_at(len).0
+    }
+    /// Returns whether the header declares UTF-8 or no charset at all.
+    const fn is_utf8_header(header: &[u8]) -> bool {
+        const KEY: &[u8] = b"charset=";
+        let mut at = 0;
+        while at + KEY.len() <= header.len() {
+            let mut i = 0;
+            while i < KEY.len() && header[at + i] == KEY[i] {
+                i += 1;
+            }
+            if i == KEY.len() {
+                let (_, value) = header.split_at(at + KEY.len());
+                let mut len = 0;
+                while len < value.len() && !matches!(value[len], b' ' | b';' | b'\n') {
+                    len += 1;
+                }
+                return matches!(
+                    value.split_at(len).0,
+                    [b'u' | b'U', b't' | b'T', b'f' | b'F', b'-', b'8']
+                        | [b'u' | b'U', b't' | b'T', b'f' | b'F', b'8']
+                );
+            }
+            at += 1;
+        }
+        true
     }
 
-    let read_u32 = get_read_u32_fn(&contents[0..4]).ok_or(BadMagic)?;
+    if bytes.len() < 28 {
+        return false;
+    }
+    let big_endian = match [bytes[0], bytes[1], bytes[2], bytes[3]] {
+        [0xde, 0x12, 0x04, 0x95] => false,
+        [0x95, 0x04, 0x12, 0xde] => true,
+        _ => return false,
+    };
+    let n = read_u32(bytes, 8, big_endian);
+    let (originals, translations) = (
+        read_u32(bytes, 12, big_endian),
+        read_u32(bytes, 16, big_endian),
+    );
+    if !check_table(bytes, originals, n, big_endian)
+        || !check_table(bytes, translations, n, big_endian)
+    {
+        return false;
+    }
+    // the header is the first entry, as the entries are sorted by msgid
+    if n > 0
+        && string(bytes, originals, 0, big_endian).is_empty()
+        && !is_utf8_header(string(bytes, translations, 0, big_endian))
+    {
+        return true;
+    }
+    let mut i = 0;
+    while i < n {
+        if core::str::from_utf8(string(bytes, originals, i, big_endian)).is_err()
+            || core::str::from_utf8(string(bytes, translations, i, big_endian)).is_err()
+        {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
 
-    // ignore hashing tables (bytes at 20..28)
-    let num_strings = read_u32(&contents[8..12]) as usize;
-    let mut off_otable = read_u32(&contents[12..16]) as usize;
-    let mut off_ttable = read_u32(&contents[16..20]) as usize;
-    if n < off_otable || n < off_ttable {
-        return Err(Eof);
+/// Applies the pseudo-localization option to the parsed catalog.
+fn pseudo_localize(catalog: Catalog, pseudo: Option<PseudoLocalizer>) -> Catalog {
+    match pseudo {
+        Some(pseudo) => catalog.pseudo_localized(&pseudo),
+        None => catalog,
     }
+}
+
+pub fn parse_catalog(
+    contents: &[u8],
+    opts: ParseOptions,
+    warnings: &mut Warnings,
+) -> Result<Catalog, Error> {
+    let table = MoTable::new(contents, &opts.limits)?;
 
     let mut catalog = Catalog::new();
-    if let Some(f) = opts.force_plural {
-        catalog.resolver = Resolver::Function(f);
+    catalog.plural_fallback = opts.plural_fallback;
+    catalog.show_header = opts.show_header;
+    if let Some(ref resolver) = opts.force_plural {
+        catalog.resolver = resolver.clone();
     }
     let mut encoding = opts.force_encoding.unwrap_or(utf8_encoding);
 
-    for i in 0..num_strings {
-        // Parse the original string
-        if n < off_otable + 8 {
-            return Err(Eof);
-        }
-        let len = read_u32(&contents[off_otable..off_otable + 4]) as usize;
-        let off = read_u32(&contents[off_otable + 4..off_otable + 8]) as usize;
-        // +1 compensates for the ending NUL byte which is not included in length
-        if n < off + len + 1 {
-            return Err(Eof);
-        }
-        let mut original = &contents[off..=off + len];
-        // check for context
-        let context = match original.iter().position(|x| *x == 4) {
-            Some(idx) => {
-                let ctx = &original[..idx];
-                original = &original[idx + 1..];
-                Some(encoding.decode(ctx, Strict)?)
+    for i in 0..table.len() {
+        let in_entry = |err: Error| err.in_entry(i, table.original_offset(i));
+        let message = match decode_message(table.original(i), table.translation(i), encoding) {
+            Ok(message) => message,
+            Err(err) => {
+                warnings.skip(None, in_entry(err))?;
+                continue;
             }
-            None => None,
-        };
-        // extract msg_id singular, ignoring the plural
-        let id = match original
-            .iter()
-            .position(|x| *x == 0)
-            .map(|i| &original[..i])
-        {
-            Some(b) => encoding.decode(b, Strict)?,
-            None => return Err(Eof),
         };
-        if id == "" && i != 0 {
-            return Err(MisplacedMetadata);
-        }
-
-        // Parse the translation strings
-        if n < off_ttable + 8 {
-            return Err(Eof);
-        }
-        let len = read_u32(&contents[off_ttable..off_ttable + 4]) as usize;
-        let off = read_u32(&contents[off_ttable + 4..off_ttable + 8]) as usize;
-        // +1 compensates for the ending NUL byte which is not included in length
-        if n < off + len + 1 {
-            return Err(Eof);
-        }
-        let translated = contents[off..off + len]
-            .split(|x| *x == 0)
-            .map(|b| encoding.decode(b, Strict))
-            .collect::<Result<Vec<_>, _>>()?;
-        if id == "" {
-            let map = parse_metadata(&*translated[0])?;
-            if let (Some(c), None) = (map.charset(), opts.force_encoding) {
-                encoding = encoding_from_whatwg_label(c).ok_or(UnknownEncoding)?;
-            }
-            if opts.force_plural.is_none() {
-                if let Some(p) = map.plural_forms().1 {
-                    catalog.resolver = Ast::parse(p).map(Resolver::Expr)?;
+        if message.id.is_empty() {
+            let options = match i {
+                0 => header_options(&message.translated[0], &opts),
+                _ => Err(MisplacedMetadata),
+            };
+            match options {
+                Ok((header_encoding, resolver)) => {
+                    encoding = header_encoding.unwrap_or(encoding);
+                    if let Some(resolver) = resolver {
+                        catalog.resolver = resolver;
+                    }
+                }
+                Err(err) => {
+                    warnings.skip(None, in_entry(err))?;
+                    continue;
                 }
             }
         }
 
-        catalog.insert(Message::new(id, context, translated));
-
-        off_otable += 8;
-        off_ttable += 8;
+        catalog.insert(message);
     }
 
     Ok(catalog)
 }
 
+/// Returns the encoding and the plural resolver declared in the given header,
+/// unless they are forced by the options.
+pub(crate) fn header_options(
+    header: &str,
+    opts: &ParseOptions,
+) -> Result<(Option<EncodingRef>, Option<Resolver>), Error> {
+    let map = parse_metadata(header)?;
+    let encoding = match (map.charset(), opts.force_encoding) {
+        (Some(c), None) => Some(encoding_from_whatwg_label(c).ok_or(UnknownEncoding)?),
+        _ => None,
+    };
+    let resolver = match opts.force_plural {
+        Some(_) => None,
+        None => map.plural_resolver()?,
+    };
+    Ok((encoding, resolver))
+}
+
+/// Decodes a message from its original and translated strings in an MO file.
+pub(crate) fn decode_message(
+    mut original: &[u8],
+    translation: &[u8],
+    encoding: EncodingRef,
+) -> Result<Message, Error> {
+    // check for context
+    let context = match original.iter().position(|x| *x == 4) {
+        Some(idx) => {
+            let ctx = &original[..idx];
+            original = &original[idx + 1..];
+            Some(encoding.decode(ctx, Strict)?)
+        }
+        None => None,
+    };
+    // extract msg_id singular and the optional plural that follows it
+    let (id, id_plural) = match original.iter().position(|x| *x == 0) {
+        Some(i) => (
+            encoding.decode(&original[..i], Strict)?,
+            Some(encoding.decode(&original[i + 1..], Strict)?),
+        ),
+        None => (encoding.decode(original, Strict)?, None),
+    };
+    let translated = translation
+        .split(|x| *x == 0)
+        .map(|b| encoding.decode(b, Strict))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut message = Message::new(id, context, translated);
+    message.id_plural = id_plural;
+    Ok(message)
+}
+
 /// The default plural resolver.
 ///
 /// It will be used if not `Plural-Forms` header is found in the .mo file, and if
@@ -183,25 +626,29 @@ pub fn default_resolver(n: u64) -> usize {
 }
 
 #[test]
-fn test_get_read_u32_fn() {
-    assert!(get_read_u32_fn(&[]).is_none());
-    assert!(get_read_u32_fn(&[0xde, 0x12, 0x04, 0x95, 0x00]).is_none());
-
-    {
-        let le_ptr = LittleEndian::read_u32 as *const ();
-        let ret_ptr = get_read_u32_fn(&[0xde, 0x12, 0x04, 0x95]).unwrap() as _;
-        assert_eq!(le_ptr, ret_ptr);
-    }
-
-    {
-        let be_ptr = BigEndian::read_u32 as *const ();
-        let ret_ptr = get_read_u32_fn(&[0x95, 0x04, 0x12, 0xde]).unwrap() as _;
-        assert_eq!(be_ptr, ret_ptr);
-    }
+fn test_is_valid_mo() {
+    let mo = include_bytes!("../test_cases/1.mo");
+    assert!(is_valid_mo(mo));
+    assert!(!is_valid_mo(&mo[..27]));
+    assert!(!is_valid_mo(&mo[..mo.len() - 1]));
+    assert!(!is_valid_mo(&mo[1..]));
+    assert!(!is_valid_mo(
+        b"\xde\x12\x04\x95\x00\x00\x00\x00\xff\xff\xff\xff\x1c\0\0\0\x1c\0\0\0\0\0\0\0\0\0\0\0"
+    ));
+    assert!(!is_valid_mo(include_bytes!(
+        "../test_cases/invalid_utf8.mo"
+    )));
+    assert!(is_valid_mo(include_bytes!("../test_cases/cp1257_meta.mo")));
+    assert!(is_valid_mo(include_bytes!("../test_cases/integration.mo")));
+    assert!(is_valid_mo(include_bytes!(
+        "../test_cases/integration_be.mo"
+    )));
 }
 
 #[test]
 fn test_parse_catalog() {
+    use crate::key::Key;
+
     macro_rules! assert_variant {
         ($value:expr, $variant:path) => {
             match $value {
@@ -216,52 +663,71 @@ fn test_parse_catalog() {
     {
         let mut reader = vec![1u8, 2, 3];
         reader.extend(fluff.iter().cloned());
-        let err = parse_catalog(&reader[..], ParseOptions::new()).unwrap_err();
+        let err =
+            parse_catalog(&reader[..], ParseOptions::new(), &mut Warnings::default()).unwrap_err();
         assert_variant!(err, Eof);
     }
 
     {
         let mut reader = vec![1u8, 2, 3, 4];
         reader.extend(fluff.iter().cloned());
-        let err = parse_catalog(&reader[..], ParseOptions::new()).unwrap_err();
+        let err =
+            parse_catalog(&reader[..], ParseOptions::new(), &mut Warnings::default()).unwrap_err();
         assert_variant!(err, BadMagic);
     }
 
     {
         let mut reader = vec![0x95, 0x04, 0x12, 0xde];
         reader.extend(fluff.iter().cloned());
-        assert!(parse_catalog(&reader[..], ParseOptions::new()).is_ok());
+        assert!(parse_catalog(&reader[..], ParseOptions::new(), &mut Warnings::default()).is_ok());
     }
 
     {
         let mut reader = vec![0xde, 0x12, 0x04, 0x95];
         reader.extend(fluff.iter().cloned());
-        assert!(parse_catalog(&reader[..], ParseOptions::new()).is_ok());
+        assert!(parse_catalog(&reader[..], ParseOptions::new(), &mut Warnings::default()).is_ok());
     }
 
     {
         let reader: &[u8] = include_bytes!("../test_cases/1.mo");
-        let catalog = parse_catalog(reader, ParseOptions::new()).unwrap();
+        let catalog = parse_catalog(reader, ParseOptions::new(), &mut Warnings::default()).unwrap();
         assert_eq!(catalog.strings.len(), 1);
         assert_eq!(
-            catalog.strings["this is context\x04Text"],
+            catalog.strings[&Key::from("this is context\x04Text")],
             Message::new("Text", Some("this is context"), vec!["Tekstas", "Tekstai"])
+                .with_plural("Texts")
         );
     }
 
     {
         let reader: &[u8] = include_bytes!("../test_cases/2.mo");
-        let catalog = parse_catalog(reader, ParseOptions::new()).unwrap();
+        let catalog = parse_catalog(reader, ParseOptions::new(), &mut Warnings::default()).unwrap();
         assert_eq!(catalog.strings.len(), 2);
         assert_eq!(
-            catalog.strings["Image"],
-            Message::new("Image", None, vec!["Nuotrauka", "Nuotraukos"])
+            catalog.strings[&Key::from("Image")],
+            Message::new("Image", None, vec!["Nuotrauka", "Nuotraukos"]).with_plural("Images")
         );
     }
 
     {
         let reader: &[u8] = include_bytes!("../test_cases/invalid_utf8.mo");
-        let err = parse_catalog(reader, ParseOptions::new()).unwrap_err();
-        assert_variant!(err, DecodingError);
+        let err = parse_catalog(reader, ParseOptions::new(), &mut Warnings::default()).unwrap_err();
+        match err {
+            InEntry { index, error, .. } => {
+                assert_eq!(index, 0);
+                assert_variant!(*error, DecodingError);
+            }
+            _ => panic!("Expected InEntry, got {:?}", err),
+        }
+
+        let mut warnings = Warnings::new(true);
+        let catalog = parse_catalog(reader, ParseOptions::new(), &mut warnings).unwrap();
+        assert_eq!(warnings.warnings.len(), 1);
+        assert_eq!(warnings.warnings[0].line(), None);
+        assert!(matches!(warnings.warnings[0].error(), InEntry { .. }));
+        assert_eq!(
+            catalog.strings.len() + 1,
+            MoTable::new(reader, &Limits::default()).unwrap().len()
+        );
     }
 }
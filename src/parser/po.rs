@@ -0,0 +1,824 @@
+//! Parsing of textual PO catalogs.
+
+use std::io;
+
+use encoding::types::DecoderTrap::Strict;
+use encoding::types::EncodingRef;
+
+use super::{header_options, utf8_encoding, Limits, ParseOptions, Warnings};
+use crate::key::Key;
+use crate::merge::unite;
+use crate::Error::{self, *};
+use crate::{Catalog, Flag, Message};
+
+/// A single PO entry whose strings have not been decoded yet.
+#[derive(Debug, Default)]
+struct RawEntry {
+    line: usize,
+    context: Option<Vec<u8>>,
+    id: Option<Vec<u8>>,
+    id_plural: Option<Vec<u8>>,
+    translated: Vec<Vec<u8>>,
+    comments: RawComments,
+    /// Whether the entry was commented out with `#~`.
+    obsolete: bool,
+}
+
+/// The comments preceding a PO entry, not decoded yet.
+#[derive(Debug, Default)]
+struct RawComments {
+    translator: Vec<Vec<u8>>,
+    extracted: Vec<Vec<u8>>,
+    references: Vec<Vec<u8>>,
+    flags: Vec<Vec<u8>>,
+    /// The previous msgctxt, msgid and msgid_plural of a fuzzy entry, after `#|`.
+    previous: [Option<Vec<u8>>; 3],
+}
+
+impl RawComments {
+    /// Parses a comment line starting with `#`.
+    fn line(&mut self, line: &[u8]) {
+        // the text of a comment starts after a single space
+        let text = |s: &[u8]| s.strip_prefix(b" ").unwrap_or(s).to_vec();
+        match line.get(1) {
+            None | Some(b' ') | Some(b'\t') => self.translator.push(text(&line[1..])),
+            Some(b'.') => self.extracted.push(text(&line[2..])),
+            Some(b':') => {
+                let references = line[2..].split(|b| b.is_ascii_whitespace());
+                self.references
+                    .extend(references.filter(|r| !r.is_empty()).map(<[u8]>::to_vec));
+            }
+            Some(b',') => {
+                let flags = line[2..].split(|b| *b == b',').map(trim);
+                self.flags
+                    .extend(flags.filter(|f| !f.is_empty()).map(<[u8]>::to_vec));
+            }
+            // previous strings and unknown comments
+            _ => {}
+        }
+    }
+
+    fn append(&mut self, other: RawComments) {
+        self.translator.extend(other.translator);
+        self.extracted.extend(other.extracted);
+        self.references.extend(other.references);
+        self.flags.extend(other.flags);
+        for (previous, other) in self.previous.iter_mut().zip(other.previous) {
+            if other.is_some() {
+                *previous = other;
+            }
+        }
+    }
+
+    fn is_fuzzy(&self) -> bool {
+        self.flags.iter().any(|flag| flag == b"fuzzy")
+    }
+}
+
+impl RawEntry {
+    fn is_empty(&self) -> bool {
+        self.context.is_none() && self.id.is_none()
+    }
+
+    fn is_header(&self) -> bool {
+        !self.obsolete && self.context.is_none() && matches!(self.id, Some(ref id) if id.is_empty())
+    }
+}
+
+/// The field of the current entry that continuation strings are appended to.
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Str(usize),
+}
+
+/// Parses the quoted string at the start of `src`, resolving C escape sequences.
+fn parse_quoted(src: &[u8], line: usize) -> Result<Vec<u8>, Error> {
+    let src = trim(src);
+    if src.len() < 2 || src[0] != b'"' || src[src.len() - 1] != b'"' {
+        return Err(PoSyntax(line));
+    }
+    let inner = &src[1..src.len() - 1];
+
+    let mut result = Vec::with_capacity(inner.len());
+    let mut i = 0;
+    while i < inner.len() {
+        match inner[i] {
+            b'"' => return Err(PoSyntax(line)),
+            b'\\' => {
+                i += 1;
+                let ch = *inner.get(i).ok_or(PoSyntax(line))?;
+                i += 1;
+                match ch {
+                    b'n' => result.push(b'\n'),
+                    b't' => result.push(b'\t'),
+                    b'r' => result.push(b'\r'),
+                    b'a' => result.push(0x07),
+                    b'b' => result.push(0x08),
+                    b'f' => result.push(0x0c),
+                    b'v' => result.push(0x0b),
+                    b'\\' | b'"' | b'\'' | b'?' => result.push(ch),
+                    b'0'..=b'7' => {
+                        let mut value = u32::from(ch - b'0');
+                        let mut digits = 1;
+                        while digits < 3 && i < inner.len() && (b'0'..=b'7').contains(&inner[i]) {
+                            value = value * 8 + u32::from(inner[i] - b'0');
+                            i += 1;
+                            digits += 1;
+                        }
+                        if value > 0xff {
+                            return Err(PoSyntax(line));
+                        }
+                        result.push(value as u8);
+                    }
+                    b'x' => {
+                        let start = i;
+                        let mut value = 0u32;
+                        while i < inner.len() && (inner[i] as char).is_ascii_hexdigit() {
+                            value = value * 16 + (inner[i] as char).to_digit(16).unwrap();
+                            if value > 0xff {
+                                return Err(PoSyntax(line));
+                            }
+                            i += 1;
+                        }
+                        if i == start {
+                            return Err(PoSyntax(line));
+                        }
+                        result.push(value as u8);
+                    }
+                    _ => return Err(PoSyntax(line)),
+                }
+            }
+            b => {
+                result.push(b);
+                i += 1;
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn trim(mut src: &[u8]) -> &[u8] {
+    while let Some((first, rest)) = src.split_first() {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        src = rest;
+    }
+    while let Some((last, rest)) = src.split_last() {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        src = rest;
+    }
+    src
+}
+
+/// Splits a keyword line such as `msgstr[1] "..."` into the keyword and the rest.
+fn split_keyword(line: &[u8]) -> (&[u8], &[u8]) {
+    match line
+        .iter()
+        .position(|b| b.is_ascii_whitespace() || *b == b'"')
+    {
+        Some(i) => (&line[..i], &line[i..]),
+        None => (line, &[]),
+    }
+}
+
+/// Splits the PO source into raw, undecoded entries line by line.
+struct EntryParser<'a> {
+    entries: Vec<RawEntry>,
+    current: RawEntry,
+    field: Option<Field>,
+    /// The comments seen for the entry that starts next.
+    comments: RawComments,
+    /// The previous string that `#|` continuation strings are appended to.
+    previous: Option<usize>,
+    limits: &'a Limits,
+    warnings: &'a mut Warnings,
+}
+
+impl EntryParser<'_> {
+    fn line(&mut self, line: &[u8], line_no: usize) -> Result<(), Error> {
+        let previous = self.previous.take();
+        if let Some(rest) = line
+            .strip_prefix(b"#|")
+            .or_else(|| line.strip_prefix(b"#~|"))
+        {
+            self.field = None;
+            return self.previous_line(trim(rest), line_no, previous);
+        }
+        // obsolete entries are parsed like active ones
+        if let Some(rest) = line.strip_prefix(b"#~") {
+            let rest = trim(rest);
+            if !rest.is_empty() {
+                return self.entry_line(rest, line_no, true);
+            }
+        }
+        if line[0] == b'#' {
+            // comments do not continue a string
+            self.field = None;
+            self.comments.line(line);
+            return Ok(());
+        }
+        self.entry_line(line, line_no, false)
+    }
+
+    /// Parses a line of the previous strings of the next entry, after its `#|`.
+    fn previous_line(
+        &mut self,
+        line: &[u8],
+        line_no: usize,
+        previous: Option<usize>,
+    ) -> Result<(), Error> {
+        let (keyword, rest) = split_keyword(line);
+        let (index, quoted) = match keyword {
+            b"" => (previous.ok_or(PoSyntax(line_no))?, line),
+            b"msgctxt" => (0, rest),
+            b"msgid" => (1, rest),
+            b"msgid_plural" => (2, rest),
+            _ => return Err(PoSyntax(line_no)),
+        };
+        let value = parse_quoted(quoted, line_no)?;
+        let target = &mut self.comments.previous[index];
+        if keyword.is_empty() {
+            target.get_or_insert_with(Vec::new).extend(value);
+        } else {
+            *target = Some(value);
+        }
+        self.previous = Some(index);
+        Ok(())
+    }
+
+    /// Parses a keyword or continuation line of an active or obsolete entry.
+    fn entry_line(&mut self, line: &[u8], line_no: usize, obsolete: bool) -> Result<(), Error> {
+        let current = &mut self.current;
+        let starts_entry = match split_keyword(line).0 {
+            b"msgctxt" => true,
+            b"msgid" => current.id.is_some() || current.is_empty(),
+            _ => false,
+        };
+        if !starts_entry && current.obsolete != obsolete {
+            return Err(PoSyntax(line_no));
+        }
+        if line[0] == b'"' {
+            let value = parse_quoted(line, line_no)?;
+            let target = match self.field {
+                Some(Field::Context) => current.context.as_mut(),
+                Some(Field::Id) => current.id.as_mut(),
+                Some(Field::IdPlural) => current.id_plural.as_mut(),
+                Some(Field::Str(n)) => current.translated.get_mut(n),
+                None => None,
+            };
+            target.ok_or(PoSyntax(line_no))?.extend(value);
+            return Ok(());
+        }
+
+        let (keyword, rest) = split_keyword(line);
+        let value = parse_quoted(rest, line_no)?;
+        match keyword {
+            b"msgctxt" => {
+                self.finish()?;
+                self.current = RawEntry {
+                    line: line_no,
+                    context: Some(value),
+                    comments: std::mem::take(&mut self.comments),
+                    obsolete,
+                    ..Default::default()
+                };
+                self.field = Some(Field::Context);
+            }
+            b"msgid" => {
+                if current.id.is_some() {
+                    self.finish()?;
+                }
+                let current = &mut self.current;
+                if current.context.is_none() {
+                    current.line = line_no;
+                    current.obsolete = obsolete;
+                }
+                current.comments.append(std::mem::take(&mut self.comments));
+                current.id = Some(value);
+                self.field = Some(Field::Id);
+            }
+            b"msgid_plural" => {
+                if current.id.is_none() || current.id_plural.is_some() {
+                    return Err(PoSyntax(line_no));
+                }
+                current.id_plural = Some(value);
+                self.field = Some(Field::IdPlural);
+            }
+            b"msgstr" => {
+                if current.id.is_none() || !current.translated.is_empty() {
+                    return Err(PoSyntax(line_no));
+                }
+                current.translated.push(value);
+                self.field = Some(Field::Str(0));
+            }
+            _ if keyword.starts_with(b"msgstr[") && keyword.ends_with(b"]") => {
+                let index = std::str::from_utf8(&keyword[7..keyword.len() - 1])
+                    .ok()
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .ok_or(PoSyntax(line_no))?;
+                if current.id.is_none() || index != current.translated.len() {
+                    return Err(PoSyntax(line_no));
+                }
+                current.translated.push(value);
+                self.field = Some(Field::Str(index));
+            }
+            _ => return Err(PoSyntax(line_no)),
+        }
+        Ok(())
+    }
+
+    /// Adds the current entry to the list, if there is one.
+    fn finish(&mut self) -> Result<(), Error> {
+        let entry = std::mem::take(&mut self.current);
+        self.field = None;
+        if entry.is_empty() {
+            return Ok(());
+        }
+        if entry.id.is_none() || entry.translated.is_empty() {
+            return self.warnings.skip(Some(entry.line), PoSyntax(entry.line));
+        }
+        // the strings are measured with their separators, like in an MO file
+        let separated = |s: &Option<Vec<u8>>| s.as_ref().map_or(0, |s| s.len() + 1);
+        let original =
+            separated(&entry.context) + separated(&entry.id) + separated(&entry.id_plural);
+        let translated = entry.translated.iter().map(|t| t.len() + 1).sum::<usize>();
+        self.limits.check_string_len(original - 1)?;
+        self.limits.check_string_len(translated - 1)?;
+        self.limits.check_entries(self.entries.len() + 1)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+}
+
+/// Splits the PO source into raw, undecoded entries,
+/// skipping the malformed ones in lenient mode.
+fn parse_entries(
+    contents: &[u8],
+    limits: &Limits,
+    warnings: &mut Warnings,
+) -> Result<Vec<RawEntry>, Error> {
+    let mut parser = EntryParser {
+        entries: vec![],
+        current: RawEntry::default(),
+        field: None,
+        comments: RawComments::default(),
+        previous: None,
+        limits,
+        warnings,
+    };
+    let mut skipping = false;
+
+    for (i, line) in contents.split(|b| *b == b'\n').enumerate() {
+        let line_no = i + 1;
+        let line = trim(line);
+        if line.is_empty() {
+            // entries are separated by blank lines, which ends a skipped one
+            skipping = false;
+            continue;
+        }
+        if skipping {
+            continue;
+        }
+        if let Err(err) = parser.line(line, line_no) {
+            parser.warnings.skip(Some(line_no), err)?;
+            parser.current = RawEntry::default();
+            parser.field = None;
+            parser.comments = RawComments::default();
+            skipping = true;
+        }
+    }
+
+    parser.finish()?;
+    Ok(parser.entries)
+}
+
+/// Decodes the strings of a raw entry into a message.
+fn decode_entry(entry: &RawEntry, encoding: EncodingRef) -> Result<Message, Error> {
+    let context = match entry.context {
+        Some(ref ctx) => Some(encoding.decode(ctx, Strict)?),
+        None => None,
+    };
+    let id = encoding.decode(entry.id.as_ref().unwrap(), Strict)?;
+    let translated = entry
+        .translated
+        .iter()
+        .map(|b| encoding.decode(b, Strict))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut message = Message::new(id, context, translated);
+    if let Some(ref plural) = entry.id_plural {
+        message.id_plural = Some(encoding.decode(plural, Strict)?);
+    }
+    let decode_all = |comments: &[Vec<u8>]| {
+        comments
+            .iter()
+            .map(|c| encoding.decode(c, Strict))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    message.translator_comments = decode_all(&entry.comments.translator)?;
+    message.extracted_comments = decode_all(&entry.comments.extracted)?;
+    message.references = decode_all(&entry.comments.references)?;
+    message.flags = decode_all(&entry.comments.flags)?
+        .iter()
+        .map(|flag| Flag::from(&flag[..]))
+        .collect();
+    let decode = |s: &Option<Vec<u8>>| s.as_ref().map(|s| encoding.decode(s, Strict)).transpose();
+    let [context, id, id_plural] = &entry.comments.previous;
+    message.previous_context = decode(context)?;
+    message.previous_id = decode(id)?;
+    message.previous_id_plural = decode(id_plural)?;
+    Ok(message)
+}
+
+pub fn parse_po_catalog<R: io::Read>(
+    file: R,
+    opts: ParseOptions,
+    warnings: &mut Warnings,
+) -> Result<Catalog, Error> {
+    let contents = opts.limits.read_to_end(file)?;
+    let mut entries = parse_entries(&contents, &opts.limits, warnings)?;
+
+    let mut catalog = Catalog::new();
+    catalog.plural_fallback = opts.plural_fallback;
+    catalog.show_header = opts.show_header;
+    if let Some(ref resolver) = opts.force_plural {
+        catalog.resolver = resolver.clone();
+    }
+    let mut encoding: EncodingRef = opts.force_encoding.unwrap_or(utf8_encoding);
+
+    // The header determines how all the other entries are decoded,
+    // so it has to be processed first wherever it is placed.
+    if let Some(i) = entries.iter().position(|e| e.is_header()) {
+        let header = &entries[i];
+        let options = encoding
+            .decode(&header.translated[0], Strict)
+            .map_err(Error::from)
+            .and_then(|blob| header_options(&blob, &opts));
+        match options {
+            Ok((header_encoding, resolver)) => {
+                encoding = header_encoding.unwrap_or(encoding);
+                if let Some(resolver) = resolver {
+                    catalog.resolver = resolver;
+                }
+            }
+            Err(err) => {
+                warnings.skip(Some(header.line), err)?;
+                entries.remove(i);
+            }
+        }
+    }
+
+    for entry in &entries {
+        // like msgfmt, the header is kept even if it is fuzzy,
+        // and obsolete entries are kept so that their translations are not lost
+        if opts.skip_fuzzy && entry.comments.is_fuzzy() && !entry.is_header() && !entry.obsolete {
+            continue;
+        }
+        match decode_entry(entry, encoding) {
+            Ok(message) => {
+                let messages = if entry.obsolete {
+                    &mut catalog.obsolete
+                } else {
+                    &mut catalog.strings
+                };
+                let key = Key::new(message.context.as_deref(), &message.id);
+                match messages.get_mut(&key) {
+                    Some(known) if opts.unite_duplicates => {
+                        unite(known, message);
+                    }
+                    _ => {
+                        messages.insert(key, message);
+                    }
+                }
+            }
+            Err(err) => warnings.skip(Some(entry.line), err)?,
+        }
+    }
+
+    Ok(catalog)
+}
+
+#[test]
+fn test_parse_quoted() {
+    assert_eq!(parse_quoted(b"\"\"", 1).unwrap(), b"");
+    assert_eq!(parse_quoted(b" \"abc\" ", 1).unwrap(), b"abc");
+    assert_eq!(
+        parse_quoted(br#""a\n\t\"\\b""#, 1).unwrap(),
+        b"a\n\t\"\\b".to_vec()
+    );
+    assert_eq!(parse_quoted(br#""\101\x42\0""#, 1).unwrap(), b"AB\0");
+    assert!(parse_quoted(b"abc", 1).is_err());
+    assert!(parse_quoted(b"\"abc", 1).is_err());
+    assert!(parse_quoted(br#""a"b""#, 1).is_err());
+    assert!(parse_quoted(br#""\q""#, 1).is_err());
+    assert!(parse_quoted(br#""\x""#, 1).is_err());
+}
+
+#[test]
+fn test_parse_po_catalog() {
+    use crate::key::Key;
+
+    macro_rules! assert_syntax_error {
+        ($src:expr, $line:expr) => {
+            match parse_po_catalog(&$src[..], ParseOptions::new(), &mut Warnings::default()) {
+                Err(PoSyntax(line)) => assert_eq!(line, $line),
+                other => panic!("Expected PoSyntax({}), got {:?}", $line, other),
+            }
+        };
+    }
+
+    {
+        let src = b"# comment\nmsgctxt \"ctx\"\nmsgid \"\"\n\"Te\"\n\"xt\"\nmsgid_plural \"Texts\"\nmsgstr[0] \"Tekstas\"\nmsgstr[1] \"Teks\"\r\n\"tai\"\n\nmsgid \"Line\"\nmsgstr \"Eilute\\n\"\n";
+        let catalog =
+            parse_po_catalog(&src[..], ParseOptions::new(), &mut Warnings::default()).unwrap();
+        assert_eq!(catalog.strings.len(), 2);
+        let mut text =
+            Message::new("Text", Some("ctx"), vec!["Tekstas", "Tekstai"]).with_plural("Texts");
+        text.translator_comments = vec!["comment".to_owned()];
+        assert_eq!(catalog.strings[&Key::from("ctx\x04Text")], text);
+        assert_eq!(
+            catalog.strings[&Key::from("Line")],
+            Message::new("Line", None, vec!["Eilute\n"])
+        );
+    }
+
+    assert_syntax_error!(b"msgid \"a\"\n", 1);
+    assert_syntax_error!(b"msgid \"a\"\nmsgstr \"b\"\nmsgstr \"c\"\n", 3);
+    assert_syntax_error!(b"msgid \"a\"\nmsgstr[1] \"b\"\n", 2);
+    assert_syntax_error!(b"\"dangling\"\n", 1);
+    assert_syntax_error!(b"msgid \"a\"\nmsgstr \"b\"\nmsgfoo \"c\"\n", 3);
+    assert_syntax_error!(
+        b"msgctxt \"c\"\nmsgctxt \"d\"\nmsgid \"a\"\nmsgstr \"\"\n",
+        1
+    );
+
+    {
+        let src = b"msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=windows-1257\\n\"\n\nmsgid \"Garlic\"\nmsgstr \"\xc8esnakas\"\n";
+        let catalog =
+            parse_po_catalog(&src[..], ParseOptions::new(), &mut Warnings::default()).unwrap();
+        assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+    }
+
+    {
+        let src = b"msgid \"\"\nmsgstr \"Language: pl\\n\"\n\nmsgid \"File\"\nmsgid_plural \"Files\"\nmsgstr[0] \"Plik\"\nmsgstr[1] \"Pliki\"\nmsgstr[2] \"Plikow\"\n";
+        let catalog =
+            parse_po_catalog(&src[..], ParseOptions::new(), &mut Warnings::default()).unwrap();
+        assert_eq!(catalog.ngettext("File", "Files", 22), "Pliki");
+        assert_eq!(catalog.ngettext("File", "Files", 25), "Plikow");
+    }
+
+    {
+        let src = b"msgid \"Garlic\"\nmsgstr \"\xc8esnakas\"\n";
+        let err =
+            parse_po_catalog(&src[..], ParseOptions::new(), &mut Warnings::default()).unwrap_err();
+        match err {
+            DecodingError => (),
+            _ => panic!("Expected DecodingError, got {:?}", err),
+        }
+    }
+
+    {
+        let src = b"msgid \"a\"\nmsgstr \"b\"\nmsgfoo \"c\"\n\nmsgid \"Garlic\"\nmsgstr \"\xc8esnakas\"\n\nmsgid \"d\"\n\nmsgid \"Line\"\nmsgstr \"Eilute\"\n";
+        let mut warnings = Warnings::new(true);
+        let catalog = parse_po_catalog(&src[..], ParseOptions::new(), &mut warnings).unwrap();
+        assert_eq!(catalog.strings.len(), 1);
+        assert_eq!(catalog.gettext("Line"), "Eilute");
+        let lines = warnings
+            .warnings
+            .iter()
+            .map(|w| w.line())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [Some(3), Some(8), Some(5)]);
+        assert!(matches!(warnings.warnings[2].error(), DecodingError));
+    }
+}
+
+#[test]
+fn test_parse_po_fuzzy() {
+    let src = b"#, fuzzy\nmsgid \"\"\nmsgstr \"Language: lt\\n\"\n\n#: src/main.rs:1\n#, fuzzy, c-format\nmsgid \"Open\"\nmsgstr \"Atidaryti\"\n\n#, rust-format\nmsgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"Atverti\"\n\n#,fuzzy\nmsgctxt \"menu\"\nmsgid \"Close\"\nmsgstr \"Uzdaryti\"\nmsgid \"Quit\"\nmsgstr \"Baigti\"\n";
+    let catalog =
+        parse_po_catalog(&src[..], ParseOptions::new(), &mut Warnings::default()).unwrap();
+    assert_eq!(catalog.strings.len(), 5);
+    assert!(catalog.message(None, "").unwrap().is_fuzzy());
+    assert!(catalog.get("Open").unwrap().is_fuzzy());
+    assert_eq!(catalog.gettext("Open"), "Atidaryti");
+    assert!(!catalog.get_with_context("menu", "Open").unwrap().is_fuzzy());
+    assert!(catalog
+        .get_with_context("menu", "Close")
+        .unwrap()
+        .is_fuzzy());
+    assert!(!catalog.get("Quit").unwrap().is_fuzzy());
+
+    let opts = ParseOptions::new().skip_fuzzy(true);
+    let catalog = parse_po_catalog(&src[..], opts, &mut Warnings::default()).unwrap();
+    assert_eq!(catalog.strings.len(), 3);
+    assert_eq!(catalog.metadata().language(), Some("lt"));
+    assert_eq!(catalog.gettext("Open"), "Open");
+    assert_eq!(catalog.pgettext("menu", "Open"), "Atverti");
+    assert_eq!(catalog.pgettext("menu", "Close"), "Close");
+    assert_eq!(catalog.gettext("Quit"), "Baigti");
+}
+
+#[test]
+fn test_parse_po_flags() {
+    let src = "#, c-format, no-wrap\n#, range: 0..10,python-format\nmsgid \"%d files\"\nmsgstr \"%d failai\"\n\n#,\nmsgid \"Quit\"\nmsgstr \"Baigti\"\n";
+    let catalog = parse_po_catalog(
+        src.as_bytes(),
+        ParseOptions::new(),
+        &mut Warnings::default(),
+    )
+    .unwrap();
+    let files = catalog.get("%d files").unwrap();
+    assert!(files.has_flag(Flag::CFormat));
+    assert!(files.has_flag(Flag::NoWrap));
+    assert!(files.has_flag(Flag::Other("range: 0..10".to_owned())));
+    assert!(!files.has_flag(Flag::Fuzzy));
+    assert_eq!(
+        files.flags().iter().map(Flag::as_str).collect::<Vec<_>>(),
+        ["c-format", "no-wrap", "python-format", "range: 0..10"]
+    );
+    assert!(catalog.get("Quit").unwrap().flags().is_empty());
+
+    let mut po = vec![];
+    catalog.write_po(&mut po).unwrap();
+    let po = String::from_utf8(po).unwrap();
+    assert!(po.contains("\n#, c-format, no-wrap, python-format, range: 0..10\nmsgid \"%d files\""));
+    assert!(po.contains("\n\nmsgid \"Quit\""));
+    let parsed = Catalog::parse_po(po.as_bytes()).unwrap();
+    assert_eq!(parsed.get("%d files"), Some(files));
+}
+
+#[test]
+fn test_parse_po_previous() {
+    let src = "#, fuzzy\n#| msgctxt \"menu\"\n#| msgid \"\"\n#| \"Open \"\n#| \"file\"\n#| msgid_plural \"Open files\"\nmsgid \"Open a file\"\nmsgid_plural \"Open files\"\nmsgstr[0] \"Atidaryti failą\"\nmsgstr[1] \"Atidaryti failus\"\n\n#, fuzzy\n#~| msgid \"Exit\"\n#~ msgid \"Quit\"\n#~ msgstr \"Išeiti\"\n\nmsgid \"Save\"\nmsgstr \"Saugoti\"\n";
+    let catalog = parse_po_catalog(
+        src.as_bytes(),
+        ParseOptions::new(),
+        &mut Warnings::default(),
+    )
+    .unwrap();
+    let open = catalog.get("Open a file").unwrap();
+    assert_eq!(open.previous_context(), Some("menu"));
+    assert_eq!(open.previous_id(), Some("Open file"));
+    assert_eq!(open.previous_id_plural(), Some("Open files"));
+    let quit = &catalog.obsolete[&Key::from("Quit")];
+    assert_eq!(quit.previous_id(), Some("Exit"));
+    assert_eq!(quit.previous_context(), None);
+    assert_eq!(catalog.get("Save").unwrap().previous_id(), None);
+
+    let mut po = vec![];
+    catalog.write_po(&mut po).unwrap();
+    let po = String::from_utf8(po).unwrap();
+    assert!(po.contains("#, fuzzy\n#| msgctxt \"menu\"\n#| msgid \"Open file\"\n#| msgid_plural \"Open files\"\nmsgid \"Open a file\"\n"));
+    assert!(po.contains("\n#~| msgid \"Exit\"\n#~ msgid \"Quit\"\n"));
+    let parsed = Catalog::parse_po(po.as_bytes()).unwrap();
+    assert_eq!(parsed.get("Open a file"), Some(open));
+    assert_eq!(parsed.obsolete, catalog.obsolete);
+
+    for bad in &["#| msgstr \"Open\"\n", "#| \"Open\"\n", "#| msgid Open\n"] {
+        let src = format!("{}msgid \"Open\"\nmsgstr \"\"\n", bad);
+        let result = parse_po_catalog(
+            src.as_bytes(),
+            ParseOptions::new(),
+            &mut Warnings::default(),
+        );
+        assert!(matches!(result, Err(PoSyntax(1))), "{}", bad);
+    }
+}
+
+#[test]
+fn test_parse_po_unite_duplicates() {
+    let src = "msgid \"\"\nmsgstr \"Language: lt\\n\"\n\n#: a.rs:1\nmsgid \"Open\"\nmsgstr \"\"\n\n# first\nmsgid \"Save\"\nmsgstr \"Saugoti\"\n\nmsgid \"\"\nmsgstr \"Language: pl\\n\"\n\n#: b.rs:2\n#, fuzzy\nmsgid \"Open\"\nmsgstr \"Atidaryti\"\n\n# second\nmsgid \"Save\"\nmsgstr \"Zapisz\"\n";
+    let catalog = parse_po_catalog(
+        src.as_bytes(),
+        ParseOptions::new(),
+        &mut Warnings::default(),
+    )
+    .unwrap();
+    assert_eq!(catalog.gettext("Save"), "Zapisz");
+    assert_eq!(catalog.get("Open").unwrap().references(), ["b.rs:2"]);
+
+    let opts = ParseOptions::new().unite_duplicates(true);
+    let catalog = parse_po_catalog(src.as_bytes(), opts, &mut Warnings::default()).unwrap();
+    assert_eq!(catalog.len(), 3);
+    assert_eq!(catalog.metadata().language(), Some("lt"));
+    assert_eq!(catalog.gettext("Save"), "Saugoti");
+    assert_eq!(
+        catalog.get("Save").unwrap().translator_comments(),
+        ["first", "second"]
+    );
+    let open = catalog.get("Open").unwrap();
+    assert_eq!(open.translated(), ["Atidaryti"]);
+    assert_eq!(open.references(), ["a.rs:1", "b.rs:2"]);
+    assert!(open.is_fuzzy());
+}
+
+#[test]
+fn test_parse_po_non_ascii_plural_forms() {
+    let src = "msgid \"\"\nmsgstr \"Plural-Forms: é=2; plural=n != 1;\\n\"\n\nmsgid \"File\"\nmsgid_plural \"Files\"\nmsgstr[0] \"Failas\"\nmsgstr[1] \"Failai\"\n";
+    let catalog = parse_po_catalog(
+        src.as_bytes(),
+        ParseOptions::new(),
+        &mut Warnings::default(),
+    )
+    .unwrap();
+    assert_eq!(catalog.ngettext("File", "Files", 2), "Failai");
+}
+
+#[test]
+fn test_parse_po_obsolete() {
+    let src = b"msgid \"Open\"\nmsgstr \"Atidaryti\"\n#~ msgid \"Quit\"\n#~ msgstr \"\"\n#~ \"Baigti\"\n\n#, fuzzy\n#~| msgid \"Old\"\n#~ msgctxt \"menu\"\n#~ msgid \"Close\"\n#~ msgstr \"Uzdaryti\"\n\n#~ msgid \"\"\n#~ msgstr \"Language: pl\\n\"\nmsgid \"Save\"\nmsgstr \"Saugoti\"\n";
+    let opts = ParseOptions::new().skip_fuzzy(true);
+    let catalog = parse_po_catalog(&src[..], opts, &mut Warnings::default()).unwrap();
+    assert_eq!(catalog.strings.len(), 2);
+    assert_eq!(catalog.gettext("Open"), "Atidaryti");
+    assert_eq!(catalog.gettext("Save"), "Saugoti");
+    assert_eq!(catalog.gettext("Quit"), "Quit");
+    assert!(catalog.metadata().is_empty());
+    let mut obsolete = catalog.obsolete().collect::<Vec<_>>();
+    obsolete.sort_by_key(|msg| msg.id());
+    assert_eq!(obsolete.len(), 3);
+    assert_eq!(obsolete[0].translated(), &["Language: pl\n"]);
+    assert_eq!(obsolete[1].context(), Some("menu"));
+    assert!(obsolete[1].is_fuzzy());
+    assert_eq!(obsolete[2].translated(), &["Baigti"]);
+
+    // the translations come back when the strings do
+    let template = crate::CatalogBuilder::new()
+        .add("Quit", "")
+        .build()
+        .unwrap();
+    assert_eq!(catalog.update(&template).gettext("Quit"), "Baigti");
+    let mut po = vec![];
+    catalog.write_po(&mut po).unwrap();
+    let parsed = Catalog::parse_po(&po[..]).unwrap();
+    // but an obsolete header is not written back
+    assert_eq!(parsed.obsolete.len(), 2);
+    assert!(parsed.obsolete.values().all(|msg| obsolete.contains(&msg)));
+
+    // an entry cannot be partly obsolete
+    for src in [
+        &b"msgid \"a\"\n#~ msgstr \"b\"\n"[..],
+        b"#~ msgid \"a\"\nmsgstr \"b\"\n",
+        b"#~ msgctxt \"c\"\nmsgid \"a\"\nmsgstr \"b\"\n",
+        b"msgid \"a\"\nmsgstr \"b\"\n#~ \"c\"\n",
+    ] {
+        let result = parse_po_catalog(src, ParseOptions::new(), &mut Warnings::default());
+        assert!(
+            matches!(result, Err(PoSyntax(2)) | Err(PoSyntax(3))),
+            "{:?}",
+            result
+        );
+    }
+}
+
+#[test]
+fn test_parse_po_comments() {
+    let src = "# Lithuanian translation.\n#\nmsgid \"\"\nmsgstr \"Language: lt\\n\"\n\n# checked by Ona\n#  indented\n#. TRANSLATORS: a verb\n#: src/main.rs:10\n#| msgid \"Opem\"\nmsgctxt \"menu\"\n#. in a menu\nmsgid \"Open\"\nmsgstr \"Atidaryti\"\n\n#~ msgid \"Quit\"\n#~ msgstr \"Baigti\"\n\nmsgid \"Close\"\nmsgstr \"Uždaryti\"\n";
+    let catalog = parse_po_catalog(
+        src.as_bytes(),
+        ParseOptions::new(),
+        &mut Warnings::default(),
+    )
+    .unwrap();
+    let header = catalog.message(None, "").unwrap();
+    assert_eq!(
+        header.translator_comments(),
+        ["Lithuanian translation.", ""]
+    );
+    let open = catalog.get_with_context("menu", "Open").unwrap();
+    assert_eq!(open.translator_comments(), ["checked by Ona", " indented"]);
+    assert_eq!(
+        open.extracted_comments(),
+        ["TRANSLATORS: a verb", "in a menu"]
+    );
+    assert_eq!(open.references(), ["src/main.rs:10"]);
+    let close = catalog.get("Close").unwrap();
+    assert!(close.translator_comments().is_empty());
+    assert!(close.references().is_empty());
+    assert!(close.extracted_comments().is_empty());
+
+    let mut po = vec![];
+    catalog.write_po(&mut po).unwrap();
+    let po = String::from_utf8(po).unwrap();
+    assert!(po.starts_with("# Lithuanian translation.\n#\nmsgid \"\"\n"));
+    assert!(po.contains(
+        "\n# checked by Ona\n#  indented\n#. TRANSLATORS: a verb\n#. in a menu\n#: src/main.rs:10\n#| msgid \"Opem\"\nmsgctxt"
+    ));
+    let parsed = Catalog::parse_po(po.as_bytes()).unwrap();
+    assert_eq!(parsed.strings, catalog.strings);
+
+    // comments are in the encoding of the file
+    let src = b"msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=windows-1257\\n\"\n\n# \xc8esnakas\nmsgid \"Garlic\"\nmsgstr \"\"\n";
+    let catalog =
+        parse_po_catalog(&src[..], ParseOptions::new(), &mut Warnings::default()).unwrap();
+    assert_eq!(
+        catalog.get("Garlic").unwrap().translator_comments(),
+        ["Česnakas"]
+    );
+}
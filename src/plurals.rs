@@ -1,54 +1,122 @@
-use crate::Error;
+//! Parsing and evaluation of plural form expressions,
+//! as found in the `Plural-Forms` header of catalogs.
+//!
+//! # Examples
+//!
+//! ```
+//! use gettext::plurals::PluralForms;
+//!
+//! let forms = PluralForms::parse("nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;").unwrap();
+//! assert_eq!(forms.nplurals(), Some(3));
+//! assert_eq!(forms.evaluate(2), 1);
+//! assert_eq!(forms.evaluate(5), 2);
+//! assert_eq!(forms.to_string(), "nplurals=3; plural=n == 1 ? 0 : n == 2 ? 1 : 2;");
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::parser::default_resolver;
+use crate::{untranslated, Error};
 
 use self::Resolver::*;
 
-#[derive(Clone, Debug)]
-pub enum Resolver {
-    /// A boolean expression
-    /// Use Ast::parse to get an Ast
-    Expr(Ast),
+mod cldr;
+mod compiled;
+mod languages;
+
+pub use self::cldr::{PluralCategory, PluralOperands};
+pub(crate) use self::compiled::Compiled;
+
+/// Resolver decides the plural form used for a number of objects.
+#[derive(Clone)]
+pub(crate) enum Resolver {
+    /// A boolean expression, compiled from the Ast that Ast::parse gives
+    Expr(Compiled),
     /// A function
     Function(fn(u64) -> usize),
+    /// A closure, shareable between threads
+    Closure(Arc<dyn Fn(u64) -> usize + Send + Sync>),
 }
 
-/// Finds the index of a pattern, outside of parenthesis
-fn index_of(src: &str, pat: &str) -> Option<usize> {
-    src.chars()
-        .fold(
-            (None, 0, 0, 0),
-            |(match_index, i, n_matches, paren_level), ch| {
-                if let Some(x) = match_index {
-                    (Some(x), i, n_matches, paren_level)
-                } else {
-                    let new_par_lvl = match ch {
-                        '(' => paren_level + 1,
-                        ')' => paren_level - 1,
-                        _ => paren_level,
-                    };
-
-                    if Some(ch) == pat.chars().nth(n_matches) {
-                        let length = n_matches + 1;
-                        if length == pat.len() && new_par_lvl == 0 {
-                            (Some(i - n_matches), i + 1, length, new_par_lvl)
-                        } else {
-                            (match_index, i + 1, length, new_par_lvl)
-                        }
-                    } else {
-                        (match_index, i + 1, 0, new_par_lvl)
-                    }
+impl fmt::Debug for Resolver {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expr(ref expr) => fmt.debug_tuple("Expr").field(expr).finish(),
+            Function(ref f) => fmt.debug_tuple("Function").field(f).finish(),
+            Closure(_) => fmt.debug_tuple("Closure").finish(),
+        }
+    }
+}
+
+/// Splits the value of a `Plural-Forms` header
+/// into the number of plural forms and the plural expression.
+pub(crate) fn split_plural_forms(src: &str) -> (Option<usize>, Option<&str>) {
+    src.split(';').fold((None, None), |(n_pl, pl), prop| {
+        match prop.split_once('=') {
+            Some((name, value)) => {
+                let value = value.trim();
+                match name.trim() {
+                    "n_plurals" | "nplurals" => (value.parse().ok(), pl),
+                    "plural" => (n_pl, Some(value)),
+                    _ => (n_pl, pl),
                 }
-            },
-        )
-        .0
+            }
+            None => (n_pl, pl),
+        }
+    })
+}
+
+/// PluralForms represents a parsed `Plural-Forms` header:
+/// the number of plural forms, if specified,
+/// and the expression that selects a plural form for a number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PluralForms {
+    nplurals: Option<usize>,
+    expr: Ast,
+}
+
+impl PluralForms {
+    /// Parses the value of a `Plural-Forms` header,
+    /// e.g. `nplurals=2; plural=n != 1;`.
+    ///
+    /// Returns the `Err` variant if the `plural` expression
+    /// is missing or invalid.
+    pub fn parse(src: &str) -> Result<Self, Error> {
+        match split_plural_forms(src) {
+            (nplurals, Some(expr)) => Ok(PluralForms {
+                nplurals,
+                expr: Ast::parse(expr)?,
+            }),
+            (_, None) => Err(Error::PluralParsing),
+        }
+    }
+
+    /// Returns the number of plural forms, as given by `nplurals`.
+    pub fn nplurals(&self) -> Option<usize> {
+        self.nplurals
+    }
+
+    /// Returns the parsed plural expression.
+    pub fn expr(&self) -> &Ast {
+        &self.expr
+    }
+
+    /// Returns the index of the plural form to use for `n` objects.
+    pub fn evaluate(&self, n: u64) -> usize {
+        self.expr.evaluate(n)
+    }
 }
 
 use self::Ast::*;
+/// Ast is the parsed form of a plural expression.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Ast {
     /// A ternary expression
     /// x ? a : b
     ///
-    /// the three Ast<'a> are respectively x, a and b.
+    /// the three Ast are respectively x, a and b.
     Ternary(Box<Ast>, Box<Ast>, Box<Ast>),
     /// The n variable.
     N,
@@ -60,262 +128,742 @@ pub enum Ast {
     Not(Box<Ast>),
 }
 
+/// Operator is a binary operator of a plural expression.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Operator {
+    /// `==`
     Equal,
+    /// `!=`
     NotEqual,
+    /// `>=`
     GreaterOrEqual,
+    /// `<=`
     SmallerOrEqual,
+    /// `>`
     Greater,
+    /// `<`
     Smaller,
+    /// `&&`
     And,
+    /// `||`
     Or,
+    /// `%`
     Modulo,
 }
 
+/// The longest plural expression that is parsed, in bytes.
+const MAX_LENGTH: usize = 1024;
+
+/// The deepest nesting of plural expressions that is parsed.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+/// Returns the value of an expression as a plural form, saturating.
+pub(crate) fn form(value: u64) -> usize {
+    usize::try_from(value).unwrap_or(usize::MAX)
+}
+
 impl Ast {
-    fn resolve(&self, n: u64) -> usize {
+    /// Evaluates the expression for `n` objects.
+    ///
+    /// Evaluation never panics, as expressions may come from untrusted catalogs:
+    /// `x % 0` is 0, and values that do not fit a `usize` saturate.
+    pub fn evaluate(&self, n: u64) -> usize {
+        form(self.value(n))
+    }
+
+    /// Returns the value of the expression for `n` objects.
+    pub(crate) fn value(&self, n: u64) -> u64 {
         match *self {
             Ternary(ref cond, ref ok, ref nok) => {
-                if cond.resolve(n) == 0 {
-                    nok.resolve(n)
+                if cond.value(n) == 0 {
+                    nok.value(n)
                 } else {
-                    ok.resolve(n)
+                    ok.value(n)
                 }
             }
-            N => n as usize,
-            Integer(x) => x as usize,
+            N => n,
+            Integer(x) => x,
             Op(ref op, ref lhs, ref rhs) => match *op {
-                Operator::Equal => (lhs.resolve(n) == rhs.resolve(n)) as usize,
-                Operator::NotEqual => (lhs.resolve(n) != rhs.resolve(n)) as usize,
-                Operator::GreaterOrEqual => (lhs.resolve(n) >= rhs.resolve(n)) as usize,
-                Operator::SmallerOrEqual => (lhs.resolve(n) <= rhs.resolve(n)) as usize,
-                Operator::Greater => (lhs.resolve(n) > rhs.resolve(n)) as usize,
-                Operator::Smaller => (lhs.resolve(n) < rhs.resolve(n)) as usize,
-                Operator::And => (lhs.resolve(n) != 0 && rhs.resolve(n) != 0) as usize,
-                Operator::Or => (lhs.resolve(n) != 0 || rhs.resolve(n) != 0) as usize,
-                Operator::Modulo => lhs.resolve(n) % rhs.resolve(n),
-            },
-            Not(ref val) => match val.resolve(n) {
-                0 => 1,
-                _ => 0,
+                Operator::Equal => (lhs.value(n) == rhs.value(n)) as u64,
+                Operator::NotEqual => (lhs.value(n) != rhs.value(n)) as u64,
+                Operator::GreaterOrEqual => (lhs.value(n) >= rhs.value(n)) as u64,
+                Operator::SmallerOrEqual => (lhs.value(n) <= rhs.value(n)) as u64,
+                Operator::Greater => (lhs.value(n) > rhs.value(n)) as u64,
+                Operator::Smaller => (lhs.value(n) < rhs.value(n)) as u64,
+                Operator::And => (lhs.value(n) != 0 && rhs.value(n) != 0) as u64,
+                Operator::Or => (lhs.value(n) != 0 || rhs.value(n) != 0) as u64,
+                Operator::Modulo => lhs.value(n).checked_rem(rhs.value(n)).unwrap_or(0),
             },
+            Not(ref val) => (val.value(n) == 0) as u64,
+        }
+    }
+
+    /// Returns the depth of the expression, which is 1 for `n` or an integer.
+    fn depth(&self) -> usize {
+        match *self {
+            Ternary(ref cond, ref ok, ref nok) => 1 + cond.depth().max(ok.depth()).max(nok.depth()),
+            N | Integer(_) => 1,
+            Op(_, ref lhs, ref rhs) => 1 + lhs.depth().max(rhs.depth()),
+            Not(ref val) => 1 + val.depth(),
         }
     }
 
+    /// Parses a plural expression written in C syntax, e.g. `n != 1`.
+    ///
+    /// Operators have the same precedence and associativity as in C.
+    /// Returns `PluralParsing` for expressions longer than 1024 bytes
+    /// or nested more than 64 levels deep, which no language needs.
     pub fn parse(src: &str) -> Result<Ast, Error> {
-        Self::parse_parens(src.trim())
-    }
-
-    fn parse_parens(src: &str) -> Result<Ast, Error> {
-        if src.starts_with('(') {
-            let end = src[1..src.len() - 1]
-                .chars()
-                .fold((1, 2), |(level, index), ch| match (level, ch) {
-                    (0, '(') => (level + 1, index + 1),
-                    (0, _) => (level, index),
-                    (_, '(') => (level + 1, index + 1),
-                    (_, ')') => (level - 1, index + 1),
-                    (_, _) => (level, index + 1),
-                })
-                .1;
-            if end == src.len() {
-                Ast::parse(src[1..src.len() - 1].trim())
-            } else {
-                Ast::parse_and(src.trim())
-            }
-        } else {
-            Ast::parse_and(src.trim())
+        if src.len() > MAX_LENGTH {
+            return Err(Error::PluralParsing);
         }
+        let mut parser = Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+            depth: 0,
+        };
+        let ast = parser.parse_ternary()?;
+        if parser.pos != parser.tokens.len() || ast.depth() > MAX_DEPTH {
+            return Err(Error::PluralParsing);
+        }
+        Ok(ast)
     }
 
-    fn parse_and(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "&&") {
-            Ok(Ast::Op(
-                Operator::And,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 2..])?),
-            ))
-        } else {
-            Self::parse_or(src)
+    /// Returns a simpler expression that selects the same plural forms,
+    /// e.g. `n != 1` for `n == 1 ? 0 : 1`.
+    ///
+    /// Constant subexpressions are folded, ternary expressions that choose
+    /// between the values of a condition are replaced by the condition,
+    /// negated comparisons are replaced by the opposite ones,
+    /// and integers are moved to the right of comparisons,
+    /// so that formulas written in different ways often simplify to the same expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::plurals::Ast;
+    ///
+    /// let simplified = Ast::parse("n == 1 ? 0 : 1").unwrap().simplify();
+    /// assert_eq!(simplified, Ast::parse("n != 1").unwrap());
+    /// let simplified = Ast::parse("!(1 < n)").unwrap().simplify();
+    /// assert_eq!(simplified, Ast::parse("n <= 1").unwrap());
+    /// ```
+    pub fn simplify(&self) -> Ast {
+        match *self {
+            Ternary(ref cond, ref ok, ref nok) => {
+                match (cond.simplify(), ok.simplify(), nok.simplify()) {
+                    (Integer(0), _, nok) => nok,
+                    (Integer(_), ok, _) => ok,
+                    (_, ok, nok) if ok == nok => ok,
+                    (cond, Integer(1), Integer(0)) if cond.is_boolean() => cond,
+                    (cond, Integer(0), Integer(1)) if cond.is_boolean() => {
+                        Not(Box::new(cond)).simplify()
+                    }
+                    (cond, ok, nok) => Ternary(Box::new(cond), Box::new(ok), Box::new(nok)),
+                }
+            }
+            N | Integer(_) => self.clone(),
+            Op(ref op, ref lhs, ref rhs) => match (op, lhs.simplify(), rhs.simplify()) {
+                (op, Integer(a), Integer(b)) => {
+                    Integer(Op(op.clone(), Box::new(Integer(a)), Box::new(Integer(b))).value(0))
+                }
+                (&Operator::And, Integer(0), _) | (&Operator::And, _, Integer(0)) => Integer(0),
+                (&Operator::Or, Integer(x), _) | (&Operator::Or, _, Integer(x)) if x != 0 => {
+                    Integer(1)
+                }
+                (&Operator::And, Integer(_), other)
+                | (&Operator::And, other, Integer(_))
+                | (&Operator::Or, Integer(_), other)
+                | (&Operator::Or, other, Integer(_)) => other.truth(),
+                (op, Integer(a), rhs) if op.swapped().is_some() => {
+                    Op(op.swapped().unwrap(), Box::new(rhs), Box::new(Integer(a)))
+                }
+                (op, lhs, rhs) => Op(op.clone(), Box::new(lhs), Box::new(rhs)),
+            },
+            Not(ref val) => match val.simplify() {
+                Integer(x) => Integer((x == 0) as u64),
+                Not(val) if val.is_boolean() => *val,
+                Op(op, lhs, rhs) if op.negated().is_some() => Op(op.negated().unwrap(), lhs, rhs),
+                val => Not(Box::new(val)),
+            },
         }
     }
 
-    fn parse_or(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "||") {
-            Ok(Ast::Op(
-                Operator::Or,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 2..])?),
-            ))
-        } else {
-            Self::parse_ternary(src)
+    /// Returns whether the expression is always 0 or 1.
+    fn is_boolean(&self) -> bool {
+        match *self {
+            Integer(x) => x <= 1,
+            Op(ref op, _, _) => *op != Operator::Modulo,
+            Not(_) => true,
+            Ternary(_, ref ok, ref nok) => ok.is_boolean() && nok.is_boolean(),
+            N => false,
         }
     }
 
-    fn parse_ternary(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "?") {
-            if let Some(l) = index_of(src, ":") {
-                Ok(Ast::Ternary(
-                    Box::new(Ast::parse(&src[0..i])?),
-                    Box::new(Ast::parse(&src[i + 1..l])?),
-                    Box::new(Ast::parse(&src[l + 1..])?),
-                ))
-            } else {
-                Err(Error::PluralParsing)
-            }
+    /// Returns the expression as a condition, which is 1 if the expression is not 0.
+    fn truth(self) -> Ast {
+        if self.is_boolean() {
+            self
         } else {
-            Self::parse_ge(src)
+            Op(Operator::NotEqual, Box::new(self), Box::new(Integer(0)))
         }
     }
+}
 
-    fn parse_ge(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, ">=") {
-            Ok(Ast::Op(
-                Operator::GreaterOrEqual,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 2..])?),
-            ))
-        } else {
-            Self::parse_gt(src)
+impl Operator {
+    /// Returns the C syntax of the operator, e.g. `!=`.
+    fn as_str(&self) -> &'static str {
+        match *self {
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::GreaterOrEqual => ">=",
+            Operator::SmallerOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::Smaller => "<",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Modulo => "%",
         }
     }
 
-    fn parse_gt(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, ">") {
-            Ok(Ast::Op(
-                Operator::Greater,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 1..])?),
-            ))
-        } else {
-            Self::parse_le(src)
+    /// Returns the comparison that is true when this one is false, e.g. `<` for `>=`.
+    fn negated(&self) -> Option<Operator> {
+        match *self {
+            Operator::Equal => Some(Operator::NotEqual),
+            Operator::NotEqual => Some(Operator::Equal),
+            Operator::GreaterOrEqual => Some(Operator::Smaller),
+            Operator::SmallerOrEqual => Some(Operator::Greater),
+            Operator::Greater => Some(Operator::SmallerOrEqual),
+            Operator::Smaller => Some(Operator::GreaterOrEqual),
+            Operator::And | Operator::Or | Operator::Modulo => None,
         }
     }
 
-    fn parse_le(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "<=") {
-            Ok(Ast::Op(
-                Operator::SmallerOrEqual,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 2..])?),
-            ))
-        } else {
-            Self::parse_lt(src)
+    /// Returns the comparison with the operands swapped, e.g. `<` for `>`.
+    fn swapped(&self) -> Option<Operator> {
+        match *self {
+            Operator::Equal => Some(Operator::Equal),
+            Operator::NotEqual => Some(Operator::NotEqual),
+            Operator::GreaterOrEqual => Some(Operator::SmallerOrEqual),
+            Operator::SmallerOrEqual => Some(Operator::GreaterOrEqual),
+            Operator::Greater => Some(Operator::Smaller),
+            Operator::Smaller => Some(Operator::Greater),
+            Operator::And | Operator::Or | Operator::Modulo => None,
         }
     }
 
-    fn parse_lt(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "<") {
-            Ok(Ast::Op(
-                Operator::Smaller,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 1..])?),
-            ))
-        } else {
-            Self::parse_eq(src)
+    /// Returns how tightly the operator binds, from 1 for `||` to 5 for `%`.
+    fn precedence(&self) -> u8 {
+        match *self {
+            Operator::Or => 1,
+            Operator::And => 2,
+            Operator::Equal | Operator::NotEqual => 3,
+            Operator::GreaterOrEqual
+            | Operator::SmallerOrEqual
+            | Operator::Greater
+            | Operator::Smaller => 4,
+            Operator::Modulo => 5,
         }
     }
+}
 
-    fn parse_eq(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "==") {
-            Ok(Ast::Op(
-                Operator::Equal,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 2..])?),
-            ))
-        } else {
-            Self::parse_neq(src)
+impl Ast {
+    /// Returns how tightly the expression binds, with 0 for a ternary expression.
+    fn precedence(&self) -> u8 {
+        match *self {
+            Ternary(..) => 0,
+            Op(ref op, _, _) => op.precedence(),
+            Not(_) => 6,
+            N | Integer(_) => 7,
         }
     }
 
-    fn parse_neq(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "!=") {
-            Ok(Ast::Op(
-                Operator::NotEqual,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 2..])?),
-            ))
-        } else {
-            Self::parse_mod(src)
+    /// Writes the expression, in parentheses if it binds less tightly than `min`.
+    fn write(&self, fmt: &mut fmt::Formatter, min: u8) -> fmt::Result {
+        if self.precedence() < min {
+            fmt.write_str("(")?;
+            self.write(fmt, 0)?;
+            return fmt.write_str(")");
+        }
+        match *self {
+            Ternary(ref cond, ref ok, ref nok) => {
+                cond.write(fmt, 1)?;
+                fmt.write_str(" ? ")?;
+                ok.write(fmt, 0)?;
+                fmt.write_str(" : ")?;
+                nok.write(fmt, 0)
+            }
+            N => fmt.write_str("n"),
+            Integer(x) => write!(fmt, "{}", x),
+            Op(ref op, ref lhs, ref rhs) => {
+                lhs.write(fmt, op.precedence())?;
+                write!(fmt, " {} ", op.as_str())?;
+                rhs.write(fmt, op.precedence() + 1)
+            }
+            Not(ref val) => {
+                fmt.write_str("!")?;
+                val.write(fmt, 6)
+            }
         }
     }
-    fn parse_mod(src: &str) -> Result<Ast, Error> {
-        if let Some(i) = index_of(src, "%") {
-            Ok(Ast::Op(
-                Operator::Modulo,
-                Box::new(Ast::parse(&src[0..i])?),
-                Box::new(Ast::parse(&src[i + 1..])?),
-            ))
-        } else {
-            Self::parse_not(src.trim())
+}
+
+/// Writes the expression in C syntax, with only the parentheses it needs,
+/// so that `Ast::parse` reads it back as the same expression.
+impl fmt::Display for Ast {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.write(fmt, 0)
+    }
+}
+
+/// Writes the value of a `Plural-Forms` header, e.g. `nplurals=2; plural=n != 1;`,
+/// that `PluralForms::parse` reads back as the same plural forms.
+impl fmt::Display for PluralForms {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(nplurals) = self.nplurals {
+            write!(fmt, "nplurals={}; ", nplurals)?;
         }
+        write!(fmt, "plural={};", self.expr)
     }
+}
 
-    fn parse_not(src: &str) -> Result<Ast, Error> {
-        if index_of(src, "!") == Some(0) {
-            Ok(Ast::Not(Box::new(Ast::parse(&src[1..])?)))
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    N,
+    Integer(u64),
+    Op(&'static str),
+}
+
+/// Operator tokens, ordered so that longer ones are matched first.
+const OPERATORS: [&str; 14] = [
+    "==", "!=", ">=", "<=", "&&", "||", ">", "<", "%", "!", "?", ":", "(", ")",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut rest = src.trim_start();
+    while !rest.is_empty() {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        if digits > 0 {
+            let value = rest[..digits].parse().map_err(|_| Error::PluralParsing)?;
+            tokens.push(Token::Integer(value));
+            rest = &rest[digits..];
+        } else if rest.starts_with('n') {
+            tokens.push(Token::N);
+            rest = &rest[1..];
         } else {
-            Self::parse_int(src.trim())
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or(Error::PluralParsing)?;
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
         }
+        rest = rest.trim_start();
     }
+    Ok(tokens)
+}
 
-    fn parse_int(src: &str) -> Result<Ast, Error> {
-        if let Ok(x) = u64::from_str_radix(src, 10) {
-            Ok(Ast::Integer(x))
-        } else {
-            Self::parse_n(src.trim())
+/// A recursive descent parser over the tokens of a plural expression.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
         }
     }
 
-    fn parse_n(src: &str) -> Result<Ast, Error> {
-        if src == "n" {
-            Ok(Ast::N)
+    fn expect(&mut self, op: &str) -> Result<(), Error> {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            Ok(())
         } else {
             Err(Error::PluralParsing)
         }
     }
+
+    /// Parses a nested expression, failing if it is nested too deeply.
+    fn nested<F: FnOnce(&mut Self) -> Result<Ast, Error>>(
+        &mut self,
+        parse: F,
+    ) -> Result<Ast, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::PluralParsing);
+        }
+        self.depth += 1;
+        let ast = parse(self)?;
+        self.depth -= 1;
+        Ok(ast)
+    }
+
+    fn parse_ternary(&mut self) -> Result<Ast, Error> {
+        let cond = self.parse_binary(0)?;
+        if self.peek_op() != Some("?") {
+            return Ok(cond);
+        }
+        self.pos += 1;
+        let ok = self.nested(Parser::parse_ternary)?;
+        self.expect(":")?;
+        let nok = self.nested(Parser::parse_ternary)?;
+        Ok(Ternary(Box::new(cond), Box::new(ok), Box::new(nok)))
+    }
+
+    /// Parses left-associative binary operators,
+    /// starting at the given precedence level.
+    fn parse_binary(&mut self, level: usize) -> Result<Ast, Error> {
+        const LEVELS: [&[(&str, Operator)]; 6] = [
+            &[("||", Operator::Or)],
+            &[("&&", Operator::And)],
+            &[("==", Operator::Equal), ("!=", Operator::NotEqual)],
+            &[
+                (">=", Operator::GreaterOrEqual),
+                ("<=", Operator::SmallerOrEqual),
+                (">", Operator::Greater),
+                ("<", Operator::Smaller),
+            ],
+            &[("%", Operator::Modulo)],
+            &[],
+        ];
+
+        if LEVELS[level].is_empty() {
+            return self.parse_unary();
+        }
+        let mut lhs = self.parse_binary(level + 1)?;
+        while let Some(op) = self.peek_op().and_then(|tok| {
+            LEVELS[level]
+                .iter()
+                .find(|(s, _)| *s == tok)
+                .map(|(_, op)| op.clone())
+        }) {
+            self.pos += 1;
+            let rhs = self.parse_binary(level + 1)?;
+            lhs = Op(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Ast, Error> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op("!")) => {
+                self.pos += 1;
+                Ok(Not(Box::new(self.nested(Parser::parse_unary)?)))
+            }
+            Some(Token::Op("(")) => {
+                self.pos += 1;
+                let ast = self.nested(Parser::parse_ternary)?;
+                self.expect(")")?;
+                Ok(ast)
+            }
+            Some(Token::N) => {
+                self.pos += 1;
+                Ok(N)
+            }
+            Some(Token::Integer(x)) => {
+                let x = *x;
+                self.pos += 1;
+                Ok(Integer(x))
+            }
+            _ => Err(Error::PluralParsing),
+        }
+    }
+}
+
+/// PluralFallback decides the string returned by plural lookups
+/// when the plural form for a number of objects is missing or untranslated,
+/// e.g. when a message has fewer forms than the plural formula uses.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PluralFallback {
+    /// The singular msgid for one object and the plural msgid otherwise,
+    /// like in GNU gettext
+    #[default]
+    Original,
+    /// The singular msgid
+    Singular,
+    /// The plural msgid
+    Plural,
+    /// The last translated form of the message,
+    /// or `Original` if none of its forms is translated
+    LastForm,
+}
+
+impl PluralFallback {
+    /// Returns the fallback for `n` objects,
+    /// getting the last translated form of the message from `last_form`.
+    pub(crate) fn select<'a, F>(
+        self,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+        last_form: F,
+    ) -> &'a str
+    where
+        F: FnOnce() -> Option<&'a str>,
+    {
+        match self {
+            PluralFallback::Original => untranslated(msg_id, msg_id_plural, n),
+            PluralFallback::Singular => msg_id,
+            PluralFallback::Plural => msg_id_plural,
+            PluralFallback::LastForm => {
+                last_form().unwrap_or_else(|| untranslated(msg_id, msg_id_plural, n))
+            }
+        }
+    }
 }
 
 impl Resolver {
+    /// Returns the resolver for a plural expression, which is simplified
+    /// and compiled, or replaced by `default_resolver` if it is `n != 1`.
+    pub(crate) fn from_ast(ast: &Ast) -> Resolver {
+        match ast.simplify() {
+            Op(Operator::NotEqual, ref lhs, ref rhs) if **lhs == N && **rhs == Integer(1) => {
+                Function(default_resolver)
+            }
+            ast => Expr(Compiled::new(ast)),
+        }
+    }
+
     /// Returns the number of the correct plural form
     /// for `n` objects, as defined by the rule contained in this resolver.
     pub fn resolve(&self, n: u64) -> usize {
         match *self {
-            Expr(ref ast) => ast.resolve(n),
+            Expr(ref expr) => expr.evaluate(n),
             Function(ref f) => f(n),
+            Closure(ref f) => f(n),
         }
     }
 }
 
+mod private {
+    pub trait Sealed {}
+}
+
+/// ToPluralNumber is implemented by the integer types that can be given
+/// as the number of objects to the plural lookups, e.g. `ngettext`.
+///
+/// Negative numbers use the plural form of their absolute value,
+/// so that "-1 degree" is singular like "1 degree".
+/// The trait is sealed, and can not be implemented outside of this crate.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::CatalogBuilder;
+///
+/// let catalog = CatalogBuilder::new()
+///     .add_plural("degree", "degrees", vec!["laipsnis", "laipsniai"])
+///     .build()
+///     .unwrap();
+/// let readings: Vec<i64> = vec![-1, 4];
+/// assert_eq!(catalog.ngettext("degree", "degrees", readings[0]), "laipsnis");
+/// assert_eq!(catalog.ngettext("degree", "degrees", readings.len()), "laipsniai");
+/// ```
+pub trait ToPluralNumber: private::Sealed + Copy {
+    /// Returns the number that decides the plural form.
+    fn to_plural_number(self) -> u64;
+}
+
+macro_rules! impl_to_plural_number {
+    ($($ty:ty => |$n:ident| $number:expr;)*) => {$(
+        impl private::Sealed for $ty {}
+
+        impl ToPluralNumber for $ty {
+            fn to_plural_number(self) -> u64 {
+                let $n = self;
+                $number
+            }
+        }
+    )*};
+}
+
+impl_to_plural_number! {
+    u8 => |n| n.into();
+    u16 => |n| n.into();
+    u32 => |n| n.into();
+    u64 => |n| n;
+    usize => |n| n as u64;
+    i8 => |n| n.unsigned_abs().into();
+    i16 => |n| n.unsigned_abs().into();
+    i32 => |n| n.unsigned_abs().into();
+    i64 => |n| n.unsigned_abs();
+    isize => |n| n.unsigned_abs() as u64;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_to_plural_number() {
+        assert_eq!(3u8.to_plural_number(), 3);
+        assert_eq!(3usize.to_plural_number(), 3);
+        assert_eq!(u64::MAX.to_plural_number(), u64::MAX);
+        assert_eq!((-1i32).to_plural_number(), 1);
+        assert_eq!(i64::MIN.to_plural_number(), 1 << 63);
+        assert_eq!((-5isize).to_plural_number(), 5);
+    }
+
+    #[test]
+    fn test_simplify() {
+        for &(src, simplified) in &[
+            ("(n != 1)", "n != 1"),
+            ("n == 1 ? 0 : 1", "n != 1"),
+            ("n != 1 ? 1 : 0", "n != 1"),
+            ("!(n == 1)", "n != 1"),
+            ("1 != n", "n != 1"),
+            ("1 < n ? 1 : 0", "n > 1"),
+            ("n > 1 ? 0 : 1", "n <= 1"),
+            ("n % 10 ? 1 : 0", "n % 10 ? 1 : 0"),
+            ("3 % 2 == 1 ? n : 4", "n"),
+            ("1 < 2 || n", "1"),
+            ("1 && n % 10 == 1", "n % 10 == 1"),
+            ("n && 1", "n != 0"),
+            ("n > 5 ? 2 : 2", "2"),
+            ("!!(n > 5)", "n > 5"),
+            ("!!n", "!!n"),
+            ("n == 1 ? 0 : n == 2 ? 1 : 2", "n == 1 ? 0 : n == 2 ? 1 : 2"),
+        ] {
+            let ast = Ast::parse(src).unwrap();
+            let simplified = Ast::parse(simplified).unwrap();
+            assert_eq!(ast.simplify(), simplified, "{}", src);
+            for n in 0..200 {
+                assert_eq!(simplified.evaluate(n), ast.evaluate(n), "{}", src);
+            }
+        }
+        let ast = Ast::parse("n % (2 % 0)").unwrap();
+        assert_eq!(ast.simplify(), Ast::parse("n % 0").unwrap());
+
+        assert!(matches!(
+            Resolver::from_ast(&Ast::parse("n == 1 ? 0 : 1").unwrap()),
+            Function(_)
+        ));
+        assert!(matches!(
+            Resolver::from_ast(&Ast::parse("n > 1").unwrap()),
+            Expr(_)
+        ));
+    }
+
+    #[test]
+    fn test_hardening() {
+        let ast = Ast::parse("n % 0 ? 1 : n % (n % 2)").unwrap();
+        for n in 0..10 {
+            assert_eq!(ast.evaluate(n), 0);
+            assert_eq!(Compiled::new(ast.clone()).evaluate(n), 0);
+        }
+        assert_eq!(Ast::parse("n").unwrap().value(u64::MAX), u64::MAX);
+
+        let nested = format!("{}n{}", "(".repeat(64), ")".repeat(64));
+        assert_eq!(Ast::parse(&nested).unwrap(), N);
+        let nested = format!("{}n{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(Ast::parse(&nested).is_err());
+        assert!(Ast::parse(&"!".repeat(65)).is_err());
+        assert!(Ast::parse(&format!("{} 0", "n ? 1 :".repeat(65))).is_err());
+        assert!(Ast::parse("n == 1 || ".repeat(70).trim_end_matches("|| ")).is_err());
+        assert!(Ast::parse(&format!("n{}", " ".repeat(1024))).is_err());
+    }
+
     #[test]
     fn test_expr_resolver() {
-        assert_eq!(Expr(N).resolve(42), 42);
+        assert_eq!(Expr(Compiled::new(N)).resolve(42), 42);
+    }
+
+    #[test]
+    fn test_closure_resolver() {
+        let offset = 3;
+        let resolver = Closure(Arc::new(move |n| n as usize + offset));
+        assert_eq!(resolver.resolve(2), 5);
+        assert_eq!(resolver.clone().resolve(0), 3);
+        assert_eq!(format!("{:?}", resolver), "Closure");
+    }
+
+    #[test]
+    fn test_split_plural_forms() {
+        assert_eq!(split_plural_forms(""), (None, None));
+        assert_eq!(split_plural_forms("nplurals=42"), (Some(42), None));
+        assert_eq!(
+            split_plural_forms("plural=n==12; n_plurals=2"),
+            (Some(2), Some("n==12"))
+        );
+        assert_eq!(split_plural_forms("é=2; plural=n"), (None, Some("n")));
+        assert_eq!(split_plural_forms("nplurals=é"), (None, None));
+    }
+
+    #[test]
+    fn test_plural_forms() {
+        let forms = PluralForms::parse(" nplurals = 2 ; plural = n != 1 ;").unwrap();
+        assert_eq!(forms.nplurals(), Some(2));
+        assert_eq!(
+            forms.expr(),
+            &Ast::Op(
+                Operator::NotEqual,
+                Box::new(Ast::N),
+                Box::new(Ast::Integer(1))
+            )
+        );
+        assert_eq!(forms.evaluate(1), 0);
+        assert_eq!(forms.evaluate(0), 1);
+
+        let forms = PluralForms::parse("plural=0").unwrap();
+        assert_eq!(forms.nplurals(), None);
+        assert_eq!(forms.evaluate(7), 0);
+
+        assert!(PluralForms::parse("nplurals=2").is_err());
+        assert!(PluralForms::parse("nplurals=2; plural=n !=").is_err());
+        assert!(PluralForms::parse("é=").is_err());
+        assert_eq!(PluralForms::parse("é=1; plural=n").unwrap().evaluate(3), 3);
+    }
+
+    #[test]
+    fn test_display() {
+        for &(src, shown) in &[
+            ("(n != 1)", "n != 1"),
+            (
+                "n%10==1 && n%100!=11 ? 0 : 1",
+                "n % 10 == 1 && n % 100 != 11 ? 0 : 1",
+            ),
+            ("(n == 1 || n == 2) && n", "(n == 1 || n == 2) && n"),
+            ("n % (10 % 3)", "n % (10 % 3)"),
+            ("(n % 10) % 3", "n % 10 % 3"),
+            ("!(n > 1)", "!(n > 1)"),
+            ("!!n", "!!n"),
+            ("(n ? 1 : 2) ? 3 : n ? 4 : 5", "(n ? 1 : 2) ? 3 : n ? 4 : 5"),
+            ("n ? (n ? 1 : 2) : 3", "n ? n ? 1 : 2 : 3"),
+        ] {
+            let ast = Ast::parse(src).unwrap();
+            assert_eq!(ast.to_string(), shown);
+            assert_eq!(Ast::parse(shown).unwrap(), ast);
+        }
+
+        let forms = PluralForms::parse("nplurals=2; plural=(n > 1)").unwrap();
+        assert_eq!(forms.to_string(), "nplurals=2; plural=n > 1;");
+        let forms = PluralForms::parse("plural=0").unwrap();
+        assert_eq!(forms.to_string(), "plural=0;");
     }
 
     #[test]
     fn test_parser() {
         assert_eq!(
             Ast::parse("n == 42 ? n : 6 && n < 7").expect("Invalid plural"),
-            Ast::Op(
-                Operator::And,
-                Box::new(Ast::Ternary(
-                    Box::new(Ast::Op(
-                        Operator::Equal,
-                        Box::new(Ast::N),
-                        Box::new(Ast::Integer(42))
-                    )),
+            Ast::Ternary(
+                Box::new(Ast::Op(
+                    Operator::Equal,
                     Box::new(Ast::N),
-                    Box::new(Ast::Integer(6))
+                    Box::new(Ast::Integer(42))
                 )),
+                Box::new(Ast::N),
                 Box::new(Ast::Op(
-                    Operator::Smaller,
-                    Box::new(Ast::N),
-                    Box::new(Ast::Integer(7))
+                    Operator::And,
+                    Box::new(Ast::Integer(6)),
+                    Box::new(Ast::Op(
+                        Operator::Smaller,
+                        Box::new(Ast::N),
+                        Box::new(Ast::Integer(7))
+                    ))
                 ))
             )
         );
@@ -343,7 +891,61 @@ mod tests {
             )
         );
 
+        assert_eq!(
+            Ast::parse("n % 10 == 1 && n != 11 || !n").expect("Invalid plural"),
+            Ast::Op(
+                Operator::Or,
+                Box::new(Ast::Op(
+                    Operator::And,
+                    Box::new(Ast::Op(
+                        Operator::Equal,
+                        Box::new(Ast::Op(
+                            Operator::Modulo,
+                            Box::new(Ast::N),
+                            Box::new(Ast::Integer(10))
+                        )),
+                        Box::new(Ast::Integer(1))
+                    )),
+                    Box::new(Ast::Op(
+                        Operator::NotEqual,
+                        Box::new(Ast::N),
+                        Box::new(Ast::Integer(11))
+                    ))
+                )),
+                Box::new(Ast::Not(Box::new(Ast::N)))
+            )
+        );
+
+        for invalid in &[
+            "",
+            "n +",
+            "(n",
+            "n)",
+            "n ? 1",
+            "n ? 1 : ",
+            "x",
+            "n n",
+            "99999999999999999999",
+        ] {
+            assert!(
+                Ast::parse(invalid).is_err(),
+                "{:?} should not parse",
+                invalid
+            );
+        }
+
         let ru_plural = "((n%10==1 && n%100!=11) ? 0 : ((n%10 >= 2 && n%10 <=4 && (n%100 < 12 || n%100 > 14)) ? 1 : ((n%10 == 0 || (n%10 >= 5 && n%10 <=9)) || (n%100 >= 11 && n%100 <= 14)) ? 2 : 3))";
         assert!(Ast::parse(ru_plural).is_ok());
+
+        // the same formula, relying on operator precedence instead of parentheses
+        let ru_plural = Ast::parse(
+            "n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2",
+        )
+        .unwrap();
+        let forms = [1, 2, 5, 11, 12, 21, 22, 25, 111]
+            .iter()
+            .map(|&n| ru_plural.evaluate(n))
+            .collect::<Vec<_>>();
+        assert_eq!(forms, vec![0, 1, 2, 2, 2, 0, 1, 2, 2]);
     }
 }
@@ -0,0 +1,543 @@
+use std::fmt;
+use std::str::FromStr;
+
+use self::PluralCategory::*;
+
+/// PluralCategory is one of the plural categories defined by
+/// [CLDR](https://cldr.unicode.org/index/cldr-spec/plural-rules).
+///
+/// Unlike gettext plural forms, which are plain indices
+/// whose meaning depends on the catalog,
+/// categories have the same meaning across languages.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PluralCategory {
+    /// The `zero` category
+    Zero,
+    /// The `one` category
+    One,
+    /// The `two` category
+    Two,
+    /// The `few` category
+    Few,
+    /// The `many` category
+    Many,
+    /// The `other` category
+    Other,
+}
+
+/// PluralOperands is a decimal number as CLDR plural rules see it,
+/// with the fraction digits that are shown, e.g. `1.50`.
+///
+/// Many languages treat fractions differently from whole numbers,
+/// and "1.0 hours" differently from "1 hour", so the digits matter.
+/// The sign is left out, as it does not change the category.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{PluralCategory, PluralOperands};
+///
+/// let one: PluralOperands = "1".parse().unwrap();
+/// let decimal: PluralOperands = "1.0".parse().unwrap();
+/// assert_eq!(PluralCategory::for_operands("en", &one), PluralCategory::One);
+/// assert_eq!(PluralCategory::for_operands("en", &decimal), PluralCategory::Other);
+/// assert_eq!(PluralOperands::from(1.5), "1.5".parse().unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PluralOperands {
+    /// The integer digits (`i`)
+    pub(crate) i: u64,
+    /// The number of fraction digits (`v`)
+    pub(crate) v: usize,
+    /// The fraction digits (`f`)
+    pub(crate) f: u64,
+    /// The fraction digits without trailing zeros (`t`)
+    pub(crate) t: u64,
+}
+
+/// The most fraction digits that operands keep.
+const MAX_FRACTION_DIGITS: usize = 18;
+
+impl From<u64> for PluralOperands {
+    fn from(n: u64) -> Self {
+        PluralOperands {
+            i: n,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<f64> for PluralOperands {
+    /// Returns the operands of the shortest decimal that reads back as the number,
+    /// e.g. `1.5`, so that whole numbers have no fraction digits.
+    ///
+    /// The fraction digits of very small numbers are cut off after the eighteenth,
+    /// and numbers beyond the range of `u64` saturate.
+    fn from(n: f64) -> Self {
+        let n = n.abs();
+        // from 2^53 on, all the numbers are whole
+        if n.is_nan() || n >= (1u64 << 53) as f64 {
+            return PluralOperands::from(n as u64);
+        }
+        let s = n.to_string();
+        let end = s
+            .find('.')
+            .map_or(s.len(), |dot| s.len().min(dot + 1 + MAX_FRACTION_DIGITS));
+        s[..end].parse().unwrap_or_default()
+    }
+}
+
+impl FromStr for PluralOperands {
+    type Err = ();
+
+    /// Parses a decimal number like `-1.50`, keeping its trailing zeros.
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.strip_prefix('-').unwrap_or(s);
+        let (integer, fraction) = match s.find('.') {
+            Some(dot) => (&s[..dot], &s[dot + 1..]),
+            None => (s, ""),
+        };
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !digits(integer) || !(fraction.is_empty() || digits(fraction)) {
+            return Err(());
+        }
+        if fraction.len() > MAX_FRACTION_DIGITS || s.ends_with('.') {
+            return Err(());
+        }
+        let value = |s: &str| if s.is_empty() { Ok(0) } else { s.parse() };
+        Ok(PluralOperands {
+            i: integer.parse().map_err(|_| ())?,
+            v: fraction.len(),
+            f: value(fraction).map_err(|_| ())?,
+            t: value(fraction.trim_end_matches('0')).map_err(|_| ())?,
+        })
+    }
+}
+
+/// Returns the primary language subtag of a locale name,
+/// e.g. `pt` for `pt_BR.UTF-8`, and the region subtag if there is one.
+pub(crate) fn split_language(locale: &str) -> (String, Option<String>) {
+    let locale = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let mut parts = locale.split(['_', '-']);
+    let language = parts.next().unwrap_or("").to_owned();
+    let region =
+        parts.find(|p| p.len() == 2 || p.len() == 3 && p.bytes().all(|b| b.is_ascii_digit()));
+    (language, region.map(str::to_owned))
+}
+
+impl PluralCategory {
+    /// Returns all the categories, in CLDR order.
+    pub fn all() -> &'static [PluralCategory] {
+        &[Zero, One, Two, Few, Many, Other]
+    }
+
+    /// Returns the category that CLDR rules assign to the integer `n`
+    /// in the given language, e.g. `ru` or `pt_BR`.
+    ///
+    /// Languages without built-in rules are treated like English.
+    pub fn for_number(language: &str, n: u64) -> PluralCategory {
+        let (language, region) = split_language(language);
+        let (n10, n100) = (n % 10, n % 100);
+        match &*language {
+            "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" | "lo" | "my" | "km" | "jv" | "su" => {
+                Other
+            }
+            "fr" | "pt" if language == "fr" || region.as_deref() != Some("pt") => match n {
+                0 | 1 => One,
+                _ if n % 1_000_000 == 0 => Many,
+                _ => Other,
+            },
+            "es" | "it" | "ca" | "pt" => match n {
+                1 => One,
+                _ if n != 0 && n % 1_000_000 == 0 => Many,
+                _ => Other,
+            },
+            "hi" | "bn" | "fa" | "gu" | "kn" | "am" | "zu" | "as" | "mr" => match n {
+                0 | 1 => One,
+                _ => Other,
+            },
+            "ru" | "uk" | "be" => match (n10, n100) {
+                (1, _) if n100 != 11 => One,
+                (2..=4, _) if !(12..=14).contains(&n100) => Few,
+                _ => Many,
+            },
+            "pl" => match (n10, n100) {
+                _ if n == 1 => One,
+                (2..=4, _) if !(12..=14).contains(&n100) => Few,
+                _ => Many,
+            },
+            "cs" | "sk" => match n {
+                1 => One,
+                2..=4 => Few,
+                _ => Other,
+            },
+            "hr" | "sr" | "bs" | "sh" => match (n10, n100) {
+                (1, _) if n100 != 11 => One,
+                (2..=4, _) if !(12..=14).contains(&n100) => Few,
+                _ => Other,
+            },
+            "lt" => match (n10, n100) {
+                (_, 11..=19) => Other,
+                (1, _) => One,
+                (2..=9, _) => Few,
+                _ => Other,
+            },
+            "lv" => match (n10, n100) {
+                (0, _) | (_, 11..=19) => Zero,
+                (1, _) => One,
+                _ => Other,
+            },
+            "is" | "mk" => match (n10, n100) {
+                (1, _) if n100 != 11 => One,
+                _ => Other,
+            },
+            "ro" | "mo" => match n100 {
+                _ if n == 1 => One,
+                _ if n == 0 => Few,
+                1..=19 => Few,
+                _ => Other,
+            },
+            "sl" => match n100 {
+                1 => One,
+                2 => Two,
+                3 | 4 => Few,
+                _ => Other,
+            },
+            "ar" => match n {
+                0 => Zero,
+                1 => One,
+                2 => Two,
+                _ if (3..=10).contains(&n100) => Few,
+                _ if (11..=99).contains(&n100) => Many,
+                _ => Other,
+            },
+            "he" | "iw" => match n {
+                1 => One,
+                2 => Two,
+                _ => Other,
+            },
+            "ga" => match n {
+                1 => One,
+                2 => Two,
+                3..=6 => Few,
+                7..=10 => Many,
+                _ => Other,
+            },
+            "cy" => match n {
+                0 => Zero,
+                1 => One,
+                2 => Two,
+                3 => Few,
+                6 => Many,
+                _ => Other,
+            },
+            "mt" => match n {
+                1 => One,
+                2 => Two,
+                _ if n == 0 || (3..=10).contains(&n100) => Few,
+                _ if (11..=19).contains(&n100) => Many,
+                _ => Other,
+            },
+            _ => match n {
+                1 => One,
+                _ => Other,
+            },
+        }
+    }
+
+    /// Returns the category that CLDR rules assign to the decimal number `operands`
+    /// in the given language.
+    ///
+    /// Numbers without fraction digits have the category of the integer,
+    /// as given by `for_number`.
+    pub fn for_operands(language: &str, operands: &PluralOperands) -> PluralCategory {
+        let PluralOperands { i, v, f, t } = *operands;
+        if v == 0 {
+            return PluralCategory::for_number(language, i);
+        }
+        // the rules with the absolute value apply to decimals like `1.0` like to integers
+        let whole = f == 0;
+        let (f10, f100) = (f % 10, f % 100);
+        let (code, region) = split_language(language);
+        match &*code {
+            "fr" | "pt" if code == "fr" || region.as_deref() != Some("pt") => match i {
+                0 | 1 => One,
+                _ => Other,
+            },
+            "es" if i == 1 && whole => One,
+            "hi" | "bn" | "fa" | "gu" | "kn" | "am" | "zu" | "as" | "mr" => match i {
+                0 => One,
+                1 if whole => One,
+                _ => Other,
+            },
+            "cs" | "sk" => Many,
+            "hr" | "sr" | "bs" | "sh" => match (f10, f100) {
+                (1, _) if f100 != 11 => One,
+                (2..=4, _) if !(12..=14).contains(&f100) => Few,
+                _ => Other,
+            },
+            "mk" if f10 == 1 && f100 != 11 => One,
+            "is" if t == 0 => PluralCategory::for_number(language, i),
+            "is" if t % 10 == 1 && t % 100 != 11 => One,
+            "lt" if !whole => Many,
+            "lv" if !whole => match (v, f10, f100) {
+                (2, _, 11..=19) => Zero,
+                (2, 1, _) if f100 != 11 => One,
+                (_, 1, _) if v != 2 => One,
+                _ => Other,
+            },
+            "ro" | "mo" | "sl" => Few,
+            "he" | "iw" if i == 0 => One,
+            "be" | "lt" | "lv" | "ar" | "ga" | "cy" | "mt" if whole => {
+                PluralCategory::for_number(language, i)
+            }
+            _ => Other,
+        }
+    }
+
+    /// Returns the smallest positive integer that falls into this category
+    /// in the given language, or zero if it is the only such integer.
+    /// Returns `None` if the language does not use the category for integers.
+    pub fn sample(self, language: &str) -> Option<u64> {
+        (1..=200)
+            .chain(Some(1_000_000))
+            .chain(Some(0))
+            .find(|&n| PluralCategory::for_number(language, n) == self)
+    }
+
+    /// Returns the category that CLDR rules assign to the range of integers
+    /// from `start` to `end`, e.g. of "1–3 days", in the given language.
+    ///
+    /// The category of a range is given by the categories of its ends,
+    /// and is that of the end for most languages, which is what languages
+    /// without built-in rules get. English and some others use `Other` for all ranges,
+    /// even "0–1 days". A range whose ends are equal is a single number.
+    pub fn for_range(language: &str, start: u64, end: u64) -> PluralCategory {
+        let (start, end) = (start.min(end), start.max(end));
+        if start == end {
+            return PluralCategory::for_number(language, end);
+        }
+        let from = PluralCategory::for_number(language, start);
+        let to = PluralCategory::for_number(language, end);
+        match (&*split_language(language).0, from, to) {
+            (
+                "af" | "bg" | "ca" | "en" | "es" | "et" | "eu" | "fi" | "mk" | "nb" | "sv" | "ur",
+                _,
+                _,
+            ) => Other,
+            ("ro" | "mo", Few, One) => Few,
+            ("sl", _, One) => Few,
+            ("lv", _, Zero) => Other,
+            ("ar", Zero, One | Two) => Zero,
+            ("ar", _, One | Two) => Other,
+            ("he" | "iw", One, Two) => Other,
+            _ => to,
+        }
+    }
+
+    /// Returns the CLDR name of the category, e.g. `few`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Zero => "zero",
+            One => "one",
+            Two => "two",
+            Few => "few",
+            Many => "many",
+            Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for PluralCategory {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PluralCategory {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        PluralCategory::all()
+            .iter()
+            .cloned()
+            .find(|c| c.as_str() == s)
+            .ok_or(())
+    }
+}
+
+#[test]
+fn test_split_language() {
+    assert_eq!(
+        split_language("pt_BR.UTF-8"),
+        ("pt".to_owned(), Some("br".to_owned()))
+    );
+    assert_eq!(
+        split_language("sr_RS@latin"),
+        ("sr".to_owned(), Some("rs".to_owned()))
+    );
+    assert_eq!(
+        split_language("zh-Hant-TW"),
+        ("zh".to_owned(), Some("tw".to_owned()))
+    );
+    assert_eq!(
+        split_language("es-419"),
+        ("es".to_owned(), Some("419".to_owned()))
+    );
+    assert_eq!(split_language("DE"), ("de".to_owned(), None));
+    assert_eq!(split_language(""), ("".to_owned(), None));
+}
+
+#[test]
+fn test_for_number() {
+    let categories = |lang: &str, ns: &[u64]| {
+        ns.iter()
+            .map(|&n| PluralCategory::for_number(lang, n))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(categories("en", &[0, 1, 2]), vec![Other, One, Other]);
+    assert_eq!(categories("ja", &[0, 1, 2]), vec![Other, Other, Other]);
+    assert_eq!(
+        categories("fr", &[0, 1, 2, 1_000_000]),
+        vec![One, One, Other, Many]
+    );
+    assert_eq!(categories("pt_BR", &[0, 1]), vec![One, One]);
+    assert_eq!(categories("pt_PT", &[0, 1]), vec![Other, One]);
+    assert_eq!(
+        categories("ru", &[1, 2, 5, 11, 12, 21, 22, 25, 111]),
+        vec![One, Few, Many, Many, Many, One, Few, Many, Many]
+    );
+    assert_eq!(
+        categories("pl", &[1, 2, 5, 21, 22]),
+        vec![One, Few, Many, Many, Few]
+    );
+    assert_eq!(
+        categories("lt_LT.UTF-8", &[1, 2, 10, 11, 21, 29]),
+        vec![One, Few, Other, Other, One, Few]
+    );
+    assert_eq!(
+        categories("ar", &[0, 1, 2, 3, 11, 100, 103]),
+        vec![Zero, One, Two, Few, Many, Other, Few]
+    );
+    assert_eq!(
+        categories("cy", &[0, 1, 2, 3, 6, 7]),
+        vec![Zero, One, Two, Few, Many, Other]
+    );
+    assert_eq!(
+        categories("ro", &[0, 1, 2, 20, 101]),
+        vec![Few, One, Few, Other, Few]
+    );
+    assert_eq!(categories("xx", &[1, 3]), vec![One, Other]);
+}
+
+#[test]
+fn test_sample() {
+    assert_eq!(Few.sample("ru"), Some(2));
+    assert_eq!(Many.sample("ru"), Some(5));
+    assert_eq!(One.sample("fr"), Some(1));
+    assert_eq!(Many.sample("fr"), Some(1_000_000));
+    assert_eq!(Zero.sample("ar"), Some(0));
+    assert_eq!(Many.sample("ar"), Some(11));
+    assert_eq!(Few.sample("en"), None);
+}
+
+#[test]
+fn test_for_range() {
+    assert_eq!(PluralCategory::for_range("en", 0, 1), Other);
+    assert_eq!(PluralCategory::for_range("en", 1, 1), One);
+    assert_eq!(PluralCategory::for_range("de", 0, 1), One);
+    assert_eq!(PluralCategory::for_range("fr", 0, 1), One);
+    assert_eq!(PluralCategory::for_range("ru", 1, 5), Many);
+    assert_eq!(PluralCategory::for_range("ru_RU", 5, 21), One);
+    assert_eq!(PluralCategory::for_range("ru", 21, 5), One);
+    assert_eq!(PluralCategory::for_range("ro", 0, 1), Few);
+    assert_eq!(PluralCategory::for_range("sl", 2, 101), Few);
+    assert_eq!(PluralCategory::for_range("lv", 1, 10), Other);
+    assert_eq!(PluralCategory::for_range("ar", 0, 2), Zero);
+    assert_eq!(PluralCategory::for_range("ar", 3, 11), Many);
+    assert_eq!(PluralCategory::for_range("xx", 0, 1), One);
+}
+
+#[test]
+fn test_operands() {
+    let operands = |s: &str| s.parse::<PluralOperands>().map(|o| (o.i, o.v, o.f, o.t));
+    assert_eq!(operands("12"), Ok((12, 0, 0, 0)));
+    assert_eq!(operands("-1.50"), Ok((1, 2, 50, 5)));
+    assert_eq!(operands("0.05"), Ok((0, 2, 5, 5)));
+    for bad in &[
+        "",
+        "1.",
+        ".5",
+        "1e3",
+        "1.2.3",
+        "--1",
+        "1.0000000000000000000",
+    ] {
+        assert_eq!(operands(bad), Err(()), "{}", bad);
+    }
+
+    assert_eq!(PluralOperands::from(2.0), PluralOperands::from(2));
+    assert_eq!(PluralOperands::from(-0.25), "0.25".parse().unwrap());
+    assert_eq!(PluralOperands::from(1e20).v, 0);
+    assert_eq!(PluralOperands::from(f64::NAN), PluralOperands::from(0));
+    assert_eq!(PluralOperands::from(1e-30).v, 18);
+}
+
+#[test]
+fn test_for_operands() {
+    let categories = |lang: &str, ns: &[&str]| {
+        ns.iter()
+            .map(|n| PluralCategory::for_operands(lang, &n.parse().unwrap()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        categories("en", &["1", "1.0", "1.5"]),
+        vec![One, Other, Other]
+    );
+    assert_eq!(
+        categories("fr", &["0.5", "1.5", "2.5"]),
+        vec![One, One, Other]
+    );
+    assert_eq!(categories("pt_PT", &["0.5"]), vec![Other]);
+    assert_eq!(categories("es", &["1.0", "1.5"]), vec![One, Other]);
+    assert_eq!(
+        categories("hi", &["0.5", "1.0", "1.5"]),
+        vec![One, One, Other]
+    );
+    assert_eq!(categories("ru", &["1.5", "21.0"]), vec![Other, Other]);
+    assert_eq!(categories("cs", &["1.5", "2"]), vec![Many, Few]);
+    assert_eq!(
+        categories("hr", &["0.1", "0.2", "0.11", "0.5"]),
+        vec![One, Few, Other, Other]
+    );
+    assert_eq!(
+        categories("is", &["0.1", "1.0", "2.0", "0.11"]),
+        vec![One, One, Other, Other]
+    );
+    assert_eq!(
+        categories("lt", &["1.0", "2.0", "1.5"]),
+        vec![One, Few, Many]
+    );
+    assert_eq!(
+        categories("lv", &["0.11", "0.21", "0.1", "0.5", "10.0"]),
+        vec![Zero, One, One, Other, Zero]
+    );
+    assert_eq!(categories("ro", &["1.0"]), vec![Few]);
+    assert_eq!(categories("he", &["0.5", "1.5"]), vec![One, Other]);
+    assert_eq!(categories("ar", &["2.0", "2.5"]), vec![Two, Other]);
+}
+
+#[test]
+fn test_category_names() {
+    for category in PluralCategory::all() {
+        assert_eq!(category.as_str().parse(), Ok(*category));
+        assert_eq!(category.to_string(), category.as_str());
+    }
+    assert_eq!("several".parse::<PluralCategory>(), Err(()));
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::Ast::{self, *};
+use super::{form, Operator};
+
+type Eval = Box<dyn Fn(u64) -> u64 + Send + Sync>;
+
+/// The largest number of objects whose plural form is resolved in advance.
+/// User interfaces mostly show small numbers, which are then looked up in a table.
+const HOT: u64 = 100;
+
+/// Compiled is a plural expression compiled to a chain of closures,
+/// which `resolve` calls without matching on the nodes of the expression.
+///
+/// Each operator gets a closure of its own, and the operands that are `n`
+/// or integer literals, e.g. in `n % 10 == 1`, are inlined into it.
+/// The plural forms for up to `HOT` objects are kept in a table.
+#[derive(Clone)]
+pub(crate) struct Compiled {
+    ast: Ast,
+    eval: Arc<dyn Fn(u64) -> u64 + Send + Sync>,
+    hot: Arc<[usize]>,
+}
+
+impl Compiled {
+    /// Compiles the expression.
+    pub fn new(ast: Ast) -> Self {
+        let eval = compile(&ast);
+        Compiled {
+            hot: (0..=HOT).map(|n| form(eval(n))).collect(),
+            eval: Arc::from(eval),
+            ast,
+        }
+    }
+
+    /// Returns the expression that was compiled.
+    pub fn ast(&self) -> &Ast {
+        &self.ast
+    }
+
+    /// Evaluates the expression for `n` objects, like `Ast::evaluate`.
+    pub fn evaluate(&self, n: u64) -> usize {
+        if n <= HOT {
+            self.hot[n as usize]
+        } else {
+            form((self.eval)(n))
+        }
+    }
+}
+
+impl fmt::Debug for Compiled {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.ast.fmt(fmt)
+    }
+}
+
+fn compile(ast: &Ast) -> Eval {
+    match *ast {
+        Ternary(ref cond, ref ok, ref nok) => {
+            let (cond, ok, nok) = (compile(cond), compile(ok), compile(nok));
+            Box::new(move |n| if cond(n) != 0 { ok(n) } else { nok(n) })
+        }
+        N => Box::new(|n| n),
+        Integer(x) => Box::new(move |_| x),
+        Op(ref op, ref lhs, ref rhs) => match (&**lhs, &**rhs) {
+            (N, &Integer(x)) => binary(op, |n| n, move |_| x),
+            (_, &Integer(x)) => binary(op, compile(lhs), move |_| x),
+            _ => binary(op, compile(lhs), compile(rhs)),
+        },
+        Not(ref val) => {
+            let val = compile(val);
+            Box::new(move |n| (val(n) == 0) as u64)
+        }
+    }
+}
+
+/// Returns a closure that applies `op` to the values of the operands.
+fn binary<L, R>(op: &Operator, lhs: L, rhs: R) -> Eval
+where
+    L: Fn(u64) -> u64 + Send + Sync + 'static,
+    R: Fn(u64) -> u64 + Send + Sync + 'static,
+{
+    match *op {
+        Operator::Equal => Box::new(move |n| (lhs(n) == rhs(n)) as u64),
+        Operator::NotEqual => Box::new(move |n| (lhs(n) != rhs(n)) as u64),
+        Operator::GreaterOrEqual => Box::new(move |n| (lhs(n) >= rhs(n)) as u64),
+        Operator::SmallerOrEqual => Box::new(move |n| (lhs(n) <= rhs(n)) as u64),
+        Operator::Greater => Box::new(move |n| (lhs(n) > rhs(n)) as u64),
+        Operator::Smaller => Box::new(move |n| (lhs(n) < rhs(n)) as u64),
+        Operator::And => Box::new(move |n| (lhs(n) != 0 && rhs(n) != 0) as u64),
+        Operator::Or => Box::new(move |n| (lhs(n) != 0 || rhs(n) != 0) as u64),
+        Operator::Modulo => Box::new(move |n| lhs(n).checked_rem(rhs(n)).unwrap_or(0)),
+    }
+}
+
+#[test]
+fn test_compiled() {
+    for src in &[
+        "0",
+        "n",
+        "n != 1",
+        "!(n > 1)",
+        "n % 10 == 1 && n % 100 != 11 ? 0 : n % 10 >= 2 && n % 10 <= 4 && (n % 100 < 10 || n % 100 >= 20) ? 1 : 2",
+        "n == 0 ? 0 : n == 1 ? 1 : n == 2 ? 2 : n % 100 >= 3 && n % 100 <= 10 ? 3 : n % 100 >= 11 ? 4 : 5",
+        "(n % 7) % (n % 3 ? n % 3 : 3) < 2 || 7 % (n ? n : 1) == 1",
+    ] {
+        let ast = Ast::parse(src).unwrap();
+        let compiled = Compiled::new(ast.clone());
+        for n in (0..1000).chain(Some(u64::MAX)) {
+            assert_eq!(compiled.evaluate(n), ast.evaluate(n), "{} for {}", src, n);
+        }
+        assert_eq!(compiled.ast(), &ast);
+        assert_eq!(format!("{:?}", compiled), format!("{:?}", ast));
+    }
+}
+
+#[test]
+fn test_hot_table() {
+    let lt =
+        "n % 10 == 1 && n % 100 != 11 ? 0 : n % 10 >= 2 && (n % 100 < 10 || n % 100 >= 20) ? 1 : 2";
+    let compiled = Compiled::new(Ast::parse(lt).unwrap());
+    assert_eq!(compiled.hot.len() as u64, HOT + 1);
+    for &(n, expected) in &[
+        (0, 2),
+        (1, 0),
+        (HOT, 2),
+        (HOT + 1, 0),
+        (HOT + 2, 1),
+        (u64::MAX, 2),
+    ] {
+        assert_eq!(compiled.evaluate(n), expected, "for {}", n);
+        assert_eq!(form((compiled.eval)(n)), expected, "for {}", n);
+    }
+}
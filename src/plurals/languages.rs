@@ -0,0 +1,147 @@
+use super::cldr::split_language;
+#[cfg(test)]
+use super::Compiled;
+use super::PluralForms;
+
+/// Default `Plural-Forms` headers for common languages,
+/// as suggested by the GNU gettext manual.
+///
+/// Entries with a region take precedence over the bare language.
+const PLURAL_FORMS: &[(&str, &str)] = &[
+    ("ja", "nplurals=1; plural=0;"),
+    ("zh", "nplurals=1; plural=0;"),
+    ("ko", "nplurals=1; plural=0;"),
+    ("vi", "nplurals=1; plural=0;"),
+    ("th", "nplurals=1; plural=0;"),
+    ("id", "nplurals=1; plural=0;"),
+    ("ms", "nplurals=1; plural=0;"),
+    ("lo", "nplurals=1; plural=0;"),
+    ("km", "nplurals=1; plural=0;"),
+    ("my", "nplurals=1; plural=0;"),
+    ("jv", "nplurals=1; plural=0;"),
+    ("en", "nplurals=2; plural=(n != 1);"),
+    ("de", "nplurals=2; plural=(n != 1);"),
+    ("nl", "nplurals=2; plural=(n != 1);"),
+    ("sv", "nplurals=2; plural=(n != 1);"),
+    ("da", "nplurals=2; plural=(n != 1);"),
+    ("nb", "nplurals=2; plural=(n != 1);"),
+    ("nn", "nplurals=2; plural=(n != 1);"),
+    ("no", "nplurals=2; plural=(n != 1);"),
+    ("fi", "nplurals=2; plural=(n != 1);"),
+    ("et", "nplurals=2; plural=(n != 1);"),
+    ("it", "nplurals=2; plural=(n != 1);"),
+    ("es", "nplurals=2; plural=(n != 1);"),
+    ("pt", "nplurals=2; plural=(n != 1);"),
+    ("el", "nplurals=2; plural=(n != 1);"),
+    ("hu", "nplurals=2; plural=(n != 1);"),
+    ("tr", "nplurals=2; plural=(n != 1);"),
+    ("bg", "nplurals=2; plural=(n != 1);"),
+    ("ca", "nplurals=2; plural=(n != 1);"),
+    ("eu", "nplurals=2; plural=(n != 1);"),
+    ("gl", "nplurals=2; plural=(n != 1);"),
+    ("he", "nplurals=2; plural=(n != 1);"),
+    ("eo", "nplurals=2; plural=(n != 1);"),
+    ("af", "nplurals=2; plural=(n != 1);"),
+    ("az", "nplurals=2; plural=(n != 1);"),
+    ("ka", "nplurals=2; plural=(n != 1);"),
+    ("kk", "nplurals=2; plural=(n != 1);"),
+    ("sq", "nplurals=2; plural=(n != 1);"),
+    ("sw", "nplurals=2; plural=(n != 1);"),
+    ("ta", "nplurals=2; plural=(n != 1);"),
+    ("te", "nplurals=2; plural=(n != 1);"),
+    ("ur", "nplurals=2; plural=(n != 1);"),
+    ("hi", "nplurals=2; plural=(n != 1);"),
+    ("bn", "nplurals=2; plural=(n != 1);"),
+    ("fr", "nplurals=2; plural=(n > 1);"),
+    ("pt_BR", "nplurals=2; plural=(n > 1);"),
+    ("br", "nplurals=2; plural=(n > 1);"),
+    ("oc", "nplurals=2; plural=(n > 1);"),
+    ("fil", "nplurals=2; plural=(n > 1);"),
+    ("is", "nplurals=2; plural=(n%10 != 1 || n%100 == 11);"),
+    ("mk", "nplurals=2; plural=(n%10 == 1 && n%100 != 11 ? 0 : 1);"),
+    ("lv", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n != 0 ? 1 : 2);"),
+    ("lt", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n%10 >= 2 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("ru", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n%10 >= 2 && n%10 <= 4 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("uk", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n%10 >= 2 && n%10 <= 4 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("be", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n%10 >= 2 && n%10 <= 4 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("sr", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n%10 >= 2 && n%10 <= 4 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("hr", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n%10 >= 2 && n%10 <= 4 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("bs", "nplurals=3; plural=(n%10 == 1 && n%100 != 11 ? 0 : n%10 >= 2 && n%10 <= 4 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("pl", "nplurals=3; plural=(n == 1 ? 0 : n%10 >= 2 && n%10 <= 4 && (n%100 < 10 || n%100 >= 20) ? 1 : 2);"),
+    ("cs", "nplurals=3; plural=(n == 1 ? 0 : n >= 2 && n <= 4 ? 1 : 2);"),
+    ("sk", "nplurals=3; plural=(n == 1 ? 0 : n >= 2 && n <= 4 ? 1 : 2);"),
+    ("ro", "nplurals=3; plural=(n == 1 ? 0 : n == 0 || (n%100 > 0 && n%100 < 20) ? 1 : 2);"),
+    ("sl", "nplurals=4; plural=(n%100 == 1 ? 0 : n%100 == 2 ? 1 : n%100 == 3 || n%100 == 4 ? 2 : 3);"),
+    ("mt", "nplurals=4; plural=(n == 1 ? 0 : n == 0 || (n%100 > 1 && n%100 < 11) ? 1 : n%100 > 10 && n%100 < 20 ? 2 : 3);"),
+    ("cy", "nplurals=4; plural=(n == 1 ? 0 : n == 2 ? 1 : n != 8 && n != 11 ? 2 : 3);"),
+    ("ga", "nplurals=5; plural=(n == 1 ? 0 : n == 2 ? 1 : n >= 3 && n <= 6 ? 2 : n >= 7 && n <= 10 ? 3 : 4);"),
+    ("ar", "nplurals=6; plural=(n == 0 ? 0 : n == 1 ? 1 : n == 2 ? 2 : n%100 >= 3 && n%100 <= 10 ? 3 : n%100 >= 11 ? 4 : 5);"),
+];
+
+/// Returns the built-in `Plural-Forms` header for the given locale.
+pub fn plural_forms_for(locale: &str) -> Option<&'static str> {
+    let (language, region) = split_language(locale);
+    let find = |name: &str| {
+        PLURAL_FORMS
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(name))
+            .map(|(_, forms)| *forms)
+    };
+    region
+        .and_then(|region| find(&format!("{}_{}", language, region)))
+        .or_else(|| find(&language))
+}
+
+impl PluralForms {
+    /// Returns the default plural forms for the given language or locale,
+    /// e.g. `pl` or `pt_BR.UTF-8`, out of a table of common languages.
+    ///
+    /// Returns `None` if the language is not in the table.
+    pub fn for_language(language: &str) -> Option<PluralForms> {
+        plural_forms_for(language).map(|forms| PluralForms::parse(forms).unwrap())
+    }
+}
+
+#[test]
+fn test_plural_forms_table() {
+    for (language, forms) in PLURAL_FORMS {
+        let forms = PluralForms::parse(forms).unwrap();
+        assert_eq!(PluralForms::parse(&forms.to_string()).unwrap(), forms);
+        let nplurals = forms.nplurals().unwrap();
+        let compiled = Compiled::new(forms.expr().simplify());
+        for n in 0..1000 {
+            assert_eq!(compiled.evaluate(n), forms.evaluate(n));
+            assert!(
+                forms.evaluate(n) < nplurals,
+                "{}: form {} for {} out of range",
+                language,
+                forms.evaluate(n),
+                n
+            );
+        }
+    }
+}
+
+#[test]
+fn test_for_language() {
+    let evaluate = |lang: &str, ns: &[u64]| {
+        let forms = PluralForms::for_language(lang).unwrap();
+        ns.iter().map(|&n| forms.evaluate(n)).collect::<Vec<_>>()
+    };
+
+    assert_eq!(evaluate("ja", &[0, 1, 2]), vec![0, 0, 0]);
+    assert_eq!(evaluate("en_US.UTF-8", &[0, 1, 2]), vec![1, 0, 1]);
+    assert_eq!(evaluate("pt", &[0, 1, 2]), vec![1, 0, 1]);
+    assert_eq!(evaluate("pt_BR", &[0, 1, 2]), vec![0, 0, 1]);
+    assert_eq!(evaluate("pt-br", &[0, 1, 2]), vec![0, 0, 1]);
+    assert_eq!(
+        evaluate("pl", &[1, 2, 5, 12, 22, 25]),
+        vec![0, 1, 2, 2, 1, 2]
+    );
+    assert_eq!(
+        evaluate("ar", &[0, 1, 2, 3, 11, 100]),
+        vec![0, 1, 2, 3, 4, 5]
+    );
+    assert!(PluralForms::for_language("tlh").is_none());
+    assert!(PluralForms::for_language("").is_none());
+}
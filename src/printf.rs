@@ -0,0 +1,191 @@
+//! Analysis of the directives of C `printf` format strings, e.g. `%s`, `%1$d` or `%.2f`,
+//! with which `ParseOptions::validate_format_strings` checks translations.
+//!
+//! # Examples
+//!
+//! ```
+//! use gettext::printf;
+//!
+//! let directives = printf::parse("%2$s has %1$d files").unwrap();
+//! assert_eq!(directives[0].position(), 1);
+//! assert_eq!(directives[0].conversion(), 's');
+//! assert_eq!(directives[1].range(), 9..13);
+//!
+//! assert!(printf::compatible("%s has %d files", "%2$d failai turi %1$s"));
+//! assert!(!printf::compatible("%s has %d files", "%d failai turi %s"));
+//! ```
+
+use std::ops::Range;
+
+use crate::Error;
+
+/// Directive is a directive of a format string, which converts one of the arguments.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Directive {
+    range: Range<usize>,
+    position: usize,
+    positional: bool,
+    conversion: char,
+}
+
+impl Directive {
+    /// Returns the byte range of the directive in the format string.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the index of the argument that the directive converts, counted from 0,
+    /// so that it is `n - 1` for a `%n$` directive.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns whether the directive gives the position of its argument with `%n$`.
+    pub fn is_positional(&self) -> bool {
+        self.positional
+    }
+
+    /// Returns the conversion of the directive, e.g. `d` or `s`,
+    /// with `i` returned as `d`, which it is the same as.
+    pub fn conversion(&self) -> char {
+        self.conversion
+    }
+}
+
+/// Returns the directives of a format string in the order of the string,
+/// leaving out the `%%` escapes.
+///
+/// Directives without a position take the argument after the one of the previous
+/// directive without a position. Flags, widths, precisions and length modifiers
+/// do not matter, and are skipped.
+///
+/// Returns `PrintfSyntax` with the byte offset of a directive that is malformed,
+/// or that converts the same argument as a previous one with another conversion.
+pub fn parse(s: &str) -> Result<Vec<Directive>, Error> {
+    let mut directives = Vec::<Directive>::new();
+    let mut next = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        if chars.peek().map(|&(_, c)| c) == Some('%') {
+            chars.next();
+            continue;
+        }
+        let error = || Error::PrintfSyntax(start);
+        let mut digits = String::new();
+        while let Some(&(_, d)) = chars.peek().filter(|(_, d)| d.is_ascii_digit()) {
+            digits.push(d);
+            chars.next();
+        }
+        let positional = !digits.is_empty() && chars.peek().map(|&(_, c)| c) == Some('$');
+        let position = if positional {
+            chars.next();
+            let n = digits.parse::<usize>().map_err(|_| error())?;
+            n.checked_sub(1).ok_or_else(error)?
+        } else {
+            next += 1;
+            next - 1
+        };
+        while chars
+            .peek()
+            .is_some_and(|&(_, c)| "-+ #0'*.0123456789hlLqjzt".contains(c))
+        {
+            chars.next();
+        }
+        let (end, conversion) = match chars.next() {
+            Some((i, 'i')) => (i + 1, 'd'),
+            Some((i, c)) if "diouxXeEfFgGaAcspn".contains(c) => (i + c.len_utf8(), c),
+            _ => return Err(error()),
+        };
+        let conflicting = directives
+            .iter()
+            .any(|d| d.position == position && d.conversion != conversion);
+        if conflicting {
+            return Err(error());
+        }
+        directives.push(Directive {
+            range: start..end,
+            position,
+            positional,
+            conversion,
+        });
+    }
+    Ok(directives)
+}
+
+/// Returns whether a translation has the directives of the original string:
+/// both have to be well-formed and convert the same arguments in the same ways,
+/// in any order, as translators may reorder them with `%n$`.
+///
+/// The translations of a message with a plural form may leave out arguments,
+/// e.g. the number in the form for one object, which this does not allow.
+pub fn compatible(original: &str, translation: &str) -> bool {
+    let arguments = |s| {
+        let mut arguments = parse(s)
+            .ok()?
+            .into_iter()
+            .map(|d| (d.position, d.conversion))
+            .collect::<Vec<_>>();
+        arguments.sort_unstable();
+        arguments.dedup();
+        Some(arguments)
+    };
+    match (arguments(original), arguments(translation)) {
+        (Some(original), Some(translation)) => original == translation,
+        _ => false,
+    }
+}
+
+#[test]
+fn test_parse() {
+    let directives = parse("%d%% of %-10.2f %s, %lu ą%c").unwrap();
+    let summary = directives
+        .iter()
+        .map(|d| (d.position(), d.conversion(), d.range(), d.is_positional()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            (0, 'd', 0..2, false),
+            (1, 'f', 8..15, false),
+            (2, 's', 16..18, false),
+            (3, 'u', 20..23, false),
+            (4, 'c', 26..28, false),
+        ]
+    );
+
+    let directives = parse("%2$s %1$i %2$s").unwrap();
+    assert_eq!(directives[1].position(), 0);
+    assert_eq!(directives[1].conversion(), 'd');
+    assert!(directives[1].is_positional());
+    assert_eq!(directives[2].range(), 10..14);
+
+    for &(s, offset) in &[
+        ("%1$s %1$d", 5),
+        ("%0$s", 0),
+        ("ok %y", 3),
+        ("50%", 2),
+        ("%99999999999999999999$s", 0),
+    ] {
+        assert!(
+            matches!(parse(s), Err(Error::PrintfSyntax(o)) if o == offset),
+            "{}",
+            s
+        );
+    }
+}
+
+#[test]
+fn test_compatible() {
+    assert!(compatible("%d files", "%d failai"));
+    assert!(compatible("%s of %d", "%2$d iš %1$s"));
+    assert!(compatible("%s and %s", "%1$s ir %1$s bei %2$s"));
+    assert!(compatible("100%%", "100 %%"));
+    assert!(!compatible("%d files", "%s failai"));
+    assert!(!compatible("%s of %d", "%d iš %s"));
+    assert!(!compatible("%d file", "vienas failas"));
+    assert!(!compatible("%d", "%d %d"));
+    assert!(!compatible("50%", "50%"));
+}
@@ -0,0 +1,315 @@
+//! Conversion of catalogs from and to Java `.properties` files.
+
+use std::io::{Read, Write};
+
+use crate::key::{Key, KeyParts};
+use crate::Error::{self, *};
+use crate::{Catalog, CatalogBuilder, Message};
+
+/// The encoding of a written `.properties` file.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PropertiesEncoding {
+    /// ISO-8859-1, with the characters outside of ASCII escaped as `\uXXXX`
+    /// like `Properties.store` writes them, which any version of Java reads
+    Latin1,
+    /// UTF-8, which `ResourceBundle` reads since Java 9
+    Utf8,
+}
+
+/// Returns the logical lines of a file, with their line numbers,
+/// joining the lines that end with an odd number of backslashes to the next ones.
+fn logical_lines(src: &str) -> Vec<(usize, String)> {
+    let mut lines = vec![];
+    let mut physical = src.lines().enumerate();
+    while let Some((i, line)) = physical.next() {
+        let mut logical = line.trim_start().to_owned();
+        if logical.starts_with(['#', '!']) {
+            lines.push((i + 1, logical));
+            continue;
+        }
+        loop {
+            let backslashes = logical.len() - logical.trim_end_matches('\\').len();
+            if backslashes % 2 == 0 {
+                break;
+            }
+            logical.pop();
+            match physical.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+        lines.push((i + 1, logical));
+    }
+    lines
+}
+
+/// Returns the text of a key or value, undoing the escapes of `.properties` files.
+fn unescape(src: &str, line: usize) -> Result<String, Error> {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.chars();
+    let mut high = None;
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('f') => '\u{c}',
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    let unit = u16::from_str_radix(&hex, 16).ok();
+                    let unit = unit.filter(|_| hex.len() == 4 && hex.is_ascii());
+                    let unit = unit.ok_or(PropertiesSyntax(line))?;
+                    // characters outside the BMP are escaped as surrogate pairs
+                    let units = match high.take() {
+                        Some(high) => vec![high, unit],
+                        None if (0xd800..0xdc00).contains(&unit) => {
+                            high = Some(unit);
+                            continue;
+                        }
+                        None => vec![unit],
+                    };
+                    let c = char::decode_utf16(units).next().unwrap();
+                    c.map_err(|_| PropertiesSyntax(line))?
+                }
+                Some(c) => c,
+                None => break,
+            },
+            c => c,
+        };
+        if high.is_some() {
+            return Err(PropertiesSyntax(line));
+        }
+        out.push(c);
+    }
+    match high {
+        Some(_) => Err(PropertiesSyntax(line)),
+        None => Ok(out),
+    }
+}
+
+/// Returns the text escaped for a `.properties` file, the way `unescape` reads it back.
+///
+/// In keys, the separators are escaped too.
+fn escape(text: &str, key: bool, encoding: PropertiesEncoding) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{c}' => out.push_str("\\f"),
+            ' ' if key || i == 0 => out.push_str("\\ "),
+            '=' | ':' | '#' | '!' if key => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() || (c > '~' && encoding == PropertiesEncoding::Latin1) => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl Catalog {
+    /// Reads a catalog from a Java `.properties` file, e.g. `Messages_lt.properties`.
+    ///
+    /// The keys are the msgids, joined to the msgctxt with `\u{4}`
+    /// for messages with a context, like in MO files.
+    /// The comments before an entry become its extracted comments.
+    /// The file is read as UTF-8 if it is valid UTF-8, and as ISO-8859-1 otherwise,
+    /// like `ResourceBundle` does since Java 9.
+    ///
+    /// Returns `PropertiesSyntax` if a `\uXXXX` escape is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::Catalog;
+    ///
+    /// let properties = b"# A menu item\nopen = Atidaryti\n! Leaves\nquit: I\\u0161eiti\nhelp Pagalba \\\n    ir patarimai\n";
+    /// let catalog = Catalog::parse_properties(&properties[..]).unwrap();
+    /// assert_eq!(catalog.gettext("open"), "Atidaryti");
+    /// assert_eq!(catalog.get("quit").unwrap().extracted_comments(), &["Leaves"]);
+    /// assert_eq!(catalog.gettext("quit"), "Išeiti");
+    /// assert_eq!(catalog.gettext("help"), "Pagalba ir patarimai");
+    /// ```
+    pub fn parse_properties<R: Read>(mut reader: R) -> Result<Catalog, Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let src = match String::from_utf8(bytes) {
+            Ok(src) => src,
+            Err(err) => err.into_bytes().into_iter().map(char::from).collect(),
+        };
+        let src = src.strip_prefix('\u{feff}').unwrap_or(&src);
+        let mut catalog = CatalogBuilder::new()
+            .header("Content-Type", "text/plain; charset=UTF-8")
+            .build()?;
+        let mut comments = vec![];
+        for (line_no, line) in logical_lines(src) {
+            if line.is_empty() {
+                comments.clear();
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix(['#', '!']) {
+                comments.push(comment.trim().to_owned());
+                continue;
+            }
+            // the key ends at the first unescaped separator or whitespace
+            let mut end = line.len();
+            let mut escaped = false;
+            for (i, c) in line.char_indices() {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '=' | ':' | ' ' | '\t' | '\u{c}' => {
+                        end = i;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            let rest = line[end..].trim_start_matches([' ', '\t', '\u{c}']);
+            let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+            let value = rest.trim_start_matches([' ', '\t', '\u{c}']);
+
+            let key = Key::from(&unescape(&line[..end], line_no)?[..]);
+            let (context, id) = key.parts();
+            let mut msg = Message::new(id, context, vec![]);
+            msg.translated = vec![unescape(value, line_no)?];
+            msg.extracted_comments = comments.split_off(0);
+            catalog.insert(msg);
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the translated messages of the catalog without a plural form
+    /// out as a Java `.properties` file in the given encoding, keyed as `parse_properties`
+    /// reads them and sorted by their keys.
+    ///
+    /// Extracted comments are written as comments before their entries.
+    /// Untranslated messages are left out, so that `ResourceBundle` falls back
+    /// to the parent bundle, and so are plural messages, which `.properties` files can not have.
+    pub fn write_properties<W: Write>(
+        &self,
+        mut writer: W,
+        encoding: PropertiesEncoding,
+    ) -> Result<(), Error> {
+        let mut messages = self
+            .strings
+            .iter()
+            .filter(|(key, msg)| !key.is_header() && msg.id_plural.is_none())
+            .filter(|(_, msg)| msg.is_translated())
+            .collect::<Vec<_>>();
+        messages.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut out = String::new();
+        for (key, msg) in messages {
+            for comment in &msg.extracted_comments {
+                out.push_str(format!("# {}", comment).trim_end());
+                out.push('\n');
+            }
+            out.push_str(&format!(
+                "{}={}\n",
+                escape(key.as_str(), true, encoding),
+                escape(&msg.translated[0], false, encoding)
+            ));
+        }
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_escape() {
+    use PropertiesEncoding::*;
+
+    for &(text, key, escaped) in &[
+        ("plain", false, "plain"),
+        (" lead and trail ", false, "\\ lead and trail "),
+        ("a b=c:d#e!f", true, "a\\ b\\=c\\:d\\#e\\!f"),
+        ("a=b:c", false, "a=b:c"),
+        ("tab\tnew\nline\\", false, "tab\\tnew\\nline\\\\"),
+        ("ąž 😀", false, "\\u0105\\u017E \\uD83D\\uDE00"),
+        ("\u{4}", true, "\\u0004"),
+    ] {
+        assert_eq!(escape(text, key, Latin1), escaped);
+        assert_eq!(unescape(escaped, 1).unwrap(), text);
+    }
+    assert_eq!(escape("ąž 😀", false, Utf8), "ąž 😀");
+    assert_eq!(unescape("\\a\\", 1).unwrap(), "a");
+    for bad in &["\\u12", "\\u12g4", "\\uD83D", "\\uD83Dx", "\\uDE00"] {
+        assert!(
+            matches!(unescape(bad, 3), Err(PropertiesSyntax(3))),
+            "{}",
+            bad
+        );
+    }
+}
+
+#[test]
+fn test_properties() {
+    let src = b"\
+# Generated file
+
+! The menu
+#   with comments
+open=Atidaryti
+quit : I\\u0161eiti
+  key\\ with\\ spaces   value with spaces
+multi = first \\
+        second\\\\
+last\\:key
+menu\\u0004Open=Meniu
+latin1=\xe0 la carte
+";
+    let catalog = Catalog::parse_properties(&src[..]).unwrap();
+    assert_eq!(catalog.len(), 8);
+    assert_eq!(catalog.gettext("open"), "Atidaryti");
+    assert_eq!(
+        catalog.get("open").unwrap().extracted_comments(),
+        &["The menu", "with comments"]
+    );
+    assert_eq!(catalog.gettext("quit"), "Išeiti");
+    assert_eq!(catalog.gettext("key with spaces"), "value with spaces");
+    assert_eq!(catalog.gettext("multi"), "first second\\");
+    assert!(catalog.get("last:key").is_some());
+    assert_eq!(catalog.pgettext("menu", "Open"), "Meniu");
+    assert_eq!(catalog.gettext("latin1"), "à la carte");
+
+    let mut out = vec![];
+    catalog
+        .write_properties(&mut out, PropertiesEncoding::Latin1)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        "\
+key\\ with\\ spaces=value with spaces\n\
+latin1=\\u00E0 la carte\n\
+menu\\u0004Open=Meniu\n\
+multi=first second\\\\\n\
+# The menu\n\
+# with comments\n\
+open=Atidaryti\n\
+quit=I\\u0161eiti\n"
+    );
+    let read = Catalog::parse_properties(out.as_bytes()).unwrap();
+    let translated = catalog.strings.values().filter(|msg| msg.is_translated());
+    for msg in translated.filter(|msg| !msg.is_header()) {
+        assert_eq!(read.lookup(msg.context(), msg.id()), Some(msg));
+    }
+
+    let mut out = vec![];
+    catalog
+        .write_properties(&mut out, PropertiesEncoding::Utf8)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("quit=Išeiti\n"));
+}
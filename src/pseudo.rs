@@ -0,0 +1,242 @@
+//! Pseudo-localization of catalogs, for finding hardcoded and truncated strings
+//! before real translations exist.
+
+use crate::parser::default_resolver;
+use crate::plurals::Resolver;
+use crate::Catalog;
+
+const LOWER: [char; 26] = [
+    'ȧ', 'ƀ', 'ƈ', 'ḓ', 'ḗ', 'ƒ', 'ɠ', 'ħ', 'ī', 'ĵ', 'ķ', 'ŀ', 'ḿ', 'ƞ', 'ǿ', 'ƥ', 'ɋ', 'ř', 'ş',
+    'ŧ', 'ŭ', 'ṽ', 'ẇ', 'ẋ', 'ẏ', 'ẑ',
+];
+const UPPER: [char; 26] = [
+    'Ȧ', 'Ɓ', 'Ƈ', 'Ḓ', 'Ḗ', 'Ƒ', 'Ɠ', 'Ħ', 'Ī', 'Ĵ', 'Ķ', 'Ŀ', 'Ḿ', 'Ƞ', 'Ǿ', 'Ƥ', 'Ɋ', 'Ř', 'Ş',
+    'Ŧ', 'Ŭ', 'Ṽ', 'Ẇ', 'Ẋ', 'Ẏ', 'Ẑ',
+];
+
+/// The largest expansion, as a share of the length of the strings.
+const MAX_EXPANSION: f64 = 10.0;
+
+/// PseudoLocalizer turns source strings into pseudo-translations
+/// that are still readable but stand out in the user interface,
+/// e.g. `[Ǿƥḗƞ ƒīŀḗ ~~~]` for `Open file`.
+///
+/// Letters are replaced by accented lookalikes, the result is enclosed in brackets
+/// so that truncation is visible, and padding simulates the longer text
+/// of other languages. `format!` and `printf` placeholders as well as markup tags
+/// are left untouched, so that the strings can still be formatted.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::PseudoLocalizer;
+///
+/// let pseudo = PseudoLocalizer::new();
+/// assert_eq!(pseudo.transform("Open {name}"), "[Ǿƥḗƞ {name} ~~~~]");
+/// let pseudo = pseudo.brackets(false).expansion(0.0);
+/// assert_eq!(pseudo.transform("%d files"), "%d ƒīŀḗş");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PseudoLocalizer {
+    accents: bool,
+    brackets: bool,
+    expansion: f64,
+}
+
+impl Default for PseudoLocalizer {
+    fn default() -> Self {
+        PseudoLocalizer {
+            accents: true,
+            brackets: true,
+            expansion: 0.3,
+        }
+    }
+}
+
+impl PseudoLocalizer {
+    /// Creates a localizer replacing letters with accented ones,
+    /// adding brackets and making strings 30% longer.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets whether letters are replaced by accented lookalikes.
+    pub fn accents(mut self, accents: bool) -> Self {
+        self.accents = accents;
+        self
+    }
+
+    /// Sets whether the strings are enclosed in brackets.
+    pub fn brackets(mut self, brackets: bool) -> Self {
+        self.brackets = brackets;
+        self
+    }
+
+    /// Sets by how much the strings are made longer,
+    /// as a share of their number of characters, e.g. `0.3` for 30%.
+    ///
+    /// Negative and NaN factors are treated as 0, and factors above 10
+    /// (including infinity) as 10.
+    pub fn expansion(mut self, expansion: f64) -> Self {
+        self.expansion = if expansion.is_nan() {
+            0.0
+        } else {
+            expansion.clamp(0.0, MAX_EXPANSION)
+        };
+        self
+    }
+
+    /// Returns the pseudo-translation of `text`.
+    /// The empty string is left as it is.
+    pub fn transform(&self, text: &str) -> String {
+        if text.is_empty() {
+            return String::new();
+        }
+        let mut result = String::with_capacity(text.len() * 2);
+        if self.brackets {
+            result.push('[');
+        }
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            let len = match verbatim(rest) {
+                0 => {
+                    result.push(if self.accents { accented(c) } else { c });
+                    c.len_utf8()
+                }
+                len => {
+                    result.push_str(&rest[..len]);
+                    len
+                }
+            };
+            rest = &rest[len..];
+        }
+        let padding = (text.chars().count() as f64 * self.expansion).ceil() as usize;
+        if padding > 0 {
+            result.push(' ');
+            result.extend((0..padding).map(|_| '~'));
+        }
+        if self.brackets {
+            result.push(']');
+        }
+        result
+    }
+}
+
+/// Returns the accented lookalike of an ASCII letter, or the character itself.
+fn accented(c: char) -> char {
+    match c {
+        'a'..='z' => LOWER[c as usize - 'a' as usize],
+        'A'..='Z' => UPPER[c as usize - 'A' as usize],
+        _ => c,
+    }
+}
+
+/// Returns the length of the placeholder or tag that `s` starts with,
+/// or 0 if it starts with text.
+fn verbatim(s: &str) -> usize {
+    let end = |close: char| s.find(close).map_or(0, |i| i + 1);
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some('{'), Some('{')) | (Some('%'), Some('%')) => 2,
+        (Some('{'), _) => end('}'),
+        (Some('<'), Some(c)) if c == '/' || c.is_ascii_alphabetic() => end('>'),
+        (Some('%'), Some(_)) => {
+            let directive = s[1..]
+                .find(|c: char| !"-+ #0'*.$0123456789hlLqjzt".contains(c))
+                .map_or(0, |i| i + 1);
+            match s[directive..].chars().next() {
+                Some(c) if "diouxXeEfFgGaAcspn".contains(c) => directive + 1,
+                _ => 0,
+            }
+        }
+        _ => 0,
+    }
+}
+
+impl Catalog {
+    /// Returns a catalog translating every message of this one
+    /// into its pseudo-translation, made from the msgid and its plural form.
+    ///
+    /// The plural forms of the result are those of English,
+    /// and messages missing from this catalog are still left untranslated.
+    pub fn pseudo_localized(&self, pseudo: &PseudoLocalizer) -> Catalog {
+        let mut catalog = self.clone();
+        catalog.resolver = Resolver::Function(default_resolver);
+        for message in catalog.strings.values_mut() {
+            if message.is_header() {
+                continue;
+            }
+            message.translated = match message.id_plural {
+                Some(ref plural) => vec![pseudo.transform(&message.id), pseudo.transform(plural)],
+                None => vec![pseudo.transform(&message.id)],
+            };
+        }
+        catalog
+    }
+}
+
+#[test]
+fn test_pseudo_localizer() {
+    let pseudo = PseudoLocalizer::new();
+    assert_eq!(pseudo.transform(""), "");
+    assert_eq!(pseudo.transform("Open file"), "[Ǿƥḗƞ ƒīŀḗ ~~~]");
+    assert_eq!(
+        pseudo.transform("Hi {0}, {{ok}} <b>%1$s</b> 100%% %-5.2f %y"),
+        "[Ħī {0}, {{ǿķ}} <b>%1$s</b> 100%% %-5.2f %ẏ ~~~~~~~~~~~~~]"
+    );
+    assert_eq!(pseudo.transform("a < b"), "[ȧ < ƀ ~~]");
+    assert_eq!(pseudo.transform("{unclosed"), "[{ŭƞƈŀǿşḗḓ ~~~]");
+    let plain = pseudo.accents(false).expansion(1.0);
+    assert_eq!(plain.transform("Ünï"), "[Ünï ~~~]");
+    assert_eq!(plain.brackets(false).expansion(-1.0).transform("x"), "x");
+    assert_eq!(
+        plain.brackets(false).expansion(f64::NAN).transform("x"),
+        "x"
+    );
+    let long = format!("x {}", "~".repeat(10));
+    assert_eq!(
+        plain
+            .brackets(false)
+            .expansion(f64::INFINITY)
+            .transform("x"),
+        long
+    );
+    assert_eq!(plain.brackets(false).expansion(1e300).transform("x"), long);
+}
+
+#[test]
+fn test_pseudo_localized() {
+    use crate::CatalogBuilder;
+
+    let catalog = CatalogBuilder::new()
+        .plural_forms("nplurals=3; plural=n%10==1 ? 0 : n == 2 ? 1 : 2;")
+        .add("Open", "Atidaryti")
+        .add("Close", "")
+        .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+        .add_with_context("menu", "Edit", "Redaguoti")
+        .build()
+        .unwrap()
+        .pseudo_localized(&PseudoLocalizer::new().expansion(0.0));
+    assert_eq!(catalog.gettext("Open"), "[Ǿƥḗƞ]");
+    assert_eq!(catalog.gettext("Close"), "[Ƈŀǿşḗ]");
+    assert_eq!(catalog.ngettext("File", "Files", 1), "[Ƒīŀḗ]");
+    assert_eq!(catalog.ngettext("File", "Files", 2), "[Ƒīŀḗş]");
+    assert_eq!(catalog.ngettext("File", "Files", 11), "[Ƒīŀḗş]");
+    assert_eq!(catalog.pgettext("menu", "Edit"), "[Ḗḓīŧ]");
+    assert_eq!(catalog.gettext("Missing"), "Missing");
+    assert_eq!(catalog.stats().translated(), 4);
+
+    let pseudo = PseudoLocalizer::new().brackets(false).expansion(0.0);
+    let mo = &include_bytes!("../test_cases/1.mo")[..];
+    let catalog = crate::ParseOptions::new()
+        .pseudo_localize(pseudo)
+        .parse(mo)
+        .unwrap();
+    assert_eq!(catalog.pgettext("this is context", "Text"), "Ŧḗẋŧ");
+    let po = &include_bytes!("../test_cases/1.po")[..];
+    let catalog = crate::ParseOptions::new()
+        .pseudo_localize(pseudo)
+        .parse_po(po)
+        .unwrap();
+    assert_eq!(catalog.pgettext("this is context", "Text"), "Ŧḗẋŧ");
+}
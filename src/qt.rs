@@ -0,0 +1,418 @@
+//! Conversion of catalogs from and to Qt Linguist TS files.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::key::Key;
+use crate::xml::{self, Element};
+use crate::{Catalog, CatalogBuilder, Error, Flag, Message};
+
+/// The header field keeping the source language of a TS file.
+const SOURCE_LANGUAGE_HEADER: &str = "X-Source-Language";
+
+/// Separates the Qt context from the disambiguating comment in a msgctxt.
+const SEPARATOR: char = '|';
+
+/// Returns the Qt context and the disambiguating comment of a msgctxt.
+fn split_context(context: Option<&str>) -> (&str, Option<&str>) {
+    match context {
+        Some(context) => match context.split_once(SEPARATOR) {
+            Some((name, comment)) => (name, Some(comment)),
+            None => (context, None),
+        },
+        None => ("", None),
+    }
+}
+
+/// Returns the msgctxt of a Qt context and disambiguating comment.
+fn join_context(name: &str, comment: Option<String>) -> Option<String> {
+    match comment.filter(|c| !c.is_empty()) {
+        Some(comment) => Some(format!("{}{}{}", name, SEPARATOR, comment)),
+        None if name.is_empty() => None,
+        None => Some(name.to_owned()),
+    }
+}
+
+/// Returns the lines of a comment, which TS files keep in a single element.
+fn lines(element: Option<&Element>) -> Vec<String> {
+    match element.map(Element::content) {
+        Some(text) if !text.is_empty() => text.split('\n').map(str::to_owned).collect(),
+        _ => vec![],
+    }
+}
+
+/// Reads a `message` element, returning it with whether it is obsolete.
+fn read_message(context: &str, element: &Element) -> Result<(Message, bool), Error> {
+    let text = |name: &str| element.find(name).map(Element::content);
+    let id = text("source").ok_or_else(|| element.error())?;
+    let context = join_context(context, text("comment"));
+    let translation = element.find("translation");
+    let numerus = element.get("numerus") == Some("yes");
+    let translated = match translation {
+        Some(translation) if numerus => translation
+            .elements()
+            .filter(|e| e.name == "numerusform" || e.name == "lengthvariant")
+            .map(Element::content)
+            .collect(),
+        Some(translation) => vec![translation.content()],
+        None => vec![],
+    };
+    let kind = translation.and_then(|t| t.get("type"));
+    let obsolete = kind == Some("obsolete") || kind == Some("vanished");
+    let mut msg = Message::new(id, context, translated);
+    if numerus {
+        if msg.translated.len() < 2 {
+            msg.translated.resize(2, String::new());
+        }
+        msg.id_plural = Some(text("extra-po-msgid_plural").unwrap_or_else(|| msg.id.clone()));
+    } else if msg.translated.is_empty() {
+        msg.translated.push(String::new());
+    }
+    for flag in text("extra-po-flags").iter().flat_map(|f| f.split(',')) {
+        if !flag.trim().is_empty() {
+            msg.flags.insert(Flag::from(flag.trim()));
+        }
+    }
+    // unfinished translations with all of their forms need to be reviewed
+    if kind == Some("unfinished") && msg.translated.iter().all(|s| !s.is_empty()) {
+        msg.flags.insert(Flag::Fuzzy);
+    }
+    msg.previous_id = text("oldsource");
+    msg.translator_comments = lines(element.find("translatorcomment"));
+    msg.extracted_comments = lines(element.find("extracomment"));
+    for location in element.elements().filter(|e| e.name == "location") {
+        let file = location.get("filename").ok_or_else(|| location.error())?;
+        msg.references.push(match location.get("line") {
+            Some(line) => format!("{}:{}", file, line),
+            None => file.to_owned(),
+        });
+    }
+    Ok((msg, obsolete))
+}
+
+/// Returns the `message` element of a message.
+fn message(msg: &Message, obsolete: bool) -> Element {
+    let mut element = Element::new("message");
+    if msg.id_plural.is_some() {
+        element = element.attr("numerus", "yes");
+    }
+    for reference in &msg.references {
+        let mut location = Element::new("location");
+        location = match reference.rsplit_once(':') {
+            Some((file, line)) if line.parse::<u64>().is_ok() => {
+                location.attr("filename", file).attr("line", line)
+            }
+            _ => location.attr("filename", reference),
+        };
+        element = element.child(location);
+    }
+    element = element.child(Element::new("source").text(&msg.id));
+    if let Some(ref previous) = msg.previous_id {
+        element = element.child(Element::new("oldsource").text(previous));
+    }
+    if let (_, Some(comment)) = split_context(msg.context.as_deref()) {
+        element = element.child(Element::new("comment").text(comment));
+    }
+    if !msg.extracted_comments.is_empty() {
+        let comment = msg.extracted_comments.join("\n");
+        element = element.child(Element::new("extracomment").text(&comment));
+    }
+    if !msg.translator_comments.is_empty() {
+        let comment = msg.translator_comments.join("\n");
+        element = element.child(Element::new("translatorcomment").text(&comment));
+    }
+    let mut translation = Element::new("translation");
+    if obsolete {
+        translation = translation.attr("type", "obsolete");
+    } else if msg.is_fuzzy() || msg.translated.iter().any(String::is_empty) {
+        translation = translation.attr("type", "unfinished");
+    }
+    translation = match msg.id_plural {
+        Some(_) => msg
+            .translated
+            .iter()
+            .fold(translation, |translation, form| {
+                translation.child(Element::new("numerusform").text(form))
+            }),
+        None => translation.text(&msg.translated[0]),
+    };
+    element = element.child(translation);
+    match msg.id_plural {
+        Some(ref plural) if *plural != msg.id => {
+            element = element.child(Element::new("extra-po-msgid_plural").text(plural));
+        }
+        _ => {}
+    }
+    let flags = msg
+        .flags
+        .iter()
+        .filter(|&f| *f != Flag::Fuzzy)
+        .map(Flag::as_str)
+        .collect::<Vec<_>>();
+    if !flags.is_empty() {
+        element = element.child(Element::new("extra-po-flags").text(&flags.join(", ")));
+    }
+    element
+}
+
+impl Catalog {
+    /// Reads a catalog from a Qt Linguist TS file, as written by `lupdate`.
+    ///
+    /// The Qt context of each message becomes its msgctxt, followed by `|`
+    /// and the disambiguating comment if there is one.
+    /// Numerus messages become plural messages with the same msgid and plural msgid,
+    /// as TS files have a single source for all the forms.
+    /// Unfinished translations with all of their forms are flagged as `fuzzy`,
+    /// and obsolete and vanished messages become obsolete messages.
+    ///
+    /// The `language` of the file becomes the `Language` of the catalog,
+    /// which also decides its plural formula.
+    ///
+    /// Returns `XmlSyntax` if the file is malformed or not a TS file.
+    pub fn parse_ts<R: Read>(reader: R) -> Result<Catalog, Error> {
+        let root = xml::read(reader)?;
+        if root.name != "TS" {
+            return Err(root.error());
+        }
+        let mut builder = CatalogBuilder::new().header("Content-Type", "text/plain; charset=UTF-8");
+        if let Some(language) = root.get("language") {
+            builder = builder.header("Language", language);
+        }
+        if let Some(language) = root.get("sourcelanguage") {
+            builder = builder.header(SOURCE_LANGUAGE_HEADER, language);
+        }
+        let mut catalog = builder.build()?;
+        for context in root.elements().filter(|e| e.name == "context") {
+            let name = context.find("name").map(Element::content);
+            let name = name.ok_or_else(|| context.error())?;
+            for element in context.elements().filter(|e| e.name == "message") {
+                match read_message(&name, element)? {
+                    (msg, true) => {
+                        let key = Key::new(msg.context.as_deref(), &msg.id);
+                        catalog.obsolete.insert(key, msg);
+                    }
+                    (msg, false) => catalog.insert(msg),
+                }
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the catalog out as a Qt Linguist TS file,
+    /// so that Qt applications and translators using Qt Linguist can use it.
+    ///
+    /// Messages are grouped in Qt contexts by their msgctxt, split at the first `|`
+    /// as `parse_ts` joins them. Plural messages become numerus messages,
+    /// keeping their plural msgid as an `extra-po-msgid_plural` element,
+    /// and `fuzzy` messages and messages with untranslated forms are marked as unfinished.
+    /// The header is left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{Catalog, CatalogBuilder};
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .header("Language", "lt")
+    ///     .add_with_context("MainWindow", "Open", "Atidaryti")
+    ///     .add_plural("%n file", "%n files", vec!["%n failas", "%n failai", "%n failų"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut ts = vec![];
+    /// catalog.write_ts(&mut ts).unwrap();
+    /// let read = Catalog::parse_ts(&ts[..]).unwrap();
+    /// assert_eq!(read.pgettext("MainWindow", "Open"), "Atidaryti");
+    /// assert_eq!(read.ngettext("%n file", "%n files", 10), "%n failų");
+    /// ```
+    pub fn write_ts<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let messages = self
+            .strings
+            .iter()
+            .filter(|(key, _)| !key.is_header())
+            .map(|entry| (entry, false))
+            .chain(self.obsolete.iter().map(|entry| (entry, true)));
+        let mut contexts = BTreeMap::new();
+        for ((key, msg), obsolete) in messages {
+            let (name, _) = split_context(msg.context.as_deref());
+            let context = contexts.entry(name).or_insert_with(Vec::new);
+            context.push((obsolete, key, msg));
+        }
+
+        let metadata = self.metadata();
+        let mut root = Element::new("TS").attr("version", "2.1");
+        if let Some(language) = metadata.language() {
+            root = root.attr("language", language);
+        }
+        if let Some(language) = metadata.get(SOURCE_LANGUAGE_HEADER) {
+            root = root.attr("sourcelanguage", language);
+        }
+        for (name, mut messages) in contexts {
+            // the obsolete messages come last, as lupdate writes them
+            messages.sort_by_key(|&(obsolete, key, _)| (obsolete, key));
+            let context = Element::new("context").child(Element::new("name").text(name));
+            root = root.child(
+                messages
+                    .into_iter()
+                    .fold(context, |context, (obsolete, _, msg)| {
+                        context.child(message(msg, obsolete))
+                    }),
+            );
+        }
+        xml::write(&root, writer)
+    }
+}
+
+#[test]
+fn test_context() {
+    assert_eq!(split_context(None), ("", None));
+    assert_eq!(split_context(Some("Main")), ("Main", None));
+    assert_eq!(split_context(Some("Main|verb|x")), ("Main", Some("verb|x")));
+    assert_eq!(join_context("", None), None);
+    assert_eq!(join_context("", Some(String::new())), None);
+    assert_eq!(join_context("Main", None), Some("Main".to_owned()));
+    assert_eq!(
+        join_context("", Some("verb".to_owned())),
+        Some("|verb".to_owned())
+    );
+    assert_eq!(split_context(Some("|verb")), ("", Some("verb")));
+}
+
+#[test]
+fn test_parse_ts() {
+    let ts = r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE TS>
+<TS version="2.1" language="lt_LT" sourcelanguage="en">
+<context>
+    <name>MainWindow</name>
+    <message>
+        <location filename="../src/mainwindow.cpp" line="42"/>
+        <location filename="../src/menu.cpp"/>
+        <source>&amp;Open</source>
+        <oldsource>Open</oldsource>
+        <comment>verb</comment>
+        <extracomment>The menu item
+opening a file</extracomment>
+        <translatorcomment>checked</translatorcomment>
+        <translation type="unfinished">&amp;Atidaryti</translation>
+    </message>
+    <message numerus="yes">
+        <source>%n file(s)</source>
+        <translation>
+            <numerusform>%n failas</numerusform>
+            <numerusform>%n failai</numerusform>
+            <numerusform>%n failų</numerusform>
+        </translation>
+    </message>
+    <message>
+        <source>Quit</source>
+        <translation type="unfinished"></translation>
+    </message>
+    <message>
+        <source>Exit</source>
+        <translation type="vanished">Išeiti</translation>
+    </message>
+</context>
+<context>
+    <name></name>
+    <message><source>Help</source><translation>Pagalba</translation></message>
+</context>
+</TS>
+"#;
+    let catalog = Catalog::parse_ts(ts.as_bytes()).unwrap();
+    assert_eq!(catalog.metadata().language(), Some("lt_LT"));
+    assert_eq!(catalog.len(), 5);
+
+    let open = catalog.lookup(Some("MainWindow|verb"), "&Open").unwrap();
+    assert_eq!(open.translated(), &["&Atidaryti"]);
+    assert!(open.is_fuzzy());
+    assert_eq!(open.previous_id(), Some("Open"));
+    assert_eq!(
+        open.references(),
+        &["../src/mainwindow.cpp:42", "../src/menu.cpp"]
+    );
+    assert_eq!(
+        open.extracted_comments(),
+        &["The menu item", "opening a file"]
+    );
+    assert_eq!(open.translator_comments(), &["checked"]);
+
+    let files = catalog.lookup(Some("MainWindow"), "%n file(s)").unwrap();
+    assert_eq!(files.id_plural(), Some("%n file(s)"));
+    assert!(!files.is_fuzzy());
+    assert_eq!(
+        catalog.npgettext("MainWindow", "%n file(s)", "%n file(s)", 12),
+        "%n failų"
+    );
+    let quit = catalog.lookup(Some("MainWindow"), "Quit").unwrap();
+    assert!(!quit.is_translated());
+    assert!(!quit.is_fuzzy());
+    assert_eq!(catalog.gettext("Help"), "Pagalba");
+    assert_eq!(catalog.obsolete().next().unwrap().id(), "Exit");
+
+    for bad in &[
+        "<TT/>",
+        "<TS><context><message><source>a</source></message></context></TS>",
+        "<TS><context><name/><message/></context></TS>",
+        "<TS><context><name/><message><location/><source>a</source></message></context></TS>",
+    ] {
+        let result = Catalog::parse_ts(bad.as_bytes());
+        assert!(matches!(result, Err(Error::XmlSyntax(_))), "{}", bad);
+    }
+}
+
+#[test]
+fn test_write_ts() {
+    let po = "msgid \"\"\nmsgstr \"\"\n\"Language: lt\\n\"\n\"X-Source-Language: en\\n\"\n\n# checked\n#. a verb\n#: src/main.rs:10\n#, fuzzy, rust-format\n#| msgid \"Open\"\nmsgctxt \"Main|verb\"\nmsgid \"Open <b>file</b>\"\nmsgstr \"Atidaryti <b>failą</b>\"\n\nmsgctxt \"Main\"\nmsgid \"{} file\"\nmsgid_plural \"{} files\"\nmsgstr[0] \"{} failas\"\nmsgstr[1] \"{} failai\"\nmsgstr[2] \"\"\n\nmsgid \"Quit\"\nmsgstr \"\"\n\n#~ msgctxt \"Main\"\n#~ msgid \"Old\"\n#~ msgstr \"Senas\"\n";
+    let catalog = Catalog::parse_po(po.as_bytes()).unwrap();
+    let mut ts = vec![];
+    catalog.write_ts(&mut ts).unwrap();
+    assert_eq!(
+        String::from_utf8(ts.clone()).unwrap(),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<TS version="2.1" language="lt" sourcelanguage="en">
+  <context>
+    <name></name>
+    <message>
+      <source>Quit</source>
+      <translation type="unfinished"></translation>
+    </message>
+  </context>
+  <context>
+    <name>Main</name>
+    <message numerus="yes">
+      <source>{} file</source>
+      <translation type="unfinished">
+        <numerusform>{} failas</numerusform>
+        <numerusform>{} failai</numerusform>
+        <numerusform></numerusform>
+      </translation>
+      <extra-po-msgid_plural>{} files</extra-po-msgid_plural>
+    </message>
+    <message>
+      <location filename="src/main.rs" line="10"/>
+      <source>Open &lt;b&gt;file&lt;/b&gt;</source>
+      <oldsource>Open</oldsource>
+      <comment>verb</comment>
+      <extracomment>a verb</extracomment>
+      <translatorcomment>checked</translatorcomment>
+      <translation type="unfinished">Atidaryti &lt;b&gt;failą&lt;/b&gt;</translation>
+      <extra-po-flags>rust-format</extra-po-flags>
+    </message>
+    <message>
+      <source>Old</source>
+      <translation type="obsolete">Senas</translation>
+    </message>
+  </context>
+</TS>
+"#
+    );
+
+    let read = Catalog::parse_ts(&ts[..]).unwrap();
+    assert_eq!(read.len(), catalog.len());
+    for msg in catalog.strings.values().filter(|msg| !msg.is_header()) {
+        let key = Key::new(msg.context(), msg.id());
+        assert_eq!(read.strings.get(&key), Some(msg));
+    }
+    assert_eq!(read.obsolete, catalog.obsolete);
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::extract::TEMPLATE_HEADER;
+use crate::{Catalog, Error, Message, Translator};
+
+/// RecordingCatalog wraps a translator and records every message looked up through it,
+/// so that the strings a program actually requests can be written out as a POT file.
+///
+/// This catches the msgids built at runtime, which static extraction cannot see,
+/// and lets integration tests check which strings a code path uses.
+/// The messages are recorded whether or not they are translated.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{Catalog, RecordingCatalog, Translator};
+///
+/// let recording = RecordingCatalog::new(Catalog::empty());
+/// let status = format!("status.{}", "idle");
+/// recording.gettext(&status);
+/// recording.ngettext("One file", "{} files", 2);
+///
+/// let mut pot = vec![];
+/// recording.write_pot(&mut pot).unwrap();
+/// assert!(String::from_utf8(pot).unwrap().contains("msgid \"status.idle\"\n"));
+/// ```
+pub struct RecordingCatalog<T> {
+    inner: T,
+    template: Mutex<Catalog>,
+}
+
+impl<T: Translator> RecordingCatalog<T> {
+    /// Wraps `inner`, with no messages recorded yet.
+    pub fn new(inner: T) -> Self {
+        let mut template = Catalog::new();
+        template.insert(Message::new("", None, vec![TEMPLATE_HEADER]));
+        RecordingCatalog {
+            inner,
+            template: Mutex::new(template),
+        }
+    }
+
+    /// Returns the wrapped translator.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the wrapped translator, dropping the recorded messages.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns the template catalog of the messages recorded so far,
+    /// with empty translations.
+    pub fn template(&self) -> Catalog {
+        self.template.lock().unwrap().clone()
+    }
+
+    /// Writes the messages recorded so far as a POT file.
+    pub fn write_pot<W: Write>(&self, writer: W) -> Result<(), Error> {
+        self.template.lock().unwrap().write_po(writer)
+    }
+
+    /// Forgets the messages recorded so far.
+    pub fn clear(&self) {
+        let mut template = self.template.lock().unwrap();
+        template.strings.retain(|_, msg| msg.is_header());
+    }
+
+    fn record(&self, msg_context: Option<&str>, msg_id: &str, msg_id_plural: Option<&str>) {
+        let mut template = self.template.lock().unwrap();
+        let known = template.message(msg_context, msg_id);
+        if known.is_some_and(|msg| msg_id_plural.is_none() || msg.id_plural.is_some()) {
+            return;
+        }
+        let mut msg = Message::new(msg_id, msg_context, vec![""]);
+        if let Some(plural) = msg_id_plural {
+            msg.id_plural = Some(plural.to_owned());
+            msg.translated.push(String::new());
+        }
+        template.insert(msg);
+    }
+}
+
+impl<T: Translator> Translator for RecordingCatalog<T> {
+    fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str {
+        self.record(None, msg_id, None);
+        self.inner.gettext(msg_id)
+    }
+
+    fn ngettext<'a>(&'a self, msg_id: &'a str, msg_id_plural: &'a str, n: u64) -> &'a str {
+        self.record(None, msg_id, Some(msg_id_plural));
+        self.inner.ngettext(msg_id, msg_id_plural, n)
+    }
+
+    fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str {
+        self.record(Some(msg_context), msg_id, None);
+        self.inner.pgettext(msg_context, msg_id)
+    }
+
+    fn npgettext<'a>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        self.record(Some(msg_context), msg_id, Some(msg_id_plural));
+        self.inner.npgettext(msg_context, msg_id, msg_id_plural, n)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RecordingCatalog<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("RecordingCatalog")
+            .field("inner", &self.inner)
+            .field("recorded", &(self.template.lock().unwrap().len() - 1))
+            .finish()
+    }
+}
+
+#[test]
+fn test_recording_catalog() {
+    use crate::CatalogBuilder;
+
+    let catalog = CatalogBuilder::new().add("Open", "Ouvrir").build().unwrap();
+    let recording = RecordingCatalog::new(&catalog);
+    assert_eq!(recording.gettext("Open"), "Ouvrir");
+    assert_eq!(recording.gettext("Open"), "Ouvrir");
+    assert_eq!(recording.gettext("File"), "File");
+    assert_eq!(recording.ngettext("File", "Files", 2), "Files");
+    assert_eq!(recording.pgettext("menu", "Open"), "Open");
+    assert_eq!(recording.npgettext("menu", "Tab", "Tabs", 1), "Tab");
+    assert_eq!(recording.pgettext("menu", "Tab"), "Tab");
+
+    let template = recording.template();
+    assert_eq!(template.len(), 5);
+    assert!(template.get("Open").is_some());
+    let file = template.get("File").unwrap();
+    assert_eq!(file.id_plural(), Some("Files"));
+    assert_eq!(file.translated(), &["", ""]);
+    let tab = template.get_with_context("menu", "Tab").unwrap();
+    assert_eq!(tab.id_plural(), Some("Tabs"));
+    assert!(template.get_with_context("menu", "Open").is_some());
+
+    let mut pot = vec![];
+    recording.write_pot(&mut pot).unwrap();
+    let pot = String::from_utf8(pot).unwrap();
+    assert!(pot.contains("msgctxt \"menu\"\nmsgid \"Tab\"\nmsgid_plural \"Tabs\"\n"));
+    assert!(pot.contains("Content-Type: text/plain; charset=UTF-8"));
+
+    recording.clear();
+    assert_eq!(recording.template().len(), 1);
+    assert!(format!("{:?}", recording).contains("recorded: 0"));
+    assert!(std::ptr::eq(recording.into_inner(), &catalog));
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::locale::{fallbacks, negotiate, parse_accept_language};
+use crate::{Catalog, Error};
+
+type Loader = Box<dyn Fn() -> Result<Catalog, Error> + Send + Sync>;
+
+/// CatalogRegistry maps locales to the loaders of their catalogs,
+/// loading each catalog on first use and sharing it afterwards.
+///
+/// Loaders can read a file, parse embedded bytes or fetch a catalog
+/// from anywhere else. A registry can be shared between threads,
+/// and a catalog is loaded only once even if it is requested concurrently.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{CatalogBuilder, CatalogRegistry};
+///
+/// let mut registry = CatalogRegistry::new();
+/// registry.register("fr", || CatalogBuilder::new().add("Open", "Ouvrir").build());
+///
+/// let catalog = registry.get("fr_CA").unwrap().unwrap();
+/// assert_eq!(catalog.gettext("Open"), "Ouvrir");
+/// assert!(registry.get("de").unwrap().is_none());
+/// ```
+#[derive(Default)]
+pub struct CatalogRegistry {
+    entries: HashMap<String, Entry>,
+}
+
+struct Entry {
+    load: Loader,
+    catalog: Mutex<Option<Arc<Catalog>>>,
+}
+
+impl CatalogRegistry {
+    /// Creates a registry with no locales.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers the loader of the catalog for `locale`,
+    /// replacing an earlier one and the catalog it loaded.
+    pub fn register<L, F>(&mut self, locale: L, load: F)
+    where
+        L: Into<String>,
+        F: Fn() -> Result<Catalog, Error> + Send + Sync + 'static,
+    {
+        let entry = Entry {
+            load: Box::new(load),
+            catalog: Mutex::new(None),
+        };
+        self.entries.insert(locale.into(), entry);
+    }
+
+    /// Registers the MO file at `path` as the catalog for `locale`.
+    pub fn register_file<L: Into<String>, P: Into<PathBuf>>(&mut self, locale: L, path: P) {
+        let path = path.into();
+        self.register(locale, move || Catalog::parse(File::open(&path)?));
+    }
+
+    /// Registers an embedded MO file as the catalog for `locale`.
+    pub fn register_bytes<L: Into<String>>(&mut self, locale: L, bytes: &'static [u8]) {
+        self.register(locale, move || Catalog::parse(bytes));
+    }
+
+    /// Returns the registered locales, sorted.
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales = self.entries.keys().map(String::as_str).collect::<Vec<_>>();
+        locales.sort();
+        locales
+    }
+
+    /// Returns whether the catalog for `locale` has been loaded already.
+    pub fn is_loaded(&self, locale: &str) -> bool {
+        self.entries
+            .get(locale)
+            .is_some_and(|entry| entry.catalog.lock().unwrap().is_some())
+    }
+
+    /// Returns the catalog for `locale`, loading it on first use.
+    ///
+    /// Less specific locales are tried if `locale` itself is not registered,
+    /// e.g. `pt_BR` and then `pt` for `pt_BR.UTF-8`, as returned by `locale::fallbacks`.
+    /// Returns `Ok(None)` if none of them is registered
+    /// and the error of the loader if it fails, in which case
+    /// the catalog is loaded again on the next call.
+    pub fn get(&self, locale: &str) -> Result<Option<Arc<Catalog>>, Error> {
+        let entry = match fallbacks(locale)
+            .iter()
+            .find_map(|locale| self.entries.get(locale))
+        {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let mut catalog = entry.catalog.lock().unwrap();
+        if catalog.is_none() {
+            *catalog = Some(Arc::new((entry.load)()?));
+        }
+        Ok(catalog.clone())
+    }
+
+    /// Negotiates the catalog for an HTTP request, loading it on first use,
+    /// and returns it together with its locale, e.g. for a `Content-Language` header.
+    ///
+    /// The locale stored in a cookie, if any, is preferred over the languages
+    /// of the `Accept-Language` header, and both can be BCP 47 language tags
+    /// or gettext locales. Returns `Ok(None)` if no registered locale matches,
+    /// for the caller to pick a default, and the error of the loader if it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{CatalogBuilder, CatalogRegistry};
+    ///
+    /// let mut registry = CatalogRegistry::new();
+    /// registry.register("de", || CatalogBuilder::new().add("Open", "Öffnen").build());
+    /// registry.register("pt_BR", || CatalogBuilder::new().add("Open", "Abrir").build());
+    ///
+    /// let (locale, catalog) = registry.negotiate(None, Some("en-US, de;q=0.8")).unwrap().unwrap();
+    /// assert_eq!((locale, catalog.gettext("Open")), ("de", "Öffnen"));
+    /// let (locale, _) = registry.negotiate(Some("pt-BR"), Some("de")).unwrap().unwrap();
+    /// assert_eq!(locale, "pt_BR");
+    /// assert!(registry.negotiate(None, Some("fr")).unwrap().is_none());
+    /// ```
+    ///
+    /// This crate has no integration with web frameworks. With axum, for example,
+    /// a middleware can negotiate the catalog and store it in the request extensions,
+    /// for handlers to take it with the `Extension<Arc<Catalog>>` extractor:
+    ///
+    /// ```ignore
+    /// async fn localize(
+    ///     State(registry): State<Arc<CatalogRegistry>>,
+    ///     mut request: Request,
+    ///     next: Next,
+    /// ) -> Result<Response, StatusCode> {
+    ///     let header = |name| request.headers().get(name).and_then(|v| v.to_str().ok());
+    ///     let cookie = header(COOKIE).and_then(|cookies| {
+    ///         cookies.split(';').find_map(|c| c.trim().strip_prefix("lang="))
+    ///     });
+    ///     let catalog = match registry.negotiate(cookie, header(ACCEPT_LANGUAGE)) {
+    ///         Ok(Some((_, catalog))) => catalog,
+    ///         Ok(None) => Arc::new(Catalog::empty()),
+    ///         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    ///     };
+    ///     request.extensions_mut().insert(catalog);
+    ///     Ok(next.run(request).await)
+    /// }
+    ///
+    /// let app = Router::new()
+    ///     .route("/", get(|Extension(catalog): Extension<Arc<Catalog>>| async move {
+    ///         catalog.gettext("Hello").to_owned()
+    ///     }))
+    ///     .layer(middleware::from_fn_with_state(registry.clone(), localize));
+    /// ```
+    pub fn negotiate(
+        &self,
+        cookie: Option<&str>,
+        accept_language: Option<&str>,
+    ) -> Result<Option<(&str, Arc<Catalog>)>, Error> {
+        let requested = cookie
+            .map(str::trim)
+            .filter(|locale| !locale.is_empty())
+            .into_iter()
+            .chain(accept_language.map_or_else(Vec::new, parse_accept_language))
+            .collect::<Vec<_>>();
+        let locale = match negotiate(&requested, &self.locales()).first() {
+            Some(&locale) => locale,
+            None => return Ok(None),
+        };
+        Ok(self.get(locale)?.map(|catalog| (locale, catalog)))
+    }
+
+    /// Drops the loaded catalog for `locale`, so that it is loaded again on next use.
+    pub fn unload(&self, locale: &str) {
+        if let Some(entry) = self.entries.get(locale) {
+            *entry.catalog.lock().unwrap() = None;
+        }
+    }
+}
+
+impl fmt::Debug for CatalogRegistry {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let loaded = self
+            .locales()
+            .into_iter()
+            .filter(|locale| self.is_loaded(locale))
+            .collect::<Vec<_>>();
+        fmt.debug_struct("CatalogRegistry")
+            .field("locales", &self.locales())
+            .field("loaded", &loaded)
+            .finish()
+    }
+}
+
+#[test]
+fn test_catalog_registry() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use crate::CatalogBuilder;
+
+    fn check<T: Send + Sync>(_: &T) {}
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let mut registry = CatalogRegistry::new();
+    let counter = loads.clone();
+    registry.register("de", move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        CatalogBuilder::new().add("Open", "Öffnen").build()
+    });
+    registry.register_bytes("lt", include_bytes!("../test_cases/integration.mo"));
+    registry.register_file("xx", "test_cases/missing.mo");
+    registry.register_file("xx_YY", "test_cases/invalid_utf8.mo");
+    check(&registry);
+    assert_eq!(registry.locales(), &["de", "lt", "xx", "xx_YY"]);
+    assert!(!registry.is_loaded("de"));
+
+    let registry = Arc::new(registry);
+    let threads = (0..4)
+        .map(|_| {
+            let registry = registry.clone();
+            thread::spawn(move || registry.get("de_AT.UTF-8").unwrap().unwrap())
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap().gettext("Open"), "Öffnen");
+    }
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+    assert!(registry.is_loaded("de"));
+    let de = registry.get("de").unwrap().unwrap();
+    assert!(Arc::ptr_eq(&de, &registry.get("de").unwrap().unwrap()));
+    registry.unload("de");
+    assert!(!registry.is_loaded("de"));
+    registry.get("de").unwrap();
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+    let lt = registry.get("lt").unwrap().unwrap();
+    assert_eq!(lt.gettext("existent"), "egzistuojantis");
+    assert!(registry.get("fr").unwrap().is_none());
+    assert!(matches!(registry.get("xx"), Err(Error::Io(_))));
+    assert!(registry.get("xx_YY").is_err());
+    assert!(!registry.is_loaded("xx_YY"));
+    assert!(format!("{:?}", registry).contains("loaded: [\"de\", \"lt\"]"));
+
+    let (locale, catalog) = registry
+        .negotiate(Some("de-CH"), Some("lt, de;q=0.5"))
+        .unwrap()
+        .unwrap();
+    assert_eq!((locale, catalog.gettext("Open")), ("de", "Öffnen"));
+    let (locale, _) = registry
+        .negotiate(Some(" "), Some("fr, lt"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(locale, "lt");
+    assert!(registry.negotiate(Some("fr"), None).unwrap().is_none());
+    assert!(registry.negotiate(None, Some("xx-YY")).is_err());
+}
@@ -0,0 +1,128 @@
+use crate::Catalog;
+
+/// CatalogStats counts the messages of a catalog by their translation state,
+/// like `msgfmt --statistics` does.
+///
+/// The header entry is not counted.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::CatalogBuilder;
+///
+/// let catalog = CatalogBuilder::new()
+///     .add("Open", "Ouvrir")
+///     .add("Close", "")
+///     .add_plural("File", "Files", vec!["Fichier", ""])
+///     .build()
+///     .unwrap();
+///
+/// let stats = catalog.stats();
+/// assert_eq!(stats.messages(), 3);
+/// assert_eq!(stats.translated(), 1);
+/// assert_eq!(stats.partially_translated(), 1);
+/// assert_eq!(stats.untranslated(), 1);
+/// assert_eq!(stats.plural(), 1);
+/// assert!(stats.coverage() < 0.5);
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CatalogStats {
+    messages: usize,
+    translated: usize,
+    untranslated: usize,
+    plural: usize,
+}
+
+impl CatalogStats {
+    /// Returns the number of messages.
+    pub fn messages(&self) -> usize {
+        self.messages
+    }
+
+    /// Returns the number of messages with all of their forms translated.
+    pub fn translated(&self) -> usize {
+        self.translated
+    }
+
+    /// Returns the number of messages with only some of their forms translated.
+    pub fn partially_translated(&self) -> usize {
+        self.messages - self.translated - self.untranslated
+    }
+
+    /// Returns the number of messages with none of their forms translated.
+    pub fn untranslated(&self) -> usize {
+        self.untranslated
+    }
+
+    /// Returns the number of messages with a plural form.
+    pub fn plural(&self) -> usize {
+        self.plural
+    }
+
+    /// Returns the share of fully translated messages, between 0 and 1.
+    ///
+    /// A catalog without messages has nothing left to translate
+    /// and its coverage is 1.
+    pub fn coverage(&self) -> f64 {
+        match self.messages {
+            0 => 1.0,
+            n => self.translated as f64 / n as f64,
+        }
+    }
+}
+
+impl Catalog {
+    /// Returns the number of messages in the catalog, including the header.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns whether the catalog has no messages at all.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Counts the messages of the catalog by their translation state.
+    pub fn stats(&self) -> CatalogStats {
+        let mut stats = CatalogStats::default();
+        for msg in self.iter().filter(|msg| !msg.is_header()) {
+            stats.messages += 1;
+            if msg.id_plural.is_some() {
+                stats.plural += 1;
+            }
+            if !msg.is_translated() {
+                stats.untranslated += 1;
+            } else if msg.translated.iter().all(|s| !s.is_empty()) {
+                stats.translated += 1;
+            }
+        }
+        stats
+    }
+}
+
+#[test]
+fn test_stats() {
+    use crate::Message;
+
+    let mut catalog = Catalog::new();
+    assert!(catalog.is_empty());
+    assert_eq!(catalog.stats(), CatalogStats::default());
+    assert_eq!(catalog.stats().coverage(), 1.0);
+
+    catalog.insert(Message::new("", None, vec!["Language: lt\n"]));
+    catalog.insert(Message::new("Text", None, vec!["Tekstas"]));
+    catalog.insert(Message::new("Open", Some("menu"), vec![""]));
+    catalog.insert(Message::new("Image", None, vec![]));
+    catalog.insert(Message::new("File", None, vec!["Failas", "", "Failų"]).with_plural("Files"));
+    catalog.insert(Message::new("Tab", None, vec!["Kortelė", "Kortelės"]).with_plural("Tabs"));
+    assert_eq!(catalog.len(), 6);
+    assert!(!catalog.is_empty());
+
+    let stats = catalog.stats();
+    assert_eq!(stats.messages(), 5);
+    assert_eq!(stats.translated(), 2);
+    assert_eq!(stats.partially_translated(), 1);
+    assert_eq!(stats.untranslated(), 2);
+    assert_eq!(stats.plural(), 2);
+    assert_eq!(stats.coverage(), 0.4);
+}
@@ -0,0 +1,164 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{Catalog, ChainedCatalog, MoCatalog, TextDomain};
+
+/// Translator is implemented by everything that looks up translations,
+/// so that libraries can take a `&dyn Translator` and let the application
+/// pass a `Catalog`, a `ChainedCatalog`, a `TextDomain` or a test double.
+///
+/// It is implemented for shared pointers as well,
+/// e.g. for the `Arc<Catalog>` handed out by `CatalogRegistry`.
+///
+/// # Examples
+///
+/// ```
+/// use gettext::{CatalogBuilder, Translator};
+///
+/// fn greeting(tr: &dyn Translator, n: u64) -> String {
+///     format!("{} {}", n, tr.ngettext("apple", "apples", n))
+/// }
+///
+/// let catalog = CatalogBuilder::new()
+///     .add_plural("apple", "apples", vec!["pomme", "pommes"])
+///     .build()
+///     .unwrap();
+/// assert_eq!(greeting(&catalog, 2), "2 pommes");
+/// ```
+pub trait Translator {
+    /// Returns the singular translation of `msg_id`
+    /// or `msg_id` itself if a translation does not exist.
+    fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str;
+
+    /// Returns the plural translation of `msg_id`
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    fn ngettext<'a>(&'a self, msg_id: &'a str, msg_id_plural: &'a str, n: u64) -> &'a str;
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context`
+    /// or `msg_id` itself if a translation does not exist.
+    fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str;
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context`
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    fn npgettext<'a>(
+        &'a self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+    ) -> &'a str;
+}
+
+macro_rules! impl_translator {
+    ($([$($generics:tt)*] $ty:ty => |$this:ident| $target:expr;)*) => {$(
+        impl<$($generics)*> Translator for $ty {
+            fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str {
+                let $this = self;
+                $target.gettext(msg_id)
+            }
+
+            fn ngettext<'a>(&'a self, msg_id: &'a str, msg_id_plural: &'a str, n: u64) -> &'a str {
+                let $this = self;
+                $target.ngettext(msg_id, msg_id_plural, n)
+            }
+
+            fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str {
+                let $this = self;
+                $target.pgettext(msg_context, msg_id)
+            }
+
+            fn npgettext<'a>(
+                &'a self,
+                msg_context: &str,
+                msg_id: &'a str,
+                msg_id_plural: &'a str,
+                n: u64,
+            ) -> &'a str {
+                let $this = self;
+                $target.npgettext(msg_context, msg_id, msg_id_plural, n)
+            }
+        }
+    )*};
+}
+
+// The inherent methods take precedence, so the impls of the catalogs forward to them.
+impl_translator! {
+    [] Catalog => |this| this;
+    [B: AsRef<[u8]>] MoCatalog<B> => |this| this;
+    [] ChainedCatalog => |this| this;
+    [] TextDomain => |this| this;
+    [T: Translator + ?Sized] &T => |this| **this;
+    [T: Translator + ?Sized] Box<T> => |this| **this;
+    [T: Translator + ?Sized] Rc<T> => |this| **this;
+    [T: Translator + ?Sized] Arc<T> => |this| **this;
+}
+
+#[test]
+fn test_translator() {
+    use crate::CatalogBuilder;
+
+    struct Upper(String);
+
+    impl Translator for Upper {
+        fn gettext<'a>(&'a self, _: &'a str) -> &'a str {
+            &self.0
+        }
+
+        fn ngettext<'a>(&'a self, _: &'a str, _: &'a str, _: u64) -> &'a str {
+            &self.0
+        }
+
+        fn pgettext<'a>(&'a self, _: &str, _: &'a str) -> &'a str {
+            &self.0
+        }
+
+        fn npgettext<'a>(&'a self, _: &str, _: &'a str, _: &'a str, _: u64) -> &'a str {
+            &self.0
+        }
+    }
+
+    fn check(tr: &dyn Translator, open: &str, files: &str, edit: &str, images: &str) {
+        assert_eq!(tr.gettext("Open"), open);
+        assert_eq!(tr.ngettext("File", "Files", 2), files);
+        assert_eq!(tr.pgettext("menu", "Edit"), edit);
+        assert_eq!(tr.npgettext("menu", "Image", "Images", 2), images);
+    }
+
+    let catalog = CatalogBuilder::new()
+        .add("Open", "Ouvrir")
+        .add_plural("File", "Files", vec!["Fichier", "Fichiers"])
+        .add_with_context("menu", "Edit", "Modifier")
+        .build()
+        .unwrap();
+    check(&catalog, "Ouvrir", "Fichiers", "Modifier", "Images");
+    let mo = MoCatalog::from_bytes(&include_bytes!("../test_cases/1.mo")[..]).unwrap();
+    check(&mo, "Open", "Files", "Edit", "Images");
+    check(
+        &ChainedCatalog::default(),
+        "Open",
+        "Files",
+        "Edit",
+        "Images",
+    );
+    check(&TextDomain::new("app"), "Open", "Files", "Edit", "Images");
+    let test_double = Upper("TEXT".to_owned());
+    check(&test_double, "TEXT", "TEXT", "TEXT", "TEXT");
+
+    let catalog = Arc::new(catalog);
+    check(&catalog, "Ouvrir", "Fichiers", "Modifier", "Images");
+    check(
+        &Rc::new(&*catalog),
+        "Ouvrir",
+        "Fichiers",
+        "Modifier",
+        "Images",
+    );
+    let boxed: Box<dyn Translator> = Box::new(test_double);
+    check(&boxed, "TEXT", "TEXT", "TEXT", "TEXT");
+}
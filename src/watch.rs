@@ -0,0 +1,156 @@
+//! Reloading of catalogs when their MO files change.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::{Catalog, Error};
+
+/// WatchedCatalog is a catalog read from an MO file
+/// that is parsed again and swapped in when the file changes,
+/// so that edits of translators show up in a running program.
+///
+/// Changes are detected by polling the modification time and size of the file,
+/// either explicitly with `reload` or in a background thread started by `spawn`.
+/// Polling needs no OS notification API (and no dependency for one),
+/// at the cost of some limits:
+///
+/// - a change is only seen at the next call of `reload`, so up to one interval late;
+/// - a rewrite that keeps the size and lands within the same tick of the
+///   file system's modification time resolution (up to 2 seconds on FAT) is missed;
+/// - without modification times, the file is parsed again on every call.
+///
+/// Lookups go through the `Arc<Catalog>` returned by `catalog`,
+/// which stays valid while a newer version is swapped in.
+///
+/// # Examples
+///
+/// ```ignore
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let watched = Arc::new(gettext::WatchedCatalog::open("locale/fr/LC_MESSAGES/myapp.mo").unwrap());
+/// watched.spawn(Duration::from_secs(1));
+/// println!("{}", watched.catalog().gettext("Hello"));
+/// ```
+pub struct WatchedCatalog {
+    path: PathBuf,
+    catalog: RwLock<Arc<Catalog>>,
+    version: Mutex<Option<(SystemTime, u64)>>,
+}
+
+impl WatchedCatalog {
+    /// Reads the catalog from the MO file at the given path.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self, Error> {
+        let path = path.into();
+        let version = version(&path)?;
+        let catalog = Catalog::parse(File::open(&path)?)?;
+        Ok(WatchedCatalog {
+            path,
+            catalog: RwLock::new(Arc::new(catalog)),
+            version: Mutex::new(version),
+        })
+    }
+
+    /// Returns the path of the watched MO file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the current version of the catalog.
+    pub fn catalog(&self) -> Arc<Catalog> {
+        self.catalog.read().unwrap().clone()
+    }
+
+    /// Parses the MO file again if it changed since it was last read
+    /// and returns whether a new catalog was swapped in.
+    ///
+    /// Returns the `Err` variant if the file cannot be read or parsed,
+    /// e.g. while it is being written, in which case the current catalog is kept
+    /// and the file is read again on the next call.
+    pub fn reload(&self) -> Result<bool, Error> {
+        let mut current = self.version.lock().unwrap();
+        let version = version(&self.path)?;
+        if version.is_some() && version == *current {
+            return Ok(false);
+        }
+        let catalog = Catalog::parse(File::open(&self.path)?)?;
+        *self.catalog.write().unwrap() = Arc::new(catalog);
+        *current = version;
+        Ok(true)
+    }
+
+    /// Starts a thread calling `reload` every `interval`,
+    /// which stops once the last `Arc` of the catalog is dropped.
+    ///
+    /// Errors are ignored, so that a broken file only delays the reload
+    /// until it is fixed.
+    pub fn spawn(self: &Arc<Self>, interval: Duration) -> thread::JoinHandle<()> {
+        let watched: Weak<Self> = Arc::downgrade(self);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match watched.upgrade() {
+                Some(watched) => drop(watched.reload()),
+                None => break,
+            }
+        })
+    }
+}
+
+impl fmt::Debug for WatchedCatalog {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("WatchedCatalog")
+            .field("path", &self.path)
+            .field("catalog", &self.catalog())
+            .finish()
+    }
+}
+
+/// Returns the modification time and size of the file,
+/// or `None` for the time if the platform does not record it.
+fn version(path: &Path) -> Result<Option<(SystemTime, u64)>, Error> {
+    let metadata = fs::metadata(path)?;
+    Ok(metadata.modified().ok().map(|time| (time, metadata.len())))
+}
+
+#[test]
+fn test_watched_catalog() {
+    use std::env;
+
+    let path = env::temp_dir().join(format!("gettext-watch-{}.mo", std::process::id()));
+    fs::copy("test_cases/1.mo", &path).unwrap();
+    let watched = Arc::new(WatchedCatalog::open(&path).unwrap());
+    assert_eq!(watched.path(), path);
+    let old = watched.catalog();
+    assert_eq!(old.pgettext("this is context", "Text"), "Tekstas");
+    assert!(!watched.reload().unwrap());
+
+    fs::write(&path, "garbage").unwrap();
+    assert!(watched.reload().is_err());
+    assert!(Arc::ptr_eq(&old, &watched.catalog()));
+
+    fs::copy("test_cases/integration.mo", &path).unwrap();
+    assert!(watched.reload().unwrap());
+    assert_eq!(watched.catalog().gettext("existent"), "egzistuojantis");
+    assert_eq!(old.pgettext("this is context", "Text"), "Tekstas");
+    assert!(!watched.reload().unwrap());
+
+    let handle = watched.spawn(Duration::from_millis(10));
+    fs::copy("test_cases/1.mo", &path).unwrap();
+    for _ in 0..500 {
+        if watched.catalog().gettext("existent") == "existent" {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        watched.catalog().pgettext("this is context", "Text"),
+        "Tekstas"
+    );
+    drop(watched);
+    handle.join().unwrap();
+    fs::remove_file(path).unwrap();
+}
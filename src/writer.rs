@@ -0,0 +1,214 @@
+mod po;
+
+use std::io;
+
+use byteorder::{ByteOrder, LittleEndian};
+use encoding::label::encoding_from_whatwg_label;
+use encoding::types::EncoderTrap::Strict;
+use encoding::types::EncodingRef;
+
+use crate::metadata::parse_metadata;
+use crate::mo::hash_string;
+use crate::Error::{self, *};
+use crate::{Catalog, Message};
+
+pub use self::po::write_po;
+
+/// The header used for catalogs that do not carry one themselves.
+const DEFAULT_HEADER: &str = "Content-Type: text/plain; charset=UTF-8\n";
+
+/// Returns the size of the hash table used for `n` strings,
+/// following the same rule as GNU msgfmt.
+fn hash_table_size(n: usize) -> usize {
+    fn is_prime(x: usize) -> bool {
+        x >= 2 && (2..).take_while(|d| d * d <= x).all(|d| x % d != 0)
+    }
+    let mut size = n * 4 / 3;
+    while !is_prime(size) {
+        size += 1;
+    }
+    size.max(3)
+}
+
+/// Returns the encoding that the header of the catalog declares.
+fn header_encoding(header: &str) -> Result<EncodingRef, Error> {
+    let map = parse_metadata(header)?;
+    match map.charset() {
+        Some(c) => encoding_from_whatwg_label(c).ok_or(UnknownEncoding),
+        None => Ok(&encoding::codec::utf_8::UTF8Encoding),
+    }
+}
+
+/// Returns the lookup key of the message, as stored in an MO file.
+fn key_bytes(msg: &Message, encoding: EncodingRef) -> Result<Vec<u8>, Error> {
+    let mut key = vec![];
+    if let Some(ref ctxt) = msg.context {
+        key.extend(encoding.encode(ctxt, Strict).map_err(|_| Unencodable)?);
+        key.push(4);
+    }
+    key.extend(encoding.encode(&msg.id, Strict).map_err(|_| Unencodable)?);
+    Ok(key)
+}
+
+pub fn write_mo<W: io::Write>(catalog: &Catalog, mut writer: W) -> Result<(), Error> {
+    let header = catalog
+        .message(None, "")
+        .and_then(|m| m.translated.first())
+        .map(|s| &s[..])
+        .unwrap_or(DEFAULT_HEADER);
+    let encoding = header_encoding(header)?;
+    let encode = |s: &str| encoding.encode(s, Strict).map_err(|_| Unencodable);
+
+    // Pairs of (key, original, translation), where the original
+    // is the key followed by the plural msgid if there is one.
+    let mut entries = vec![(vec![], vec![], encode(header)?)];
+    for msg in catalog.strings.values() {
+        if msg.id.is_empty() && msg.context.is_none() {
+            continue;
+        }
+        // msgfmt leaves out messages that have no translation at all,
+        // and those whose translation is fuzzy
+        if !msg.is_translated() || msg.is_fuzzy() {
+            continue;
+        }
+        let key = key_bytes(msg, encoding)?;
+        let mut original = key.clone();
+        if let Some(ref plural) = msg.id_plural {
+            original.push(0);
+            original.extend(encode(plural)?);
+        }
+        let mut translation = vec![];
+        for (i, form) in msg.translated.iter().enumerate() {
+            if i != 0 {
+                translation.push(0);
+            }
+            translation.extend(encode(form)?);
+        }
+        entries.push((key, original, translation));
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let n = entries.len();
+    let hash_size = hash_table_size(n);
+    let off_otable = 28;
+    let off_ttable = off_otable + 8 * n;
+    let off_hash = off_ttable + 8 * n;
+    let mut off_strings = off_hash + 4 * hash_size;
+
+    let mut buf = vec![0u8; off_strings];
+    let put_u32 = |buf: &mut Vec<u8>, at: usize, value: usize| {
+        LittleEndian::write_u32(&mut buf[at..at + 4], value as u32);
+    };
+    put_u32(&mut buf, 0, 0x9504_12de);
+    put_u32(&mut buf, 4, 0);
+    put_u32(&mut buf, 8, n);
+    put_u32(&mut buf, 12, off_otable);
+    put_u32(&mut buf, 16, off_ttable);
+    put_u32(&mut buf, 20, hash_size);
+    put_u32(&mut buf, 24, off_hash);
+
+    let mut hash_table = vec![0usize; hash_size];
+    for (i, (key, original, _)) in entries.iter().enumerate() {
+        put_u32(&mut buf, off_otable + 8 * i, original.len());
+        put_u32(&mut buf, off_otable + 8 * i + 4, off_strings);
+        buf.extend(original);
+        buf.push(0);
+        off_strings += original.len() + 1;
+
+        let hval = hash_string(key) as usize;
+        let incr = 1 + hval % (hash_size - 2);
+        let mut idx = hval % hash_size;
+        while hash_table[idx] != 0 {
+            idx = (idx + incr) % hash_size;
+        }
+        // hash table entries are 1-based, 0 marks an empty slot
+        hash_table[idx] = i + 1;
+    }
+    for (i, (_, _, translation)) in entries.iter().enumerate() {
+        put_u32(&mut buf, off_ttable + 8 * i, translation.len());
+        put_u32(&mut buf, off_ttable + 8 * i + 4, off_strings);
+        buf.extend(translation);
+        buf.push(0);
+        off_strings += translation.len() + 1;
+    }
+    for (i, string_no) in hash_table.into_iter().enumerate() {
+        put_u32(&mut buf, off_hash + 4 * i, string_no);
+    }
+
+    writer.write_all(&buf)?;
+    Ok(())
+}
+
+#[test]
+fn test_hash_table_size() {
+    assert_eq!(hash_table_size(0), 3);
+    assert_eq!(hash_table_size(2), 3);
+    assert_eq!(hash_table_size(3), 5);
+    assert_eq!(hash_table_size(30), 41);
+}
+
+#[test]
+fn test_write_mo() {
+    use crate::key::Key;
+
+    let mut catalog = Catalog::new();
+    catalog
+        .insert(Message::new("Text", Some("ctx"), vec!["Tekstas", "Tekstai"]).with_plural("Texts"));
+    catalog.insert(Message::new("Image", None, vec!["Nuotrauka"]));
+    catalog.insert(Message::new("Untranslated", None, vec![""]));
+    let mut fuzzy = Message::new("Fuzzy", None, vec!["Neaiškus"]);
+    fuzzy.flags.insert(crate::Flag::Fuzzy);
+    catalog.insert(fuzzy);
+
+    let mut buf = vec![];
+    write_mo(&catalog, &mut buf).unwrap();
+    let parsed = Catalog::parse(&buf[..]).unwrap();
+
+    assert_eq!(parsed.strings.len(), 3);
+    assert_eq!(
+        parsed.strings[&Key::from("ctx\x04Text")],
+        catalog.strings[&Key::from("ctx\x04Text")]
+    );
+    assert_eq!(
+        parsed.strings[&Key::from("Image")],
+        catalog.strings[&Key::from("Image")]
+    );
+    assert_eq!(
+        parsed.strings[&Key::from("")].translated,
+        vec![DEFAULT_HEADER]
+    );
+    assert!(!parsed.strings.contains_key(&Key::from("Untranslated")));
+    assert!(!parsed.strings.contains_key(&Key::from("Fuzzy")));
+
+    let po = "msgid \"\"\nmsgstr \"Language: lt\\n\"\n\n#, fuzzy\nmsgid \"Open\"\nmsgstr \"Atidaryti\"\n\nmsgid \"Quit\"\nmsgstr \"Baigti\"\n";
+    let mut buf = vec![];
+    write_mo(&Catalog::parse_po(po.as_bytes()).unwrap(), &mut buf).unwrap();
+    let parsed = Catalog::parse(&buf[..]).unwrap();
+    assert_eq!(parsed.gettext("Open"), "Open");
+    assert_eq!(parsed.gettext("Quit"), "Baigti");
+}
+
+#[test]
+fn test_write_mo_encoding() {
+    let reader: &[u8] = include_bytes!("../test_cases/cp1257_meta.mo");
+    let catalog = Catalog::parse(reader).unwrap();
+    let mut buf = vec![];
+    write_mo(&catalog, &mut buf).unwrap();
+    assert!(buf.windows(8).any(|w| w == b"\xc8esnakas"));
+    assert_eq!(
+        Catalog::parse(&buf[..]).unwrap().gettext("Garlic"),
+        "Česnakas"
+    );
+
+    let mut catalog = Catalog::new();
+    catalog.insert(Message::new(
+        "",
+        None,
+        vec!["Content-Type: text/plain; charset=cp1257\n"],
+    ));
+    catalog.insert(Message::new("Snowman", None, vec!["☃"]));
+    match write_mo(&catalog, &mut vec![]).unwrap_err() {
+        Unencodable => (),
+        err => panic!("Expected Unencodable, got {:?}", err),
+    }
+}
@@ -0,0 +1,214 @@
+//! Writing of textual PO catalogs.
+
+use std::fmt::Write as _;
+use std::io;
+
+use encoding::types::EncoderTrap::Strict;
+
+use super::{header_encoding, DEFAULT_HEADER};
+use crate::key::Key;
+use crate::Error::{self, *};
+use crate::{Catalog, Flag, Message};
+
+/// Appends `s` to `out` as a quoted PO string.
+fn push_quoted(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            c if c.is_ascii_control() => write!(out, "\\{:03o}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Appends a `keyword "value"` line to `out`,
+/// splitting multi-line values after each newline like msgcat does.
+fn push_field(out: &mut String, prefix: &str, keyword: &str, value: &str) {
+    out.push_str(prefix);
+    out.push_str(keyword);
+    out.push(' ');
+    let lines = value.split_inclusive('\n').collect::<Vec<_>>();
+    if lines.len() <= 1 {
+        push_quoted(out, value);
+        out.push('\n');
+        return;
+    }
+    out.push_str("\"\"\n");
+    for line in lines {
+        out.push_str(prefix);
+        push_quoted(out, line);
+        out.push('\n');
+    }
+}
+
+/// Appends the comments of `msg` to `out`, in the order used by msgcat.
+fn push_comments(out: &mut String, msg: &Message) {
+    let mut push = |marker: &str, comment: &str| {
+        out.push_str(marker);
+        if !comment.is_empty() {
+            out.push(' ');
+            out.push_str(comment);
+        }
+        out.push('\n');
+    };
+    for comment in &msg.translator_comments {
+        push("#", comment);
+    }
+    for comment in &msg.extracted_comments {
+        push("#.", comment);
+    }
+    if !msg.references.is_empty() {
+        push("#:", &msg.references.join(" "));
+    }
+    if !msg.flags.is_empty() {
+        let flags = msg.flags.iter().map(Flag::as_str).collect::<Vec<_>>();
+        push("#,", &flags.join(", "));
+    }
+}
+
+/// Appends the entry of `msg` to `out`, prefixing every line with `prefix`.
+fn push_entry(out: &mut String, prefix: &str, msg: &Message) {
+    out.push('\n');
+    push_comments(out, msg);
+    let previous = if prefix.is_empty() { "#| " } else { "#~| " };
+    let fields = [
+        ("msgctxt", &msg.previous_context),
+        ("msgid", &msg.previous_id),
+        ("msgid_plural", &msg.previous_id_plural),
+    ];
+    for &(keyword, value) in &fields {
+        if let Some(ref value) = *value {
+            push_field(out, previous, keyword, value);
+        }
+    }
+    if let Some(ref ctxt) = msg.context {
+        push_field(out, prefix, "msgctxt", ctxt);
+    }
+    push_field(out, prefix, "msgid", &msg.id);
+    match msg.id_plural {
+        Some(ref plural) => {
+            push_field(out, prefix, "msgid_plural", plural);
+            let forms = msg.translated.len().max(2);
+            for i in 0..forms {
+                let form = msg.translated.get(i).map_or("", |s| &s[..]);
+                push_field(out, prefix, &format!("msgstr[{}]", i), form);
+            }
+        }
+        None => {
+            let form = msg.translated.first().map_or("", |s| &s[..]);
+            push_field(out, prefix, "msgstr", form);
+        }
+    }
+}
+
+/// Returns the messages of the map sorted by their lookup keys.
+fn sorted<'a, I: Iterator<Item = (&'a Key, &'a Message)>>(messages: I) -> Vec<&'a Message> {
+    let mut messages = messages.filter(|(k, _)| !k.is_header()).collect::<Vec<_>>();
+    messages.sort_by(|a, b| a.0.cmp(b.0));
+    messages.into_iter().map(|(_, m)| m).collect()
+}
+
+pub fn write_po<W: io::Write>(catalog: &Catalog, mut writer: W) -> Result<(), Error> {
+    let header = catalog
+        .message(None, "")
+        .and_then(|m| m.translated.first())
+        .map(|s| &s[..])
+        .unwrap_or(DEFAULT_HEADER);
+    let encoding = header_encoding(header)?;
+
+    let mut out = String::new();
+    if let Some(header) = catalog.message(None, "") {
+        push_comments(&mut out, header);
+    }
+    push_field(&mut out, "", "msgid", "");
+    out.push_str("msgstr \"\"\n");
+    for line in header.split_inclusive('\n') {
+        push_quoted(&mut out, line);
+        out.push('\n');
+    }
+    for msg in sorted(catalog.strings.iter()) {
+        push_entry(&mut out, "", msg);
+    }
+    for msg in sorted(catalog.obsolete.iter()) {
+        push_entry(&mut out, "#~ ", msg);
+    }
+
+    let bytes = encoding.encode(&out, Strict).map_err(|_| Unencodable)?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+#[test]
+fn test_push_field() {
+    let field = |value: &str| {
+        let mut out = String::new();
+        push_field(&mut out, "#~ ", "msgid", value);
+        out
+    };
+    assert_eq!(field(""), "#~ msgid \"\"\n");
+    assert_eq!(field("a\"b\\\t\x01"), "#~ msgid \"a\\\"b\\\\\\t\\001\"\n");
+    assert_eq!(field("line\n"), "#~ msgid \"line\\n\"\n");
+    assert_eq!(
+        field("one\ntwo"),
+        "#~ msgid \"\"\n#~ \"one\\n\"\n#~ \"two\"\n"
+    );
+}
+
+#[test]
+fn test_write_po() {
+    use crate::CatalogBuilder;
+
+    let catalog = CatalogBuilder::new()
+        .header("Content-Type", "text/plain; charset=UTF-8")
+        .header("Language", "lt")
+        .add_plural("File", "Files", vec!["Failas", "Failai", "Failų"])
+        .add_with_context("menu", "Open", "Atidaryti")
+        .add("Untranslated", "")
+        .build()
+        .unwrap();
+    let mut po = vec![];
+    catalog.write_po(&mut po).unwrap();
+    assert_eq!(
+        String::from_utf8(po.clone()).unwrap(),
+        r#"msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Language: lt\n"
+
+msgid "File"
+msgid_plural "Files"
+msgstr[0] "Failas"
+msgstr[1] "Failai"
+msgstr[2] "Failų"
+
+msgid "Untranslated"
+msgstr ""
+
+msgctxt "menu"
+msgid "Open"
+msgstr "Atidaryti"
+"#
+    );
+
+    let parsed = Catalog::parse_po(&po[..]).unwrap();
+    assert_eq!(parsed.strings, catalog.strings);
+
+    let catalog = CatalogBuilder::new()
+        .header("Content-Type", "text/plain; charset=ISO-8859-1")
+        .add("Name", "Vardas ą")
+        .build()
+        .unwrap();
+    assert!(matches!(catalog.write_po(vec![]), Err(Unencodable)));
+
+    let src = "#, fuzzy\nmsgid \"\"\nmsgstr \"\"\n\"Language: lt\\n\"\n\n#, fuzzy\nmsgid \"Open\"\nmsgstr \"Atidaryti\"\n";
+    let catalog = Catalog::parse_po(src.as_bytes()).unwrap();
+    let mut po = vec![];
+    catalog.write_po(&mut po).unwrap();
+    assert_eq!(String::from_utf8(po).unwrap(), src);
+}
@@ -0,0 +1,685 @@
+//! Conversion of catalogs from and to XLIFF 1.2 and 2.0 documents.
+
+use std::io::{Read, Write};
+
+use crate::xml::{self, Element};
+use crate::{Catalog, CatalogBuilder, Error, Flag, Message};
+
+/// The language of the msgids, unless the header tells otherwise.
+const SOURCE_LANGUAGE: &str = "en";
+
+/// The header field keeping the source language of a document read from XLIFF.
+const SOURCE_LANGUAGE_HEADER: &str = "X-Source-Language";
+
+/// The gettext-specific names used in the documents.
+const CONTEXT: &str = "x-gettext-msgctxt";
+const PLURALS_1_2: &str = "x-gettext-plurals";
+const PLURALS_2_0: &str = "x-gettext:plurals";
+
+/// XliffVersion is the version of XLIFF written by `Catalog::write_xliff`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum XliffVersion {
+    /// XLIFF 1.2, the version most translation management systems support
+    V1_2,
+    /// XLIFF 2.0
+    V2_0,
+}
+
+/// A message as found in a document, before it is added to the catalog.
+#[derive(Default)]
+struct Unit {
+    context: Option<String>,
+    sources: Vec<String>,
+    targets: Vec<Option<String>>,
+    fuzzy: bool,
+    translator_comments: Vec<String>,
+    extracted_comments: Vec<String>,
+    references: Vec<String>,
+}
+
+impl Unit {
+    /// Reads the notes and contexts that XLIFF 1.2 keeps in a unit or a group.
+    fn notes_1_2(&mut self, element: &Element) {
+        for child in element.elements() {
+            match &child.name[..] {
+                "note" if child.get("from") == Some("translator") => {
+                    self.translator_comments.push(child.content());
+                }
+                "note" => self.extracted_comments.push(child.content()),
+                "context-group" => {
+                    let context = |kind: &str| {
+                        child
+                            .elements()
+                            .find(|c| c.name == "context" && c.get("context-type") == Some(kind))
+                            .map(Element::content)
+                    };
+                    if let Some(ctxt) = context(CONTEXT) {
+                        self.context = Some(ctxt);
+                    }
+                    match (context("sourcefile"), context("linenumber")) {
+                        (Some(file), Some(line)) => self.references.push(file + ":" + &line),
+                        (Some(file), None) => self.references.push(file),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads the source and target of an XLIFF 1.2 trans-unit.
+    fn trans_unit(&mut self, unit: &Element) -> Result<(), Error> {
+        let source = unit.find("source").ok_or_else(|| unit.error())?;
+        self.sources.push(source.content());
+        let target = unit.find("target").map(|t| (t.content(), t.get("state")));
+        let target = target.filter(|(text, _)| !text.is_empty());
+        if let Some((_, state)) = target {
+            let review = state.is_some_and(|s| s == "new" || s.starts_with("needs-"));
+            self.fuzzy |= review && unit.get("approved") != Some("yes");
+        }
+        self.targets.push(target.map(|(text, _)| text));
+        Ok(())
+    }
+
+    /// Reads the notes that XLIFF 2.0 keeps in a unit or a group.
+    fn notes_2_0(&mut self, element: &Element) {
+        let notes = element
+            .find("notes")
+            .into_iter()
+            .flat_map(Element::elements);
+        for note in notes.filter(|n| n.name == "note") {
+            match note.get("category") {
+                Some(CONTEXT) => self.context = Some(note.content()),
+                Some("location") => self.references.push(note.content()),
+                Some("translator") => self.translator_comments.push(note.content()),
+                _ => self.extracted_comments.push(note.content()),
+            }
+        }
+    }
+
+    /// Reads the segments of an XLIFF 2.0 unit, joined into a single source and target.
+    fn unit(&mut self, unit: &Element) -> Result<(), Error> {
+        let (mut source, mut target) = (String::new(), String::new());
+        let mut states = vec![];
+        for part in unit.elements() {
+            if part.name != "segment" && part.name != "ignorable" {
+                continue;
+            }
+            let text = |name: &str| part.find(name).map(Element::content);
+            source.push_str(&text("source").ok_or_else(|| part.error())?);
+            target.push_str(&text("target").unwrap_or_default());
+            if part.name == "segment" {
+                states.push(part.get("state").unwrap_or("initial"));
+            }
+        }
+        if states.is_empty() {
+            return Err(unit.error());
+        }
+        self.sources.push(source);
+        if target.is_empty() {
+            self.targets.push(None);
+        } else {
+            self.fuzzy |= states.iter().any(|&s| s == "initial" || s == "translated");
+            self.targets.push(Some(target));
+        }
+        Ok(())
+    }
+
+    fn into_message(self, element: &Element) -> Result<Message, Error> {
+        let mut sources = self.sources.into_iter();
+        let id = sources.next().ok_or_else(|| element.error())?;
+        let translated = match self.targets.iter().any(Option::is_some) {
+            true => self
+                .targets
+                .into_iter()
+                .map(Option::unwrap_or_default)
+                .collect(),
+            false => vec![String::new(); self.targets.len()],
+        };
+        let mut msg = Message::new(id, self.context, translated);
+        if msg.translated.len() > 1 {
+            msg.id_plural = Some(sources.next().unwrap());
+        }
+        msg.translator_comments = self.translator_comments;
+        msg.extracted_comments = self.extracted_comments;
+        msg.references = self.references;
+        if self.fuzzy {
+            msg.flags.insert(Flag::Fuzzy);
+        }
+        Ok(msg)
+    }
+}
+
+/// Adds the messages of an XLIFF 1.2 body or group to the catalog.
+fn read_1_2(element: &Element, catalog: &mut Catalog) -> Result<(), Error> {
+    for child in element.elements() {
+        match &child.name[..] {
+            "trans-unit" => {
+                let mut unit = Unit::default();
+                unit.trans_unit(child)?;
+                unit.notes_1_2(child);
+                catalog.insert(unit.into_message(child)?);
+            }
+            "group" if child.get("restype") == Some(PLURALS_1_2) => {
+                let mut unit = Unit::default();
+                unit.notes_1_2(child);
+                for form in child.elements().filter(|e| e.name == "trans-unit") {
+                    unit.trans_unit(form)?;
+                    unit.notes_1_2(form);
+                }
+                catalog.insert(unit.into_message(child)?);
+            }
+            "group" => read_1_2(child, catalog)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Adds the messages of an XLIFF 2.0 file or group to the catalog.
+fn read_2_0(element: &Element, catalog: &mut Catalog) -> Result<(), Error> {
+    for child in element.elements() {
+        match &child.name[..] {
+            "unit" => {
+                let mut unit = Unit::default();
+                unit.unit(child)?;
+                unit.notes_2_0(child);
+                catalog.insert(unit.into_message(child)?);
+            }
+            "group" if child.get("type") == Some(PLURALS_2_0) => {
+                let mut unit = Unit::default();
+                unit.notes_2_0(child);
+                for form in child.elements().filter(|e| e.name == "unit") {
+                    unit.unit(form)?;
+                }
+                catalog.insert(unit.into_message(child)?);
+            }
+            "group" => read_2_0(child, catalog)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the catalog with the header for the given languages.
+fn with_languages(source: Option<&str>, target: Option<&str>) -> Result<Catalog, Error> {
+    let mut builder = CatalogBuilder::new().header("Content-Type", "text/plain; charset=UTF-8");
+    if let Some(target) = target {
+        builder = builder.header("Language", target);
+    }
+    if let Some(source) = source {
+        builder = builder.header(SOURCE_LANGUAGE_HEADER, source);
+    }
+    builder.build()
+}
+
+/// Returns the messages of the catalog but the header, sorted by context and msgid.
+fn sorted(catalog: &Catalog) -> Vec<&Message> {
+    let mut messages = catalog
+        .strings
+        .iter()
+        .filter(|(key, _)| !key.is_header())
+        .collect::<Vec<_>>();
+    messages.sort_by(|a, b| a.0.cmp(b.0));
+    messages.into_iter().map(|(_, msg)| msg).collect()
+}
+
+/// Returns the source of the given plural form of a message.
+fn source(msg: &Message, form: usize) -> &str {
+    match msg.id_plural {
+        Some(ref plural) if form > 0 => plural,
+        _ => &msg.id,
+    }
+}
+
+/// Returns the number of units written for a message.
+fn forms(msg: &Message) -> usize {
+    match msg.id_plural {
+        Some(_) => msg.translated.len().max(2),
+        None => 1,
+    }
+}
+
+fn translation(msg: &Message, form: usize) -> Option<&str> {
+    msg.translated
+        .get(form)
+        .map(|s| &s[..])
+        .filter(|s| !s.is_empty())
+}
+
+/// Adds the context, references and comments of a message to an XLIFF 1.2 unit or group.
+fn notes_1_2(mut element: Element, msg: &Message) -> Element {
+    if let Some(ref ctxt) = msg.context {
+        let context = Element::new("context")
+            .attr("context-type", CONTEXT)
+            .text(ctxt);
+        let group = Element::new("context-group")
+            .attr("name", "x-gettext")
+            .attr("purpose", "information")
+            .child(context);
+        element = element.child(group);
+    }
+    for reference in &msg.references {
+        let mut group = Element::new("context-group").attr("purpose", "location");
+        let (file, line) = match reference.rsplit_once(':') {
+            Some((file, line)) if line.parse::<u64>().is_ok() => (file, Some(line)),
+            _ => (&reference[..], None),
+        };
+        group = group.child(
+            Element::new("context")
+                .attr("context-type", "sourcefile")
+                .text(file),
+        );
+        if let Some(line) = line {
+            group = group.child(
+                Element::new("context")
+                    .attr("context-type", "linenumber")
+                    .text(line),
+            );
+        }
+        element = element.child(group);
+    }
+    for comment in &msg.extracted_comments {
+        element = element.child(Element::new("note").attr("from", "developer").text(comment));
+    }
+    for comment in &msg.translator_comments {
+        element = element.child(
+            Element::new("note")
+                .attr("from", "translator")
+                .text(comment),
+        );
+    }
+    element
+}
+
+fn trans_unit(id: &str, msg: &Message, form: usize) -> Element {
+    let mut unit = Element::new("trans-unit").attr("id", id);
+    let target = translation(msg, form);
+    if target.is_some() && !msg.is_fuzzy() {
+        unit = unit.attr("approved", "yes");
+    }
+    unit = unit.child(Element::new("source").text(source(msg, form)));
+    if let Some(target) = target {
+        let state = match msg.is_fuzzy() {
+            true => "needs-review-translation",
+            false => "translated",
+        };
+        unit = unit.child(Element::new("target").attr("state", state).text(target));
+    }
+    unit
+}
+
+fn write_1_2(catalog: &Catalog, source: &str, target: Option<&str>) -> Element {
+    let mut body = Element::new("body");
+    for (i, msg) in sorted(catalog).into_iter().enumerate() {
+        let id = (i + 1).to_string();
+        body = body.child(match msg.id_plural {
+            Some(_) => {
+                let mut group = Element::new("group")
+                    .attr("id", &id)
+                    .attr("restype", PLURALS_1_2);
+                group = notes_1_2(group, msg);
+                for form in 0..forms(msg) {
+                    group = group.child(trans_unit(&format!("{}[{}]", id, form), msg, form));
+                }
+                group
+            }
+            None => notes_1_2(trans_unit(&id, msg, 0), msg),
+        });
+    }
+    let mut file = Element::new("file")
+        .attr("original", "messages")
+        .attr("datatype", "plaintext")
+        .attr("source-language", source);
+    if let Some(target) = target {
+        file = file.attr("target-language", target);
+    }
+    Element::new("xliff")
+        .attr("version", "1.2")
+        .attr("xmlns", "urn:oasis:names:tc:xliff:document:1.2")
+        .child(file.child(body))
+}
+
+/// Adds the context, references and comments of a message to an XLIFF 2.0 unit or group.
+fn notes_2_0(element: Element, msg: &Message) -> Element {
+    let mut notes = Element::new("notes");
+    let mut note = |category: &str, text: &str| {
+        let note = Element::new("note").attr("category", category).text(text);
+        notes.children.push(xml::Node::Element(note));
+    };
+    if let Some(ref ctxt) = msg.context {
+        note(CONTEXT, ctxt);
+    }
+    for reference in &msg.references {
+        note("location", reference);
+    }
+    for comment in &msg.extracted_comments {
+        note("developer", comment);
+    }
+    for comment in &msg.translator_comments {
+        note("translator", comment);
+    }
+    match notes.children.is_empty() {
+        true => element,
+        false => element.child(notes),
+    }
+}
+
+fn unit(id: &str, msg: &Message, form: usize) -> Element {
+    let target = translation(msg, form);
+    let state = match target {
+        None => "initial",
+        Some(_) if msg.is_fuzzy() => "translated",
+        Some(_) => "final",
+    };
+    let mut segment = Element::new("segment")
+        .attr("state", state)
+        .child(Element::new("source").text(source(msg, form)));
+    if let Some(target) = target {
+        segment = segment.child(Element::new("target").text(target));
+    }
+    Element::new("unit").attr("id", id).child(segment)
+}
+
+fn write_2_0(catalog: &Catalog, source: &str, target: Option<&str>) -> Element {
+    let mut file = Element::new("file").attr("id", "messages");
+    for (i, msg) in sorted(catalog).into_iter().enumerate() {
+        let id = i + 1;
+        file = file.child(match msg.id_plural {
+            Some(_) => {
+                let mut group = Element::new("group")
+                    .attr("id", &format!("g{}", id))
+                    .attr("type", PLURALS_2_0);
+                group = notes_2_0(group, msg);
+                for form in 0..forms(msg) {
+                    group = group.child(unit(&format!("u{}-{}", id, form), msg, form));
+                }
+                group
+            }
+            None => {
+                let mut unit = unit(&format!("u{}", id), msg, 0);
+                // the notes come before the segments
+                let segment = unit.children.pop().unwrap();
+                unit = notes_2_0(unit, msg);
+                unit.children.push(segment);
+                unit
+            }
+        });
+    }
+    let mut xliff = Element::new("xliff")
+        .attr("version", "2.0")
+        .attr("xmlns", "urn:oasis:names:tc:xliff:document:2.0")
+        .attr("srcLang", source);
+    if let Some(target) = target {
+        xliff = xliff.attr("trgLang", target);
+    }
+    xliff.child(file)
+}
+
+impl Catalog {
+    /// Reads a catalog from an XLIFF 1.2 or 2.0 document,
+    /// e.g. one exported by a translation management system.
+    ///
+    /// The target language of the document becomes the `Language` of the catalog,
+    /// which also decides its plural formula, and the source language is kept
+    /// in the `X-Source-Language` header.
+    /// Contexts, plural forms, comments and references are read back
+    /// from the way `write_xliff` writes them, and translations that still need
+    /// to be reviewed are flagged as `fuzzy`. Inline markup is flattened to its text.
+    ///
+    /// Returns `XmlSyntax` if the document is malformed or not XLIFF.
+    pub fn parse_xliff<R: Read>(reader: R) -> Result<Catalog, Error> {
+        let root = xml::read(reader)?;
+        let version = root.get("version").filter(|_| root.name == "xliff");
+        match version.and_then(|v| v.split('.').next()) {
+            Some("1") => {
+                let file = root.find("file").ok_or_else(|| root.error())?;
+                let languages = (file.get("source-language"), file.get("target-language"));
+                let mut catalog = with_languages(languages.0, languages.1)?;
+                for file in root.elements().filter(|e| e.name == "file") {
+                    if let Some(body) = file.find("body") {
+                        read_1_2(body, &mut catalog)?;
+                    }
+                }
+                Ok(catalog)
+            }
+            Some("2") => {
+                let mut catalog = with_languages(root.get("srcLang"), root.get("trgLang"))?;
+                for file in root.elements().filter(|e| e.name == "file") {
+                    read_2_0(file, &mut catalog)?;
+                }
+                Ok(catalog)
+            }
+            _ => Err(root.error()),
+        }
+    }
+
+    /// Writes the catalog out as an XLIFF document of the given version,
+    /// for translation management systems that do not read PO files.
+    ///
+    /// Messages with a plural form become groups of units, one for each form.
+    /// Contexts, comments and references are written as contexts and notes,
+    /// and `fuzzy` translations are marked as needing review.
+    /// The header and the obsolete messages are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::{Catalog, CatalogBuilder, XliffVersion};
+    ///
+    /// let catalog = CatalogBuilder::new()
+    ///     .header("Language", "lt")
+    ///     .add_with_context("menu", "Open", "Atidaryti")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut xliff = vec![];
+    /// catalog.write_xliff(&mut xliff, XliffVersion::V1_2).unwrap();
+    /// let read = Catalog::parse_xliff(&xliff[..]).unwrap();
+    /// assert_eq!(read.pgettext("menu", "Open"), "Atidaryti");
+    /// assert_eq!(read.metadata().language(), Some("lt"));
+    /// ```
+    pub fn write_xliff<W: Write>(&self, writer: W, version: XliffVersion) -> Result<(), Error> {
+        let metadata = self.metadata();
+        let source = metadata.get(SOURCE_LANGUAGE_HEADER).copied();
+        let source = source.unwrap_or(SOURCE_LANGUAGE);
+        let root = match version {
+            XliffVersion::V1_2 => write_1_2(self, source, metadata.language()),
+            XliffVersion::V2_0 => write_2_0(self, source, metadata.language()),
+        };
+        xml::write(&root, writer)
+    }
+}
+
+#[cfg(test)]
+fn test_catalog() -> Catalog {
+    let po = "msgid \"\"\nmsgstr \"\"\n\"Language: lt\\n\"\n\"X-Source-Language: en-US\\n\"\n\n# checked\n#. a verb\n#: src/main.rs:10 src/menu.rs\nmsgctxt \"menu\"\nmsgid \"Open <b>file</b>\"\nmsgstr \"Atidaryti <b>failą</b>\"\n\n#, fuzzy\nmsgid \"{} file\"\nmsgid_plural \"{} files\"\nmsgstr[0] \"{} failas\"\nmsgstr[1] \"\"\nmsgstr[2] \"{} failų\"\n\nmsgid \"Quit\"\nmsgstr \"\"\n\nmsgid \"Two\\nlines \"\nmsgstr \"Dvi\\neilutės \"\n\n#~ msgid \"Old\"\n#~ msgstr \"Senas\"\n";
+    Catalog::parse_po(po.as_bytes()).unwrap()
+}
+
+#[test]
+fn test_xliff_round_trip() {
+    let catalog = test_catalog();
+    for &version in &[XliffVersion::V1_2, XliffVersion::V2_0] {
+        let mut xliff = vec![];
+        catalog.write_xliff(&mut xliff, version).unwrap();
+        let read = Catalog::parse_xliff(&xliff[..]).unwrap();
+        assert_eq!(read.len(), 5, "{:?}", version);
+        for msg in sorted(&catalog) {
+            let key = crate::key::Key::new(msg.context(), msg.id());
+            assert_eq!(read.strings.get(&key), Some(msg), "{:?}", version);
+        }
+        assert!(read.obsolete.is_empty());
+        assert_eq!(read.metadata().language(), Some("lt"));
+        assert_eq!(read.metadata().get(SOURCE_LANGUAGE_HEADER), Some(&"en-US"));
+        assert_eq!(
+            read.pgettext("menu", "Open <b>file</b>"),
+            "Atidaryti <b>failą</b>"
+        );
+    }
+}
+
+#[test]
+fn test_write_xliff() {
+    let catalog = test_catalog();
+    let write = |version| {
+        let mut xliff = vec![];
+        catalog.write_xliff(&mut xliff, version).unwrap();
+        String::from_utf8(xliff).unwrap()
+    };
+    let xliff = write(XliffVersion::V1_2);
+    assert_eq!(
+        xliff,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file original="messages" datatype="plaintext" source-language="en-US" target-language="lt">
+    <body>
+      <trans-unit id="1">
+        <source>Quit</source>
+      </trans-unit>
+      <trans-unit id="2" approved="yes">
+        <source>Two
+lines </source>
+        <target state="translated">Dvi
+eilutės </target>
+      </trans-unit>
+      <trans-unit id="3" approved="yes">
+        <source>Open &lt;b&gt;file&lt;/b&gt;</source>
+        <target state="translated">Atidaryti &lt;b&gt;failą&lt;/b&gt;</target>
+        <context-group name="x-gettext" purpose="information">
+          <context context-type="x-gettext-msgctxt">menu</context>
+        </context-group>
+        <context-group purpose="location">
+          <context context-type="sourcefile">src/main.rs</context>
+          <context context-type="linenumber">10</context>
+        </context-group>
+        <context-group purpose="location">
+          <context context-type="sourcefile">src/menu.rs</context>
+        </context-group>
+        <note from="developer">a verb</note>
+        <note from="translator">checked</note>
+      </trans-unit>
+      <group id="4" restype="x-gettext-plurals">
+        <trans-unit id="4[0]">
+          <source>{} file</source>
+          <target state="needs-review-translation">{} failas</target>
+        </trans-unit>
+        <trans-unit id="4[1]">
+          <source>{} files</source>
+        </trans-unit>
+        <trans-unit id="4[2]">
+          <source>{} files</source>
+          <target state="needs-review-translation">{} failų</target>
+        </trans-unit>
+      </group>
+    </body>
+  </file>
+</xliff>
+"#
+    );
+
+    let xliff = write(XliffVersion::V2_0);
+    assert_eq!(
+        xliff,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="en-US" trgLang="lt">
+  <file id="messages">
+    <unit id="u1">
+      <segment state="initial">
+        <source>Quit</source>
+      </segment>
+    </unit>
+    <unit id="u2">
+      <segment state="final">
+        <source>Two
+lines </source>
+        <target>Dvi
+eilutės </target>
+      </segment>
+    </unit>
+    <unit id="u3">
+      <notes>
+        <note category="x-gettext-msgctxt">menu</note>
+        <note category="location">src/main.rs:10</note>
+        <note category="location">src/menu.rs</note>
+        <note category="developer">a verb</note>
+        <note category="translator">checked</note>
+      </notes>
+      <segment state="final">
+        <source>Open &lt;b&gt;file&lt;/b&gt;</source>
+        <target>Atidaryti &lt;b&gt;failą&lt;/b&gt;</target>
+      </segment>
+    </unit>
+    <group id="g4" type="x-gettext:plurals">
+      <unit id="u4-0">
+        <segment state="translated">
+          <source>{} file</source>
+          <target>{} failas</target>
+        </segment>
+      </unit>
+      <unit id="u4-1">
+        <segment state="initial">
+          <source>{} files</source>
+        </segment>
+      </unit>
+      <unit id="u4-2">
+        <segment state="translated">
+          <source>{} files</source>
+          <target>{} failų</target>
+        </segment>
+      </unit>
+    </group>
+  </file>
+</xliff>
+"#
+    );
+}
+
+#[test]
+fn test_parse_xliff() {
+    let xliff = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file original="a.po" source-language="en" target-language="pl" datatype="po">
+    <body>
+      <group id="nested">
+        <trans-unit id="open"><source>Open</source><target state="new">Otwórz</target></trans-unit>
+        <trans-unit id="save" approved="yes"><source>Save <g id="1">all</g></source><target state="needs-review-translation">Zapisz <g id="1">wszystko</g></target></trans-unit>
+      </group>
+      <trans-unit id="quit"><source>Quit</source><target/></trans-unit>
+      <trans-unit id="close"><source>Close</source><target>Zamknij</target><note>a button</note></trans-unit>
+    </body>
+  </file>
+</xliff>"#;
+    let catalog = Catalog::parse_xliff(xliff.as_bytes()).unwrap();
+    assert_eq!(catalog.len(), 5);
+    assert_eq!(catalog.metadata().language(), Some("pl"));
+    assert_eq!(catalog.resolver.resolve(5), 2);
+    assert!(catalog.get("Open").unwrap().is_fuzzy());
+    let save = catalog.get("Save all").unwrap();
+    assert_eq!(save.translated(), &["Zapisz wszystko"]);
+    assert!(!save.is_fuzzy());
+    assert!(!catalog.get("Quit").unwrap().is_translated());
+    let close = catalog.get("Close").unwrap();
+    assert!(!close.is_fuzzy());
+    assert_eq!(close.extracted_comments(), &["a button"]);
+
+    let xliff = r#"<xliff version="2.1" srcLang="en"><file id="f"><unit id="a"><segment state="reviewed"><source>Hello, </source><target>Labas, </target></segment><ignorable><source> </source></ignorable><segment><source>world</source><target>pasauli</target></segment></unit></file></xliff>"#;
+    let catalog = Catalog::parse_xliff(xliff.as_bytes()).unwrap();
+    let hello = catalog.get("Hello,  world").unwrap();
+    assert_eq!(hello.translated(), &["Labas, pasauli"]);
+    assert!(hello.is_fuzzy());
+    assert_eq!(catalog.metadata().language(), None);
+
+    for bad in &[
+        "<xliff/>",
+        "<tmx version=\"1.4\"/>",
+        "<xliff version=\"3.0\"/>",
+        "<xliff version=\"1.2\"/>",
+        "<xliff version=\"1.2\"><file><body><trans-unit id=\"a\"/></body></file></xliff>",
+        "<xliff version=\"2.0\"><file>\n<unit id=\"a\"/></file></xliff>",
+        "<xliff version=\"2.0\"><file><group type=\"x-gettext:plurals\"/></file></xliff>",
+    ] {
+        let result = Catalog::parse_xliff(bad.as_bytes());
+        assert!(matches!(result, Err(Error::XmlSyntax(_))), "{}", bad);
+    }
+}
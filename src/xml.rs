@@ -0,0 +1,424 @@
+//! A minimal XML tree, enough for the XML-based translation formats.
+//!
+//! Only well-formed UTF-8 documents are read. The XML declaration, processing
+//! instructions, comments and the document type declaration are skipped,
+//! and only the predefined and numeric character references are understood.
+
+// each format only uses some of the helpers, and the formats are optional
+#![allow(dead_code)]
+
+use std::io;
+
+use crate::Error::{self, *};
+
+/// The deepest nesting of elements read, so that a crafted document cannot exhaust memory.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+    /// The line the start tag is on, for errors.
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Element {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds an attribute, for writing.
+    pub fn attr(mut self, name: &str, value: &str) -> Self {
+        self.attributes.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Adds a child element, for writing.
+    pub fn child(mut self, child: Element) -> Self {
+        self.children.push(Node::Element(child));
+        self
+    }
+
+    /// Adds text content, for writing.
+    pub fn text(mut self, text: &str) -> Self {
+        self.children.push(Node::Text(text.to_owned()));
+        self
+    }
+
+    /// Returns the value of the attribute with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| &v[..])
+    }
+
+    /// Returns the child elements.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match *node {
+            Node::Element(ref element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Returns the first child element with the given name.
+    pub fn find(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    /// Returns the text of the element and all of its descendants.
+    pub fn content(&self) -> String {
+        let mut content = String::new();
+        self.push_content(&mut content);
+        content
+    }
+
+    fn push_content(&self, out: &mut String) {
+        for node in &self.children {
+            match *node {
+                Node::Element(ref element) => element.push_content(out),
+                Node::Text(ref text) => out.push_str(text),
+            }
+        }
+    }
+
+    /// Returns the error for malformed content of this element.
+    pub fn error(&self) -> Error {
+        XmlSyntax(self.line)
+    }
+}
+
+/// Reads the whole document and returns its root element.
+pub fn read<R: io::Read>(mut reader: R) -> Result<Element, Error> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let src = String::from_utf8(bytes).map_err(|_| DecodingError)?;
+    parse(src.strip_prefix('\u{feff}').unwrap_or(&src))
+}
+
+/// Counts the lines of a document up to increasing positions.
+struct Lines<'a> {
+    src: &'a str,
+    line: usize,
+    counted: usize,
+}
+
+impl Lines<'_> {
+    fn at(&mut self, pos: usize) -> usize {
+        self.line += self.src[self.counted..pos].matches('\n').count();
+        self.counted = pos;
+        self.line
+    }
+}
+
+/// Parses a document and returns its root element.
+pub fn parse(src: &str) -> Result<Element, Error> {
+    let mut lines = Lines {
+        src,
+        line: 1,
+        counted: 0,
+    };
+    let mut line = |pos: usize| lines.at(pos);
+    let mut stack: Vec<Element> = vec![];
+    let mut root = None;
+    let mut pos = 0;
+    while pos < src.len() {
+        let rest = &src[pos..];
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Node::Text(
+                    unescape(text).ok_or_else(|| XmlSyntax(line(pos)))?,
+                )),
+                None if text.trim().is_empty() => {}
+                None => return Err(XmlSyntax(line(pos))),
+            }
+            pos += end;
+            continue;
+        }
+
+        let mut skip = |open: &str, close: &str| -> Result<Option<usize>, Error> {
+            if !rest.starts_with(open) {
+                return Ok(None);
+            }
+            let end = rest.find(close).ok_or_else(|| XmlSyntax(line(pos)))?;
+            Ok(Some(end + close.len()))
+        };
+        if let Some(len) = skip("<!--", "-->")? {
+            pos += len;
+        } else if let Some(len) = skip("<?", "?>")? {
+            pos += len;
+        } else if rest.starts_with("<![CDATA[") {
+            let len = skip("<![CDATA[", "]]>")?.unwrap();
+            let text = &rest[9..len - 3];
+            let parent = stack.last_mut().ok_or_else(|| XmlSyntax(line(pos)))?;
+            parent.children.push(Node::Text(text.to_owned()));
+            pos += len;
+        } else if rest.starts_with("<!") {
+            // the document type declaration, with its internal subset if any
+            let mut depth = 0;
+            let end = rest.char_indices().find(|&(_, c)| {
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    _ => {}
+                }
+                c == '>' && depth == 0
+            });
+            pos += end.ok_or_else(|| XmlSyntax(line(pos)))?.0 + 1;
+        } else if let Some(tag) = rest.strip_prefix("</") {
+            let end = tag.find('>').ok_or_else(|| XmlSyntax(line(pos)))?;
+            let element = stack.pop().ok_or_else(|| XmlSyntax(line(pos)))?;
+            if tag[..end].trim_end() != element.name {
+                return Err(XmlSyntax(line(pos)));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Node::Element(element)),
+                None => root = Some(element),
+            }
+            pos += 2 + end + 1;
+        } else {
+            if root.is_some() || stack.len() >= MAX_DEPTH {
+                return Err(XmlSyntax(line(pos)));
+            }
+            let (element, len, empty) = start_tag(rest, line(pos))?;
+            if empty {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(element)),
+                    None => root = Some(element),
+                }
+            } else {
+                stack.push(element);
+            }
+            pos += len;
+        }
+    }
+    match root {
+        Some(root) if stack.is_empty() => Ok(root),
+        _ => Err(XmlSyntax(line(src.len()))),
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-.:".contains(c)
+}
+
+/// Parses the start tag at the beginning of `src`,
+/// returning the element, the length of the tag and whether it is an empty-element tag.
+fn start_tag(src: &str, line: usize) -> Result<(Element, usize, bool), Error> {
+    let name_len = src[1..].find(|c| !is_name_char(c)).unwrap_or(src.len() - 1);
+    if name_len == 0 {
+        return Err(XmlSyntax(line));
+    }
+    let mut element = Element::new(&src[1..1 + name_len]);
+    element.line = line;
+    let mut pos = 1 + name_len;
+    loop {
+        let rest = &src[pos..];
+        let trimmed = rest.trim_start();
+        pos += rest.len() - trimmed.len();
+        if trimmed.starts_with("/>") {
+            return Ok((element, pos + 2, true));
+        }
+        if trimmed.starts_with('>') {
+            return Ok((element, pos + 1, false));
+        }
+        // attributes have to be separated from the name and from each other
+        if trimmed.len() == rest.len() {
+            return Err(XmlSyntax(line));
+        }
+        let name_len = trimmed.find(|c| !is_name_char(c)).unwrap_or(trimmed.len());
+        let name = &trimmed[..name_len];
+        let after = trimmed[name_len..].trim_start();
+        let value = after.strip_prefix('=').map(str::trim_start);
+        let quote = value
+            .and_then(|v| v.chars().next())
+            .filter(|&q| q == '"' || q == '\'');
+        let (value, quote) = match (value, quote) {
+            (Some(value), Some(quote)) if !name.is_empty() => (&value[1..], quote),
+            _ => return Err(XmlSyntax(line)),
+        };
+        let end = value.find(quote).ok_or(XmlSyntax(line))?;
+        let unescaped = unescape(&value[..end]).ok_or(XmlSyntax(line))?;
+        element.attributes.push((name.to_owned(), unescaped));
+        pos = src.len() - value.len() + end + 1;
+    }
+}
+
+/// Resolves the character references of text or an attribute value.
+fn unescape(src: &str) -> Option<String> {
+    if src.contains('<') {
+        return None;
+    }
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        let end = rest[i..].find(';')? + i;
+        let c = match &rest[i + 1..end] {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            reference => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => reference.strip_prefix('#')?.parse().ok()?,
+                };
+                std::char::from_u32(code)?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Appends `s` to `out`, escaping the characters that cannot appear as they are.
+pub fn push_escaped(out: &mut String, s: &str, attribute: bool) {
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '\n' if attribute => out.push_str("&#10;"),
+            '\t' if attribute => out.push_str("&#9;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Writes the document, indenting the elements that only contain elements.
+pub fn write<W: io::Write>(root: &Element, mut writer: W) -> Result<(), Error> {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    push_element(&mut out, root, Some(0));
+    out.push('\n');
+    writer.write_all(out.as_bytes())?;
+    Ok(())
+}
+
+/// Appends the element to `out`, at the given indentation level or inline.
+fn push_element(out: &mut String, element: &Element, level: Option<usize>) {
+    out.push('<');
+    out.push_str(&element.name);
+    for (name, value) in &element.attributes {
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        push_escaped(out, value, true);
+        out.push('"');
+    }
+    if element.children.is_empty() {
+        out.push_str("/>");
+        return;
+    }
+    out.push('>');
+    let nested = element
+        .children
+        .iter()
+        .all(|node| matches!(*node, Node::Element(_)));
+    for node in &element.children {
+        match *node {
+            Node::Element(ref child) => match level {
+                Some(level) if nested => {
+                    out.push('\n');
+                    out.push_str(&"  ".repeat(level + 1));
+                    push_element(out, child, Some(level + 1));
+                }
+                _ => push_element(out, child, None),
+            },
+            Node::Text(ref text) => push_escaped(out, text, false),
+        }
+    }
+    if let Some(level) = level.filter(|_| nested) {
+        out.push('\n');
+        out.push_str(&"  ".repeat(level));
+    }
+    out.push_str("</");
+    out.push_str(&element.name);
+    out.push('>');
+}
+
+#[test]
+fn test_parse() {
+    let src = "\u{feff}<?xml version=\"1.0\"?>\n<!DOCTYPE TS [<!ENTITY x \"y\">]>\n<!-- a <comment> -->\n<root a=\"1 &amp; 2\" b = 'it&apos;s'>\n  <empty/>\n  <text>x &lt; y&#10;&#x105;</text>\n  <mixed>a <b>bold</b> c<![CDATA[<raw>]]></mixed>\n</root>\n";
+    let root = read(src.as_bytes()).unwrap();
+    assert_eq!(root.name, "root");
+    assert_eq!(root.line, 4);
+    assert_eq!(root.get("a"), Some("1 & 2"));
+    assert_eq!(root.get("b"), Some("it's"));
+    assert_eq!(root.get("c"), None);
+    assert_eq!(
+        root.elements().map(|e| &e.name[..]).collect::<Vec<_>>(),
+        ["empty", "text", "mixed"]
+    );
+    assert!(root.find("empty").unwrap().children.is_empty());
+    assert_eq!(root.find("text").unwrap().content(), "x < y\ną");
+    assert_eq!(root.find("mixed").unwrap().content(), "a bold c<raw>");
+    assert_eq!(root.find("mixed").unwrap().line, 7);
+
+    for bad in &[
+        "",
+        "text",
+        "<a>",
+        "<a></b>",
+        "<a/><b/>",
+        "<a x=1/>",
+        "<a x=\"1\"y=\"2\"/>",
+        "<a>&unknown;</a>",
+        "<a>&amp</a>",
+        "<a><!-- unclosed</a>",
+        "<>",
+    ] {
+        assert!(matches!(parse(bad), Err(XmlSyntax(_))), "{}", bad);
+    }
+    assert!(matches!(parse("<a>\n\n</b>"), Err(XmlSyntax(3))));
+    let deep = "<a>".repeat(MAX_DEPTH + 1);
+    assert!(matches!(parse(&deep), Err(XmlSyntax(1))));
+    assert!(matches!(read(&b"<a>\xff</a>"[..]), Err(DecodingError)));
+}
+
+#[test]
+fn test_write() {
+    let root = Element::new("root")
+        .attr("a", "\"1\" & <2>\n")
+        .child(Element::new("empty"))
+        .child(Element::new("text").text("x < y"))
+        .child(Element::new("group").child(Element::new("item").text("ą")))
+        .child(
+            Element::new("mixed")
+                .text("a ")
+                .child(Element::new("b").text("bold")),
+        );
+    let mut out = vec![];
+    write(&root, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <root a=\"&quot;1&quot; &amp; &lt;2&gt;&#10;\">\n  \
+           <empty/>\n  \
+           <text>x &lt; y</text>\n  \
+           <group>\n    <item>ą</item>\n  </group>\n  \
+           <mixed>a <b>bold</b></mixed>\n\
+         </root>\n"
+    );
+    let parsed = parse(&out).unwrap();
+    assert_eq!(parsed.get("a"), Some("\"1\" & <2>\n"));
+    assert_eq!(parsed.find("group").unwrap().content(), "\n    ą\n  ");
+    assert_eq!(parsed.find("mixed").unwrap().content(), "a bold");
+}
@@ -0,0 +1,1099 @@
+//! Conversion of catalogs from and to the YAML locale files of Rails.
+
+use std::io::{Read, Write};
+
+use crate::key::{Key, KeyParts};
+use crate::Error::{self, *};
+use crate::{Catalog, CatalogBuilder, Message, PluralCategory};
+
+/// A node of a YAML document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Node {
+    /// A `~` or empty value
+    Null,
+    /// A string, or any other scalar, kept as it is written
+    Scalar(String),
+    /// A sequence, whose items are skipped
+    Sequence,
+    /// A mapping, in the order of its keys
+    Mapping(Vec<Entry>),
+}
+
+/// An entry of a mapping.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Entry {
+    key: String,
+    value: Node,
+    /// The comments on the lines before the entry
+    comments: Vec<String>,
+    line: usize,
+}
+
+impl Entry {
+    fn new(key: &str, value: Node) -> Self {
+        Entry {
+            key: key.to_owned(),
+            value,
+            comments: vec![],
+            line: 0,
+        }
+    }
+}
+
+/// Returns a plain scalar as a node, which is null if it is written as one.
+fn plain_node(text: String) -> Node {
+    match &text[..] {
+        "~" | "null" | "Null" | "NULL" => Node::Null,
+        _ => Node::Scalar(text),
+    }
+}
+
+/// Returns whether a mapping has the plural forms of a message,
+/// which are keyed by CLDR categories and include `other`.
+fn is_plural(entries: &[Entry]) -> bool {
+    entries.iter().any(|entry| entry.key == "other")
+        && entries.iter().all(|entry| {
+            entry.key.parse::<PluralCategory>().is_ok()
+                && matches!(entry.value, Node::Scalar(_) | Node::Null)
+        })
+}
+
+/// Returns the number of spaces that a line is indented with.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Returns whether a line has nothing but whitespace or a comment.
+fn is_blank(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Returns whether a line starts or ends a document.
+fn is_marker(line: &str) -> bool {
+    ["---", "..."]
+        .iter()
+        .any(|marker| line == *marker || line.starts_with(&format!("{} ", marker)))
+}
+
+/// Returns whether a line is an item of a block sequence.
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Returns a plain scalar without the comment after it, if any.
+fn strip_comment(text: &str) -> &str {
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        if c == '#' && (prev == ' ' || prev == '\t') {
+            return text[..i].trim_end();
+        }
+        prev = c;
+    }
+    text.trim_end()
+}
+
+/// Returns the end of a quoted scalar at the start of `text`, after its closing quote.
+fn closing(text: &str) -> Option<usize> {
+    let quote = text.chars().next()?;
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            '\'' if quote == '\'' && text[i + 1..].starts_with('\'') => {
+                chars.next();
+            }
+            c if c == quote => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the character of a `\x`, `\u` or `\U` escape with `n` hexadecimal digits.
+fn hex(chars: &mut impl Iterator<Item = char>, n: usize) -> Option<char> {
+    let digits = chars.take(n).collect::<String>();
+    if digits.len() != n {
+        return None;
+    }
+    char::from_u32(u32::from_str_radix(&digits, 16).ok()?)
+}
+
+/// Returns the value of a quoted scalar, with its quotes, folding its line breaks
+/// like YAML does, or `None` if an escape is malformed.
+fn unquote(text: &str) -> Option<String> {
+    let quote = text.chars().next()?;
+    let mut chars = text[1..text.len() - 1].chars().peekable();
+    let mut out = String::new();
+    // the length of `out` that is kept when whitespace is trimmed at a line break
+    let mut kept = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if quote == '\'' => {
+                chars.next();
+                out.push('\'');
+            }
+            '\\' if quote == '"' => {
+                let c = match chars.next()? {
+                    '0' => '\0',
+                    'a' => '\u{7}',
+                    'b' => '\u{8}',
+                    't' | '\t' => '\t',
+                    'n' => '\n',
+                    'v' => '\u{b}',
+                    'f' => '\u{c}',
+                    'r' => '\r',
+                    'e' => '\u{1b}',
+                    'N' => '\u{85}',
+                    '_' => '\u{a0}',
+                    'L' => '\u{2028}',
+                    'P' => '\u{2029}',
+                    'x' => hex(&mut chars, 2)?,
+                    'u' => hex(&mut chars, 4)?,
+                    'U' => hex(&mut chars, 8)?,
+                    // an escaped line break joins the lines without a space
+                    '\n' => {
+                        while matches!(chars.peek(), Some(' ') | Some('\t')) {
+                            chars.next();
+                        }
+                        kept = out.len();
+                        continue;
+                    }
+                    c @ ' ' | c @ '"' | c @ '/' | c @ '\\' => c,
+                    _ => return None,
+                };
+                out.push(c);
+                kept = out.len();
+            }
+            '\n' => {
+                out.truncate(kept.max(out.trim_end_matches([' ', '\t']).len()));
+                let mut breaks = 0;
+                loop {
+                    while matches!(chars.peek(), Some(' ') | Some('\t')) {
+                        chars.next();
+                    }
+                    if chars.peek() != Some(&'\n') {
+                        break;
+                    }
+                    chars.next();
+                    breaks += 1;
+                }
+                match breaks {
+                    0 => out.push(' '),
+                    _ => (0..breaks).for_each(|_| out.push('\n')),
+                }
+                kept = out.len();
+            }
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Returns the end of a flow collection at the start of `text`, after its closing bracket.
+fn flow_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    // the last character other than whitespace, after which a quote starts a scalar
+    let mut prev = ',';
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        match c {
+            '"' | '\'' if matches!(prev, '[' | '{' | ',' | ':') => {
+                i += closing(&text[i..])?;
+                prev = c;
+                continue;
+            }
+            '[' | '{' => depth += 1,
+            ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            prev = c;
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// Reads the nodes of a flow collection, e.g. `{one: failas, other: failai}`.
+struct Flow<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl Flow<'_> {
+    fn skip(&mut self) {
+        self.pos = self.src.len() - self.src[self.pos..].trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    /// Reads the next character if it is one of `ends`.
+    fn end(&mut self, ends: &[char]) -> Option<char> {
+        self.skip();
+        let c = self.peek().filter(|c| ends.contains(c))?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn node(&mut self) -> Option<Node> {
+        self.skip();
+        match self.peek()? {
+            '[' => {
+                self.pos += 1;
+                if self.end(&[']']).is_some() {
+                    return Some(Node::Sequence);
+                }
+                loop {
+                    self.node()?;
+                    if self.end(&[',', ']'])? == ']' {
+                        return Some(Node::Sequence);
+                    }
+                }
+            }
+            '{' => {
+                self.pos += 1;
+                let mut entries = vec![];
+                if self.end(&['}']).is_some() {
+                    return Some(Node::Mapping(entries));
+                }
+                loop {
+                    let key = match self.node()? {
+                        Node::Scalar(key) => key,
+                        _ => return None,
+                    };
+                    self.end(&[':'])?;
+                    self.skip();
+                    let value = match self.peek()? {
+                        ',' | '}' => Node::Null,
+                        _ => self.node()?,
+                    };
+                    let mut entry = Entry::new(&key, value);
+                    entry.line = self.line;
+                    entries.push(entry);
+                    if self.end(&[',', '}'])? == '}' {
+                        return Some(Node::Mapping(entries));
+                    }
+                }
+            }
+            '"' | '\'' => {
+                let end = self.pos + closing(&self.src[self.pos..])?;
+                let value = unquote(&self.src[self.pos..end])?;
+                self.pos = end;
+                Some(Node::Scalar(value))
+            }
+            _ => {
+                let rest = &self.src[self.pos..];
+                let end =
+                    rest.char_indices()
+                        .find(|&(i, c)| match c {
+                            ',' | '[' | ']' | '{' | '}' => true,
+                            ':' => rest[i + 1..].chars().next().map_or(true, |c| {
+                                c.is_whitespace() || matches!(c, ',' | ']' | '}')
+                            }),
+                            _ => false,
+                        })
+                        .map_or(rest.len(), |(i, _)| i);
+                let lines = rest[..end].split('\n').map(str::trim).collect::<Vec<_>>();
+                self.pos += end;
+                match lines.join(" ") {
+                    text if text.is_empty() => None,
+                    text => Some(plain_node(text)),
+                }
+            }
+        }
+    }
+}
+
+/// Reads the nodes of a YAML document, line by line.
+///
+/// Only the block and flow styles that locale files are written in are supported,
+/// so anchors, aliases, tags and complex keys are syntax errors.
+struct Parser<'a> {
+    lines: Vec<&'a str>,
+    /// The index of the next line to read, which is the number of the last one read
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self) -> Error {
+        YamlSyntax(self.pos.max(1))
+    }
+
+    /// Returns the index of the next line with content, without reading it.
+    fn peek_content(&self) -> Option<usize> {
+        (self.pos..self.lines.len()).find(|&i| !is_blank(self.lines[i]))
+    }
+
+    /// Reads the entries of the mapping at the root of the document.
+    fn document(&mut self) -> Result<Vec<Entry>, Error> {
+        if let Some(i) = self.peek_content() {
+            let line = self.lines[i];
+            if line.starts_with('%') || (is_marker(line) && !is_blank(&line[3..])) {
+                self.pos = i + 1;
+                return Err(self.error());
+            }
+            if line.starts_with("---") {
+                self.pos = i + 1;
+            }
+        }
+        let entries = match self.peek_content() {
+            Some(i) if !is_marker(self.lines[i]) => self.mapping(indentation(self.lines[i]))?,
+            _ => vec![],
+        };
+        // only the end of the document may follow, as a file has a single one
+        if let Some(i) = self.peek_content() {
+            self.pos = i + 1;
+            if !self.lines[i].starts_with("...") {
+                return Err(self.error());
+            }
+            if let Some(i) = self.peek_content() {
+                self.pos = i + 1;
+                return Err(self.error());
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Reads the entries of a block mapping indented by `indent` spaces.
+    fn mapping(&mut self, indent: usize) -> Result<Vec<Entry>, Error> {
+        let mut entries = vec![];
+        let mut comments = vec![];
+        // the first line of the comments, which belong to the next mapping if this one ends
+        let mut comments_start = self.pos;
+        while let Some(&line) = self.lines.get(self.pos) {
+            let text = line.trim_start();
+            if text.is_empty() {
+                comments.clear();
+                self.pos += 1;
+                continue;
+            }
+            if let Some(comment) = text.strip_prefix('#') {
+                if comments.is_empty() {
+                    comments_start = self.pos;
+                }
+                comments.push(comment.trim().to_owned());
+                self.pos += 1;
+                continue;
+            }
+            let n = indentation(line);
+            if n < indent || is_marker(line) {
+                if !comments.is_empty() {
+                    self.pos = comments_start;
+                }
+                break;
+            }
+            self.pos += 1;
+            let text = &line[n..];
+            if n > indent || text.starts_with('\t') || is_item(text) {
+                return Err(self.error());
+            }
+            let line_no = self.pos;
+            let (key, rest) = self.key(text)?;
+            let value = self.value(indent, rest)?;
+            entries.push(Entry {
+                key,
+                value,
+                comments: comments.split_off(0),
+                line: line_no,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Reads the key of an entry, returning it with the rest of the line after the colon.
+    fn key(&self, text: &'a str) -> Result<(String, &'a str), Error> {
+        if text.starts_with(['"', '\'']) {
+            let end = closing(text).ok_or_else(|| self.error())?;
+            let key = unquote(&text[..end]).ok_or_else(|| self.error())?;
+            let rest = text[end..].trim_start();
+            return match rest.strip_prefix(':') {
+                Some(rest) if rest.is_empty() || rest.starts_with([' ', '\t']) => Ok((key, rest)),
+                _ => Err(self.error()),
+            };
+        }
+        if text.starts_with(['?', '&', '*', '!', '[', '{', '|', '>', '%', '@', '`']) {
+            return Err(self.error());
+        }
+        let colon = text.char_indices().find(|&(i, c)| {
+            c == ':' && (text[i + 1..].is_empty() || text[i + 1..].starts_with([' ', '\t']))
+        });
+        match colon {
+            Some((i, _)) if strip_comment(&text[..i]) == text[..i].trim_end() => {
+                Ok((text[..i].trim_end().to_owned(), &text[i + 1..]))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    /// Reads the value of an entry of a mapping indented by `indent` spaces,
+    /// which starts with `rest` on the line of its key.
+    fn value(&mut self, indent: usize, rest: &'a str) -> Result<Node, Error> {
+        let rest = rest.trim_start();
+        if is_blank(rest) {
+            return self.block(indent);
+        }
+        match rest.chars().next().unwrap() {
+            '"' | '\'' => {
+                let text = self.gather(rest, closing)?;
+                let end = closing(&text).unwrap();
+                let value = unquote(&text[..end]).ok_or_else(|| self.error())?;
+                match is_blank(&text[end..]) {
+                    true => Ok(Node::Scalar(value)),
+                    false => Err(self.error()),
+                }
+            }
+            '[' | '{' => {
+                let text = self.gather(rest, flow_end)?;
+                let end = flow_end(&text).unwrap();
+                let mut flow = Flow {
+                    src: &text[..end],
+                    pos: 0,
+                    line: self.pos,
+                };
+                match flow.node() {
+                    Some(node) if is_blank(&text[end..]) => Ok(node),
+                    _ => Err(self.error()),
+                }
+            }
+            '|' | '>' => self.block_scalar(indent, rest),
+            '&' | '*' | '!' | '%' | '@' | '`' => Err(self.error()),
+            _ => self.plain(indent, rest),
+        }
+    }
+
+    /// Returns `text` with as many of the next lines as `end` needs to find its end.
+    fn gather(&mut self, text: &str, end: fn(&str) -> Option<usize>) -> Result<String, Error> {
+        let mut text = text.to_owned();
+        while end(&text).is_none() {
+            let line = self.lines.get(self.pos).ok_or_else(|| self.error())?;
+            self.pos += 1;
+            text.push('\n');
+            text.push_str(line);
+        }
+        Ok(text)
+    }
+
+    /// Reads a value on the lines after its key, a nested mapping or sequence,
+    /// or `Null` if it has none.
+    fn block(&mut self, indent: usize) -> Result<Node, Error> {
+        let line = match self.peek_content() {
+            Some(i) => self.lines[i],
+            None => return Ok(Node::Null),
+        };
+        let n = indentation(line);
+        // the items of a sequence may be indented as much as the key
+        if is_item(&line[n..]) && n >= indent {
+            while let Some(i) = self.peek_content() {
+                let line = self.lines[i];
+                let m = indentation(line);
+                if m <= n && !(m == n && is_item(&line[m..])) {
+                    break;
+                }
+                self.pos = i + 1;
+            }
+            Ok(Node::Sequence)
+        } else if n > indent && !is_marker(line) {
+            Ok(Node::Mapping(self.mapping(n)?))
+        } else {
+            Ok(Node::Null)
+        }
+    }
+
+    /// Reads a plain scalar, which may go on on the lines after it that are indented more.
+    fn plain(&mut self, indent: usize, first: &str) -> Result<Node, Error> {
+        let mut out = strip_comment(first).to_owned();
+        let mut commented = out.len() < first.trim_end().len();
+        while !commented {
+            let next = (self.pos..self.lines.len()).find(|&i| !self.lines[i].trim().is_empty());
+            let i = match next {
+                Some(i) => i,
+                None => break,
+            };
+            let text = self.lines[i].trim_start();
+            if indentation(self.lines[i]) <= indent || text.starts_with('#') {
+                break;
+            }
+            match i - self.pos {
+                0 => out.push(' '),
+                breaks => (0..breaks).for_each(|_| out.push('\n')),
+            }
+            let text = text.trim_end();
+            self.pos = i + 1;
+            // a key indented more than the one of the scalar
+            if text.ends_with(':') || text.contains(": ") {
+                return Err(self.error());
+            }
+            let stripped = strip_comment(text);
+            out.push_str(stripped);
+            commented = stripped.len() < text.len();
+        }
+        Ok(plain_node(out))
+    }
+
+    /// Reads a literal `|` or folded `>` block scalar with the given header.
+    fn block_scalar(&mut self, indent: usize, header: &str) -> Result<Node, Error> {
+        let literal = header.starts_with('|');
+        let header = &header[1..];
+        let end = header.find([' ', '\t']).unwrap_or(header.len());
+        if !is_blank(&header[end..]) {
+            return Err(self.error());
+        }
+        let (mut chomping, mut explicit) = (None, None);
+        for c in header[..end].chars() {
+            match c {
+                '+' | '-' if chomping.is_none() => chomping = Some(c),
+                '1'..='9' if explicit.is_none() => explicit = c.to_digit(10),
+                _ => return Err(self.error()),
+            }
+        }
+        let content_indent = match explicit {
+            Some(n) => indent + n as usize,
+            None => {
+                let first =
+                    (self.pos..self.lines.len()).find(|&i| !self.lines[i].trim().is_empty());
+                match first.map(|i| indentation(self.lines[i])) {
+                    Some(n) if n > indent => n,
+                    _ => usize::MAX,
+                }
+            }
+        };
+
+        let mut lines = vec![];
+        while let Some(&line) = self.lines.get(self.pos) {
+            if line.trim().is_empty() {
+                lines.push(line.get(content_indent..).unwrap_or(""));
+            } else if indentation(line) >= content_indent {
+                lines.push(&line[content_indent..]);
+            } else {
+                break;
+            }
+            self.pos += 1;
+        }
+        let trailing = lines
+            .iter()
+            .rev()
+            .take_while(|line| line.is_empty())
+            .count();
+        let content = &lines[..lines.len() - trailing];
+
+        let mut out = String::new();
+        for (i, line) in content.iter().enumerate() {
+            if i > 0 {
+                // folding joins lines with a space, and drops the break before empty lines,
+                // but keeps the breaks of lines that are indented more
+                let prev = content[i - 1];
+                let normal = |line: &str| !line.is_empty() && !line.starts_with([' ', '\t']);
+                if literal || !normal(prev) || (!line.is_empty() && !normal(line)) {
+                    out.push('\n');
+                } else if normal(line) {
+                    out.push(' ');
+                }
+            }
+            out.push_str(line);
+        }
+        if chomping != Some('-') && !content.is_empty() {
+            out.push('\n');
+        }
+        if chomping == Some('+') {
+            (0..trailing).for_each(|_| out.push('\n'));
+        }
+        Ok(Node::Scalar(out))
+    }
+}
+
+/// Collects the entries of nested mappings with their keys joined by dots,
+/// down to the scalars and the mappings of plural forms.
+fn flatten(entries: Vec<Entry>, prefix: &str, leaves: &mut Vec<(String, Entry)>) {
+    for entry in entries {
+        let key = match prefix {
+            "" => entry.key.clone(),
+            _ => format!("{}.{}", prefix, entry.key),
+        };
+        match entry.value {
+            Node::Mapping(entries) if !is_plural(&entries) => flatten(entries, &key, leaves),
+            _ => leaves.push((key, entry)),
+        }
+    }
+}
+
+/// Returns whether text can be written as a plain scalar that a YAML 1.1 parser,
+/// like the one of Ruby, reads back as the same string.
+fn is_plain(text: &str) -> bool {
+    let first = match text.chars().next() {
+        Some(c) => c,
+        None => return false,
+    };
+    let special = [
+        "null", "true", "false", "yes", "no", "on", "off", "y", "n", "<<",
+    ];
+    !first.is_ascii_digit()
+        && !"-?:,[]{}#&*!|>'\"%@`+.~=".contains(first)
+        && text.trim() == text
+        && !text.ends_with(':')
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text
+            .chars()
+            .any(|c| c.is_control() || matches!(c, '\u{85}' | '\u{2028}' | '\u{2029}' | '\u{feff}'))
+        && !special.contains(&&*text.to_ascii_lowercase())
+}
+
+/// Returns text as a scalar, quoted if it is not plain.
+fn scalar(text: &str) -> String {
+    if is_plain(text) {
+        return text.to_owned();
+    }
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{85}' => out.push_str("\\N"),
+            '\u{2028}' => out.push_str("\\L"),
+            '\u{2029}' => out.push_str("\\P"),
+            c if c.is_control() => out.push_str(&format!("\\x{:02X}", c as u32)),
+            '\u{feff}' => out.push_str("\\uFEFF"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Inserts `entry` at `path` in nested mappings, returning false if it clashes with another key.
+fn nest(entries: &mut Vec<Entry>, path: &[&str], entry: &Entry) -> bool {
+    let existing = entries.iter().position(|e| e.key == path[0]);
+    if path.len() == 1 {
+        if existing.is_none() {
+            entries.push(Entry {
+                key: path[0].to_owned(),
+                ..entry.clone()
+            });
+        }
+        return existing.is_none();
+    }
+    let i = match existing {
+        Some(i) => i,
+        None => {
+            entries.push(Entry::new(path[0], Node::Mapping(vec![])));
+            entries.len() - 1
+        }
+    };
+    match entries[i].value {
+        Node::Mapping(ref mut entries) if !is_plural(entries) => nest(entries, &path[1..], entry),
+        _ => false,
+    }
+}
+
+/// Appends the entries of a mapping at the given depth to a document.
+fn push_entries(out: &mut String, entries: &[Entry], depth: usize) {
+    let indent = "  ".repeat(depth);
+    for entry in entries {
+        for comment in &entry.comments {
+            out.push_str(format!("{}# {}", indent, comment).trim_end());
+            out.push('\n');
+        }
+        match entry.value {
+            Node::Mapping(ref entries) => {
+                out.push_str(&format!("{}{}:\n", indent, scalar(&entry.key)));
+                push_entries(out, entries, depth + 1);
+            }
+            Node::Scalar(ref text) => {
+                let line = format!("{}{}: {}\n", indent, scalar(&entry.key), scalar(text));
+                out.push_str(&line);
+            }
+            Node::Null | Node::Sequence => {}
+        }
+    }
+}
+
+impl Catalog {
+    /// Reads a catalog from a YAML locale file of Rails, e.g. `config/locales/lt.yml`.
+    ///
+    /// The key at the root of the file is the `Language` of the catalog,
+    /// and the keys of the nested mappings under it are joined by dots into msgids,
+    /// as `I18n.t` looks them up. A msgctxt is put before the msgid with `\u{4}`,
+    /// like in MO files. Mappings with CLDR categories as their keys, including `other`,
+    /// are plural messages: their forms are mapped through the numbers that CLDR assigns
+    /// to each category in the language, and the forms without a category
+    /// of their own get the `other` text. The plural msgid is the same as the msgid,
+    /// as the files have no other. The comments before an entry become its
+    /// extracted comments. `~` values and sequences, like the day names of `date`,
+    /// are skipped, and other scalars are read as they are written.
+    ///
+    /// Returns `YamlSyntax` if the file is malformed, has more than one locale,
+    /// or uses anchors, aliases or tags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gettext::Catalog;
+    ///
+    /// let yaml = "\
+    /// lt:
+    ///   greeting: Labas, %{name}!
+    ///   inbox:
+    ///     title: Gauti laiškai
+    ///     count:
+    ///       one: \"%{count} laiškas\"
+    ///       few: \"%{count} laiškai\"
+    ///       other: \"%{count} laiškų\"
+    /// ";
+    /// let catalog = Catalog::parse_yaml(yaml.as_bytes()).unwrap();
+    /// assert_eq!(catalog.metadata().language(), Some("lt"));
+    /// assert_eq!(catalog.gettext("greeting"), "Labas, %{name}!");
+    /// assert_eq!(catalog.gettext("inbox.title"), "Gauti laiškai");
+    /// assert_eq!(catalog.ngettext("inbox.count", "inbox.count", 3), "%{count} laiškai");
+    /// ```
+    pub fn parse_yaml<R: Read>(mut reader: R) -> Result<Catalog, Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let src = String::from_utf8(bytes).map_err(|_| DecodingError)?;
+        let src = src.strip_prefix('\u{feff}').unwrap_or(&src);
+        let mut parser = Parser {
+            lines: src.lines().collect(),
+            pos: 0,
+        };
+        let mut root = parser.document()?;
+        if let Some(entry) = root.get(1) {
+            return Err(YamlSyntax(entry.line));
+        }
+
+        let mut builder = CatalogBuilder::new().header("Content-Type", "text/plain; charset=UTF-8");
+        let mut language = "en".to_owned();
+        let mut entries = vec![];
+        if let Some(locale) = root.pop() {
+            builder = builder.header("Language", &locale.key);
+            language = locale.key;
+            match locale.value {
+                Node::Mapping(mapping) => entries = mapping,
+                Node::Null => {}
+                _ => return Err(YamlSyntax(locale.line)),
+            }
+        }
+        let mut catalog = builder.build()?;
+        let nplurals = catalog.nplurals();
+
+        let mut leaves = vec![];
+        flatten(entries, "", &mut leaves);
+        for (key, entry) in leaves {
+            let (translated, plural) = match entry.value {
+                Node::Scalar(text) => (vec![text], false),
+                Node::Mapping(forms) => {
+                    let mut translated = vec![String::new(); nplurals];
+                    let mut other = None;
+                    for form in forms {
+                        let text = match form.value {
+                            Node::Scalar(text) => text,
+                            _ => continue,
+                        };
+                        let category = form.key.parse::<PluralCategory>().unwrap();
+                        let n = category.sample(&language);
+                        let form_no = n.map(|n| catalog.resolver.resolve(n));
+                        if let Some(slot) = form_no.and_then(|i| translated.get_mut(i)) {
+                            *slot = text.clone();
+                        }
+                        if category == PluralCategory::Other {
+                            other = Some(text);
+                        }
+                    }
+                    if let Some(other) = other {
+                        for slot in translated.iter_mut().filter(|s| s.is_empty()) {
+                            *slot = other.clone();
+                        }
+                    }
+                    (translated, true)
+                }
+                Node::Null | Node::Sequence => continue,
+            };
+            let key = Key::from(&key[..]);
+            let (context, id) = key.parts();
+            let mut msg = Message::new(id, context, vec![]);
+            if plural {
+                msg.id_plural = Some(msg.id.clone());
+            }
+            msg.translated = translated;
+            msg.extracted_comments = entry.comments;
+            catalog.insert(msg);
+        }
+        Ok(catalog)
+    }
+
+    /// Writes the translated messages of the catalog out as a YAML locale file of Rails,
+    /// under the `Language` of the catalog and keyed as `parse_yaml` reads them.
+    ///
+    /// The keys are split on their dots into nested mappings, sorted by the keys,
+    /// but those that would clash with another key, or have empty parts, are written whole.
+    /// A plural message gets a key for each category that CLDR defines for the language,
+    /// taken from the plural form that the catalog uses for that category,
+    /// and an `other` key with the last form if the language only uses it for fractions.
+    /// Extracted comments are written as comments before their entries.
+    /// Untranslated messages and forms are left out, so that Rails falls back
+    /// to its default locale.
+    pub fn write_yaml<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let metadata = self.metadata();
+        let language = metadata.language().unwrap_or("en");
+        let mut messages = self
+            .strings
+            .iter()
+            .filter(|(key, msg)| !key.is_header() && msg.is_translated())
+            .collect::<Vec<_>>();
+        messages.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut entries = vec![];
+        for (key, msg) in messages {
+            let value = match msg.id_plural {
+                Some(_) => {
+                    let mut forms = vec![];
+                    for &category in PluralCategory::all() {
+                        let form = category.sample(language).map(|n| self.resolver.resolve(n));
+                        if let Some(text) = form.and_then(|form| msg.get_translated(form)) {
+                            forms
+                                .push(Entry::new(category.as_str(), Node::Scalar(text.to_owned())));
+                        }
+                    }
+                    // Rails requires `other`, which some languages only use for fractions
+                    if !forms.iter().any(|form| form.key == "other") {
+                        let last = msg.translated.iter().rev().find(|s| !s.is_empty());
+                        forms.push(Entry::new("other", Node::Scalar(last.unwrap().clone())));
+                    }
+                    Node::Mapping(forms)
+                }
+                None => Node::Scalar(msg.translated[0].clone()),
+            };
+            let mut entry = Entry::new(key.as_str(), value);
+            entry.comments = msg.extracted_comments.clone();
+            let path = entry.key.split('.').collect::<Vec<_>>();
+            if path.iter().any(|part| part.is_empty()) || !nest(&mut entries, &path, &entry) {
+                entries.push(entry);
+            }
+        }
+
+        let mut out = String::new();
+        push_entries(&mut out, &[Entry::new(language, Node::Mapping(entries))], 0);
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_scalars() {
+    for &(src, value) in &[
+        ("plain text # comment", Some("plain text")),
+        ("a#b", Some("a#b")),
+        ("'it''s'", Some("it's")),
+        (
+            "\"tab\\there \\u0105\\x21 \\\"q\\\"\"",
+            Some("tab\there ą! \"q\""),
+        ),
+        (
+            "\"folded\n   lines\n\n  and  \\\n  joined\"",
+            Some("folded lines\nand  joined"),
+        ),
+        ("'single\n  folded'", Some("single folded")),
+        ("~", None),
+        ("", None),
+        (
+            "|\n  line one\n  line two\n\n",
+            Some("line one\nline two\n"),
+        ),
+        ("|-\n  stripped\n", Some("stripped")),
+        ("|+\n  kept\n\n", Some("kept\n\n")),
+        (
+            ">\n  folded\n  text\n\n  next\n    indented\n",
+            Some("folded text\nnext\n  indented\n"),
+        ),
+        (
+            "plain\n  continued\n\n  after",
+            Some("plain continued\nafter"),
+        ),
+    ] {
+        let src = format!("key: {}", src);
+        let mut parser = Parser {
+            lines: src.lines().collect(),
+            pos: 0,
+        };
+        let entries = parser.mapping(0).unwrap();
+        let expected = value.map_or(Node::Null, |v| Node::Scalar(v.to_owned()));
+        assert_eq!(entries[0].value, expected, "{}", src);
+    }
+
+    for text in &[
+        "plain",
+        "Hello, world!",
+        "",
+        " lead",
+        "%{count} files",
+        "yes",
+        "No",
+        "12",
+        "a: b",
+        "a #b",
+        "line\nbreak",
+        "\u{4}",
+        "it's \"q\"",
+        "- item",
+        "back\\slash",
+    ] {
+        let src = format!("{}: {}", scalar("key"), scalar(text));
+        let mut parser = Parser {
+            lines: src.lines().collect(),
+            pos: 0,
+        };
+        let entries = parser.mapping(0).unwrap();
+        assert_eq!(entries[0].value, Node::Scalar(text.to_string()), "{}", src);
+    }
+    assert!(is_plain("Hello, world!"));
+    assert!(!is_plain("yes"));
+    assert!(!is_plain("%{count} files"));
+}
+
+#[test]
+fn test_parse_yaml() {
+    let src = "\
+# Lithuanian translations
+---
+lt:
+  # The menu
+  menu:
+    open: Atidaryti # a verb
+    # Quits the app
+    quit: \"Baigti\"
+  flow: {one: '%{count} failas', few: \"%{count} failai\",
+    other: '%{count} failų'}
+  files:
+    zero: nėra failų
+    one: \"%{count} failas\"
+    other: \"%{count} failų\"
+  notice: >-
+    Pranešimas per
+    dvi eilutes
+  date:
+    day_names:
+    - Sekmadienis
+    - Pirmadienis
+    abbr_day_names: [Sk, Pr]
+  missing: ~
+  \"menu\\u0004Open\": Meniu
+...
+";
+    let catalog = Catalog::parse_yaml(src.as_bytes()).unwrap();
+    assert_eq!(catalog.metadata().language(), Some("lt"));
+    assert_eq!(catalog.len(), 7);
+    assert_eq!(catalog.gettext("menu.open"), "Atidaryti");
+    let quit = catalog.get("menu.quit").unwrap();
+    assert_eq!(quit.translated(), &["Baigti"]);
+    assert_eq!(quit.extracted_comments(), &["Quits the app"]);
+    assert_eq!(catalog.gettext("notice"), "Pranešimas per dvi eilutes");
+    assert_eq!(catalog.pgettext("menu", "Open"), "Meniu");
+    let flow = catalog.get("flow").unwrap();
+    assert_eq!(flow.id_plural(), Some("flow"));
+    assert_eq!(
+        flow.translated,
+        ["%{count} failas", "%{count} failai", "%{count} failų"]
+    );
+    // `zero` is not a category of Lithuanian, and `few` is missing, so it gets the `other` text
+    let files = catalog.get("files").unwrap();
+    assert_eq!(
+        files.translated,
+        ["%{count} failas", "%{count} failų", "%{count} failų"]
+    );
+    assert!(catalog.get("date.day_names").is_none());
+    assert!(catalog.get("missing").is_none());
+
+    for &(src, line) in &[
+        ("lt:\n  a: b\nen:\n  a: b\n", 3),
+        ("lt:\n  a: b\n    c: d\n", 3),
+        ("lt:\n  defaults: &defaults\n    a: b\n", 2),
+        ("lt:\n  a: \"unclosed\n", 2),
+        ("lt:\n  a: \"\\q\"\n", 2),
+        ("lt:\n  a: |x\n    b\n", 2),
+        ("lt:\n  a: b\n---\nen:\n", 3),
+        ("lt:\n  just text\n", 2),
+        ("- a\n- b\n", 1),
+    ] {
+        let result = Catalog::parse_yaml(src.as_bytes());
+        assert!(
+            matches!(result, Err(YamlSyntax(l)) if l == line),
+            "{}: {:?}",
+            src,
+            result.map(|_| ())
+        );
+    }
+    let catalog = Catalog::parse_yaml(&b""[..]).unwrap();
+    assert_eq!(catalog.len(), 1);
+}
+
+#[test]
+fn test_write_yaml() {
+    let catalog = CatalogBuilder::new()
+        .header("Language", "pl")
+        .add("app.name", "Aplikacja")
+        .add("menu.open", "Otwórz")
+        .add("menu", "Menu")
+        .add("menu.quit.", "Zakończ.")
+        .add("answer", "Tak")
+        .add("answer.yes", "yes")
+        .add_with_context("verb", "Save", "Zapisz")
+        .add_plural(
+            "file",
+            "files",
+            vec!["%{count} plik", "%{count} pliki", "%{count} plików"],
+        )
+        .add_plural("dir", "dirs", vec!["katalog", "", ""])
+        .add("untranslated", "")
+        .build()
+        .unwrap();
+
+    let mut out = vec![];
+    catalog.write_yaml(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        "\
+pl:
+  answer: Tak
+  answer.yes: \"yes\"
+  app:
+    name: Aplikacja
+  dir:
+    one: katalog
+    other: katalog
+  file:
+    one: \"%{count} plik\"
+    few: \"%{count} pliki\"
+    many: \"%{count} plików\"
+    other: \"%{count} plików\"
+  menu: Menu
+  menu.open: Otwórz
+  menu.quit.: Zakończ.
+  \"verb\\x04Save\": Zapisz
+"
+    );
+
+    let read = Catalog::parse_yaml(out.as_bytes()).unwrap();
+    for msg in catalog.strings.values().filter(|msg| msg.is_translated()) {
+        if msg.is_header() {
+            continue;
+        }
+        let read = read.lookup(msg.context(), msg.id()).unwrap();
+        match msg.id_plural {
+            Some(_) => assert_eq!(read.translated[0], msg.translated[0]),
+            None => assert_eq!(read, msg),
+        }
+    }
+}
@@ -1,55 +1,153 @@
 use encoding::label::encoding_from_whatwg_label;
-use gettext::{Catalog, ParseOptions};
+use gettext::{Catalog, CatalogRef, Error, FormatStyle, MoReader, ParseOptions};
 
 use std::fs::File;
 
+/// Checks the lookups in integration.mo on any of the catalog types.
+macro_rules! check_integration {
+    ($catalog:expr) => {{
+        let catalog = $catalog;
+        let metadata = catalog.metadata();
+        assert_eq!(metadata.project_id_version(), Some("PACKAGE VERSION"));
+        assert_eq!(metadata.charset(), Some("utf-8"));
+        assert_eq!(metadata.language(), None);
+
+        assert_eq!(catalog.gettext("non-existent"), "non-existent");
+        // the header is not a translation
+        assert_eq!(catalog.gettext(""), "");
+        assert_eq!(catalog.gettext("existent"), "egzistuojantis");
+
+        assert_eq!(
+            catalog.ngettext("a bad string", "bad strings", 1),
+            "a bad string"
+        );
+        assert_eq!(
+            catalog.ngettext("a bad string", "bad strings", 2),
+            "bad strings"
+        );
+        assert_eq!(
+            catalog.ngettext("a good string", "good strings", 1),
+            "gera eilute"
+        );
+        assert_eq!(
+            catalog.ngettext("a good string", "good strings", 2),
+            "geros eilutes"
+        );
+
+        assert_eq!(catalog.pgettext("ctxt", "non-existent"), "non-existent");
+        assert_eq!(
+            catalog.pgettext("ctxt", "existent"),
+            "egzistuojantis kontekste"
+        );
+
+        assert_eq!(
+            catalog.npgettext("ctxt", "a bad string", "bad strings", 1),
+            "a bad string"
+        );
+        assert_eq!(
+            catalog.npgettext("ctxt", "a bad string", "bad strings", 2),
+            "bad strings"
+        );
+        assert_eq!(
+            catalog.npgettext("ctxt", "a good string", "good strings", 1),
+            "gera eilute kontekste"
+        );
+        assert_eq!(
+            catalog.npgettext("ctxt", "a good string", "good strings", 2),
+            "geros eilutes kontekste"
+        );
+    }};
+}
+
 #[test]
 fn test_integration() {
     let f = File::open("test_cases/integration.mo").unwrap();
-    let catalog = Catalog::parse(f).unwrap();
-
-    assert_eq!(catalog.gettext("non-existent"), "non-existent");
-    assert_eq!(catalog.gettext("existent"), "egzistuojantis");
-
-    assert_eq!(
-        catalog.ngettext("a bad string", "bad strings", 1),
-        "a bad string"
-    );
-    assert_eq!(
-        catalog.ngettext("a bad string", "bad strings", 2),
-        "bad strings"
-    );
-    assert_eq!(
-        catalog.ngettext("a good string", "good strings", 1),
-        "gera eilute"
-    );
-    assert_eq!(
-        catalog.ngettext("a good string", "good strings", 2),
-        "geros eilutes"
-    );
-
-    assert_eq!(catalog.pgettext("ctxt", "non-existent"), "non-existent");
-    assert_eq!(
-        catalog.pgettext("ctxt", "existent"),
-        "egzistuojantis kontekste"
-    );
-
-    assert_eq!(
-        catalog.npgettext("ctxt", "a bad string", "bad strings", 1),
-        "a bad string"
-    );
-    assert_eq!(
-        catalog.npgettext("ctxt", "a bad string", "bad strings", 2),
-        "bad strings"
-    );
-    assert_eq!(
-        catalog.npgettext("ctxt", "a good string", "good strings", 1),
-        "gera eilute kontekste"
-    );
-    assert_eq!(
-        catalog.npgettext("ctxt", "a good string", "good strings", 2),
-        "geros eilutes kontekste"
-    );
+    check_integration!(&Catalog::parse(f).unwrap());
+    let contents = std::fs::read("test_cases/integration.mo").unwrap();
+    check_integration!(&ParseOptions::new().parse_slice(&contents).unwrap());
+}
+
+#[test]
+fn test_integration_big_endian() {
+    // the same catalog as integration.mo, with the byte order of the integers swapped
+    let f = File::open("test_cases/integration_be.mo").unwrap();
+    check_integration!(&Catalog::parse(f).unwrap());
+    let catalog: &'static Catalog = gettext::include_mo!("../test_cases/integration_be.mo");
+    check_integration!(catalog);
+}
+
+#[test]
+fn test_integration_hashed() {
+    let f = File::open("test_cases/integration.mo").unwrap();
+    check_integration!(&ParseOptions::new().parse_hashed(f).unwrap());
+    let f = File::open("test_cases/integration_be.mo").unwrap();
+    check_integration!(&ParseOptions::new().parse_hashed(f).unwrap());
+    let bytes: &'static [u8] = include_bytes!("../test_cases/integration.mo");
+    check_integration!(&CatalogRef::from_bytes(bytes).unwrap());
+}
+
+#[test]
+fn test_integration_mo_reader() {
+    for path in &["test_cases/integration.mo", "test_cases/integration_be.mo"] {
+        let reader = MoReader::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().charset(), Some("utf-8"));
+        let messages = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        let message = messages
+            .iter()
+            .find(|m| m.context() == Some("ctxt") && m.id() == "a good string")
+            .unwrap();
+        assert_eq!(message.id_plural(), Some("good strings"));
+        assert_eq!(
+            message.translated(),
+            ["gera eilute kontekste", "geros eilutes kontekste"]
+        );
+    }
+}
+
+#[cfg(all(feature = "mmap", unix))]
+#[test]
+fn test_integration_mmap() {
+    let catalog = unsafe { ParseOptions::new().parse_mmap("test_cases/integration.mo") }.unwrap();
+    check_integration!(&catalog);
+}
+
+#[test]
+fn test_integration_show_header() {
+    let mo: &[u8] = include_bytes!("../test_cases/integration.mo");
+    let catalog = ParseOptions::new().hide_header(false).parse(mo).unwrap();
+    assert!(catalog.gettext("").contains("Content-Type"));
+    let catalog = ParseOptions::new()
+        .hide_header(false)
+        .parse_bytes(mo)
+        .unwrap();
+    assert!(catalog.gettext("").contains("Content-Type"));
+    let po: &[u8] = include_bytes!("../test_cases/integration.po");
+    let catalog = ParseOptions::new().hide_header(false).parse_po(po).unwrap();
+    assert!(catalog.gettext("").contains("Content-Type"));
+}
+
+#[test]
+fn test_integration_po() {
+    let f = File::open("test_cases/integration.po").unwrap();
+    check_integration!(&Catalog::parse_po(f).unwrap());
+}
+
+#[test]
+fn test_integration_po_to_mo() {
+    let f = File::open("test_cases/integration.po").unwrap();
+    let mut buf = vec![];
+    Catalog::parse_po(f).unwrap().write_mo(&mut buf).unwrap();
+    check_integration!(&Catalog::parse(&buf[..]).unwrap());
+}
+
+#[test]
+fn test_integration_include_mo() {
+    fn catalog() -> &'static Catalog {
+        gettext::include_mo!("../test_cases/integration.mo")
+    }
+    check_integration!(catalog());
+    // the catalog is only parsed once
+    assert!(std::ptr::eq(catalog(), catalog()));
 }
 
 #[test]
@@ -61,6 +159,20 @@ fn test_cp1257() {
         assert_eq!(catalog.gettext("Garlic"), "Česnakas");
     }
 
+    // cp1257_meta, looked up through the hash table
+    {
+        let reader: &[u8] = include_bytes!("../test_cases/cp1257_meta.mo");
+        let catalog = CatalogRef::from_bytes(reader).unwrap();
+        assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+    }
+
+    // cp1257_meta, parsed from the PO source
+    {
+        let reader: &[u8] = include_bytes!("../test_cases/cp1257_meta.po");
+        let catalog = ParseOptions::new().parse_po(reader).unwrap();
+        assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+    }
+
     // cp1257_forced
     {
         let reader: &[u8] = include_bytes!("../test_cases/cp1257_forced.mo");
@@ -71,6 +183,11 @@ fn test_cp1257() {
                 .parse(reader)
                 .unwrap();
             assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+            let catalog = ParseOptions::new()
+                .force_encoding(encoding)
+                .parse_bytes(reader)
+                .unwrap();
+            assert_eq!(catalog.gettext("Garlic"), "Česnakas");
         }
     }
 }
@@ -105,13 +222,31 @@ fn test_lt_plural() {
         }
         assert_eq!(cat.ngettext("Garlic", "Garlics", 21), "Česnakas");
     }
+
+    // lt_plural_forced, with a closure
+    {
+        let reader: &[u8] = include_bytes!("../test_cases/lt_plural_forced.mo");
+        let few = 2;
+        let cat = ParseOptions::new()
+            .plural_resolver(move |n| if n == 1 { 0 } else { few })
+            .parse(reader)
+            .unwrap();
+
+        assert_eq!(cat.ngettext("Garlic", "Garlics", 1), "Česnakas");
+        assert_eq!(cat.ngettext("Garlic", "Garlics", 2), "Česnakų");
+    }
 }
 
 #[test]
 fn test_complex_plural() {
     let reader: &[u8] = include_bytes!("../test_cases/complex_plural.mo");
-    let cat = ParseOptions::new().parse(reader).unwrap();
+    check_complex_plural(&ParseOptions::new().parse(reader).unwrap());
 
+    let reader: &[u8] = include_bytes!("../test_cases/complex_plural.po");
+    check_complex_plural(&ParseOptions::new().parse_po(reader).unwrap());
+}
+
+fn check_complex_plural(cat: &Catalog) {
     assert_eq!(cat.ngettext("Test", "Tests", 0), "Plural 2");
     assert_eq!(cat.ngettext("Test", "Tests", 1), "Singular");
     assert_eq!(cat.ngettext("Test", "Tests", 2), "Plural 1");
@@ -119,3 +254,156 @@ fn test_complex_plural() {
         assert_eq!(cat.ngettext("Test", "Tests", i), "Plural 2");
     }
 }
+
+#[test]
+fn test_validate_format_strings() {
+    let po = br#"
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d failas"
+msgstr[1] "%s failai"
+"#;
+    let mut mo = vec![];
+    Catalog::parse_po(&po[..])
+        .unwrap()
+        .write_mo(&mut mo)
+        .unwrap();
+
+    for style in &[FormatStyle::Printf, FormatStyle::Rust] {
+        let po_result = ParseOptions::new()
+            .validate_format_strings(*style)
+            .parse_po(&po[..]);
+        let mo_result = ParseOptions::new()
+            .validate_format_strings(*style)
+            .parse(&mo[..]);
+        for result in [po_result, mo_result] {
+            match (style, result) {
+                (FormatStyle::Printf, Err(Error::FormatMismatch(id))) => assert_eq!(id, "%d file"),
+                (FormatStyle::Rust, Ok(_)) => {}
+                (_, result) => panic!("unexpected result {:?}", result.map(|_| ())),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_error_location() {
+    let mo: &[u8] = include_bytes!("../test_cases/integration.mo");
+    let truncated = &mo[..mo.len() - 1];
+    let err = Catalog::parse(truncated).unwrap_err();
+    let index = match err {
+        Error::InEntry {
+            index,
+            offset,
+            ref error,
+        } => {
+            assert!(matches!(**error, Error::Eof));
+            assert!(offset < mo.len());
+            index
+        }
+        _ => panic!("unexpected error {:?}", err),
+    };
+    assert!(std::error::Error::source(&err).is_some());
+    assert!(err
+        .to_string()
+        .starts_with(&format!("in entry {} at byte ", index)));
+}
+
+#[test]
+fn test_parse_limits() {
+    let mo: &[u8] = include_bytes!("../test_cases/integration.mo");
+    let po: &[u8] = include_bytes!("../test_cases/integration.po");
+    let entries = MoReader::new(std::io::Cursor::new(mo)).unwrap().len();
+    // the longest string is the header
+    let header_len = ParseOptions::new()
+        .hide_header(false)
+        .parse(mo)
+        .unwrap()
+        .gettext("")
+        .len();
+
+    // the options at each of the limits, or just over them
+    let options = |limit: usize, over: usize| match limit {
+        0 => ParseOptions::new().max_entries(entries - over),
+        1 => ParseOptions::new().max_string_len(header_len - over),
+        _ => ParseOptions::new().max_total_size(mo.len() - over),
+    };
+    for i in 0..3 {
+        assert!(options(i, 0).parse(mo).is_ok());
+        assert!(options(i, 0).parse_hashed(mo).is_ok());
+        let reader = options(i, 0)
+            .read_entries(std::io::Cursor::new(mo))
+            .unwrap();
+        assert!(reader.collect::<Result<Vec<_>, _>>().is_ok());
+
+        let results = [
+            options(i, 1).parse(mo).map(|_| ()),
+            options(i, 1).parse_hashed(mo).map(|_| ()),
+            options(i, 1)
+                .read_entries(std::io::Cursor::new(mo))
+                .and_then(|r| r.collect::<Result<Vec<_>, _>>())
+                .map(|_| ()),
+        ];
+        for result in results {
+            // the string length is checked for each entry
+            let result = match result {
+                Err(Error::InEntry { error, .. }) => Err(*error),
+                result => result,
+            };
+            assert!(matches!(result, Err(Error::LimitExceeded)), "limit {}", i);
+        }
+    }
+
+    // unlike msgfmt, the PO parser keeps untranslated entries
+    let entries = po
+        .split(|&b| b == b'\n')
+        .filter(|l| l.starts_with(b"msgid "))
+        .count();
+    assert!(ParseOptions::new()
+        .max_entries(entries)
+        .parse_po(po)
+        .is_ok());
+    assert!(matches!(
+        ParseOptions::new().max_entries(entries - 1).parse_po(po),
+        Err(Error::LimitExceeded)
+    ));
+    assert!(ParseOptions::new()
+        .max_string_len(header_len)
+        .parse_po(po)
+        .is_ok());
+    assert!(matches!(
+        ParseOptions::new()
+            .max_string_len(header_len - 1)
+            .parse_po(po),
+        Err(Error::LimitExceeded)
+    ));
+    assert!(matches!(
+        ParseOptions::new()
+            .max_total_size(po.len() - 1)
+            .parse_po(po),
+        Err(Error::LimitExceeded)
+    ));
+}
+
+#[test]
+fn test_lenient() {
+    let mo: &[u8] = include_bytes!("../test_cases/invalid_utf8.mo");
+    assert!(Catalog::parse(mo).is_err());
+    let (_, warnings) = ParseOptions::new()
+        .lenient(true)
+        .parse_with_warnings(mo)
+        .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0]
+        .to_string()
+        .starts_with("skipped entry: in entry 0"));
+
+    let po: &[u8] = include_bytes!("../test_cases/invalid_utf8.po");
+    assert!(Catalog::parse_po(po).is_err());
+    let (_, warnings) = ParseOptions::new()
+        .lenient(true)
+        .parse_po_with_warnings(po)
+        .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].line().is_some());
+}